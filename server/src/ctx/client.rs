@@ -1,13 +1,16 @@
 //! This module implements support for X protocol clients.
 
 use crate::ctx::gc::GC;
+use crate::ctx::image;
 use crate::ctx::Context;
 use crate::ctx::Screen;
+use crate::input;
 use crate::net::Stream;
 use crate::protocol;
 use crate::protocol::connect::ClientConnect;
 use crate::protocol::connect::ConnectFailed;
 use crate::protocol::connect::ConnectSuccess;
+use crate::protocol::event;
 use crate::protocol::pad;
 use crate::protocol::request::DefaultRequestReader;
 use crate::protocol::request::HandleError;
@@ -127,6 +130,24 @@ impl Client {
 		self.write(&data)
 	}
 
+	/// Writes the events waiting to be sent to the client.
+	///
+	/// `ctx` is the current context.
+	///
+	/// If the connection is not established, events are kept until it is.
+	pub fn write_events(&mut self, ctx: &mut Context) -> io::Result<()> {
+		if !matches!(self.state, ClientState::ConnectSuccess) {
+			return Ok(());
+		}
+		let seq = self.sequence_number.0;
+		for mut e in ctx.take_events(self.id) {
+			event::set_sequence_number(&mut e, seq);
+			self.write(&e)?;
+		}
+
+		Ok(())
+	}
+
 	/// Writes a connect failed message with the given reason.
 	pub fn write_connect_failed(&mut self, reason: &str) -> io::Result<()> {
 		eprintln!("New client connection failed: {}", reason);
@@ -173,6 +194,19 @@ impl Client {
 		println!("New client connection succeeded");
 		self.state = ClientState::ConnectSuccess;
 
+		// A format is advertised for each depth supported by a screen
+		let mut depths = screens
+			.iter()
+			.flat_map(|s| s.get_visuals().get_depths())
+			.map(|d| d.depth)
+			.collect::<Vec<_>>();
+		depths.sort_unstable();
+		depths.dedup();
+		let formats = depths
+			.into_iter()
+			.filter_map(image::get_format)
+			.collect::<Vec<_>>();
+
 		let screens = screens
 			.iter()
 			.map(|s| s.to_protocol_screen())
@@ -182,7 +216,7 @@ impl Client {
 		let additional_data_len = 32
 			+ VENDOR_NAME.len()
 			+ pad(VENDOR_NAME.len())
-			+ 1 * size_of::<protocol::Format>() // TODO
+			+ formats.len() * size_of::<protocol::Format>()
 			+ screens_len;
 
 		let msg = ConnectSuccess {
@@ -200,29 +234,20 @@ impl Client {
 			vendor_length: VENDOR_NAME.len() as _,
 			max_request_length: u16::MAX,
 			roots_screens_number: 1, // TODO
-			pixmap_formats_count: 1, // TODO
-			image_byte_order: 1,     // MSB first
+			pixmap_formats_count: formats.len() as _,
+			image_byte_order: image::IMAGE_BYTE_ORDER,
 
-			bitmap_format_bit_order: 0,      // LSB first
-			bitmap_format_scanline_unit: 32, // TODO
-			bitmap_format_scanline_pad: 8,   // TODO
+			bitmap_format_bit_order: image::BITMAP_BIT_ORDER,
+			bitmap_format_scanline_unit: image::BITMAP_SCANLINE_UNIT,
+			bitmap_format_scanline_pad: image::BITMAP_SCANLINE_PAD,
 
-			min_keycode: 8,
-			max_keycode: 255,
+			min_keycode: input::MIN_KEYCODE,
+			max_keycode: input::MAX_KEYCODE,
 
 			/// Padding.
 			_padding1: 0,
 		};
 
-		// TODO Get from screens
-		let format = protocol::Format {
-			depth: 32,
-			bits_per_pixel: 24,
-			scanline_pad: 8,
-
-			_padding: [0; 5],
-		};
-
 		let len = 8 + additional_data_len;
 		let mut buf = vec![0; len];
 
@@ -242,12 +267,24 @@ impl Client {
 			let vendor_name = VENDOR_NAME.as_bytes();
 			ptr::copy_nonoverlapping::<u8>(vendor_name.as_ptr(), &mut buf[off], vendor_name.len());
 			off += vendor_name.len() + pad(vendor_name.len());
+		}
 
-			ptr::copy_nonoverlapping::<u8>(
-				&format as *const _ as *const u8,
-				&mut buf[off],
-				size_of::<protocol::Format>(),
-			);
+		for f in formats {
+			let format = protocol::Format {
+				depth: f.depth,
+				bits_per_pixel: f.bits_per_pixel,
+				scanline_pad: f.scanline_pad,
+
+				_padding: [0; 5],
+			};
+
+			unsafe {
+				ptr::copy_nonoverlapping::<u8>(
+					&format as *const _ as *const u8,
+					&mut buf[off],
+					size_of::<protocol::Format>(),
+				);
+			}
 			off += size_of::<protocol::Format>();
 		}
 
@@ -343,13 +380,22 @@ impl Client {
 						// IO error, close connection
 						Err(HandleError::IO(e)) => return Err(Box::new(e)),
 					}
+
+					self.write_events(ctx)?;
 				}
 
 				// No request to handle, break
 				Ok(None) => break,
 
-				// Handle error
-				Err(e) => self.write_obj(&e)?,
+				// The request cannot be skipped since it doesn't fit in the buffer, close the
+				// connection
+				Err(e) => {
+					self.write_obj(&e)?;
+					return Err(Box::new(io::Error::new(
+						io::ErrorKind::InvalidData,
+						"request too long",
+					)));
+				}
 			}
 		}
 
@@ -358,16 +404,21 @@ impl Client {
 
 	/// Ticks the client.
 	///
-	/// `ctx` is the current context.
-	pub fn tick(&mut self, ctx: &mut Context) -> Result<(), Box<dyn Error>> {
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `readable` tells whether data is available on the client's stream. If not, the stream is
+	///   not read and only pending events are written.
+	pub fn tick(&mut self, ctx: &mut Context, readable: bool) -> Result<(), Box<dyn Error>> {
 		// Reading incoming data
-		if self.buff_cursor < self.buff.len() {
+		if readable && self.buff_cursor < self.buff.len() {
 			let len = self.stream.read(&mut self.buff[self.buff_cursor..])?;
+			// A readable stream with no data has been closed by the peer
+			if len == 0 {
+				return Err(Box::new(io::Error::from(io::ErrorKind::UnexpectedEof)));
+			}
 			self.buff_cursor += len;
 		}
 
-		// TODO Notify client of event if necessary
-
 		// Reading input data
 		match self.state {
 			ClientState::Waiting | ClientState::ConnectFailed => {
@@ -376,6 +427,8 @@ impl Client {
 
 			ClientState::ConnectSuccess => {
 				self.handle_request(ctx)?;
+				// Events that are not caused by the client's requests
+				self.write_events(ctx)?;
 			}
 		}
 
@@ -387,8 +440,25 @@ impl Client {
 		self.request_reader = reader;
 	}
 
+	/// Returns an immutable reference to the Graphics Context with the given ID `cid`.
+	pub fn get_gc(&self, cid: u32) -> Option<&GC> {
+		self.gcs.get(&cid)
+	}
+
+	/// Returns a mutable reference to the Graphics Context with the given ID `cid`.
+	pub fn get_gc_mut(&mut self, cid: u32) -> Option<&mut GC> {
+		self.gcs.get_mut(&cid)
+	}
+
 	/// Sets a Graphics Context `gc` with the given ID `cid`.
 	pub fn set_gc(&mut self, cid: u32, gc: GC) {
 		self.gcs.insert(cid, gc);
 	}
+
+	/// Removes the Graphics Context with the given ID `cid`.
+	///
+	/// If the GC doesn't exist, the function returns None.
+	pub fn remove_gc(&mut self, cid: u32) -> Option<GC> {
+		self.gcs.remove(&cid)
+	}
 }
@@ -0,0 +1,833 @@
+//! A colormap maps the values of pixels to the colors displayed on screen.
+//!
+//! Depending on the class of the visual of a colormap, its entries are either predefined and
+//! read-only (`StaticGray`, `StaticColor` and `TrueColor`) or allocated and stored by clients
+//! (`GrayScale`, `PseudoColor` and `DirectColor`).
+//!
+//! With `TrueColor` and `DirectColor`, the red, green and blue subfields of a pixel index the
+//! entries separately. With other classes, the whole pixel indexes an entry.
+
+use crate::protocol::error::Error;
+use crate::protocol::Visual;
+use crate::protocol::VisualClass;
+use std::collections::HashMap;
+use std::iter;
+use std::num::NonZeroU32;
+
+/// A color, with 16 bits per component.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Color {
+	/// The red component.
+	pub red: u16,
+	/// The green component.
+	pub green: u16,
+	/// The blue component.
+	pub blue: u16,
+}
+
+impl Color {
+	/// Returns the color in the `0xRRGGBB` format.
+	pub fn to_rgb(&self) -> u32 {
+		((self.red as u32 >> 8) << 16) | ((self.green as u32 >> 8) << 8) | (self.blue as u32 >> 8)
+	}
+}
+
+/// The allocation state of a colormap entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CellState {
+	/// The entry is not allocated.
+	Free,
+	/// The entry is shared read-only. The key is the ID of a client and the value is the number
+	/// of times the client allocated the entry.
+	ReadOnly(HashMap<u32, u32>),
+	/// The entry is allocated read-write by the client with the given ID.
+	Writable(u32),
+}
+
+impl CellState {
+	/// Releases one allocation of the entry by the given client.
+	///
+	/// If the client has not allocated the entry, the function returns `false`.
+	fn release(&mut self, client: u32) -> bool {
+		match self {
+			Self::Writable(c) if *c == client => *self = Self::Free,
+			Self::ReadOnly(refs) => {
+				let Some(count) = refs.get_mut(&client) else {
+					return false;
+				};
+				*count -= 1;
+				if *count == 0 {
+					refs.remove(&client);
+				}
+				if refs.is_empty() {
+					*self = Self::Free;
+				}
+			}
+			_ => return false,
+		}
+		true
+	}
+}
+
+/// An entry of a colormap.
+#[derive(Clone, Debug)]
+pub struct Cell {
+	/// The color of the entry.
+	pub color: Color,
+	/// The allocation state of the entry.
+	pub state: CellState,
+}
+
+/// Returns the value of the `i`th level of a ramp of `levels` levels going from zero to the
+/// maximum intensity.
+fn ramp(i: u32, levels: u32) -> u16 {
+	if levels <= 1 {
+		return 0;
+	}
+	(i.min(levels - 1) * 0xffff / (levels - 1)) as _
+}
+
+/// Returns the number of values that can be represented by the given mask.
+fn mask_levels(mask: u32) -> u32 {
+	1 << mask.count_ones()
+}
+
+/// Extracts the subfield represented by `mask` from the given pixel.
+fn subfield(pixel: u32, mask: u32) -> u32 {
+	if mask == 0 {
+		return 0;
+	}
+	(pixel & mask) >> mask.trailing_zeros()
+}
+
+/// Places the given value in the subfield represented by `mask`.
+fn to_subfield(val: u32, mask: u32) -> u32 {
+	if mask == 0 {
+		return 0;
+	}
+	(val << mask.trailing_zeros()) & mask
+}
+
+/// Returns every combination of the bits of `mask`, including zero.
+fn subsets(mask: u32) -> impl Iterator<Item = u32> {
+	let mut next = Some(mask);
+	iter::from_fn(move || {
+		let sub = next?;
+		next = (sub != 0).then(|| (sub - 1) & mask);
+		Some(sub)
+	})
+}
+
+/// Returns the squared distance between two colors.
+fn distance(a: &Color, b: &Color) -> u64 {
+	let d = |x: u16, y: u16| (x as i64 - y as i64).pow(2) as u64;
+	d(a.red, b.red) + d(a.green, b.green) + d(a.blue, b.blue)
+}
+
+/// Structure representing a colormap.
+pub struct Colormap {
+	/// The ID of the colormap.
+	id: NonZeroU32,
+	/// The ID of the root window of the screen the colormap belongs to.
+	root: NonZeroU32,
+
+	/// The ID of the visual of the colormap.
+	visual: u32,
+	/// The class of the visual.
+	class: VisualClass,
+	/// The mask of the red subfield of pixels.
+	red_mask: u32,
+	/// The mask of the green subfield of pixels.
+	green_mask: u32,
+	/// The mask of the blue subfield of pixels.
+	blue_mask: u32,
+	/// The number of significant bits of each color component.
+	bits_per_rgb: u8,
+
+	/// The entries of the colormap.
+	cells: Vec<Cell>,
+	/// The client to which every entry has been allocated when the colormap was created.
+	alloc_all: Option<u32>,
+}
+
+impl Colormap {
+	/// Creates a new colormap.
+	///
+	/// Arguments:
+	/// - `id` is the ID of the colormap.
+	/// - `root` is the ID of the root window of the screen the colormap belongs to.
+	/// - `visual` is the visual of the colormap.
+	/// - `alloc_all` is the ID of the client to which every entry is allocated read-write. If
+	///   None, no entry is allocated. Entries of colormaps with a static visual class are never
+	///   allocated.
+	pub fn new(id: NonZeroU32, root: NonZeroU32, visual: &Visual, alloc_all: Option<u32>) -> Self {
+		let mut cmap = Self {
+			id,
+			root,
+
+			visual: visual.visual_id,
+			class: visual.class,
+			red_mask: visual.red_mask,
+			green_mask: visual.green_mask,
+			blue_mask: visual.blue_mask,
+			bits_per_rgb: visual.bits_per_rgb_value,
+
+			cells: vec![
+				Cell {
+					color: Color::default(),
+					state: CellState::Free,
+				};
+				visual.colormap_entries as usize
+			],
+			alloc_all: None,
+		};
+
+		let len = cmap.cells.len() as u32;
+		for (i, cell) in cmap.cells.iter_mut().enumerate() {
+			let i = i as u32;
+			cell.color = match cmap.class {
+				VisualClass::StaticGray => {
+					let level = ramp(i, len);
+					Color {
+						red: level,
+						green: level,
+						blue: level,
+					}
+				}
+				// Entries are split into three bits of red, three bits of green and two bits of
+				// blue, repeated if the colormap is larger
+				VisualClass::StaticColor => Color {
+					red: ramp((i >> 5) & 0x7, 8),
+					green: ramp((i >> 2) & 0x7, 8),
+					blue: ramp(i & 0x3, 4),
+				},
+				VisualClass::TrueColor => Color {
+					red: ramp(i, mask_levels(cmap.red_mask)),
+					green: ramp(i, mask_levels(cmap.green_mask)),
+					blue: ramp(i, mask_levels(cmap.blue_mask)),
+				},
+				_ => {
+					if let Some(client) = alloc_all {
+						cell.state = CellState::Writable(client);
+					}
+					continue;
+				}
+			};
+		}
+		if cmap.is_dynamic() {
+			cmap.alloc_all = alloc_all;
+		}
+
+		cmap
+	}
+
+	/// Returns the ID of the colormap.
+	pub fn get_id(&self) -> NonZeroU32 {
+		self.id
+	}
+
+	/// Returns the ID of the root window of the screen the colormap belongs to.
+	pub fn get_root(&self) -> NonZeroU32 {
+		self.root
+	}
+
+	/// Returns the ID of the visual of the colormap.
+	pub fn get_visual(&self) -> u32 {
+		self.visual
+	}
+
+	/// Returns the class of the visual of the colormap.
+	pub fn get_class(&self) -> VisualClass {
+		self.class
+	}
+
+	/// Tells whether the entries of the colormap can be allocated and stored by clients.
+	pub fn is_dynamic(&self) -> bool {
+		matches!(
+			self.class,
+			VisualClass::GrayScale | VisualClass::PseudoColor | VisualClass::DirectColor
+		)
+	}
+
+	/// Returns the entries of the colormap.
+	pub fn get_cells(&self) -> &[Cell] {
+		&self.cells
+	}
+
+	/// Returns a mutable reference to the entries of the colormap.
+	pub fn get_cells_mut(&mut self) -> &mut [Cell] {
+		&mut self.cells
+	}
+
+	/// Moves the entries allocated by the given client to a new colormap with the ID `id`, as
+	/// the `CopyColormapAndFree` request does.
+	///
+	/// If the colormap has been created by the client with every entry allocated, every entry is
+	/// moved and the new colormap has every entry allocated too.
+	pub fn copy_and_free(&mut self, id: NonZeroU32, client: u32) -> Self {
+		let dynamic = self.is_dynamic();
+		let all = self.alloc_all == Some(client);
+		let mut cells = self.cells.clone();
+		for (src, dst) in self.cells.iter_mut().zip(cells.iter_mut()) {
+			dst.state = CellState::Free;
+			if !dynamic {
+				continue;
+			}
+
+			match &mut src.state {
+				CellState::Writable(c) if all || *c == client => {
+					dst.state = CellState::Writable(client);
+					src.state = CellState::Free;
+				}
+				CellState::ReadOnly(refs) => {
+					if let Some(count) = refs.remove(&client) {
+						dst.state = CellState::ReadOnly(HashMap::from([(client, count)]));
+					}
+					if refs.is_empty() {
+						src.state = CellState::Free;
+					}
+				}
+				_ if all => {
+					dst.state = CellState::Writable(client);
+					src.state = CellState::Free;
+				}
+				_ => {}
+			}
+		}
+		if all {
+			self.alloc_all = None;
+		}
+
+		Self {
+			id,
+			root: self.root,
+
+			visual: self.visual,
+			class: self.class,
+			red_mask: self.red_mask,
+			green_mask: self.green_mask,
+			blue_mask: self.blue_mask,
+			bits_per_rgb: self.bits_per_rgb,
+
+			cells,
+			alloc_all: all.then_some(client),
+		}
+	}
+
+	/// Converts the given index of entry to a pixel.
+	///
+	/// With `DirectColor`, the index is used for every subfield.
+	fn index_to_pixel(&self, i: u32) -> u32 {
+		match self.class {
+			VisualClass::DirectColor | VisualClass::TrueColor => {
+				to_subfield(i, self.red_mask)
+					| to_subfield(i, self.green_mask)
+					| to_subfield(i, self.blue_mask)
+			}
+			_ => i,
+		}
+	}
+
+	/// Returns the indexes of the entries used for the red, green and blue components of the
+	/// given pixel.
+	///
+	/// If the pixel is out of the colormap, the function returns None.
+	fn pixel_to_indexes(&self, pixel: u32) -> Option<[u32; 3]> {
+		let indexes = match self.class {
+			VisualClass::DirectColor | VisualClass::TrueColor => {
+				let all = self.red_mask | self.green_mask | self.blue_mask;
+				if pixel & !all != 0 {
+					return None;
+				}
+				[
+					subfield(pixel, self.red_mask),
+					subfield(pixel, self.green_mask),
+					subfield(pixel, self.blue_mask),
+				]
+			}
+			_ => [pixel; 3],
+		};
+		indexes
+			.iter()
+			.all(|i| (*i as usize) < self.cells.len())
+			.then_some(indexes)
+	}
+
+	/// Returns the color of the given pixel. If the pixel is out of the colormap, the function
+	/// returns None.
+	pub fn query_color(&self, pixel: u32) -> Option<Color> {
+		let [r, g, b] = self.pixel_to_indexes(pixel)?;
+		Some(Color {
+			red: self.cells[r as usize].color.red,
+			green: self.cells[g as usize].color.green,
+			blue: self.cells[b as usize].color.blue,
+		})
+	}
+
+	/// Returns the closest color to the given one that can be stored in the colormap.
+	pub fn round(&self, color: &Color) -> Color {
+		let bits = self.bits_per_rgb.clamp(1, 16) as u32;
+		let max = (1u32 << bits) - 1;
+		let round = |v: u16| {
+			let v = (v as u32 * max + 0x7fff) / 0xffff;
+			(v * 0xffff / max) as u16
+		};
+		Color {
+			red: round(color.red),
+			green: round(color.green),
+			blue: round(color.blue),
+		}
+	}
+
+	/// Returns the closest predefined color to the given one, along with its pixel.
+	///
+	/// The colormap must have a static visual class.
+	fn find_static(&self, color: &Color) -> (u32, Color) {
+		if self.class == VisualClass::TrueColor {
+			let index = |v: u16, mask: u32| {
+				let max = mask_levels(mask) - 1;
+				(v as u32 * max + 0x7fff) / 0xffff
+			};
+			let pixel = to_subfield(index(color.red, self.red_mask), self.red_mask)
+				| to_subfield(index(color.green, self.green_mask), self.green_mask)
+				| to_subfield(index(color.blue, self.blue_mask), self.blue_mask);
+			// Cannot fail since indexes are in the range of each subfield
+			return (pixel, self.query_color(pixel).unwrap_or_default());
+		}
+
+		self.cells
+			.iter()
+			.enumerate()
+			.min_by_key(|(_, c)| distance(&c.color, color))
+			.map(|(i, c)| (i as u32, c.color))
+			.unwrap_or_default()
+	}
+
+	/// Returns the closest color to the given one that the colormap can hold.
+	pub fn get_closest(&self, color: &Color) -> Color {
+		if self.is_dynamic() {
+			self.round(color)
+		} else {
+			self.find_static(color).1
+		}
+	}
+
+	/// Allocates a read-only entry with the closest color to the given one for the given client.
+	///
+	/// If an entry with the same color is already allocated read-only, it is shared.
+	///
+	/// The function returns the pixel and the actual color of the entry.
+	pub fn alloc_color(&mut self, client: u32, color: &Color) -> Result<(u32, Color), Error> {
+		if !self.is_dynamic() {
+			return Ok(self.find_static(color));
+		}
+
+		let color = self.round(color);
+		let shared = self
+			.cells
+			.iter()
+			.position(|c| c.color == color && matches!(c.state, CellState::ReadOnly(_)));
+		let i = shared
+			.or_else(|| self.cells.iter().position(|c| c.state == CellState::Free))
+			.ok_or(Error::Alloc)?;
+
+		let cell = &mut self.cells[i];
+		cell.color = color;
+		match &mut cell.state {
+			CellState::ReadOnly(refs) => *refs.entry(client).or_default() += 1,
+			state => *state = CellState::ReadOnly(HashMap::from([(client, 1)])),
+		}
+		Ok((self.index_to_pixel(i as _), color))
+	}
+
+	/// Allocates read-write entries for the given client.
+	///
+	/// Arguments:
+	/// - `colors` is the number of base indexes to allocate.
+	/// - `planes` is the number of planes of each group of planes to allocate.
+	///
+	/// Every combination of a base index with the planes of a group is allocated. Planes are
+	/// always contiguous, which also satisfies allocations that don't require it.
+	///
+	/// The function returns the base indexes and the mask of planes of each group, in the space of
+	/// indexes.
+	fn alloc_cells(
+		&mut self,
+		client: u32,
+		colors: u16,
+		planes: &[u32],
+	) -> Result<(Vec<u32>, Vec<u32>), Error> {
+		if !self.is_dynamic() {
+			return Err(Error::Match);
+		}
+		if colors == 0 {
+			return Err(Error::Value(colors as _));
+		}
+
+		let bits = (self.cells.len() as u32).checked_ilog2().unwrap_or(0);
+		let total: u32 = planes.iter().sum();
+		if total > bits {
+			return Err(Error::Alloc);
+		}
+		for shift in 0..=(bits - total) {
+			let mut masks = vec![];
+			let mut off = shift;
+			for p in planes {
+				masks.push((((1u64 << p) - 1) as u32) << off);
+				off += p;
+			}
+			let all: u32 = masks.iter().fold(0, |a, m| a | m);
+			let cells = |base: u32| {
+				masks
+					.iter()
+					.flat_map(move |m| subsets(*m).map(move |sub| base | sub))
+			};
+			let bases = (0..self.cells.len() as u32)
+				.filter(|b| {
+					b & all == 0
+						&& cells(*b).all(|i| self.cells[i as usize].state == CellState::Free)
+				})
+				.take(colors as usize)
+				.collect::<Vec<_>>();
+			if bases.len() < colors as usize {
+				continue;
+			}
+
+			for base in &bases {
+				for i in cells(*base) {
+					self.cells[i as usize].state = CellState::Writable(client);
+				}
+			}
+			return Ok((bases, masks));
+		}
+
+		Err(Error::Alloc)
+	}
+
+	/// Allocates read-write entries for the given client, as the `AllocColorCells` request does.
+	///
+	/// Arguments:
+	/// - `colors` is the number of pixels to allocate.
+	/// - `planes` is the number of plane masks to allocate.
+	///
+	/// Plane masks are always contiguous.
+	///
+	/// The function returns the pixels and the plane masks.
+	pub fn alloc_color_cells(
+		&mut self,
+		client: u32,
+		colors: u16,
+		planes: u16,
+	) -> Result<(Vec<u32>, Vec<u32>), Error> {
+		let (bases, masks) = self.alloc_cells(client, colors, &[planes as _])?;
+		let mask = masks[0];
+		let pixels = bases.iter().map(|b| self.index_to_pixel(*b)).collect();
+		let masks = (0..32)
+			.map(|i| 1 << i)
+			.filter(|bit| mask & bit != 0)
+			.map(|bit| self.index_to_pixel(bit))
+			.collect();
+		Ok((pixels, masks))
+	}
+
+	/// Allocates read-write entries for the given client, as the `AllocColorPlanes` request does.
+	///
+	/// Arguments:
+	/// - `colors` is the number of pixels to allocate.
+	/// - `planes` is the number of planes to allocate for red, green and blue.
+	///
+	/// The planes of each mask are always contiguous.
+	///
+	/// The function returns the pixels and the red, green and blue masks.
+	pub fn alloc_color_planes(
+		&mut self,
+		client: u32,
+		colors: u16,
+		planes: [u16; 3],
+	) -> Result<(Vec<u32>, [u32; 3]), Error> {
+		// With `DirectColor`, each component has its own entries: only the combinations of the
+		// base with the planes of each component are used
+		let planes = planes.map(|p| p as u32);
+		let (bases, masks) = if self.class == VisualClass::DirectColor {
+			let (bases, masks) = self.alloc_cells(client, colors, &planes)?;
+			let masks = [
+				to_subfield(masks[0], self.red_mask),
+				to_subfield(masks[1], self.green_mask),
+				to_subfield(masks[2], self.blue_mask),
+			];
+			(bases, masks)
+		} else {
+			let (bases, mask) = self.alloc_cells(client, colors, &[planes.iter().sum()])?;
+			// The planes are split between components, from the lowest bit
+			let mut masks = [0; 3];
+			let mut shift = mask[0].trailing_zeros();
+			for (m, p) in masks.iter_mut().zip(planes) {
+				*m = (((1u64 << p) - 1) << shift) as u32;
+				shift += p;
+			}
+			(bases, masks)
+		};
+
+		let pixels = bases.iter().map(|b| self.index_to_pixel(*b)).collect();
+		Ok((pixels, masks))
+	}
+
+	/// Frees the given pixels, and every combination of them with the planes of `plane_mask`,
+	/// allocated by the given client.
+	///
+	/// An entry used by several of the pixels, as with the planes of `DirectColor` colormaps, is
+	/// freed once. Every pixel is freed even if an error occurs with another. In that case, the
+	/// last error is returned.
+	pub fn free_colors(
+		&mut self,
+		client: u32,
+		pixels: &[u32],
+		plane_mask: u32,
+	) -> Result<(), Error> {
+		let mut result = Ok(());
+		let mut indexes = vec![];
+		for pixel in pixels {
+			for sub in subsets(plane_mask) {
+				let pixel = pixel | sub;
+				match self.pixel_to_indexes(pixel) {
+					Some(i) => indexes.extend(i),
+					None => result = Err(Error::Value(pixel)),
+				}
+			}
+		}
+		// Entries of static colormaps are not allocated
+		if !self.is_dynamic() {
+			return result;
+		}
+
+		indexes.sort_unstable();
+		indexes.dedup();
+		for i in indexes {
+			if !self.cells[i as usize].state.release(client) {
+				result = Err(Error::Access);
+			}
+		}
+		result
+	}
+
+	/// Frees every entry allocated by the given client.
+	pub fn free_client(&mut self, client: u32) {
+		for cell in &mut self.cells {
+			while cell.state.release(client) {}
+		}
+		if self.alloc_all == Some(client) {
+			self.alloc_all = None;
+		}
+	}
+
+	/// Stores the given color in the entry of the given pixel.
+	///
+	/// `flags` tells whether the red, green and blue components are stored.
+	///
+	/// If the pixel is out of the colormap, the function returns an error. If the entry is not
+	/// allocated read-write, the function returns an error.
+	pub fn store_color(
+		&mut self,
+		pixel: u32,
+		color: &Color,
+		flags: [bool; 3],
+	) -> Result<(), Error> {
+		let indexes = self.pixel_to_indexes(pixel).ok_or(Error::Value(pixel))?;
+		let writable = indexes
+			.iter()
+			.zip(flags)
+			.filter(|(_, f)| *f)
+			.all(|(i, _)| matches!(self.cells[*i as usize].state, CellState::Writable(_)));
+		if !self.is_dynamic() || !writable {
+			return Err(Error::Access);
+		}
+
+		let color = self.round(color);
+		if flags[0] {
+			self.cells[indexes[0] as usize].color.red = color.red;
+		}
+		if flags[1] {
+			self.cells[indexes[1] as usize].color.green = color.green;
+		}
+		if flags[2] {
+			self.cells[indexes[2] as usize].color.blue = color.blue;
+		}
+		Ok(())
+	}
+
+	/// Returns the color displayed for the given pixel, in the `0xRRGGBB` format.
+	pub fn get_rgb(&self, pixel: u32) -> u32 {
+		let cell = |i: u32| {
+			self.cells
+				.get(i as usize)
+				.map(|c| c.color)
+				.unwrap_or_default()
+		};
+
+		match self.class {
+			VisualClass::TrueColor | VisualClass::DirectColor => Color {
+				red: cell(subfield(pixel, self.red_mask)).red,
+				green: cell(subfield(pixel, self.green_mask)).green,
+				blue: cell(subfield(pixel, self.blue_mask)).blue,
+			}
+			.to_rgb(),
+			// Intensities are computed from the luminance of the color
+			VisualClass::StaticGray | VisualClass::GrayScale => {
+				let c = cell(pixel);
+				let level = (c.red as u32 * 30 + c.green as u32 * 59 + c.blue as u32 * 11) / 100;
+				let level = level >> 8;
+				(level << 16) | (level << 8) | level
+			}
+			VisualClass::StaticColor | VisualClass::PseudoColor => cell(pixel).to_rgb(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Pure red.
+	const RED: Color = Color {
+		red: 0xffff,
+		green: 0,
+		blue: 0,
+	};
+	/// Pure green.
+	const GREEN: Color = Color {
+		red: 0,
+		green: 0xffff,
+		blue: 0,
+	};
+
+	/// Returns a colormap with the given class, number of entries and masks.
+	fn colormap(class: VisualClass, entries: u16, masks: [u32; 3]) -> Colormap {
+		let visual = Visual {
+			visual_id: 1,
+			class,
+			bits_per_rgb_value: 8,
+			colormap_entries: entries,
+
+			red_mask: masks[0],
+			green_mask: masks[1],
+			blue_mask: masks[2],
+
+			_padding: 0,
+		};
+		let id = NonZeroU32::new(1).unwrap();
+		Colormap::new(id, id, &visual, None)
+	}
+
+	/// Returns the states of the entries of the given colormap: `.` if free, `r` if read-only,
+	/// or the ID of the client if writable.
+	fn states(cmap: &Colormap) -> String {
+		cmap.get_cells()
+			.iter()
+			.map(|c| match &c.state {
+				CellState::Free => '.',
+				CellState::ReadOnly(_) => 'r',
+				CellState::Writable(client) => char::from_digit(*client, 10).unwrap(),
+			})
+			.collect()
+	}
+
+	/// Read-only entries with the same color are shared, and freed once every allocation is.
+	#[test]
+	fn alloc_color() {
+		let mut cmap = colormap(VisualClass::PseudoColor, 4, [0; 3]);
+		assert_eq!(cmap.alloc_color(1, &RED).unwrap(), (0, RED));
+		assert_eq!(cmap.alloc_color(2, &RED).unwrap(), (0, RED));
+		assert_eq!(cmap.alloc_color(1, &RED).unwrap(), (0, RED));
+		assert_eq!(cmap.alloc_color(1, &GREEN).unwrap(), (1, GREEN));
+		assert_eq!(states(&cmap), "rr..");
+
+		assert!(cmap.free_colors(1, &[0], 0).is_ok());
+		assert!(cmap.free_colors(2, &[0], 0).is_ok());
+		assert_eq!(states(&cmap), "rr..");
+		assert!(cmap.free_colors(1, &[0], 0).is_ok());
+		assert_eq!(states(&cmap), ".r..");
+
+		assert!(matches!(cmap.free_colors(2, &[0], 0), Err(Error::Access)));
+		assert!(matches!(
+			cmap.free_colors(1, &[1, 4], 0),
+			Err(Error::Value(4))
+		));
+		assert_eq!(states(&cmap), "....");
+	}
+
+	/// Read-write entries are allocated with contiguous planes, in free entries only.
+	#[test]
+	fn alloc_color_cells() {
+		let mut cmap = colormap(VisualClass::PseudoColor, 8, [0; 3]);
+		cmap.alloc_color(1, &RED).unwrap();
+		let (pixels, masks) = cmap.alloc_color_cells(2, 2, 1).unwrap();
+		assert_eq!((pixels, masks), (vec![2, 4], vec![1]));
+		assert_eq!(states(&cmap), "r.2222..");
+
+		assert!(cmap.store_color(3, &GREEN, [true; 3]).is_ok());
+		assert_eq!(cmap.query_color(3), Some(GREEN));
+		assert!(matches!(
+			cmap.store_color(0, &GREEN, [true; 3]),
+			Err(Error::Access)
+		));
+
+		// Not enough room for two pairs anymore
+		assert!(matches!(cmap.alloc_color_cells(3, 2, 1), Err(Error::Alloc)));
+		assert_eq!(cmap.alloc_color_cells(3, 1, 1).unwrap(), (vec![6], vec![1]));
+		assert_eq!(states(&cmap), "r.222233");
+
+		assert!(cmap.free_colors(2, &[2], 1).is_ok());
+		assert_eq!(states(&cmap), "r...2233");
+		cmap.free_client(3);
+		assert_eq!(states(&cmap), "r...22..");
+	}
+
+	/// With `DirectColor`, each component only uses the planes allocated for it.
+	#[test]
+	fn alloc_color_planes() {
+		let mut cmap = colormap(VisualClass::DirectColor, 8, [0x700, 0x70, 0x7]);
+		let (pixels, masks) = cmap.alloc_color_planes(1, 1, [1, 1, 1]).unwrap();
+		assert_eq!((pixels, masks), (vec![0], [0x100, 0x20, 0x4]));
+		assert_eq!(states(&cmap), "111.1...");
+
+		assert!(cmap.store_color(0x124, &RED, [true; 3]).is_ok());
+		assert_eq!(cmap.query_color(0x124), Some(RED));
+		// The red component is shared by every pixel with the same red index
+		assert_eq!(cmap.query_color(0x100), Some(RED));
+		assert_eq!(cmap.query_color(0x24), Some(Color::default()));
+
+		assert!(cmap.free_colors(1, &[0], 0x124).is_ok());
+		assert_eq!(states(&cmap), "........");
+	}
+
+	/// Copying a colormap moves the entries of the client to the new colormap.
+	#[test]
+	fn copy_and_free() {
+		let mut cmap = colormap(VisualClass::PseudoColor, 4, [0; 3]);
+		cmap.alloc_color(1, &RED).unwrap();
+		cmap.alloc_color(2, &RED).unwrap();
+		cmap.alloc_color_cells(1, 1, 0).unwrap();
+		cmap.alloc_color_cells(2, 1, 0).unwrap();
+		assert_eq!(states(&cmap), "r12.");
+
+		let copy = cmap.copy_and_free(NonZeroU32::new(2).unwrap(), 1);
+		assert_eq!(states(&cmap), "r.2.");
+		assert_eq!(states(&copy), "r1..");
+		assert_eq!(copy.query_color(0), Some(RED));
+	}
+
+	/// Static colormaps return the closest predefined color.
+	#[test]
+	fn static_colors() {
+		let mut cmap = colormap(VisualClass::TrueColor, 256, [0xff0000, 0xff00, 0xff]);
+		let color = Color {
+			red: 0xffff,
+			green: 0x8080,
+			blue: 0,
+		};
+		assert_eq!(cmap.alloc_color(1, &color).unwrap(), (0xff8000, color));
+		assert_eq!(cmap.get_rgb(0xff8000), 0xff8000);
+		assert!(matches!(cmap.alloc_color_cells(1, 1, 0), Err(Error::Match)));
+		assert!(cmap.free_colors(1, &[0xff8000], 0).is_ok());
+	}
+}
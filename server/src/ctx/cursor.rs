@@ -0,0 +1,210 @@
+//! A cursor is the image displayed at the position of the pointer.
+//!
+//! Cursors are made of two colors: pixels set in the source are drawn with the foreground color,
+//! other pixels with the background color. Only pixels set in the mask are drawn.
+//!
+//! Like pixmaps, cursors are shared through reference counting: a cursor freed while used by a
+//! window remains displayed until the window stops using it.
+
+use super::image::Image;
+use super::region::Rect;
+use crate::font::Font;
+use crate::font::Glyph;
+use crate::protocol::error::Error;
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+/// A shared reference to a cursor.
+pub type CursorRef = Rc<Cursor>;
+
+/// The character of the cursor font used for the default cursor. Its mask is the following
+/// character.
+pub const DEFAULT_CURSOR_CHAR: u16 = 0;
+
+/// Converts the given color, with 16 bits components, to a RGB pixel with 8 bits components.
+pub fn to_rgb(color: [u16; 3]) -> u32 {
+	let [r, g, b] = color.map(|c| (c >> 8) as u32);
+	(r << 16) | (g << 8) | b
+}
+
+/// Structure representing a cursor.
+pub struct Cursor {
+	/// The width of the cursor in pixels.
+	width: u16,
+	/// The height of the cursor in pixels.
+	height: u16,
+	/// The X position of the hotspot, relative to the cursor's top-left corner.
+	x_hot: u16,
+	/// The Y position of the hotspot, relative to the cursor's top-left corner.
+	y_hot: u16,
+
+	/// For each pixel, row by row, tells whether the pixel is set in the source.
+	source: Vec<bool>,
+	/// For each pixel, row by row, tells whether the pixel is drawn.
+	mask: Vec<bool>,
+
+	/// The foreground and background colors, as RGB pixels.
+	colors: Cell<(u32, u32)>,
+}
+
+impl fmt::Debug for Cursor {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt.debug_struct("Cursor")
+			.field("width", &self.width)
+			.field("height", &self.height)
+			.field("x_hot", &self.x_hot)
+			.field("y_hot", &self.y_hot)
+			.finish()
+	}
+}
+
+impl Cursor {
+	/// Creates a cursor from the images of pixmaps.
+	///
+	/// Arguments:
+	/// - `source` is the image of the source.
+	/// - `mask` is the image of the mask. If None, every pixel is drawn.
+	/// - `x_hot` and `y_hot` are the position of the hotspot, relative to the source's origin.
+	/// - `fore` and `back` are the foreground and background colors.
+	///
+	/// If an image isn't of depth 1, if the images don't have the same size or if the hotspot is
+	/// outside of the source, the function returns an error.
+	pub fn from_images(
+		source: &Image,
+		mask: Option<&Image>,
+		x_hot: u16,
+		y_hot: u16,
+		fore: [u16; 3],
+		back: [u16; 3],
+	) -> Result<Self, Error> {
+		let width = source.get_width();
+		let height = source.get_height();
+		if source.get_depth() != 1 || x_hot >= width || y_hot >= height {
+			return Err(Error::Match);
+		}
+		if let Some(mask) = mask {
+			if mask.get_depth() != 1 || mask.get_width() != width || mask.get_height() != height {
+				return Err(Error::Match);
+			}
+		}
+
+		let pixels = (0..height as usize).flat_map(|y| (0..width as usize).map(move |x| (x, y)));
+		Ok(Self {
+			width,
+			height,
+			x_hot,
+			y_hot,
+
+			source: pixels
+				.clone()
+				.map(|(x, y)| source.get_pixel(x, y) != 0)
+				.collect(),
+			mask: pixels
+				.map(|(x, y)| mask.map(|m| m.get_pixel(x, y) != 0).unwrap_or(true))
+				.collect(),
+
+			colors: Cell::new((to_rgb(fore), to_rgb(back))),
+		})
+	}
+
+	/// Creates a cursor from glyphs of fonts.
+	///
+	/// The hotspot is the origin of the glyphs. The cursor covers the bounding boxes of both
+	/// glyphs. If `mask` is None, the pixels set in the source are drawn.
+	///
+	/// `fore` and `back` are the foreground and background colors.
+	pub fn from_glyphs(
+		source: &Glyph,
+		mask: Option<&Glyph>,
+		fore: [u16; 3],
+		back: [u16; 3],
+	) -> Self {
+		// The bounds of the cursor, relative to the origin of the glyphs
+		let left = |g: &Glyph| g.info.left_side_bearing as i32;
+		let top = |g: &Glyph| -g.info.ascent as i32;
+		let glyphs = [Some(source), mask];
+		let glyphs = glyphs.iter().flatten();
+		let x0 = glyphs.clone().map(|g| left(g)).min().unwrap_or(0).min(0);
+		let y0 = glyphs.clone().map(|g| top(g)).min().unwrap_or(0).min(0);
+		let x1 = glyphs
+			.clone()
+			.map(|g| left(g) + g.get_width())
+			.max()
+			.unwrap_or(0)
+			.max(1);
+		let y1 = glyphs
+			.map(|g| top(g) + g.get_height())
+			.max()
+			.unwrap_or(0)
+			.max(1);
+
+		let get = |g: &Glyph, x: i32, y: i32| g.get_pixel(x - left(g), y - top(g));
+		let pixels = (y0..y1).flat_map(|y| (x0..x1).map(move |x| (x, y)));
+		Self {
+			width: (x1 - x0) as _,
+			height: (y1 - y0) as _,
+			x_hot: -x0 as _,
+			y_hot: -y0 as _,
+
+			source: pixels.clone().map(|(x, y)| get(source, x, y)).collect(),
+			mask: pixels
+				.map(|(x, y)| match mask {
+					Some(mask) => get(mask, x, y),
+					None => get(source, x, y),
+				})
+				.collect(),
+
+			colors: Cell::new((to_rgb(fore), to_rgb(back))),
+		}
+	}
+
+	/// Creates the default cursor, used by windows that have no cursor, from the given cursor
+	/// font.
+	///
+	/// The cursor is black with a white outline. If the font lacks the cursor's character, the
+	/// function returns None.
+	pub fn default_from_font(font: &Font) -> Option<Self> {
+		let source = font.get_glyph(DEFAULT_CURSOR_CHAR)?;
+		let mask = font.get_glyph(DEFAULT_CURSOR_CHAR + 1);
+		Some(Self::from_glyphs(source, mask, [0; 3], [0xffff; 3]))
+	}
+
+	/// Returns the width and height of the cursor in pixels.
+	pub fn get_size(&self) -> (u16, u16) {
+		(self.width, self.height)
+	}
+
+	/// Returns the position of the hotspot, relative to the cursor's top-left corner.
+	pub fn get_hotspot(&self) -> (u16, u16) {
+		(self.x_hot, self.y_hot)
+	}
+
+	/// Changes the colors of the cursor.
+	pub fn recolor(&self, fore: [u16; 3], back: [u16; 3]) {
+		self.colors.set((to_rgb(fore), to_rgb(back)));
+	}
+
+	/// Returns the RGB color of the pixel at the given position, relative to the cursor's
+	/// top-left corner.
+	///
+	/// If the pixel is not drawn or is outside of the cursor, the function returns None.
+	pub fn get_pixel(&self, x: i32, y: i32) -> Option<u32> {
+		if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+			return None;
+		}
+		let i = y as usize * self.width as usize + x as usize;
+		if !self.mask[i] {
+			return None;
+		}
+		let (fore, back) = self.colors.get();
+		Some(if self.source[i] { fore } else { back })
+	}
+
+	/// Returns the area covered by the cursor when its hotspot is at `(x, y)`.
+	pub fn get_bounds(&self, x: i32, y: i32) -> Rect {
+		let x0 = x - self.x_hot as i32;
+		let y0 = y - self.y_hot as i32;
+		Rect::new(x0, y0, x0 + self.width as i32, y0 + self.height as i32)
+	}
+}
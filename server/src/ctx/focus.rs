@@ -0,0 +1,347 @@
+//! The input focus is the window receiving the keyboard input.
+//!
+//! When the focus moves from a window to another, `FocusOut` and `FocusIn` events are reported
+//! on every window between both in the hierarchy. Windows between the focus window and the
+//! window containing the pointer are notified with the detail `Pointer`, since they receive
+//! keyboard input through the pointer.
+//!
+//! Grabbing the keyboard reports focus events as if the focus moved to the grab window, and
+//! ungrabbing it as if the focus moved back.
+//!
+//! When the focus window becomes unviewable, the focus reverts according to its `revert_to`
+//! value.
+
+use super::keyboard::Focus;
+use super::keyboard::KeyboardGrab;
+use super::keyboard::RevertTo;
+use super::Context;
+use crate::protocol::event;
+use crate::protocol::event::FocusInOut;
+use crate::protocol::event::NotifyDetail;
+use crate::protocol::event::NotifyMode;
+use crate::protocol::Event;
+use std::num::NonZeroU32;
+
+impl Context<'_> {
+	/// Tells whether the given window is the focus window or an inferior of it.
+	///
+	/// With the focus `PointerRoot`, every window is considered to be an inferior of the focus.
+	pub fn is_focus_or_inferior(&self, wid: NonZeroU32) -> bool {
+		match self.keyboard.get_focus() {
+			Focus::None => false,
+			Focus::PointerRoot => true,
+			Focus::Window(focus) => self.get_ancestors(wid).contains(&focus),
+		}
+	}
+
+	/// Reports a focus event on the given window.
+	///
+	/// `FocusIn` events are followed by a `KeymapNotify` event.
+	fn focus_event(&mut self, code: u8, wid: NonZeroU32, detail: NotifyDetail, mode: NotifyMode) {
+		let ev = FocusInOut {
+			code,
+			detail: detail as _,
+			seq_nbr: 0,
+
+			event: wid.get(),
+			mode: mode as _,
+
+			_padding: [0; 23],
+		};
+		self.deliver_event(wid, Event::FocusChange as u32, &ev);
+		if code == event::FOCUS_IN {
+			let keymap = self.make_keymap_notify();
+			self.deliver_event(wid, Event::KeymapState as u32, &keymap);
+		}
+	}
+
+	/// Returns the windows from the pointer window `pointer` up to `top` exclusive, from the
+	/// bottom.
+	///
+	/// If the pointer window is not a strict inferior of `top`, the list is empty.
+	fn get_pointer_path(&self, pointer: Option<NonZeroU32>, top: NonZeroU32) -> Vec<NonZeroU32> {
+		let Some(pointer) = pointer else {
+			return vec![];
+		};
+		let mut ancestors = self.get_ancestors(pointer);
+		match ancestors.iter().position(|w| *w == top) {
+			Some(i) => {
+				ancestors.truncate(i);
+				ancestors
+			}
+			None => vec![],
+		}
+	}
+
+	/// Reports the `FocusOut` events for the focus leaving window `from` for a window which is
+	/// not an inferior nor an ancestor of it.
+	///
+	/// `common` is the common ancestor of both windows. If None, the windows are on different
+	/// screens or the focus moves to `PointerRoot` or `None`, and events stop before the root
+	/// window.
+	fn focus_out_nonlinear(
+		&mut self,
+		from: NonZeroU32,
+		common: Option<NonZeroU32>,
+		pointer: Option<NonZeroU32>,
+		mode: NotifyMode,
+	) {
+		for wid in self.get_pointer_path(pointer, from) {
+			self.focus_event(event::FOCUS_OUT, wid, NotifyDetail::Pointer, mode);
+		}
+		self.focus_event(event::FOCUS_OUT, from, NotifyDetail::Nonlinear, mode);
+		let ancestors = self.get_ancestors(from);
+		// Without a common ancestor, the root window is reported separately
+		let stop = common.or(ancestors.last().copied());
+		for wid in ancestors[1..].iter().take_while(|w| Some(**w) != stop) {
+			self.focus_event(event::FOCUS_OUT, *wid, NotifyDetail::NonlinearVirtual, mode);
+		}
+	}
+
+	/// Reports the `FocusIn` events for the focus entering window `to` from a window which is
+	/// not an inferior nor an ancestor of it.
+	///
+	/// `common` is the common ancestor of both windows. If None, the windows are on different
+	/// screens or the focus moves from `PointerRoot` or `None`, and events stop before the root
+	/// window.
+	fn focus_in_nonlinear(
+		&mut self,
+		to: NonZeroU32,
+		common: Option<NonZeroU32>,
+		pointer: Option<NonZeroU32>,
+		mode: NotifyMode,
+	) {
+		let ancestors = self.get_ancestors(to);
+		// Without a common ancestor, the root window is reported separately
+		let stop = common.or(ancestors.last().copied());
+		let between: Vec<_> = ancestors[1..]
+			.iter()
+			.copied()
+			.take_while(|w| Some(*w) != stop)
+			.collect();
+		for wid in between.into_iter().rev() {
+			self.focus_event(event::FOCUS_IN, wid, NotifyDetail::NonlinearVirtual, mode);
+		}
+		self.focus_event(event::FOCUS_IN, to, NotifyDetail::Nonlinear, mode);
+		for wid in self.get_pointer_path(pointer, to).into_iter().rev() {
+			self.focus_event(event::FOCUS_IN, wid, NotifyDetail::Pointer, mode);
+		}
+	}
+
+	/// Reports the focus events for the focus moving from the window `from` to the window `to`.
+	fn focus_window_to_window(
+		&mut self,
+		from: NonZeroU32,
+		to: NonZeroU32,
+		pointer: Option<NonZeroU32>,
+		mode: NotifyMode,
+	) {
+		let from_anc = self.get_ancestors(from);
+		let to_anc = self.get_ancestors(to);
+		let pointer_anc = pointer.map(|p| self.get_ancestors(p)).unwrap_or_default();
+
+		if let Some(i) = from_anc.iter().position(|w| *w == to) {
+			// `from` is an inferior of `to`
+			self.focus_event(event::FOCUS_OUT, from, NotifyDetail::Ancestor, mode);
+			for wid in &from_anc[1..i] {
+				self.focus_event(event::FOCUS_OUT, *wid, NotifyDetail::Virtual, mode);
+			}
+			self.focus_event(event::FOCUS_IN, to, NotifyDetail::Inferior, mode);
+			// Unless the pointer is on the path between both windows
+			let on_path =
+				pointer_anc.contains(&from) || pointer.is_some_and(|p| from_anc.contains(&p));
+			if !on_path {
+				for wid in self.get_pointer_path(pointer, to).into_iter().rev() {
+					self.focus_event(event::FOCUS_IN, wid, NotifyDetail::Pointer, mode);
+				}
+			}
+		} else if let Some(i) = to_anc.iter().position(|w| *w == from) {
+			// `to` is an inferior of `from`
+			let on_path = pointer_anc.contains(&to) || pointer.is_some_and(|p| to_anc.contains(&p));
+			if !on_path {
+				for wid in self.get_pointer_path(pointer, from) {
+					self.focus_event(event::FOCUS_OUT, wid, NotifyDetail::Pointer, mode);
+				}
+			}
+			self.focus_event(event::FOCUS_OUT, from, NotifyDetail::Inferior, mode);
+			for wid in to_anc[1..i].iter().rev() {
+				self.focus_event(event::FOCUS_IN, *wid, NotifyDetail::Virtual, mode);
+			}
+			self.focus_event(event::FOCUS_IN, to, NotifyDetail::Ancestor, mode);
+		} else {
+			// On different screens, there is no common ancestor
+			let common = from_anc.iter().find(|w| to_anc.contains(w)).copied();
+			self.focus_out_nonlinear(from, common, pointer, mode);
+			// Root windows are between both windows on different screens
+			let from_root = from_anc.last().copied().filter(|r| *r != from);
+			if let (None, Some(root)) = (common, from_root) {
+				self.focus_event(event::FOCUS_OUT, root, NotifyDetail::NonlinearVirtual, mode);
+			}
+			let to_root = to_anc.last().copied().filter(|r| *r != to);
+			if let (None, Some(root)) = (common, to_root) {
+				self.focus_event(event::FOCUS_IN, root, NotifyDetail::NonlinearVirtual, mode);
+			}
+			self.focus_in_nonlinear(to, common, pointer, mode);
+		}
+	}
+
+	/// Reports the focus events on the root window of every screen for the focus leaving
+	/// `PointerRoot` or `None`.
+	fn focus_out_roots(&mut self, from: Focus, pointer: Option<NonZeroU32>, mode: NotifyMode) {
+		let detail = if from == Focus::PointerRoot {
+			// Windows containing the pointer lose the focus
+			let root = NonZeroU32::new(self.pointer.get_screen());
+			if let Some(root) = root {
+				for wid in self.get_pointer_path(pointer, root) {
+					self.focus_event(event::FOCUS_OUT, wid, NotifyDetail::Pointer, mode);
+				}
+			}
+			NotifyDetail::PointerRoot
+		} else {
+			NotifyDetail::None
+		};
+		let roots: Vec<_> = self
+			.screens
+			.iter()
+			.map(|s| s.get_root_window_id())
+			.collect();
+		for root in roots {
+			self.focus_event(event::FOCUS_OUT, root, detail, mode);
+		}
+	}
+
+	/// Reports the focus events on the root window of every screen for the focus entering
+	/// `PointerRoot` or `None`.
+	fn focus_in_roots(&mut self, to: Focus, pointer: Option<NonZeroU32>, mode: NotifyMode) {
+		let detail = if to == Focus::PointerRoot {
+			NotifyDetail::PointerRoot
+		} else {
+			NotifyDetail::None
+		};
+		let roots: Vec<_> = self
+			.screens
+			.iter()
+			.map(|s| s.get_root_window_id())
+			.collect();
+		for root in roots {
+			self.focus_event(event::FOCUS_IN, root, detail, mode);
+		}
+		if to == Focus::PointerRoot {
+			// Windows containing the pointer gain the focus
+			if let Some(root) = NonZeroU32::new(self.pointer.get_screen()) {
+				for wid in self.get_pointer_path(pointer, root).into_iter().rev() {
+					self.focus_event(event::FOCUS_IN, wid, NotifyDetail::Pointer, mode);
+				}
+			}
+		}
+	}
+
+	/// Reports the focus events for the focus moving from `from` to `to`.
+	pub fn focus_change(&mut self, from: Focus, to: Focus, mode: NotifyMode) {
+		if from == to {
+			return;
+		}
+		let pointer = self.get_pointer_window();
+		match (from, to) {
+			(Focus::Window(from), Focus::Window(to)) => {
+				self.focus_window_to_window(from, to, pointer, mode)
+			}
+			(Focus::Window(from), _) => {
+				self.focus_out_nonlinear(from, None, pointer, mode);
+				self.focus_in_roots(to, pointer, mode);
+			}
+			(_, Focus::Window(to)) => {
+				self.focus_out_roots(from, pointer, mode);
+				self.focus_in_nonlinear(to, None, pointer, mode);
+			}
+			_ => {
+				self.focus_out_roots(from, pointer, mode);
+				self.focus_in_roots(to, pointer, mode);
+			}
+		}
+	}
+
+	/// Returns the mode of focus events caused by a change of the focus.
+	fn get_focus_mode(&self) -> NotifyMode {
+		if self.keyboard.get_grab().is_some() {
+			NotifyMode::WhileGrabbed
+		} else {
+			NotifyMode::Normal
+		}
+	}
+
+	/// Sets the focus, reporting focus events.
+	///
+	/// `time` is the time of the change, which must have been checked before.
+	pub fn set_input_focus(&mut self, focus: Focus, revert_to: RevertTo, time: u32) {
+		let prev = self.keyboard.get_focus();
+		self.keyboard.set_focus(focus, revert_to, time);
+		let mode = self.get_focus_mode();
+		self.focus_change(prev, focus, mode);
+	}
+
+	/// Grabs the keyboard, reporting focus events as if the focus moved to the grab window.
+	///
+	/// `time` is the time of the grab, which must have been checked before.
+	pub fn grab_keyboard(&mut self, grab: KeyboardGrab, time: u32) {
+		let from = match self.keyboard.get_grab() {
+			Some(prev) => Focus::Window(prev.window),
+			None => self.keyboard.get_focus(),
+		};
+		let to = Focus::Window(grab.window);
+		self.keyboard.set_grab(Some(grab), time);
+		self.focus_change(from, to, NotifyMode::Grab);
+	}
+
+	/// Releases the grab of the keyboard, if any, reporting focus events as if the focus moved
+	/// from the grab window back to the focus window.
+	pub fn ungrab_keyboard(&mut self) {
+		let Some(grab) = self.keyboard.get_grab() else {
+			return;
+		};
+		let from = Focus::Window(grab.window);
+		self.keyboard.set_grab(None, 0);
+		let to = self.keyboard.get_focus();
+		self.focus_change(from, to, NotifyMode::Ungrab);
+	}
+
+	/// Checks that the keyboard grab window and the focus window are still viewable, after a
+	/// window has been unmapped.
+	///
+	/// If the grab window isn't viewable anymore, the grab is released. If the focus window
+	/// isn't viewable anymore, the focus reverts according to its `revert_to` value.
+	pub fn check_focus_viewable(&mut self) {
+		let viewable =
+			|ctx: &Self, wid: NonZeroU32| ctx.get_window(wid).is_some_and(|w| w.is_viewable());
+
+		if let Some(grab) = self.keyboard.get_grab() {
+			if !viewable(self, grab.window) {
+				self.ungrab_keyboard();
+			}
+		}
+
+		let Focus::Window(focus) = self.keyboard.get_focus() else {
+			return;
+		};
+		if viewable(self, focus) {
+			return;
+		}
+		let (focus, revert_to) = match self.keyboard.get_revert_to() {
+			RevertTo::None => (Focus::None, RevertTo::None),
+			RevertTo::PointerRoot => (Focus::PointerRoot, RevertTo::PointerRoot),
+			// The closest viewable ancestor. The root window is always viewable
+			RevertTo::Parent => {
+				let parent = self
+					.get_ancestors(focus)
+					.into_iter()
+					.skip(1)
+					.find(|w| viewable(self, *w));
+				(
+					parent.map(Focus::Window).unwrap_or(Focus::None),
+					RevertTo::None,
+				)
+			}
+		};
+		self.set_input_focus(focus, revert_to, self.get_time());
+	}
+}
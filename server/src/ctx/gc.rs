@@ -1,6 +1,13 @@
 //! This module implements Graphics Contexts (GC).
 
+use super::pixmap::PixmapRef;
+use super::Context;
+use super::Drawable;
+use crate::font::Font;
 use crate::protocol::error::Error;
+use crate::protocol::Rectangle;
+use std::num::NonZeroU32;
+use std::rc::Rc;
 
 /// TODO doc
 #[derive(Clone)]
@@ -119,15 +126,15 @@ impl TryFrom<u8> for CapStyle {
 	}
 }
 
-/// TODO doc
+/// The way corners are drawn for wide lines.
 #[derive(Clone)]
 pub enum JoinStyle {
-	/// TODO doc
-	Solid,
-	/// TODO doc
-	OnOffDash,
-	/// TODO doc
-	DoubleDash,
+	/// The outer edges of the two lines extend to meet at an angle.
+	Miter,
+	/// A circular arc with diameter equal to the line width, centered on the join point.
+	Round,
+	/// The corner is cut with a straight line.
+	Bevel,
 }
 
 impl TryFrom<u8> for JoinStyle {
@@ -135,9 +142,9 @@ impl TryFrom<u8> for JoinStyle {
 
 	fn try_from(v: u8) -> Result<Self, Self::Error> {
 		match v {
-			0 => Ok(Self::Solid),
-			1 => Ok(Self::OnOffDash),
-			2 => Ok(Self::DoubleDash),
+			0 => Ok(Self::Miter),
+			1 => Ok(Self::Round),
+			2 => Ok(Self::Bevel),
 
 			_ => Err(Error::Value(v as _)),
 		}
@@ -289,11 +296,227 @@ pub enum Value {
 	ArcMode(ArcMode),
 }
 
+/// The clipping of a graphics context.
+#[derive(Clone)]
+pub enum Clip {
+	/// No clipping.
+	None,
+	/// Only pixels for which the bit in the given bitmap is set are drawn.
+	Mask(PixmapRef),
+	/// Only pixels inside of the given rectangles are drawn.
+	Rectangles(Vec<Rectangle>),
+}
+
 /// Structure representing a graphics context.
 #[derive(Clone)]
 pub struct GC {
-	/// The ID of the drawable.
-	pub drawable: u32,
-	/// The list of values.
-	pub values: Vec<Value>,
+	/// The depth of drawables the GC can be used with.
+	pub depth: u8,
+	/// The ID of the root window of drawables the GC can be used with.
+	pub root: u32,
+
+	/// The raster operation applied when drawing.
+	pub function: Function,
+	/// The mask of planes affected by drawing.
+	pub plane_mask: u32,
+	/// The foreground pixel.
+	pub foreground: u32,
+	/// The background pixel.
+	pub background: u32,
+
+	/// The width of lines in pixels. Zero means thin lines.
+	pub line_width: u16,
+	/// The style of lines.
+	pub line_style: LineStyle,
+	/// The style of the ends of lines.
+	pub cap_style: CapStyle,
+	/// The style of corners between lines.
+	pub join_style: JoinStyle,
+
+	/// The style of fills.
+	pub fill_style: FillStyle,
+	/// The rule used to determine whether a point is inside of a polygon.
+	pub fill_rule: FillRule,
+	/// The pixmap used as tile. If None, the tile is filled with the foreground pixel.
+	pub tile: Option<PixmapRef>,
+	/// The pixmap used as stipple. If None, the stipple is filled with ones.
+	pub stipple: Option<PixmapRef>,
+	/// The X origin of the tile or stipple.
+	pub tile_stipple_x_origin: i16,
+	/// The Y origin of the tile or stipple.
+	pub tile_stipple_y_origin: i16,
+
+	/// The font used to draw text. If None, text cannot be drawn.
+	pub font: Option<Rc<Font>>,
+
+	/// Tells whether drawing on a window affects its children.
+	pub subwindow_mode: SubWindowMode,
+	/// Tells whether `GraphicsExposure` events are generated by copies.
+	pub graphics_exposures: bool,
+
+	/// The X origin of the clipping.
+	pub clip_x_origin: i16,
+	/// The Y origin of the clipping.
+	pub clip_y_origin: i16,
+	/// The clipping.
+	pub clip: Clip,
+
+	/// The offset in the dash pattern where dashed lines begin.
+	pub dash_offset: u16,
+	/// The lengths of alternating dashes, in pixels.
+	pub dashes: Vec<u8>,
+
+	/// The way arcs are filled.
+	pub arc_mode: ArcMode,
+}
+
+impl GC {
+	/// Creates a graphics context with default values for drawables with the given depth and
+	/// root.
+	pub fn new(depth: u8, root: u32) -> Self {
+		Self {
+			depth,
+			root,
+
+			function: Function::Copy,
+			plane_mask: u32::MAX,
+			foreground: 0,
+			background: 1,
+
+			line_width: 0,
+			line_style: LineStyle::Solid,
+			cap_style: CapStyle::Butt,
+			join_style: JoinStyle::Miter,
+
+			fill_style: FillStyle::Solid,
+			fill_rule: FillRule::EvenOdd,
+			tile: None,
+			stipple: None,
+			tile_stipple_x_origin: 0,
+			tile_stipple_y_origin: 0,
+
+			font: None,
+
+			subwindow_mode: SubWindowMode::ClipByChildren,
+			graphics_exposures: true,
+
+			clip_x_origin: 0,
+			clip_y_origin: 0,
+			clip: Clip::None,
+
+			dash_offset: 0,
+			dashes: vec![4, 4],
+
+			arc_mode: ArcMode::PieSlice,
+		}
+	}
+
+	/// Tells whether the GC can be used with the given drawable.
+	pub fn is_compatible(&self, drawable: &dyn Drawable) -> bool {
+		self.depth == drawable.get_depth() && self.root == drawable.get_root()
+	}
+
+	/// Resolves the pixmap with the given ID, checking it matches the GC.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `id` is the ID of the pixmap.
+	/// - `depth` is the required depth of the pixmap.
+	fn resolve_pixmap(&self, ctx: &Context, id: u32, depth: u8) -> Result<PixmapRef, Error> {
+		let pixmap = NonZeroU32::new(id)
+			.and_then(|id| ctx.get_pixmap(id))
+			.ok_or(Error::Pixmap(id))?;
+		if pixmap.get_depth() != depth || pixmap.get_root() != self.root {
+			return Err(Error::Match);
+		}
+
+		Ok(pixmap.clone())
+	}
+
+	/// Sets the given values on the GC.
+	///
+	/// Pixmaps and fonts referenced by the values are resolved, keeping a reference to them.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `values` is the list of values to set.
+	///
+	/// If a pixmap doesn't exist, or if its depth or root doesn't match, or if a font doesn't
+	/// exist, the function returns an error and the GC is left unchanged.
+	pub fn set_values(&mut self, ctx: &Context, values: &[Value]) -> Result<(), Error> {
+		let mut gc = self.clone();
+
+		for v in values {
+			match v {
+				Value::Function(f) => gc.function = f.clone(),
+				Value::PlaneMask(m) => gc.plane_mask = *m,
+				Value::Foreground(p) => gc.foreground = *p,
+				Value::Background(p) => gc.background = *p,
+				Value::LineWidth(w) => gc.line_width = *w,
+				Value::LineStyle(s) => gc.line_style = s.clone(),
+				Value::CapStyle(s) => gc.cap_style = s.clone(),
+				Value::JoinStyle(s) => gc.join_style = s.clone(),
+				Value::FillStyle(s) => gc.fill_style = s.clone(),
+				Value::FillRule(r) => gc.fill_rule = r.clone(),
+				Value::Tile(id) => gc.tile = Some(self.resolve_pixmap(ctx, *id, self.depth)?),
+				Value::Stipple(id) => gc.stipple = Some(self.resolve_pixmap(ctx, *id, 1)?),
+				Value::TileStippleXOrigin(x) => gc.tile_stipple_x_origin = *x,
+				Value::TileStippleYOrigin(y) => gc.tile_stipple_y_origin = *y,
+				Value::Font(id) => {
+					let font = NonZeroU32::new(*id)
+						.and_then(|id| ctx.get_font(id))
+						.ok_or(Error::Font(*id))?;
+					gc.font = Some(font.clone());
+				}
+				Value::SubwindowMode(m) => gc.subwindow_mode = m.clone(),
+				Value::GraphicsExposures(e) => gc.graphics_exposures = *e != 0,
+				Value::ClipXOrigin(x) => gc.clip_x_origin = *x,
+				Value::ClipYOrigin(y) => gc.clip_y_origin = *y,
+				Value::ClipMask(0) => gc.clip = Clip::None,
+				Value::ClipMask(id) => gc.clip = Clip::Mask(self.resolve_pixmap(ctx, *id, 1)?),
+				Value::DashOffset(o) => gc.dash_offset = *o,
+				Value::Dashes(0) => return Err(Error::Value(0)),
+				Value::Dashes(d) => gc.dashes = vec![*d],
+				Value::ArcMode(m) => gc.arc_mode = m.clone(),
+			}
+		}
+
+		*self = gc;
+		Ok(())
+	}
+
+	/// Copies the values selected by `mask` from `src` to the GC.
+	///
+	/// The bits of the mask are the same as the ones used by `CreateGC`.
+	pub fn copy_values(&mut self, src: &GC, mask: u32) {
+		for bit in (0..=22).filter(|i| mask & (1 << i) != 0) {
+			match bit {
+				0 => self.function = src.function.clone(),
+				1 => self.plane_mask = src.plane_mask,
+				2 => self.foreground = src.foreground,
+				3 => self.background = src.background,
+				4 => self.line_width = src.line_width,
+				5 => self.line_style = src.line_style.clone(),
+				6 => self.cap_style = src.cap_style.clone(),
+				7 => self.join_style = src.join_style.clone(),
+				8 => self.fill_style = src.fill_style.clone(),
+				9 => self.fill_rule = src.fill_rule.clone(),
+				10 => self.tile = src.tile.clone(),
+				11 => self.stipple = src.stipple.clone(),
+				12 => self.tile_stipple_x_origin = src.tile_stipple_x_origin,
+				13 => self.tile_stipple_y_origin = src.tile_stipple_y_origin,
+				14 => self.font = src.font.clone(),
+				15 => self.subwindow_mode = src.subwindow_mode.clone(),
+				16 => self.graphics_exposures = src.graphics_exposures,
+				17 => self.clip_x_origin = src.clip_x_origin,
+				18 => self.clip_y_origin = src.clip_y_origin,
+				19 => self.clip = src.clip.clone(),
+				20 => self.dash_offset = src.dash_offset,
+				21 => self.dashes = src.dashes.clone(),
+				22 => self.arc_mode = src.arc_mode.clone(),
+
+				_ => unreachable!(),
+			}
+		}
+	}
 }
@@ -0,0 +1,499 @@
+//! An image is a buffer of pixels in memory, used as the backing storage of drawables.
+//!
+//! The layout of an image in memory depends on its depth. Each scanline is padded to the
+//! scanline pad of the associated pixmap format.
+//!
+//! Images exchanged with clients follow the formats advertised in the connection setup, which are
+//! defined in this module.
+
+use crate::protocol::error::Error;
+use crate::protocol::ImageFormat;
+use std::fmt;
+
+/// Byte or bit order: least significant first.
+pub const LSB_FIRST: u8 = 0;
+/// Byte or bit order: most significant first.
+pub const MSB_FIRST: u8 = 1;
+
+/// The byte order of images exchanged with clients. Since the server writes data in its native
+/// byte order, images use it too.
+pub const IMAGE_BYTE_ORDER: u8 = if cfg!(target_endian = "little") {
+	LSB_FIRST
+} else {
+	MSB_FIRST
+};
+/// The order of bits in the units of bitmaps.
+pub const BITMAP_BIT_ORDER: u8 = LSB_FIRST;
+/// The size in bits of the units in which the bits of bitmaps are grouped.
+pub const BITMAP_SCANLINE_UNIT: u8 = 32;
+/// The number of bits each scanline of bitmaps is padded to.
+pub const BITMAP_SCANLINE_PAD: u8 = 32;
+
+/// Structure representing a pixmap format supported by the server.
+pub struct PixmapFormat {
+	/// The depth.
+	pub depth: u8,
+	/// The number of bits used to store a pixel.
+	pub bits_per_pixel: u8,
+	/// The number of bits each scanline is padded to.
+	pub scanline_pad: u8,
+}
+
+/// The list of pixmap formats known by the server.
+///
+/// Only the formats of the depths supported by a screen are advertised to clients.
+pub const PIXMAP_FORMATS: &[PixmapFormat] = &[
+	PixmapFormat {
+		depth: 1,
+		bits_per_pixel: 1,
+		scanline_pad: 32,
+	},
+	PixmapFormat {
+		depth: 4,
+		bits_per_pixel: 8,
+		scanline_pad: 32,
+	},
+	PixmapFormat {
+		depth: 8,
+		bits_per_pixel: 8,
+		scanline_pad: 32,
+	},
+	PixmapFormat {
+		depth: 15,
+		bits_per_pixel: 16,
+		scanline_pad: 32,
+	},
+	PixmapFormat {
+		depth: 16,
+		bits_per_pixel: 16,
+		scanline_pad: 32,
+	},
+	PixmapFormat {
+		depth: 24,
+		bits_per_pixel: 32,
+		scanline_pad: 32,
+	},
+	PixmapFormat {
+		depth: 32,
+		bits_per_pixel: 32,
+		scanline_pad: 32,
+	},
+];
+
+/// Returns the pixmap format for the given depth.
+///
+/// If the depth is not supported, the function returns None.
+pub fn get_format(depth: u8) -> Option<&'static PixmapFormat> {
+	PIXMAP_FORMATS.iter().find(|f| f.depth == depth)
+}
+
+/// Returns the mask of significant bits for a pixel of the given depth.
+pub fn depth_mask(depth: u8) -> u32 {
+	if depth >= 32 {
+		u32::MAX
+	} else {
+		(1 << depth) - 1
+	}
+}
+
+/// Returns the length in bytes of a scanline of `bits` bits, padded to `pad` bits.
+fn scanline_len(bits: usize, pad: u8) -> usize {
+	let pad = pad as usize;
+	bits.div_ceil(pad) * pad / 8
+}
+
+/// Returns the offset of the byte and the index of the bit holding the `i`th bit of a scanline
+/// in the bitmap format.
+fn bitmap_bit(i: usize) -> (usize, u8) {
+	let unit = BITMAP_SCANLINE_UNIT as usize;
+
+	let mut bit = i % unit;
+	if BITMAP_BIT_ORDER == MSB_FIRST {
+		bit = unit - 1 - bit;
+	}
+	let mut byte = bit / 8;
+	if IMAGE_BYTE_ORDER == MSB_FIRST {
+		byte = unit / 8 - 1 - byte;
+	}
+
+	((i / unit) * (unit / 8) + byte, (bit % 8) as u8)
+}
+
+/// Reads the `i`th pixel of a scanline in the `ZPixmap` format.
+fn read_z(line: &[u8], i: usize, bits_per_pixel: u8) -> u32 {
+	match bits_per_pixel {
+		1 => {
+			let (byte, bit) = bitmap_bit(i);
+			((line[byte] >> bit) & 1) as u32
+		}
+		8 => line[i] as u32,
+		16 => {
+			let bytes = [line[i * 2], line[i * 2 + 1]];
+			match IMAGE_BYTE_ORDER {
+				LSB_FIRST => u16::from_le_bytes(bytes) as u32,
+				_ => u16::from_be_bytes(bytes) as u32,
+			}
+		}
+		32 => {
+			let bytes = line[(i * 4)..(i * 4 + 4)].try_into().unwrap();
+			match IMAGE_BYTE_ORDER {
+				LSB_FIRST => u32::from_le_bytes(bytes),
+				_ => u32::from_be_bytes(bytes),
+			}
+		}
+
+		_ => unreachable!(),
+	}
+}
+
+/// Writes the `i`th pixel of a scanline in the `ZPixmap` format.
+fn write_z(line: &mut [u8], i: usize, bits_per_pixel: u8, pixel: u32) {
+	match bits_per_pixel {
+		1 => {
+			let (byte, bit) = bitmap_bit(i);
+			line[byte] = (line[byte] & !(1 << bit)) | (((pixel & 1) as u8) << bit);
+		}
+		8 => line[i] = pixel as u8,
+		16 => {
+			let bytes = match IMAGE_BYTE_ORDER {
+				LSB_FIRST => (pixel as u16).to_le_bytes(),
+				_ => (pixel as u16).to_be_bytes(),
+			};
+			line[(i * 2)..(i * 2 + 2)].copy_from_slice(&bytes);
+		}
+		32 => {
+			let bytes = match IMAGE_BYTE_ORDER {
+				LSB_FIRST => pixel.to_le_bytes(),
+				_ => pixel.to_be_bytes(),
+			};
+			line[(i * 4)..(i * 4 + 4)].copy_from_slice(&bytes);
+		}
+
+		_ => unreachable!(),
+	}
+}
+
+/// Returns the length in bytes of an image exchanged with a client.
+///
+/// Arguments:
+/// - `format` is the format of the image.
+/// - `width` and `height` are the dimensions of the image in pixels.
+/// - `depth` is the depth of the image.
+/// - `left_pad` is the number of bits to skip at the beginning of each scanline. Only relevant for
+///   the `XYBitmap` and `XYPixmap` formats.
+///
+/// If the depth is not supported, the function returns None.
+pub fn transfer_len(
+	format: ImageFormat,
+	width: u16,
+	height: u16,
+	depth: u8,
+	left_pad: u8,
+) -> Option<usize> {
+	let bits = width as usize + left_pad as usize;
+	let height = height as usize;
+
+	match format {
+		ImageFormat::XYBitmap => Some(scanline_len(bits, BITMAP_SCANLINE_PAD) * height),
+		ImageFormat::XYPixmap => {
+			get_format(depth)?;
+			Some(scanline_len(bits, BITMAP_SCANLINE_PAD) * height * depth as usize)
+		}
+		ImageFormat::ZPixmap => {
+			let fmt = get_format(depth)?;
+			let bits = width as usize * fmt.bits_per_pixel as usize;
+			Some(scanline_len(bits, fmt.scanline_pad) * height)
+		}
+	}
+}
+
+/// Decodes an image sent by a client.
+///
+/// Arguments:
+/// - `format`, `width`, `height`, `depth` and `left_pad` are the same as for [`transfer_len`].
+/// - `data` is the image's data. Its length must be at least the one returned by [`transfer_len`].
+/// - `f` is called with `(x, y, pixel)` for each pixel of the image. With the `XYBitmap` format,
+///   pixels are either zero or one.
+pub fn decode<F: FnMut(usize, usize, u32)>(
+	format: ImageFormat,
+	width: u16,
+	height: u16,
+	depth: u8,
+	left_pad: u8,
+	data: &[u8],
+	mut f: F,
+) {
+	let width = width as usize;
+	let height = height as usize;
+
+	match format {
+		ImageFormat::XYBitmap | ImageFormat::XYPixmap => {
+			let planes = match format {
+				ImageFormat::XYBitmap => 1,
+				_ => depth as usize,
+			};
+			let line_len = scanline_len(width + left_pad as usize, BITMAP_SCANLINE_PAD);
+			let plane_len = line_len * height;
+
+			for y in 0..height {
+				for x in 0..width {
+					let (byte, bit) = bitmap_bit(x + left_pad as usize);
+
+					// Planes are sent most significant first
+					let pixel = (0..planes).fold(0, |pixel, p| {
+						let b = data[p * plane_len + y * line_len + byte] >> bit;
+						(pixel << 1) | (b & 1) as u32
+					});
+					f(x, y, pixel);
+				}
+			}
+		}
+
+		ImageFormat::ZPixmap => {
+			let Some(fmt) = get_format(depth) else {
+				return;
+			};
+			let line_len = scanline_len(width * fmt.bits_per_pixel as usize, fmt.scanline_pad);
+
+			for y in 0..height {
+				let line = &data[(y * line_len)..];
+				for x in 0..width {
+					f(x, y, read_z(line, x, fmt.bits_per_pixel));
+				}
+			}
+		}
+	}
+}
+
+/// Structure representing an image.
+#[derive(Clone)]
+pub struct Image {
+	/// The width of the image in pixels.
+	width: u16,
+	/// The height of the image in pixels.
+	height: u16,
+
+	/// The depth of the image.
+	depth: u8,
+	/// The number of bits used to store a pixel.
+	bits_per_pixel: u8,
+	/// The length of a scanline in bytes.
+	stride: usize,
+
+	/// The image's data.
+	data: Vec<u8>,
+}
+
+impl fmt::Debug for Image {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt.debug_struct("Image")
+			.field("width", &self.width)
+			.field("height", &self.height)
+			.field("depth", &self.depth)
+			.finish()
+	}
+}
+
+impl Image {
+	/// Creates a new image, filled with zeros.
+	///
+	/// Arguments:
+	/// - `width` is the width of the image in pixels.
+	/// - `height` is the height of the image in pixels.
+	/// - `depth` is the depth of the image.
+	///
+	/// If the depth is not supported, the function returns a Value error. If the image cannot be
+	/// allocated, the function returns an Alloc error.
+	pub fn new(width: u16, height: u16, depth: u8) -> Result<Self, Error> {
+		let format = get_format(depth).ok_or(Error::Value(depth as _))?;
+
+		let bits = width as usize * format.bits_per_pixel as usize;
+		let stride = scanline_len(bits, format.scanline_pad);
+
+		// Dimensions come from clients, so the allocation may be too large to succeed
+		let len = stride * height as usize;
+		let mut data = Vec::new();
+		data.try_reserve_exact(len).map_err(|_| Error::Alloc)?;
+		data.resize(len, 0);
+
+		Ok(Self {
+			width,
+			height,
+
+			depth,
+			bits_per_pixel: format.bits_per_pixel,
+			stride,
+
+			data,
+		})
+	}
+
+	/// Returns the width of the image in pixels.
+	pub fn get_width(&self) -> u16 {
+		self.width
+	}
+
+	/// Returns the height of the image in pixels.
+	pub fn get_height(&self) -> u16 {
+		self.height
+	}
+
+	/// Returns the depth of the image.
+	pub fn get_depth(&self) -> u8 {
+		self.depth
+	}
+
+	/// Returns the number of bits used to store a pixel.
+	pub fn get_bits_per_pixel(&self) -> u8 {
+		self.bits_per_pixel
+	}
+
+	/// Returns the length of a scanline in bytes.
+	pub fn get_stride(&self) -> usize {
+		self.stride
+	}
+
+	/// Returns an immutable reference to the image's data.
+	pub fn get_data(&self) -> &[u8] {
+		&self.data
+	}
+
+	/// Returns a mutable reference to the image's data.
+	pub fn get_data_mut(&mut self) -> &mut [u8] {
+		&mut self.data
+	}
+
+	/// Tells whether the given position is inside of the image.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+	}
+
+	/// Returns the value of the pixel at the given position.
+	///
+	/// The position must be inside of the image.
+	pub fn get_pixel(&self, x: usize, y: usize) -> u32 {
+		debug_assert!(x < self.width as usize && y < self.height as usize);
+
+		let line = y * self.stride;
+		match self.bits_per_pixel {
+			1 => ((self.data[line + x / 8] >> (x % 8)) & 1) as u32,
+			8 => self.data[line + x] as u32,
+			16 => {
+				let off = line + x * 2;
+				u16::from_ne_bytes([self.data[off], self.data[off + 1]]) as u32
+			}
+			32 => {
+				let off = line + x * 4;
+				u32::from_ne_bytes([
+					self.data[off],
+					self.data[off + 1],
+					self.data[off + 2],
+					self.data[off + 3],
+				])
+			}
+
+			_ => unreachable!(),
+		}
+	}
+
+	/// Sets the value of the pixel at the given position.
+	///
+	/// Bits of `pixel` that are not significant for the image's depth are ignored.
+	///
+	/// The position must be inside of the image.
+	pub fn set_pixel(&mut self, x: usize, y: usize, pixel: u32) {
+		debug_assert!(x < self.width as usize && y < self.height as usize);
+
+		let pixel = pixel & depth_mask(self.depth);
+		let line = y * self.stride;
+		match self.bits_per_pixel {
+			1 => {
+				let byte = &mut self.data[line + x / 8];
+				*byte = (*byte & !(1 << (x % 8))) | ((pixel as u8) << (x % 8));
+			}
+			8 => self.data[line + x] = pixel as u8,
+			16 => {
+				let off = line + x * 2;
+				self.data[off..(off + 2)].copy_from_slice(&(pixel as u16).to_ne_bytes());
+			}
+			32 => {
+				let off = line + x * 4;
+				self.data[off..(off + 4)].copy_from_slice(&pixel.to_ne_bytes());
+			}
+
+			_ => unreachable!(),
+		}
+	}
+
+	/// Fills the whole image with the given pixel value.
+	pub fn fill(&mut self, pixel: u32) {
+		for y in 0..(self.height as usize) {
+			for x in 0..(self.width as usize) {
+				self.set_pixel(x, y, pixel);
+			}
+		}
+	}
+
+	/// Encodes the given area of the image to be sent to a client.
+	///
+	/// Arguments:
+	/// - `format` is the format of the encoded image. It cannot be `XYBitmap`.
+	/// - `x`, `y`, `width` and `height` are the area to encode. It must be inside of the image.
+	/// - `plane_mask` is the mask of planes to encode. With the `XYPixmap` format, only selected
+	///   planes are encoded. With the `ZPixmap` format, other planes are set to zero.
+	pub fn encode(
+		&self,
+		format: ImageFormat,
+		x: usize,
+		y: usize,
+		width: u16,
+		height: u16,
+		plane_mask: u32,
+	) -> Vec<u8> {
+		let width = width as usize;
+		let height = height as usize;
+		if width == 0 || height == 0 {
+			return vec![];
+		}
+
+		match format {
+			ImageFormat::XYBitmap => unreachable!(),
+
+			ImageFormat::XYPixmap => {
+				let line_len = scanline_len(width, BITMAP_SCANLINE_PAD);
+				let planes = (0..self.depth).rev().filter(|p| plane_mask & (1 << p) != 0);
+
+				let mut data = vec![];
+				for p in planes {
+					let mut plane = vec![0; line_len * height];
+					for (j, line) in plane.chunks_exact_mut(line_len).enumerate() {
+						for i in 0..width {
+							if (self.get_pixel(x + i, y + j) >> p) & 1 != 0 {
+								let (byte, bit) = bitmap_bit(i);
+								line[byte] |= 1 << bit;
+							}
+						}
+					}
+					data.extend(plane);
+				}
+				data
+			}
+
+			ImageFormat::ZPixmap => {
+				let bits = width * self.bits_per_pixel as usize;
+				// Cannot fail since the image's depth is supported
+				let fmt = get_format(self.depth).unwrap();
+				let line_len = scanline_len(bits, fmt.scanline_pad);
+
+				let mut data = vec![0; line_len * height];
+				for (j, line) in data.chunks_exact_mut(line_len).enumerate() {
+					for i in 0..width {
+						let pixel = self.get_pixel(x + i, y + j) & plane_mask;
+						write_z(line, i, self.bits_per_pixel, pixel);
+					}
+				}
+				data
+			}
+		}
+	}
+}
@@ -0,0 +1,610 @@
+//! Inputs from devices are turned into events reported to clients.
+//!
+//! Device events (`ButtonPress`, `ButtonRelease`, `MotionNotify`) are reported relative to the
+//! deepest window containing the pointer, called the source window. If no client selected the
+//! event on it, the event propagates to its ancestors, until a window on which the event is
+//! selected is found or until the event is in the `do_not_propagate_mask` of a window.
+//!
+//! Motions are compressed: a single `MotionNotify` is reported for the motions of a batch of
+//! inputs.
+//!
+//! When the pointer moves from a window to another, `LeaveNotify` and `EnterNotify` events are
+//! reported on every window between both in the hierarchy.
+//!
+//! Key events are reported to the focus window. If the pointer is in the focus window or one of
+//! its inferiors, the source window is the window containing the pointer and the event
+//! propagates up to the focus window. Otherwise, the source window is the focus window. While
+//! the keyboard is grabbed, key events are reported to the grabbing client only.
+
+use super::keyboard::Focus;
+use super::keyboard::KEYMAP_SIZE;
+use super::pointer::PointerGrab;
+use super::Context;
+use super::Drawable;
+use crate::input::Input;
+use crate::input::Keycode;
+use crate::input::MouseButton;
+use crate::protocol::event;
+use crate::protocol::event::EnterLeaveNotify;
+use crate::protocol::event::KeyButtonPointer;
+use crate::protocol::event::KeymapNotify;
+use crate::protocol::event::NotifyDetail;
+use crate::protocol::event::NotifyMode;
+use crate::protocol::Event;
+use std::num::NonZeroU32;
+use std::time::Instant;
+
+/// `same_screen_focus` bit of crossing events: the event window is the focus window or an
+/// inferior of it.
+const FOCUS: u8 = 0x01;
+/// `same_screen_focus` bit of crossing events: the event window is on the same screen as the
+/// root window.
+const SAME_SCREEN: u8 = 0x02;
+
+impl Context<'_> {
+	/// Returns the position of the origin of the given window, relative to the origin of its root
+	/// window.
+	pub fn get_window_origin(&self, wid: NonZeroU32) -> (i32, i32) {
+		let (mut x, mut y) = (0, 0);
+		let mut curr = self.get_window(wid);
+		while let Some(win) = curr {
+			let rect = win.get_rectangle();
+			let border_width = win.get_border_width() as i32;
+			x += rect.x as i32 + border_width;
+			y += rect.y as i32 + border_width;
+			curr = win.get_parent().and_then(|p| self.get_window(p));
+		}
+		(x, y)
+	}
+
+	/// Returns the given window followed by its ancestors, up to the root window.
+	pub fn get_ancestors(&self, wid: NonZeroU32) -> Vec<NonZeroU32> {
+		let mut ancestors = vec![];
+		let mut curr = Some(wid);
+		while let Some(win) = curr.and_then(|id| self.get_window(id)) {
+			ancestors.push(win.get_id());
+			curr = win.get_parent();
+		}
+		ancestors
+	}
+
+	/// Returns the child of `ancestor` which is `wid` or an ancestor of it. If `wid` is not an
+	/// inferior of `ancestor`, the function returns None.
+	pub fn get_child_towards(&self, ancestor: NonZeroU32, wid: NonZeroU32) -> Option<NonZeroU32> {
+		let ancestors = self.get_ancestors(wid);
+		let i = ancestors.iter().position(|w| *w == ancestor)?;
+		i.checked_sub(1).map(|i| ancestors[i])
+	}
+
+	/// Returns the state of the modifiers and buttons, as reported in events.
+	pub fn get_state_mask(&self) -> u16 {
+		self.keyboard.get_modifier_state() | self.pointer.get_button_state()
+	}
+
+	/// Returns the deepest viewable window containing the pointer. If the pointer is on no
+	/// screen, the function returns None.
+	pub fn get_pointer_window(&self) -> Option<NonZeroU32> {
+		let root = NonZeroU32::new(self.pointer.get_screen())?;
+		let (x, y) = self.pointer.get_position();
+		Some(self.get_window_at(root, x as _, y as _))
+	}
+
+	/// Returns the position of the pointer relative to the origin of the given window, and
+	/// whether the window is on the same screen as the pointer. If not, the position is zero.
+	fn get_pointer_position_in(&self, wid: NonZeroU32) -> (i16, i16, bool) {
+		let same_screen = self
+			.get_window(wid)
+			.is_some_and(|w| w.get_root() == self.pointer.get_screen());
+		if !same_screen {
+			return (0, 0, false);
+		}
+		let (x, y) = self.pointer.get_position();
+		let (origin_x, origin_y) = self.get_window_origin(wid);
+		((x as i32 - origin_x) as _, (y as i32 - origin_y) as _, true)
+	}
+
+	/// Builds a device event reported relative to the window `event_win`.
+	///
+	/// `source` is the window the event originates from.
+	fn make_device_event(
+		&self,
+		code: u8,
+		detail: u8,
+		event_win: NonZeroU32,
+		source: NonZeroU32,
+	) -> KeyButtonPointer {
+		let (root_x, root_y) = self.pointer.get_position();
+		let (event_x, event_y, same_screen) = self.get_pointer_position_in(event_win);
+		let child = self.get_child_towards(event_win, source);
+		KeyButtonPointer {
+			code,
+			detail,
+			seq_nbr: 0,
+
+			time: self.get_time(),
+			root: self.pointer.get_screen(),
+			event: event_win.get(),
+			child: child.map(NonZeroU32::get).unwrap_or(0),
+			root_x,
+			root_y,
+			event_x,
+			event_y,
+			state: self.get_state_mask(),
+			same_screen: same_screen as _,
+
+			_padding: 0,
+		}
+	}
+
+	/// Returns the window a device event in `mask` originating from `source` is reported
+	/// relative to, after propagation.
+	///
+	/// If `client` is specified, only the selection of this client is taken into account.
+	/// Otherwise, the selections of every client are.
+	///
+	/// If `top` is specified, the event does not propagate beyond this window.
+	///
+	/// If the event is not reported, the function returns None.
+	fn find_event_window(
+		&self,
+		source: NonZeroU32,
+		mask: u32,
+		client: Option<u32>,
+		top: Option<NonZeroU32>,
+	) -> Option<NonZeroU32> {
+		let mut wid = source;
+		loop {
+			let win = self.get_window(wid)?;
+			let selected = match client {
+				Some(client) => win.get_event_mask(client),
+				None => win.attributes.event_mask,
+			};
+			if selected & mask != 0 {
+				return Some(wid);
+			}
+			if win.attributes.do_not_propagate_mask & mask != 0 || Some(wid) == top {
+				return None;
+			}
+			wid = win.get_parent()?;
+		}
+	}
+
+	/// Returns the window and the clients a device event in `mask` originating from `source`
+	/// is reported to, taking the active grab into account.
+	///
+	/// If the event is not reported, the function returns None.
+	fn get_device_event_targets(
+		&self,
+		source: NonZeroU32,
+		mask: u32,
+	) -> Option<(NonZeroU32, Vec<u32>)> {
+		let Some(grab) = self.pointer.get_grab() else {
+			let wid = self.find_event_window(source, mask, None, None)?;
+			let clients = self.get_window(wid)?.get_selecting_clients(mask).collect();
+			return Some((wid, clients));
+		};
+
+		// With owner events, events are reported normally to the grabbing client
+		if grab.owner_events {
+			if let Some(wid) = self.find_event_window(source, mask, Some(grab.client), None) {
+				return Some((wid, vec![grab.client]));
+			}
+		}
+		(grab.event_mask & mask != 0).then(|| (grab.window, vec![grab.client]))
+	}
+
+	/// Reports a `MotionNotify` event if the pointer moved since the last one.
+	pub fn flush_motion(&mut self) {
+		if !self.pointer.is_motion_pending() {
+			return;
+		}
+		self.pointer.set_motion_pending(false);
+		let Some(source) = self.get_pointer_window() else {
+			return;
+		};
+
+		// Button motions are reported only while the corresponding buttons are pressed. The
+		// `ButtonNMotion` bits are the same as the state bits of the buttons
+		let buttons = self.pointer.get_button_state() as u32;
+		let mut mask = Event::PointerMotion as u32 | buttons;
+		if buttons != 0 {
+			mask |= Event::ButtonMotion as u32;
+		}
+		let Some((wid, clients)) = self.get_device_event_targets(source, mask) else {
+			return;
+		};
+
+		let hint_mask = Event::PointerMotionHint as u32;
+		for client in clients {
+			let selected = match self.pointer.get_grab() {
+				Some(grab) if grab.window == wid && !grab.owner_events => grab.event_mask,
+				_ => self
+					.get_window(wid)
+					.map(|w| w.get_event_mask(client))
+					.unwrap_or(0),
+			};
+			// With hints, a single event is reported until the client queries the pointer
+			let detail = if selected & hint_mask != 0 {
+				if self.pointer.has_motion_hint(client) {
+					continue;
+				}
+				self.pointer.add_motion_hint(client);
+				event::MOTION_HINT
+			} else {
+				event::MOTION_NORMAL
+			};
+			let ev = self.make_device_event(event::MOTION_NOTIFY, detail, wid, source);
+			self.send_event(client, &ev);
+		}
+	}
+
+	/// Handles the press or release of a button.
+	fn handle_button(&mut self, button: MouseButton, pressed: bool) {
+		// The event is reported at the position reached by the previous motions
+		self.flush_motion();
+		self.pointer.clear_motion_hints();
+		let Some(source) = self.get_pointer_window() else {
+			return;
+		};
+
+		let (code, mask) = if pressed {
+			(event::BUTTON_PRESS, Event::ButtonPress as u32)
+		} else {
+			(event::BUTTON_RELEASE, Event::ButtonRelease as u32)
+		};
+		let targets = self.get_device_event_targets(source, mask);
+		if let Some((wid, clients)) = &targets {
+			let ev = self.make_device_event(code, button as _, *wid, source);
+			for client in clients {
+				self.send_event(*client, &ev);
+			}
+		}
+
+		// The state reported in events is the one before the event
+		self.pointer.set_button(button, pressed);
+		if pressed {
+			// A press reported to a client grabs the pointer until every button is released
+			if let (None, Some((wid, clients))) = (self.pointer.get_grab(), targets) {
+				let Some(client) = clients.first().copied() else {
+					return;
+				};
+				let event_mask = self
+					.get_window(wid)
+					.map(|w| w.get_event_mask(client))
+					.unwrap_or(0);
+				self.pointer.set_grab(Some(PointerGrab {
+					window: wid,
+					client,
+					owner_events: event_mask & Event::OwnerGrabButton as u32 != 0,
+					event_mask,
+				}));
+			}
+		} else if !self.pointer.has_buttons_pressed() {
+			self.pointer.set_grab(None);
+			// The pointer may have moved to other windows during the grab
+			self.update_pointer_window_with(NotifyMode::Ungrab);
+		}
+	}
+
+	/// Returns the focus window, resolving `PointerRoot` to the root window of the screen the
+	/// pointer is on. If the focus is `None`, the function returns None.
+	pub fn get_focus_window(&self) -> Option<NonZeroU32> {
+		match self.keyboard.get_focus() {
+			Focus::None => None,
+			Focus::PointerRoot => NonZeroU32::new(self.pointer.get_screen()),
+			Focus::Window(wid) => Some(wid),
+		}
+	}
+
+	/// Reports a key event with the given code for the given key.
+	fn key_event(&mut self, code: u8, keycode: Keycode) {
+		let mask = if code == event::KEY_PRESS {
+			Event::KeyPress as u32
+		} else {
+			Event::KeyRelease as u32
+		};
+		let grab = self.keyboard.get_grab().cloned();
+		let focus = self.get_focus_window();
+		let pointer = self.get_pointer_window();
+		let source = match focus {
+			Some(focus) => pointer
+				.filter(|w| self.get_ancestors(*w).contains(&focus))
+				.unwrap_or(focus),
+			None => match (&grab, pointer) {
+				(Some(_), Some(pointer)) => pointer,
+				(Some(grab), None) => grab.window,
+				(None, _) => return,
+			},
+		};
+		// The window the event is reported to without a grab
+		let client = grab.as_ref().map(|g| g.client);
+		let normal =
+			focus.and_then(|focus| self.find_event_window(source, mask, client, Some(focus)));
+
+		let (wid, clients) = match grab {
+			// With owner events, events are reported normally to the grabbing client.
+			// Otherwise, they are reported to the grab window whatever its selection
+			Some(grab) => match normal.filter(|_| grab.owner_events) {
+				Some(wid) => (wid, vec![grab.client]),
+				None => (grab.window, vec![grab.client]),
+			},
+			None => {
+				let Some(wid) = normal else {
+					return;
+				};
+				// Cannot fail since the window has been found before
+				let win = self.get_window(wid).unwrap();
+				(wid, win.get_selecting_clients(mask).collect())
+			}
+		};
+		let ev = self.make_device_event(code, keycode, wid, source);
+		for client in clients {
+			self.send_event(client, &ev);
+		}
+	}
+
+	/// Handles the press or release of a key.
+	fn handle_key(&mut self, keycode: Keycode, pressed: bool) {
+		// The event is reported at the position reached by the previous motions
+		self.flush_motion();
+		let code = if pressed {
+			event::KEY_PRESS
+		} else {
+			event::KEY_RELEASE
+		};
+		self.key_event(code, keycode);
+		// The state reported in events is the one before the event
+		self.keyboard.set_key(keycode, pressed, Instant::now());
+	}
+
+	/// Repeats the held key if its repetition is due, reporting a `KeyRelease` and a `KeyPress`
+	/// event for it.
+	pub fn repeat_keys(&mut self) {
+		while let Some(keycode) = self.keyboard.next_repeat(Instant::now()) {
+			self.key_event(event::KEY_RELEASE, keycode);
+			self.key_event(event::KEY_PRESS, keycode);
+		}
+	}
+
+	/// Returns a `KeymapNotify` event reporting the current state of the keyboard.
+	pub fn make_keymap_notify(&self) -> KeymapNotify {
+		let mut keys = [0; KEYMAP_SIZE - 1];
+		keys.copy_from_slice(&self.keyboard.get_keys()[1..]);
+		KeymapNotify {
+			code: event::KEYMAP_NOTIFY,
+			keys,
+		}
+	}
+
+	/// Reports a crossing event on the given window.
+	///
+	/// Arguments:
+	/// - `code` is the code of the event.
+	/// - `wid` is the event window.
+	/// - `detail` is the relation between the event window and the other window.
+	/// - `child` is the child of the event window containing the pointer, if any.
+	/// - `mode` is the cause of the event.
+	fn crossing_event(
+		&mut self,
+		code: u8,
+		wid: NonZeroU32,
+		detail: NotifyDetail,
+		child: Option<NonZeroU32>,
+		mode: NotifyMode,
+	) {
+		let mask = if code == event::ENTER_NOTIFY {
+			Event::EnterWindow as u32
+		} else {
+			Event::LeaveWindow as u32
+		};
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		// During a grab, only the grabbing client is notified
+		let clients: Vec<u32> = match self.pointer.get_grab() {
+			Some(grab) => {
+				let selected = grab.owner_events && win.get_event_mask(grab.client) & mask != 0
+					|| grab.window == wid && grab.event_mask & mask != 0;
+				selected.then_some(grab.client).into_iter().collect()
+			}
+			None => win.get_selecting_clients(mask).collect(),
+		};
+		if clients.is_empty() {
+			return;
+		}
+
+		let (root_x, root_y) = self.pointer.get_position();
+		let (event_x, event_y, same_screen) = self.get_pointer_position_in(wid);
+		let mut same_screen_focus = 0;
+		if same_screen {
+			same_screen_focus |= SAME_SCREEN;
+		}
+		if self.is_focus_or_inferior(wid) {
+			same_screen_focus |= FOCUS;
+		}
+		let ev = EnterLeaveNotify {
+			code,
+			detail: detail as _,
+			seq_nbr: 0,
+
+			time: self.get_time(),
+			root: self.pointer.get_screen(),
+			event: wid.get(),
+			child: child.map(NonZeroU32::get).unwrap_or(0),
+			root_x,
+			root_y,
+			event_x,
+			event_y,
+			state: self.get_state_mask(),
+			mode: mode as _,
+			same_screen_focus,
+		};
+		let keymap = self.make_keymap_notify();
+		for client in clients {
+			self.send_event(client, &ev);
+			// Clients selecting it get the state of the keyboard on entering a window
+			let keymap_state = self
+				.get_window(wid)
+				.is_some_and(|w| w.get_event_mask(client) & Event::KeymapState as u32 != 0);
+			if code == event::ENTER_NOTIFY && keymap_state {
+				self.send_event(client, &keymap);
+			}
+		}
+	}
+
+	/// Reports the crossing events for a motion of the pointer from the window `from` to the
+	/// window `to`.
+	///
+	/// If `from` is None, the pointer enters the screen of `to`.
+	pub fn crossing(&mut self, from: Option<NonZeroU32>, to: NonZeroU32, mode: NotifyMode) {
+		if from == Some(to) {
+			return;
+		}
+		let from_anc = from.map(|w| self.get_ancestors(w)).unwrap_or_default();
+		let to_anc = self.get_ancestors(to);
+		// The child of the given window in a list of ancestors
+		let child = |anc: &[NonZeroU32], i: usize| i.checked_sub(1).map(|i| anc[i]);
+
+		if let Some(i) = to_anc.iter().position(|w| Some(*w) == from) {
+			// `to` is an inferior of `from`
+			self.crossing_event(
+				event::LEAVE_NOTIFY,
+				from_anc[0],
+				NotifyDetail::Inferior,
+				child(&to_anc, i),
+				mode,
+			);
+			for j in (1..i).rev() {
+				self.crossing_event(
+					event::ENTER_NOTIFY,
+					to_anc[j],
+					NotifyDetail::Virtual,
+					child(&to_anc, j),
+					mode,
+				);
+			}
+			self.crossing_event(event::ENTER_NOTIFY, to, NotifyDetail::Ancestor, None, mode);
+		} else if let Some(i) = from_anc.iter().position(|w| *w == to) {
+			// `from` is an inferior of `to`
+			self.crossing_event(
+				event::LEAVE_NOTIFY,
+				from_anc[0],
+				NotifyDetail::Ancestor,
+				None,
+				mode,
+			);
+			for j in 1..i {
+				self.crossing_event(
+					event::LEAVE_NOTIFY,
+					from_anc[j],
+					NotifyDetail::Virtual,
+					child(&from_anc, j),
+					mode,
+				);
+			}
+			self.crossing_event(
+				event::ENTER_NOTIFY,
+				to,
+				NotifyDetail::Inferior,
+				child(&from_anc, i),
+				mode,
+			);
+		} else {
+			// On different screens, there is no common ancestor
+			let common = from_anc.iter().position(|w| to_anc.contains(w));
+			let from_end = common.unwrap_or(from_anc.len());
+			let to_end = common
+				.and_then(|i| to_anc.iter().position(|w| *w == from_anc[i]))
+				.unwrap_or(to_anc.len());
+
+			if let Some(&from) = from_anc.first() {
+				self.crossing_event(
+					event::LEAVE_NOTIFY,
+					from,
+					NotifyDetail::Nonlinear,
+					None,
+					mode,
+				);
+			}
+			for j in 1..from_end {
+				self.crossing_event(
+					event::LEAVE_NOTIFY,
+					from_anc[j],
+					NotifyDetail::NonlinearVirtual,
+					child(&from_anc, j),
+					mode,
+				);
+			}
+			for j in (1..to_end).rev() {
+				self.crossing_event(
+					event::ENTER_NOTIFY,
+					to_anc[j],
+					NotifyDetail::NonlinearVirtual,
+					child(&to_anc, j),
+					mode,
+				);
+			}
+			self.crossing_event(event::ENTER_NOTIFY, to, NotifyDetail::Nonlinear, None, mode);
+		}
+	}
+
+	/// Updates the window containing the pointer, reporting crossing events if it changed.
+	///
+	/// This function must be called when the pointer moves or when the window under it may have
+	/// changed.
+	pub fn update_pointer_window(&mut self) {
+		self.update_pointer_window_with(NotifyMode::Normal);
+	}
+
+	/// Same as [`Self::update_pointer_window`], but crossing events have the given mode.
+	///
+	/// If a grab ends because its window is not viewable anymore, the mode is `Ungrab`.
+	fn update_pointer_window_with(&mut self, mut mode: NotifyMode) {
+		// A grab ends when its window is not viewable anymore
+		let grab_lost = self
+			.pointer
+			.get_grab()
+			.is_some_and(|g| !self.get_window(g.window).is_some_and(|w| w.is_viewable()));
+		if grab_lost {
+			self.pointer.set_grab(None);
+			mode = NotifyMode::Ungrab;
+		}
+
+		let Some(wid) = self.get_pointer_window() else {
+			return;
+		};
+		let prev = self.pointer.get_window();
+		if prev == Some(wid) {
+			return;
+		}
+		self.pointer.set_window(Some(wid));
+		self.pointer.clear_motion_hints();
+		// The previous window may have been destroyed
+		let prev = prev.filter(|w| self.get_window(*w).is_some());
+		self.crossing(prev, wid, mode);
+	}
+
+	/// Handles the given input from a device.
+	pub fn handle_input(&mut self, input: Input) {
+		match input {
+			Input::RelativeMove {
+				delta_x,
+				delta_y,
+			} => {
+				self.move_pointer_relative(delta_x, delta_y);
+				self.pointer.set_motion_pending(true);
+			}
+			Input::AbsoluteMove {
+				x,
+				y,
+			} => {
+				self.move_pointer_absolute(x, y);
+				self.pointer.set_motion_pending(true);
+			}
+			Input::ButtonPress(button) => self.handle_button(button, true),
+			Input::ButtonRelease(button) => self.handle_button(button, false),
+			Input::KeyPress(keycode) => self.handle_key(keycode, true),
+			Input::KeyRelease(keycode) => self.handle_key(keycode, false),
+		}
+	}
+}
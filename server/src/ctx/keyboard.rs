@@ -0,0 +1,396 @@
+//! The keyboard holds the state of the keys and the modifiers, and the way key presses are
+//! reported.
+//!
+//! Keys are reported to the focus window, or to the grabbing client while the keyboard is
+//! grabbed. Held keys are repeated by the server, the kernel's autorepeat being ignored, so that
+//! repetition can be enabled per key.
+//!
+//! The state of modifiers is derived from the keys that are down, according to the modifier
+//! mapping. Keys mapped to `Lock` have lock semantics: the modifier is toggled on each press.
+
+use super::keymap::Keymap;
+use crate::input::Keycode;
+use crate::protocol::error::Error;
+use crate::protocol::KeyMask;
+use std::num::NonZeroU32;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The size of a key state vector in bytes. Bit `n` represents the key with keycode `n`.
+pub const KEYMAP_SIZE: usize = 32;
+
+/// The default percentage of the volume of key clicks.
+pub const DEFAULT_KEY_CLICK_PERCENT: u8 = 0;
+/// The default percentage of the volume of the bell.
+pub const DEFAULT_BELL_PERCENT: u8 = 50;
+/// The default pitch of the bell, in Hz.
+pub const DEFAULT_BELL_PITCH: u16 = 400;
+/// The default duration of the bell, in milliseconds.
+pub const DEFAULT_BELL_DURATION: u16 = 100;
+
+/// The delay before a held key starts repeating.
+pub const REPEAT_DELAY: Duration = Duration::from_millis(660);
+/// The interval between two repetitions of a held key.
+pub const REPEAT_INTERVAL: Duration = Duration::from_millis(40);
+
+/// The modifiers, in the order of the modifier mapping.
+pub const MODIFIERS: [KeyMask; 8] = [
+	KeyMask::Shift,
+	KeyMask::Lock,
+	KeyMask::Control,
+	KeyMask::Mod1,
+	KeyMask::Mod2,
+	KeyMask::Mod3,
+	KeyMask::Mod4,
+	KeyMask::Mod5,
+];
+
+/// The default number of keycodes per modifier in the modifier mapping.
+const DEFAULT_KEYCODES_PER_MODIFIER: usize = 2;
+/// The default modifier mapping, for a standard PC keyboard. Zero is an unused entry.
+const DEFAULT_MODIFIER_MAP: [Keycode; 8 * DEFAULT_KEYCODES_PER_MODIFIER] = [
+	50, 62, // Shift: Left and Right Shift
+	66, 0, // Lock: Caps Lock
+	37, 105, // Control: Left and Right Control
+	64, 108, // Mod1: Left and Right Alt
+	77, 0, // Mod2: Num Lock
+	0, 0, // Mod3
+	133, 134, // Mod4: Left and Right Super
+	0, 0, // Mod5
+];
+
+/// The window receiving the keyboard input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Focus {
+	/// Keyboard input is discarded.
+	None,
+	/// The focus is the root window of the screen the pointer is on.
+	PointerRoot,
+	/// The focus is the given window.
+	Window(NonZeroU32),
+}
+
+/// What the focus becomes when the focus window becomes unviewable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RevertTo {
+	/// The focus reverts to `None`.
+	None = 0,
+	/// The focus reverts to `PointerRoot`.
+	PointerRoot = 1,
+	/// The focus reverts to the closest viewable ancestor of the focus window.
+	Parent = 2,
+}
+
+impl TryFrom<u8> for RevertTo {
+	type Error = Error;
+
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::None),
+			1 => Ok(Self::PointerRoot),
+			2 => Ok(Self::Parent),
+			_ => Err(Error::Value(value as _)),
+		}
+	}
+}
+
+/// A grab of the keyboard by a client.
+#[derive(Clone, Debug)]
+pub struct KeyboardGrab {
+	/// The window events are reported relative to.
+	pub window: NonZeroU32,
+	/// The ID of the grabbing client.
+	pub client: u32,
+	/// If true, events that would normally be reported to the client are reported normally.
+	/// Other events are reported relative to the grab window.
+	pub owner_events: bool,
+}
+
+/// The settings of the keyboard, changed by `ChangeKeyboardControl`.
+#[derive(Debug)]
+pub struct KeyboardControl {
+	/// The volume of key clicks, in percent.
+	pub key_click_percent: u8,
+	/// The volume of the bell, in percent.
+	pub bell_percent: u8,
+	/// The pitch of the bell, in Hz.
+	pub bell_pitch: u16,
+	/// The duration of the bell, in milliseconds.
+	pub bell_duration: u16,
+	/// The LEDs that are lit. Bit `n` represents the LED `n + 1`.
+	pub led_mask: u32,
+	/// Tells whether autorepeat is enabled.
+	pub global_auto_repeat: bool,
+	/// The keys for which autorepeat is enabled. Bit `n` represents the key with keycode `n`.
+	pub auto_repeats: [u8; KEYMAP_SIZE],
+}
+
+impl Default for KeyboardControl {
+	fn default() -> Self {
+		Self {
+			key_click_percent: DEFAULT_KEY_CLICK_PERCENT,
+			bell_percent: DEFAULT_BELL_PERCENT,
+			bell_pitch: DEFAULT_BELL_PITCH,
+			bell_duration: DEFAULT_BELL_DURATION,
+			led_mask: 0,
+			global_auto_repeat: true,
+			auto_repeats: [0xff; KEYMAP_SIZE],
+		}
+	}
+}
+
+/// A repetition of a held key.
+#[derive(Clone, Copy, Debug)]
+struct Repeat {
+	/// The repeated key.
+	keycode: Keycode,
+	/// The instant of the next repetition.
+	next: Instant,
+}
+
+/// The keyboard.
+pub struct Keyboard {
+	/// The keys that are down. Bit `n` represents the key with keycode `n`.
+	keys: [u8; KEYMAP_SIZE],
+	/// The mapping from keycodes to keysyms.
+	keymap: Keymap,
+	/// The modifier mapping. For each modifier, in the order of [`MODIFIERS`], the list of
+	/// keycodes mapped to it. Zero entries are unused.
+	modifier_map: Vec<Keycode>,
+	/// The number of keycodes per modifier in the modifier mapping.
+	keycodes_per_modifier: usize,
+	/// The modifiers locked by keys with lock semantics.
+	locked: u16,
+
+	/// The settings of the keyboard.
+	control: KeyboardControl,
+	/// The repetition of the key being held, if any.
+	repeat: Option<Repeat>,
+
+	/// The window receiving the keyboard input.
+	focus: Focus,
+	/// What the focus becomes when the focus window becomes unviewable.
+	revert_to: RevertTo,
+	/// The last time the focus changed.
+	focus_time: u32,
+
+	/// The active grab, if any.
+	grab: Option<KeyboardGrab>,
+	/// The last time the keyboard has been grabbed.
+	grab_time: u32,
+}
+
+impl Default for Keyboard {
+	fn default() -> Self {
+		Self {
+			keys: [0; KEYMAP_SIZE],
+			keymap: Keymap::default(),
+			modifier_map: DEFAULT_MODIFIER_MAP.to_vec(),
+			keycodes_per_modifier: DEFAULT_KEYCODES_PER_MODIFIER,
+			locked: 0,
+
+			control: KeyboardControl::default(),
+			repeat: None,
+
+			focus: Focus::PointerRoot,
+			revert_to: RevertTo::None,
+			focus_time: 0,
+
+			grab: None,
+			grab_time: 0,
+		}
+	}
+}
+
+impl Keyboard {
+	/// Returns the key state vector.
+	pub fn get_keys(&self) -> &[u8; KEYMAP_SIZE] {
+		&self.keys
+	}
+
+	/// Tells whether the key with the given keycode is down.
+	pub fn is_key_down(&self, keycode: Keycode) -> bool {
+		self.keys[keycode as usize / 8] & (1 << (keycode % 8)) != 0
+	}
+
+	/// Returns the mapping from keycodes to keysyms.
+	pub fn get_keymap(&self) -> &Keymap {
+		&self.keymap
+	}
+
+	/// Returns a mutable reference to the mapping from keycodes to keysyms.
+	pub fn get_keymap_mut(&mut self) -> &mut Keymap {
+		&mut self.keymap
+	}
+
+	/// Returns the modifier mapping and the number of keycodes per modifier.
+	pub fn get_modifier_mapping(&self) -> (&[Keycode], usize) {
+		(&self.modifier_map, self.keycodes_per_modifier)
+	}
+
+	/// Sets the modifier mapping, `map` containing `keycodes_per_modifier` entries for each
+	/// modifier.
+	///
+	/// If a key of a modifier whose mapping changes is down, the mapping is not changed and the
+	/// function returns `false`.
+	pub fn set_modifier_mapping(
+		&mut self,
+		map: Vec<Keycode>,
+		keycodes_per_modifier: usize,
+	) -> bool {
+		let keys_of = |map: &[Keycode], per: usize, i: usize| -> Vec<Keycode> {
+			let mut keys: Vec<_> = map
+				.iter()
+				.skip(i * per)
+				.take(per)
+				.copied()
+				.filter(|k| *k != 0)
+				.collect();
+			keys.sort_unstable();
+			keys
+		};
+		let busy = (0..MODIFIERS.len()).any(|i| {
+			let old = keys_of(&self.modifier_map, self.keycodes_per_modifier, i);
+			let new = keys_of(&map, keycodes_per_modifier, i);
+			old != new && old.iter().chain(&new).any(|k| self.is_key_down(*k))
+		});
+		if busy {
+			return false;
+		}
+		self.modifier_map = map;
+		self.keycodes_per_modifier = keycodes_per_modifier;
+		true
+	}
+
+	/// Returns the state mask of the modifiers the given key is mapped to, made of `KeyMask`
+	/// bits.
+	fn get_key_modifiers(&self, keycode: Keycode) -> u16 {
+		if keycode == 0 || self.keycodes_per_modifier == 0 {
+			return 0;
+		}
+		self.modifier_map
+			.iter()
+			.enumerate()
+			.filter(|(_, k)| **k == keycode)
+			.map(|(i, _)| MODIFIERS[i / self.keycodes_per_modifier] as u16)
+			.fold(0, |a, b| a | b)
+	}
+
+	/// Returns the state mask of the modifiers currently active, made of `KeyMask` bits.
+	pub fn get_modifier_state(&self) -> u16 {
+		let held = self
+			.modifier_map
+			.iter()
+			.filter(|k| **k != 0 && self.is_key_down(**k))
+			.map(|k| self.get_key_modifiers(*k))
+			.fold(0, |a, b| a | b);
+		// The state of `Lock` is the locked one
+		held & !(KeyMask::Lock as u16) | self.locked
+	}
+
+	/// Sets whether the given key is down, updating the modifiers and the repetition.
+	///
+	/// `now` is the instant of the input.
+	pub fn set_key(&mut self, keycode: Keycode, down: bool, now: Instant) {
+		let bit = 1 << (keycode % 8);
+		let modifiers = self.get_key_modifiers(keycode);
+		if down {
+			self.keys[keycode as usize / 8] |= bit;
+			self.locked ^= modifiers & KeyMask::Lock as u16;
+			// Modifiers are not repeated
+			if modifiers == 0 && self.is_repeat_enabled(keycode) {
+				self.repeat = Some(Repeat {
+					keycode,
+					next: now + REPEAT_DELAY,
+				});
+			}
+		} else {
+			self.keys[keycode as usize / 8] &= !bit;
+			if self.repeat.is_some_and(|r| r.keycode == keycode) {
+				self.repeat = None;
+			}
+		}
+	}
+
+	/// Tells whether autorepeat is enabled for the given key.
+	pub fn is_repeat_enabled(&self, keycode: Keycode) -> bool {
+		self.control.global_auto_repeat
+			&& self.control.auto_repeats[keycode as usize / 8] & (1 << (keycode % 8)) != 0
+	}
+
+	/// Returns the instant of the next repetition of a key, if any.
+	pub fn get_repeat_deadline(&self) -> Option<Instant> {
+		self.repeat.map(|r| r.next)
+	}
+
+	/// If a key has to be repeated at `now`, the function returns its keycode and schedules the
+	/// next repetition.
+	pub fn next_repeat(&mut self, now: Instant) -> Option<Keycode> {
+		let repeat = self.repeat.as_mut().filter(|r| r.next <= now)?;
+		// If the server is late, repetitions are not accumulated
+		repeat.next = (repeat.next + REPEAT_INTERVAL).max(now);
+		Some(repeat.keycode)
+	}
+
+	/// Returns the settings of the keyboard.
+	pub fn get_control(&self) -> &KeyboardControl {
+		&self.control
+	}
+
+	/// Returns a mutable reference to the settings of the keyboard.
+	///
+	/// After changing the autorepeat settings, [`Self::update_repeat`] must be called.
+	pub fn get_control_mut(&mut self) -> &mut KeyboardControl {
+		&mut self.control
+	}
+
+	/// Stops the repetition of the held key if autorepeat has been disabled for it.
+	pub fn update_repeat(&mut self) {
+		if let Some(repeat) = self.repeat {
+			if !self.is_repeat_enabled(repeat.keycode) {
+				self.repeat = None;
+			}
+		}
+	}
+
+	/// Returns the window receiving the keyboard input.
+	pub fn get_focus(&self) -> Focus {
+		self.focus
+	}
+
+	/// Returns what the focus becomes when the focus window becomes unviewable.
+	pub fn get_revert_to(&self) -> RevertTo {
+		self.revert_to
+	}
+
+	/// Returns the last time the focus changed.
+	pub fn get_focus_time(&self) -> u32 {
+		self.focus_time
+	}
+
+	/// Sets the focus, changed at the given time.
+	pub fn set_focus(&mut self, focus: Focus, revert_to: RevertTo, time: u32) {
+		self.focus = focus;
+		self.revert_to = revert_to;
+		self.focus_time = time;
+	}
+
+	/// Returns the active grab, if any.
+	pub fn get_grab(&self) -> Option<&KeyboardGrab> {
+		self.grab.as_ref()
+	}
+
+	/// Returns the last time the keyboard has been grabbed.
+	pub fn get_grab_time(&self) -> u32 {
+		self.grab_time
+	}
+
+	/// Sets the active grab. If a grab is given, `time` is the time of the grab.
+	pub fn set_grab(&mut self, grab: Option<KeyboardGrab>, time: u32) {
+		if grab.is_some() {
+			self.grab_time = time;
+		}
+		self.grab = grab;
+	}
+}
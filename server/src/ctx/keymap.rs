@@ -0,0 +1,269 @@
+//! The keymap maps each keycode to a list of keysyms, which clients use to interpret keys.
+//!
+//! A keymap file contains one mapping per line, in the format `keycode <keycode> = <keysyms>`,
+//! where keysyms are separated by spaces and given either by name or by value (`0x` followed by
+//! hexadecimal digits). `NoSymbol` is an empty entry. Lines starting with `!` are comments.
+//!
+//! A built-in US layout is used when no keymap file is specified.
+
+use crate::input::Keycode;
+use crate::input::MAX_KEYCODE;
+use crate::input::MIN_KEYCODE;
+use std::fs;
+
+/// A keysym, the symbol engraved on a key.
+pub type Keysym = u32;
+
+/// The keysym of an empty entry.
+pub const NO_SYMBOL: Keysym = 0;
+
+/// The number of keycodes in a keymap.
+const KEYCODES_COUNT: usize = (MAX_KEYCODE - MIN_KEYCODE) as usize + 1;
+
+/// The built-in keymap.
+const BUILTIN: &str = include_str!("keymap.txt");
+
+/// The names of keysyms other than single letters and digits.
+const NAMES: &[(&str, Keysym)] = &[
+	("NoSymbol", NO_SYMBOL),
+	("space", 0x20),
+	("exclam", 0x21),
+	("quotedbl", 0x22),
+	("numbersign", 0x23),
+	("dollar", 0x24),
+	("percent", 0x25),
+	("ampersand", 0x26),
+	("apostrophe", 0x27),
+	("parenleft", 0x28),
+	("parenright", 0x29),
+	("asterisk", 0x2a),
+	("plus", 0x2b),
+	("comma", 0x2c),
+	("minus", 0x2d),
+	("period", 0x2e),
+	("slash", 0x2f),
+	("colon", 0x3a),
+	("semicolon", 0x3b),
+	("less", 0x3c),
+	("equal", 0x3d),
+	("greater", 0x3e),
+	("question", 0x3f),
+	("at", 0x40),
+	("bracketleft", 0x5b),
+	("backslash", 0x5c),
+	("bracketright", 0x5d),
+	("asciicircum", 0x5e),
+	("underscore", 0x5f),
+	("grave", 0x60),
+	("braceleft", 0x7b),
+	("bar", 0x7c),
+	("braceright", 0x7d),
+	("asciitilde", 0x7e),
+	("ISO_Left_Tab", 0xfe20),
+	("BackSpace", 0xff08),
+	("Tab", 0xff09),
+	("Return", 0xff0d),
+	("Pause", 0xff13),
+	("Scroll_Lock", 0xff14),
+	("Escape", 0xff1b),
+	("Home", 0xff50),
+	("Left", 0xff51),
+	("Up", 0xff52),
+	("Right", 0xff53),
+	("Down", 0xff54),
+	("Prior", 0xff55),
+	("Next", 0xff56),
+	("End", 0xff57),
+	("Begin", 0xff58),
+	("Print", 0xff61),
+	("Insert", 0xff63),
+	("Menu", 0xff67),
+	("Num_Lock", 0xff7f),
+	("KP_Enter", 0xff8d),
+	("KP_Home", 0xff95),
+	("KP_Left", 0xff96),
+	("KP_Up", 0xff97),
+	("KP_Right", 0xff98),
+	("KP_Down", 0xff99),
+	("KP_Prior", 0xff9a),
+	("KP_Next", 0xff9b),
+	("KP_End", 0xff9c),
+	("KP_Begin", 0xff9d),
+	("KP_Insert", 0xff9e),
+	("KP_Delete", 0xff9f),
+	("KP_Multiply", 0xffaa),
+	("KP_Add", 0xffab),
+	("KP_Subtract", 0xffad),
+	("KP_Decimal", 0xffae),
+	("KP_Divide", 0xffaf),
+	("KP_0", 0xffb0),
+	("KP_1", 0xffb1),
+	("KP_2", 0xffb2),
+	("KP_3", 0xffb3),
+	("KP_4", 0xffb4),
+	("KP_5", 0xffb5),
+	("KP_6", 0xffb6),
+	("KP_7", 0xffb7),
+	("KP_8", 0xffb8),
+	("KP_9", 0xffb9),
+	("F1", 0xffbe),
+	("F2", 0xffbf),
+	("F3", 0xffc0),
+	("F4", 0xffc1),
+	("F5", 0xffc2),
+	("F6", 0xffc3),
+	("F7", 0xffc4),
+	("F8", 0xffc5),
+	("F9", 0xffc6),
+	("F10", 0xffc7),
+	("F11", 0xffc8),
+	("F12", 0xffc9),
+	("Shift_L", 0xffe1),
+	("Shift_R", 0xffe2),
+	("Control_L", 0xffe3),
+	("Control_R", 0xffe4),
+	("Caps_Lock", 0xffe5),
+	("Meta_L", 0xffe7),
+	("Meta_R", 0xffe8),
+	("Alt_L", 0xffe9),
+	("Alt_R", 0xffea),
+	("Super_L", 0xffeb),
+	("Super_R", 0xffec),
+	("Delete", 0xffff),
+];
+
+/// Returns the keysym with the given name or value. If invalid, the function returns None.
+fn parse_keysym(name: &str) -> Option<Keysym> {
+	if let Some(hex) = name.strip_prefix("0x") {
+		return Keysym::from_str_radix(hex, 16).ok();
+	}
+	// Letters and digits are named after their Latin-1 character
+	let mut chars = name.chars();
+	if let (Some(c), None) = (chars.next(), chars.next()) {
+		if c.is_ascii_alphanumeric() {
+			return Some(c as _);
+		}
+	}
+	NAMES.iter().find(|(n, _)| *n == name).map(|(_, k)| *k)
+}
+
+/// A mapping from keycodes to keysyms.
+#[derive(Debug)]
+pub struct Keymap {
+	/// The number of keysyms of each keycode.
+	keysyms_per_keycode: usize,
+	/// The keysyms of each keycode, starting from [`MIN_KEYCODE`], `keysyms_per_keycode`
+	/// entries each.
+	keysyms: Vec<Keysym>,
+}
+
+impl Default for Keymap {
+	fn default() -> Self {
+		// Cannot fail since the built-in keymap is valid
+		Self::parse(BUILTIN).unwrap()
+	}
+}
+
+impl Keymap {
+	/// Parses the given keymap.
+	///
+	/// On error, the function returns a message describing the error.
+	pub fn parse(content: &str) -> Result<Self, String> {
+		let mut entries = vec![];
+		for (i, line) in content.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('!') {
+				continue;
+			}
+			let invalid = || format!("line {}: invalid mapping `{}`", i + 1, line);
+
+			let (lhs, rhs) = line.split_once('=').ok_or_else(invalid)?;
+			let mut lhs = lhs.split_whitespace();
+			let (Some("keycode"), Some(keycode), None) = (lhs.next(), lhs.next(), lhs.next())
+			else {
+				return Err(invalid());
+			};
+			let keycode = keycode
+				.parse::<Keycode>()
+				.ok()
+				.filter(|k| *k >= MIN_KEYCODE)
+				.ok_or_else(invalid)?;
+			let keysyms = rhs
+				.split_whitespace()
+				.map(|name| {
+					parse_keysym(name)
+						.ok_or_else(|| format!("line {}: unknown keysym `{}`", i + 1, name))
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			entries.push((keycode, keysyms));
+		}
+
+		let keysyms_per_keycode = entries
+			.iter()
+			.map(|(_, keysyms)| keysyms.len())
+			.max()
+			.unwrap_or(0)
+			.max(1);
+		let mut keymap = Self {
+			keysyms_per_keycode,
+			keysyms: vec![NO_SYMBOL; KEYCODES_COUNT * keysyms_per_keycode],
+		};
+		for (keycode, keysyms) in entries {
+			let off = (keycode - MIN_KEYCODE) as usize * keysyms_per_keycode;
+			keymap.keysyms[off..(off + keysyms.len())].copy_from_slice(&keysyms);
+		}
+		Ok(keymap)
+	}
+
+	/// Loads the keymap from the file at the given path.
+	///
+	/// On error, the function returns a message describing the error.
+	pub fn load(path: &str) -> Result<Self, String> {
+		let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+		Self::parse(&content).map_err(|e| format!("{}: {}", path, e))
+	}
+
+	/// Returns the number of keysyms of each keycode.
+	pub fn get_keysyms_per_keycode(&self) -> usize {
+		self.keysyms_per_keycode
+	}
+
+	/// Returns the keysyms of the `count` keycodes starting from `first`.
+	///
+	/// The range of keycodes must be valid.
+	pub fn get_keysyms(&self, first: Keycode, count: usize) -> &[Keysym] {
+		let off = (first - MIN_KEYCODE) as usize * self.keysyms_per_keycode;
+		&self.keysyms[off..(off + count * self.keysyms_per_keycode)]
+	}
+
+	/// Sets the keysyms of the keycodes starting from `first`, `keysyms` containing
+	/// `keysyms_per_keycode` entries for each keycode.
+	///
+	/// If `keysyms_per_keycode` is greater than the current number of keysyms per keycode, every
+	/// keycode is extended with empty entries. If lower, the new keysyms are padded.
+	///
+	/// The range of keycodes must be valid.
+	pub fn set_keysyms(&mut self, first: Keycode, keysyms_per_keycode: usize, keysyms: &[Keysym]) {
+		if keysyms_per_keycode > self.keysyms_per_keycode {
+			let prev = self.keysyms_per_keycode;
+			self.keysyms = self
+				.keysyms
+				.chunks(prev)
+				.flat_map(|k| {
+					k.iter()
+						.copied()
+						.chain((prev..keysyms_per_keycode).map(|_| NO_SYMBOL))
+				})
+				.collect();
+			self.keysyms_per_keycode = keysyms_per_keycode;
+		}
+
+		let per = self.keysyms_per_keycode;
+		for (i, chunk) in keysyms.chunks(keysyms_per_keycode).enumerate() {
+			let off = (first - MIN_KEYCODE) as usize * per + i * per;
+			let dst = &mut self.keysyms[off..(off + per)];
+			dst.fill(NO_SYMBOL);
+			dst[..chunk.len()].copy_from_slice(chunk);
+		}
+	}
+}
@@ -1,25 +1,68 @@
 //! TODO doc
 
 pub mod client;
+pub mod colormap;
+pub mod cursor;
+pub mod focus;
 pub mod gc;
+pub mod image;
+pub mod input;
+pub mod keyboard;
+pub mod keymap;
+pub mod pixmap;
 pub mod pointer;
+pub mod region;
+pub mod rgb;
 pub mod screen;
+pub mod visual;
 pub mod window;
 
+use crate::font::catalog::FontCatalog;
+use crate::font::Font;
+use crate::input::ABS_RANGE;
 use crate::output::card::DRICard;
 use crate::output::connector::DRIConnector;
 use crate::poll::PollHandler;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::ColormapNotify;
+use crate::protocol::event::Expose;
+use crate::protocol::event::RawEvent;
 use crate::protocol::request::RequestReadFn;
+use crate::protocol::Class;
+use crate::protocol::Event;
+use crate::protocol::MapState;
 use crate::protocol::Rectangle;
 use crate::screens_layout::ScreensLayout;
 use client::Client;
+use colormap::Colormap;
+use cursor::Cursor;
+use cursor::CursorRef;
+use image::Image;
+use keyboard::Keyboard;
+use pixmap::PixmapRef;
 use pointer::Pointer;
+use region::Rect;
+use region::Region;
 use screen::Screen;
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::cell::RefMut;
 use std::cell::UnsafeCell;
+use std::cmp::max;
+use std::cmp::min;
 use std::collections::HashMap;
 use std::collections::LinkedList;
 use std::num::NonZeroU32;
+use std::os::unix::io::AsRawFd;
+use std::rc::Rc;
+use std::time::Instant;
+use visual::VisualRegistry;
+use window::Background;
+use window::Visibility;
 use window::Window;
+use window::WindowAttributes;
 
 // TODO Move in its own module?
 /// Trait representing an object that can be drawn.
@@ -35,6 +78,18 @@ pub trait Drawable {
 
 	/// Returns the width of the border.
 	fn get_border_width(&self) -> u16;
+
+	/// Returns an immutable reference to the drawable's storage.
+	///
+	/// If the drawable has no storage (for example, an input-only window), the function returns
+	/// None.
+	fn get_image(&self) -> Option<Ref<'_, Image>>;
+
+	/// Returns a mutable reference to the drawable's storage.
+	///
+	/// If the drawable has no storage (for example, an input-only window), the function returns
+	/// None.
+	fn get_image_mut(&self) -> Option<RefMut<'_, Image>>;
 }
 
 // TODO Move in its own module?
@@ -59,6 +114,26 @@ pub struct Context<'a> {
 	screens: Vec<Screen<'a>>,
 	/// The list of windows.
 	windows: HashMap<NonZeroU32, Window>,
+	/// The list of pixmaps.
+	pixmaps: HashMap<NonZeroU32, PixmapRef>,
+	/// The list of colormaps.
+	colormaps: HashMap<NonZeroU32, Colormap>,
+	/// The list of open fonts.
+	fonts: HashMap<NonZeroU32, Rc<Font>>,
+	/// The catalog of fonts available on the font path.
+	font_catalog: FontCatalog,
+	/// The list of cursors.
+	cursors: HashMap<NonZeroU32, CursorRef>,
+	/// The cursor of windows that have no cursor, if the cursor font is available.
+	default_cursor: Option<CursorRef>,
+	/// The ID of the client that created each pixmap, colormap, font and cursor, so that they
+	/// are freed when the client disconnects. The key is the ID of the resource.
+	///
+	/// Resources created by the server are not listed.
+	owners: HashMap<NonZeroU32, u32>,
+	/// The areas to recompose on the next frame. The key is the ID of a root window and the
+	/// value is the damaged region, relative to the root window's origin.
+	damage: RefCell<HashMap<NonZeroU32, Region>>,
 
 	/// The list of atoms on the server. The key is the ID of the atom.
 	atoms: HashMap<u32, String>,
@@ -70,6 +145,8 @@ pub struct Context<'a> {
 	clients: UnsafeCell<LinkedList<Client>>,
 	/// The client currently grabbing the server.
 	grabbing_client: Option<u32>,
+	/// The events waiting to be sent to each client. The key is the ID of the client.
+	events: HashMap<u32, Vec<RawEvent>>,
 
 	/// Requests handlers registered by extensions.
 	/// The key is the major opcode and the value is the handler.
@@ -77,14 +154,35 @@ pub struct Context<'a> {
 
 	/// The pointer, controller by user inputs.
 	pointer: Pointer,
+	/// The keyboard, controlled by user inputs.
+	keyboard: Keyboard,
+
+	/// The instant the server started, from which timestamps are counted.
+	start: Instant,
 }
 
 impl<'a> Context<'a> {
 	/// Creates a new instance.
-	pub fn new() -> Self {
+	///
+	/// `font_path` is the default font path.
+	pub fn new(font_path: Vec<String>) -> Self {
+		let mut font_catalog = FontCatalog::new(font_path);
+		let default_cursor = font_catalog
+			.open("cursor")
+			.and_then(|font| Cursor::default_from_font(&font))
+			.map(Rc::new);
+
 		Self {
 			screens: Vec::new(),
 			windows: HashMap::new(),
+			pixmaps: HashMap::new(),
+			colormaps: HashMap::new(),
+			fonts: HashMap::new(),
+			font_catalog,
+			cursors: HashMap::new(),
+			default_cursor,
+			owners: HashMap::new(),
+			damage: RefCell::new(HashMap::new()),
 
 			atoms: HashMap::from([
 				(1, "PRIMARY".to_owned()),
@@ -160,10 +258,30 @@ impl<'a> Context<'a> {
 
 			clients: UnsafeCell::new(LinkedList::new()),
 			grabbing_client: None,
+			events: HashMap::new(),
 
 			custom_requests: HashMap::new(),
 
 			pointer: Pointer::default(),
+			keyboard: Keyboard::default(),
+
+			start: Instant::now(),
+		}
+	}
+
+	/// Returns the current server time, in milliseconds since the start of the server.
+	///
+	/// The time wraps around after about 49.7 days.
+	pub fn get_time(&self) -> u32 {
+		self.start.elapsed().as_millis() as _
+	}
+
+	/// Returns the given timestamp, `CurrentTime` standing for the current server time.
+	pub fn resolve_time(&self, time: u32) -> u32 {
+		if time == protocol::CURRENT_TIME {
+			self.get_time()
+		} else {
+			time
 		}
 	}
 
@@ -175,6 +293,8 @@ impl<'a> Context<'a> {
 	/// If None, the function determines by itself an appropriate layout.
 	pub fn init_screens(&mut self, cards: &'a [DRICard], screens_layout: Option<ScreensLayout>) {
 		self.screens.clear();
+		// Without layout, screens are placed from left to right
+		let mut next_x = 0;
 
 		for dev in cards {
 			for conn in DRIConnector::scan(&dev) {
@@ -209,11 +329,39 @@ impl<'a> Context<'a> {
 					width: mode.hdisplay,
 					height: mode.vdisplay,
 				};
-				let root = Window::new(self, None, root_rect);
+				// TODO Allocate root IDs in a range reserved to the server
+				let root_id = NonZeroU32::new(self.screens.len() as u32 + 1).unwrap();
+				let colormap_id = NonZeroU32::new(0x100 + self.screens.len() as u32).unwrap();
+				let visuals = VisualRegistry::new(0x200 + 0x10 * self.screens.len() as u32);
+				let root_visual = visuals.get_root_visual();
+				// Cannot fail since root windows have no parent
+				let root = Window::new(self, root_id, None, root_rect).unwrap();
+				root.set_background(Background::Pixel(0));
+				// Root windows have the size of a screen, so their storage is expected to fit
+				root.set_attributes(WindowAttributes {
+					class: Class::InputOutput,
+					visual: root_visual.visual_id,
+					colormap: colormap_id.get(),
+					// Root windows are always mapped
+					map_state: MapState::Viewable,
 
-				// TODO Screen coords
-				let screen = Screen::new(&dev, conn, mode, 0, 0, root.get_id());
+					..Default::default()
+				})
+				.unwrap();
+
+				let x = next_x;
+				next_x += mode.hdisplay as u32;
+				let screen = Screen::new(&dev, conn, mode, x, 0, root_id, colormap_id, visuals);
+				// The pointer starts at the center of the first screen
+				if self.pointer.get_screen() == 0 {
+					let (width, height) = screen.get_screen_size();
+					let (x, y) = ((width / 2) as _, (height / 2) as _);
+					self.pointer.set_position(root_id.get(), x, y);
+				}
+				let colormap = Colormap::new(colormap_id, root_id, &root_visual, None);
 				self.screens.push(screen);
+				self.colormaps.insert(colormap_id, colormap);
+				self.update_visibility(root_id);
 			}
 		}
 	}
@@ -228,11 +376,27 @@ impl<'a> Context<'a> {
 		&mut self.screens
 	}
 
+	/// Returns the screen whose root window has the given ID.
+	pub fn get_screen(&self, root: NonZeroU32) -> Option<&Screen<'a>> {
+		self.screens.iter().find(|s| s.get_root_window_id() == root)
+	}
+
 	/// Returns the drawable with the given ID.
 	pub fn get_drawable(&self, id: NonZeroU32) -> Option<&dyn Drawable> {
-		// TODO Handle pixmaps
+		if let Some(win) = self.get_window(id) {
+			return Some(win as &dyn Drawable);
+		}
+
+		self.get_pixmap(id).map(|p| p.as_ref() as &dyn Drawable)
+	}
 
-		self.get_window(id).map(|d| d as &dyn Drawable)
+	/// Tells whether the given ID is already used by a resource.
+	pub fn is_id_used(&self, id: NonZeroU32) -> bool {
+		self.windows.contains_key(&id)
+			|| self.pixmaps.contains_key(&id)
+			|| self.colormaps.contains_key(&id)
+			|| self.fonts.contains_key(&id)
+			|| self.cursors.contains_key(&id)
 	}
 
 	/// Returns an immutable reference to the window with the given ID.
@@ -245,6 +409,492 @@ impl<'a> Context<'a> {
 		self.windows.get_mut(&wid)
 	}
 
+	/// Returns the pixmap with the given ID.
+	pub fn get_pixmap(&self, id: NonZeroU32) -> Option<&PixmapRef> {
+		self.pixmaps.get(&id)
+	}
+
+	/// Adds the given pixmap to the context.
+	///
+	/// `client` is the ID of the client creating the pixmap.
+	pub fn add_pixmap(&mut self, pixmap: PixmapRef, client: u32) {
+		self.owners.insert(pixmap.get_id(), client);
+		self.pixmaps.insert(pixmap.get_id(), pixmap);
+	}
+
+	/// Frees the pixmap with the given ID.
+	///
+	/// The ID is released immediately, but the storage is freed only once the pixmap isn't
+	/// referenced anymore.
+	///
+	/// If the pixmap doesn't exist, the function returns None.
+	pub fn free_pixmap(&mut self, id: NonZeroU32) -> Option<PixmapRef> {
+		let pixmap = self.pixmaps.remove(&id)?;
+		self.owners.remove(&id);
+		Some(pixmap)
+	}
+
+	/// Returns the font with the given ID.
+	pub fn get_font(&self, id: NonZeroU32) -> Option<&Rc<Font>> {
+		self.fonts.get(&id)
+	}
+
+	/// Adds the given font with the given ID.
+	///
+	/// `client` is the ID of the client opening the font.
+	pub fn add_font(&mut self, id: NonZeroU32, font: Rc<Font>, client: u32) {
+		self.owners.insert(id, client);
+		self.fonts.insert(id, font);
+	}
+
+	/// Removes the font with the given ID.
+	///
+	/// The ID is released immediately, but the font is unloaded only once it isn't referenced
+	/// anymore.
+	///
+	/// If the font doesn't exist, the function returns None.
+	pub fn free_font(&mut self, id: NonZeroU32) -> Option<Rc<Font>> {
+		let font = self.fonts.remove(&id)?;
+		self.owners.remove(&id);
+		Some(font)
+	}
+
+	/// Returns an immutable reference to the catalog of fonts.
+	pub fn get_font_catalog(&self) -> &FontCatalog {
+		&self.font_catalog
+	}
+
+	/// Returns a mutable reference to the catalog of fonts.
+	pub fn get_font_catalog_mut(&mut self) -> &mut FontCatalog {
+		&mut self.font_catalog
+	}
+
+	/// Returns the cursor with the given ID.
+	pub fn get_cursor(&self, id: NonZeroU32) -> Option<&CursorRef> {
+		self.cursors.get(&id)
+	}
+
+	/// Adds the given cursor with the given ID.
+	///
+	/// `client` is the ID of the client creating the cursor.
+	pub fn add_cursor(&mut self, id: NonZeroU32, cursor: CursorRef, client: u32) {
+		self.owners.insert(id, client);
+		self.cursors.insert(id, cursor);
+	}
+
+	/// Removes the cursor with the given ID.
+	///
+	/// The ID is released immediately, but the cursor is freed only once it isn't used anymore.
+	///
+	/// If the cursor doesn't exist, the function returns None.
+	pub fn free_cursor(&mut self, id: NonZeroU32) -> Option<CursorRef> {
+		let cursor = self.cursors.remove(&id)?;
+		self.owners.remove(&id);
+		Some(cursor)
+	}
+
+	/// Returns the cursor displayed when the pointer is in the given window.
+	///
+	/// A window without cursor uses the cursor of its parent. Root windows without cursor use
+	/// the default cursor.
+	pub fn get_window_cursor(&self, wid: NonZeroU32) -> Option<CursorRef> {
+		let mut win = self.get_window(wid)?;
+		loop {
+			if let Some(cursor) = win.get_cursor() {
+				return Some(cursor.clone());
+			}
+			match win.get_parent().and_then(|id| self.get_window(id)) {
+				Some(parent) => win = parent,
+				None => return self.default_cursor.clone(),
+			}
+		}
+	}
+
+	/// Returns the deepest viewable window containing the given position, relative to the origin
+	/// of the given root window.
+	pub fn get_window_at(&self, root: NonZeroU32, x: i32, y: i32) -> NonZeroU32 {
+		let (mut wid, mut x, mut y) = (root, x, y);
+		'outer: while let Some(win) = self.get_window(wid) {
+			// Children are checked from the top to the bottom of the stack
+			for id in win.get_children().iter().rev() {
+				let Some(child) = self.get_window(*id) else {
+					continue;
+				};
+				if !child.is_viewable() {
+					continue;
+				}
+				let rect = child.get_rectangle();
+				let border_width = child.get_border_width() as i32;
+				let cx = x - rect.x as i32;
+				let cy = y - rect.y as i32;
+				let outer_width = rect.width as i32 + border_width * 2;
+				let outer_height = rect.height as i32 + border_width * 2;
+				if cx >= 0 && cy >= 0 && cx < outer_width && cy < outer_height {
+					(wid, x, y) = (*id, cx - border_width, cy - border_width);
+					continue 'outer;
+				}
+			}
+			break;
+		}
+		wid
+	}
+
+	/// Returns the pointer.
+	pub fn get_pointer(&self) -> &Pointer {
+		&self.pointer
+	}
+
+	/// Returns a mutable reference to the pointer.
+	pub fn get_pointer_mut(&mut self) -> &mut Pointer {
+		&mut self.pointer
+	}
+
+	/// Returns the keyboard.
+	pub fn get_keyboard(&self) -> &Keyboard {
+		&self.keyboard
+	}
+
+	/// Returns a mutable reference to the keyboard.
+	pub fn get_keyboard_mut(&mut self) -> &mut Keyboard {
+		&mut self.keyboard
+	}
+
+	/// Marks the area covered by the cursor drawn by software as damaged.
+	pub fn damage_cursor(&self) {
+		let (Some(root), Some(bounds)) = (
+			NonZeroU32::new(self.pointer.get_screen()),
+			self.pointer.get_cursor_bounds(),
+		) else {
+			return;
+		};
+		self.damage(root, bounds);
+	}
+
+	/// Updates the cursor displayed at the position of the pointer, according to the window
+	/// under it.
+	///
+	/// This function must be called when the window under the pointer or its cursor may have
+	/// changed. If the displayed cursor changes, its area is damaged.
+	pub fn update_cursor(&mut self) {
+		let cursor = NonZeroU32::new(self.pointer.get_screen())
+			.filter(|_| self.pointer.is_visible())
+			.and_then(|root| {
+				let (x, y) = self.pointer.get_position();
+				let wid = self.get_window_at(root, x as _, y as _);
+				self.get_window_cursor(wid)
+			});
+
+		let changed = match (&cursor, self.pointer.get_cursor()) {
+			(Some(new), Some(old)) => !Rc::ptr_eq(new, old),
+			(new, old) => new.is_some() != old.is_some(),
+		};
+		if changed {
+			self.damage_cursor();
+			self.pointer.set_cursor(cursor);
+			self.refresh_cursor();
+		}
+	}
+
+	/// Displays the cursor of the pointer again, after it changed.
+	///
+	/// The cursor plane of the screen is used when possible. Otherwise, the cursor is drawn by
+	/// software and its area is damaged.
+	pub fn refresh_cursor(&mut self) {
+		let root = self.pointer.get_screen();
+		let (x, y) = self.pointer.get_position();
+		let screen = self
+			.screens
+			.iter_mut()
+			.find(|s| s.get_root_window_id().get() == root);
+		let cursor = self
+			.pointer
+			.get_cursor()
+			.filter(|_| self.pointer.is_visible());
+		let hardware = match (screen, cursor) {
+			(Some(screen), Some(cursor)) => screen.show_cursor(cursor, x as _, y as _),
+			(Some(screen), None) => {
+				screen.hide_cursor();
+				false
+			}
+			(None, _) => false,
+		};
+		self.pointer.set_hardware(hardware);
+		self.damage_cursor();
+	}
+
+	/// Moves the pointer to the given position on the screen whose root window has the ID
+	/// `root`.
+	///
+	/// The cursor follows the pointer.
+	pub fn set_pointer_position(&mut self, root: NonZeroU32, x: i16, y: i16) {
+		let same_screen = self.pointer.get_screen() == root.get();
+		if same_screen && self.pointer.is_hardware() {
+			self.pointer.set_position(root.get(), x, y);
+			let screen = self
+				.screens
+				.iter_mut()
+				.find(|s| s.get_root_window_id() == root);
+			let moved = match (screen, self.pointer.get_cursor()) {
+				(Some(screen), Some(cursor)) => screen.move_cursor(cursor, x as _, y as _),
+				_ => false,
+			};
+			// If the cursor plane failed, the cursor is drawn by software
+			if !moved {
+				self.refresh_cursor();
+			}
+			return;
+		}
+
+		self.damage_cursor();
+		if !same_screen {
+			let old = self.pointer.get_screen();
+			if let Some(screen) = self
+				.screens
+				.iter()
+				.find(|s| s.get_root_window_id().get() == old)
+			{
+				screen.hide_cursor();
+			}
+		}
+		self.pointer.set_position(root.get(), x, y);
+		self.refresh_cursor();
+	}
+
+	/// Returns the index of the screen the pointer is located on. If the pointer is on no
+	/// screen, the function returns None.
+	fn get_pointer_screen(&self) -> Option<usize> {
+		let root = self.pointer.get_screen();
+		self.screens
+			.iter()
+			.position(|s| s.get_root_window_id().get() == root)
+	}
+
+	/// Moves the pointer to the given absolute virtual position, clamped to the screen at index
+	/// `i`.
+	fn set_pointer_virtual_position(&mut self, i: usize, x: i64, y: i64) {
+		let screen = &self.screens[i];
+		let (screen_x, screen_y) = screen.get_position();
+		let (width, height) = screen.get_screen_size();
+		let x = (x - screen_x as i64).clamp(0, width.saturating_sub(1) as i64);
+		let y = (y - screen_y as i64).clamp(0, height.saturating_sub(1) as i64);
+		let root = screen.get_root_window_id();
+		self.set_pointer_position(root, x as _, y as _);
+		// The pointer may be on another window now
+		self.update_pointer_window();
+		self.update_cursor();
+	}
+
+	/// Moves the pointer by the given relative motion, in device units. The motion is
+	/// accelerated.
+	///
+	/// When the pointer goes past an edge of its screen, it crosses to the adjacent screen at
+	/// this position, if any. Otherwise, it stays on the edge.
+	pub fn move_pointer_relative(&mut self, delta_x: i32, delta_y: i32) {
+		let (delta_x, delta_y) = self.pointer.get_accel_mut().apply(delta_x, delta_y);
+		let Some(curr) = self.get_pointer_screen() else {
+			return;
+		};
+
+		let screen = &self.screens[curr];
+		let (screen_x, screen_y) = screen.get_position();
+		let (x, y) = self.pointer.get_position();
+		let x = screen_x as i64 + x as i64 + delta_x as i64;
+		let y = screen_y as i64 + y as i64 + delta_y as i64;
+		let target = if screen.contains(x, y) {
+			curr
+		} else {
+			self.screens
+				.iter()
+				.position(|s| s.contains(x, y) && s.adj(screen))
+				.unwrap_or(curr)
+		};
+		self.set_pointer_virtual_position(target, x, y);
+	}
+
+	/// Moves the pointer to the given absolute position, scaled to the range
+	/// `0..=input::ABS_RANGE`. The range covers the bounding box of every screen.
+	///
+	/// If the position is on no screen, the pointer stays on its screen, at the closest position.
+	pub fn move_pointer_absolute(&mut self, x: u32, y: u32) {
+		let bounds = self.screens.iter().fold(None, |acc, s| {
+			let (x, y) = s.get_position();
+			let (width, height) = s.get_screen_size();
+			let (x0, y0) = (x as i64, y as i64);
+			let (x1, y1) = (x0 + width as i64, y0 + height as i64);
+			Some(match acc {
+				Some((ax0, ay0, ax1, ay1)) => {
+					(min(ax0, x0), min(ay0, y0), max(ax1, x1), max(ay1, y1))
+				}
+				None => (x0, y0, x1, y1),
+			})
+		});
+		let Some((x0, y0, x1, y1)) = bounds else {
+			return;
+		};
+		let range = ABS_RANGE as i64;
+		let x = x0 + x as i64 * (x1 - x0 - 1) / range;
+		let y = y0 + y as i64 * (y1 - y0 - 1) / range;
+
+		let target = self
+			.screens
+			.iter()
+			.position(|s| s.contains(x, y))
+			.or_else(|| self.get_pointer_screen())
+			.unwrap_or(0);
+		self.set_pointer_virtual_position(target, x, y);
+	}
+
+	/// Returns an immutable reference to the colormap with the given ID.
+	pub fn get_colormap(&self, id: NonZeroU32) -> Option<&Colormap> {
+		self.colormaps.get(&id)
+	}
+
+	/// Returns a mutable reference to the colormap with the given ID.
+	pub fn get_colormap_mut(&mut self, id: NonZeroU32) -> Option<&mut Colormap> {
+		self.colormaps.get_mut(&id)
+	}
+
+	/// Adds the given colormap to the context.
+	///
+	/// `client` is the ID of the client creating the colormap.
+	pub fn add_colormap(&mut self, colormap: Colormap, client: u32) {
+		self.owners.insert(colormap.get_id(), client);
+		self.colormaps.insert(colormap.get_id(), colormap);
+	}
+
+	/// Frees the colormap with the given ID.
+	///
+	/// If the colormap is installed, it is uninstalled. Windows using the colormap are left
+	/// without colormap.
+	///
+	/// Default colormaps are not freed. If the colormap doesn't exist, the function returns an
+	/// error.
+	pub fn free_colormap(&mut self, id: NonZeroU32) -> Result<(), Error> {
+		let colormap = self.get_colormap(id).ok_or(Error::Colormap(id.get()))?;
+		let root = colormap.get_root();
+		let is_default = self
+			.get_screen(root)
+			.map(|s| s.get_default_colormap() == id)
+			.unwrap_or(false);
+		if is_default {
+			return Ok(());
+		}
+
+		self.uninstall_colormap(id);
+		self.colormaps.remove(&id);
+		self.owners.remove(&id);
+
+		let windows = self
+			.windows
+			.values_mut()
+			.filter(|w| w.attributes.colormap == id.get())
+			.map(|w| {
+				w.attributes.colormap = 0;
+				w.get_id()
+			})
+			.collect::<Vec<_>>();
+		for wid in windows {
+			self.colormap_notify(wid, true);
+		}
+
+		Ok(())
+	}
+
+	/// Marks every window using the given colormap as damaged, so that changes to the colormap
+	/// are displayed.
+	pub fn damage_colormap(&self, id: NonZeroU32) {
+		self.windows
+			.values()
+			.filter(|w| w.attributes.colormap == id.get())
+			.for_each(|w| self.damage_window(w.get_id()));
+	}
+
+	/// Installs the given colormap on its screen.
+	///
+	/// `ColormapNotify` events are sent for each window whose colormap is installed or
+	/// uninstalled.
+	pub fn install_colormap(&mut self, id: NonZeroU32) {
+		let Some(colormap) = self.get_colormap(id) else {
+			return;
+		};
+		let root = colormap.get_root();
+		let Some(screen) = self
+			.screens
+			.iter_mut()
+			.find(|s| s.get_root_window_id() == root)
+		else {
+			return;
+		};
+
+		let changed = screen.install_colormap(id);
+		self.colormap_state_notify(&changed);
+	}
+
+	/// Uninstalls the given colormap from its screen.
+	///
+	/// `ColormapNotify` events are sent for each window whose colormap is installed or
+	/// uninstalled.
+	pub fn uninstall_colormap(&mut self, id: NonZeroU32) {
+		let Some(colormap) = self.get_colormap(id) else {
+			return;
+		};
+		let root = colormap.get_root();
+		let Some(screen) = self
+			.screens
+			.iter_mut()
+			.find(|s| s.get_root_window_id() == root)
+		else {
+			return;
+		};
+
+		let changed = screen.uninstall_colormap(id);
+		self.colormap_state_notify(&changed);
+	}
+
+	/// Sends a `ColormapNotify` event for every window using one of the given colormaps, whose
+	/// state changed.
+	fn colormap_state_notify(&mut self, colormaps: &[NonZeroU32]) {
+		let windows = self
+			.windows
+			.values()
+			.filter(|w| colormaps.iter().any(|c| c.get() == w.attributes.colormap))
+			.map(|w| w.get_id())
+			.collect::<Vec<_>>();
+		for wid in windows {
+			self.colormap_notify(wid, false);
+		}
+	}
+
+	/// Sends a `ColormapNotify` event for the given window to the clients having selected
+	/// `ColormapChange` on it.
+	///
+	/// `new` tells whether the colormap attribute of the window changed. If not, the event
+	/// reports that the colormap has been installed or uninstalled.
+	pub fn colormap_notify(&mut self, wid: NonZeroU32, new: bool) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		let colormap = win.attributes.colormap;
+		let installed = NonZeroU32::new(colormap)
+			.and_then(|c| Some((c, self.get_colormap(c)?.get_root())))
+			.and_then(|(c, root)| Some(self.get_screen(root)?.is_colormap_installed(c)))
+			.unwrap_or(false);
+
+		let ev = ColormapNotify {
+			code: event::COLORMAP_NOTIFY,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			window: wid.get(),
+			colormap,
+			new: new as _,
+			state: installed as _,
+
+			_padding1: [0; 18],
+		};
+		self.deliver_event(wid, Event::ColormapChange as _, &ev);
+	}
+
 	/// Returns the value of the atom with the given ID. If the atom doesn't exist, the function
 	/// returns None.
 	pub fn get_atom(&self, id: u32) -> Option<&String> {
@@ -261,6 +911,14 @@ impl<'a> Context<'a> {
 			.next()
 	}
 
+	/// Returns the ID of the atom with the given name, creating it if it doesn't exist.
+	pub fn intern_atom(&mut self, name: &str) -> u32 {
+		match self.get_atom_from_name(name) {
+			Some(id) => id,
+			None => self.create_atom(name.to_owned()),
+		}
+	}
+
 	/// Creates an atom with the given name and returns its ID.
 	pub fn create_atom(&mut self, name: String) -> u32 {
 		// TODO use clean atom ID allocator
@@ -296,12 +954,15 @@ impl<'a> Context<'a> {
 
 	/// Ticks every connected client.
 	///
-	/// `poll_handler` is the poll handler on which the stream is to be registered.
-	pub fn tick_clients(&mut self, poll_handler: &mut PollHandler) {
+	/// Arguments:
+	/// - `poll_handler` is the poll handler on which the stream is to be registered.
+	/// - `ready` is the list of file descriptors ready for reading.
+	pub fn tick_clients(&mut self, poll_handler: &mut PollHandler, ready: &[i32]) {
 		let mut cursor = unsafe { (*self.clients.get()).cursor_front_mut() };
 
 		while let Some(client) = cursor.current() {
-			match client.tick(self) {
+			let readable = ready.contains(&client.get_stream().as_raw_fd());
+			match client.tick(self, readable) {
 				// On error, remove client
 				Err(e) => {
 					println!("Client disconnect: {}", e);
@@ -313,6 +974,18 @@ impl<'a> Context<'a> {
 						}
 					}
 
+					// If the client is grabbing the keyboard, ungrab
+					let grabbing_keyboard = self
+						.keyboard
+						.get_grab()
+						.is_some_and(|g| g.client == client.get_id());
+					if grabbing_keyboard {
+						self.ungrab_keyboard();
+					}
+
+					self.free_client_resources(client.get_id());
+					self.events.remove(&client.get_id());
+
 					if let Some(removed) = cursor.remove_current() {
 						poll_handler.remove_fd(removed.get_stream());
 					}
@@ -325,6 +998,89 @@ impl<'a> Context<'a> {
 		}
 	}
 
+	/// Writes the events waiting to be sent to every client.
+	///
+	/// This is required for events that are not caused by requests, which would otherwise wait
+	/// for the next tick of the client.
+	pub fn flush_events(&mut self) {
+		let clients = unsafe { &mut *self.clients.get() };
+		for client in clients.iter_mut() {
+			// On failure, the client is removed on its next tick
+			let _ = client.write_events(self);
+		}
+	}
+
+	/// Frees the resources created by the client with the given ID, the colormap entries it
+	/// allocated and its event selections.
+	fn free_client_resources(&mut self, client: u32) {
+		// Events are deselected first so that freeing resources reports nothing to the client
+		for win in self.windows.values_mut() {
+			// Cannot fail since no event is selected
+			win.select_events(client, 0).unwrap();
+		}
+
+		let ids = self
+			.owners
+			.iter()
+			.filter(|(_, c)| **c == client)
+			.map(|(id, _)| *id)
+			.collect::<Vec<_>>();
+		for id in ids {
+			// Resources of every kind share the same ID space
+			self.free_pixmap(id);
+			self.free_font(id);
+			self.free_cursor(id);
+			if self.colormaps.contains_key(&id) {
+				// Cannot fail since the colormap exists
+				self.free_colormap(id).unwrap();
+			}
+		}
+
+		for colormap in self.colormaps.values_mut() {
+			colormap.free_client(client);
+		}
+	}
+
+	/// Queues the given event to be sent to the client with the given ID.
+	///
+	/// The event is sent after the request currently being handled, or on the next tick of the
+	/// client.
+	pub fn send_event<E>(&mut self, client: u32, event: &E) {
+		self.events
+			.entry(client)
+			.or_default()
+			.push(event::to_raw(event));
+	}
+
+	/// Queues the given event to be sent to every client having selected at least one of the
+	/// events in `mask` on the given window.
+	///
+	/// If the window doesn't exist, the function does nothing.
+	pub fn deliver_event<E>(&mut self, window: NonZeroU32, mask: u32, event: &E) {
+		let Some(win) = self.windows.get(&window) else {
+			return;
+		};
+
+		let raw = event::to_raw(event);
+		for client in win.get_selecting_clients(mask) {
+			self.events.entry(client).or_default().push(raw);
+		}
+	}
+
+	/// Queues the given event to be sent to every client.
+	pub fn broadcast_event<E>(&mut self, event: &E) {
+		let raw = event::to_raw(event);
+		let clients = unsafe { &*self.clients.get() };
+		for client in clients {
+			self.events.entry(client.get_id()).or_default().push(raw);
+		}
+	}
+
+	/// Takes the events waiting to be sent to the client with the given ID.
+	pub fn take_events(&mut self, client: u32) -> Vec<RawEvent> {
+		self.events.remove(&client).unwrap_or_default()
+	}
+
 	/// Returns an immutable reference to the list of custom requests.
 	pub fn get_custom_requests(&self) -> &HashMap<u8, Box<RequestReadFn>> {
 		&self.custom_requests
@@ -345,21 +1101,365 @@ impl<'a> Context<'a> {
 		self.grabbing_client = None;
 	}
 
+	/// Marks the given area of a drawable as damaged, so that it is recomposed on the next
+	/// frame.
+	///
+	/// `area` is relative to the drawable's origin. It is clipped to the drawable and its
+	/// ancestors.
+	///
+	/// Damage on pixmaps and on windows that are not viewable is ignored.
+	pub fn damage(&self, id: NonZeroU32, area: Rect) {
+		let Some(mut win) = self.get_window(id) else {
+			return;
+		};
+		if !matches!(win.attributes.map_state, MapState::Viewable) {
+			return;
+		}
+
+		let mut area = area;
+		loop {
+			let rect = win.get_rectangle();
+			area = area.intersect(&Rect::new(0, 0, rect.width as _, rect.height as _));
+			if area.is_empty() {
+				return;
+			}
+
+			let Some(parent) = win.get_parent().and_then(|id| self.get_window(id)) else {
+				break;
+			};
+			let border_width = win.get_border_width() as i32;
+			area = area.translate(rect.x as i32 + border_width, rect.y as i32 + border_width);
+			win = parent;
+		}
+
+		self.damage
+			.borrow_mut()
+			.entry(win.get_id())
+			.or_default()
+			.union_rect(area);
+	}
+
+	/// Marks the whole area of a window, including its border, as damaged.
+	///
+	/// This function must be called when the appearance of a window changes without its
+	/// visibility changing, such as when its border is changed.
+	pub fn damage_window(&self, wid: NonZeroU32) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+
+		let rect = win.get_rectangle();
+		match win.get_parent() {
+			Some(parent) => {
+				let border_width = win.get_border_width() as i32 * 2;
+				let x = rect.x as i32;
+				let y = rect.y as i32;
+				let outer = Rect::new(
+					x,
+					y,
+					x + rect.width as i32 + border_width,
+					y + rect.height as i32 + border_width,
+				);
+				self.damage(parent, outer);
+			}
+
+			None => self.damage(wid, Rect::new(0, 0, rect.width as _, rect.height as _)),
+		}
+	}
+
+	/// Computes the visible parts of the given window and of its inferiors.
+	///
+	/// Arguments:
+	/// - `win` is the window.
+	/// - `x` and `y` are the position of the window's origin, relative to the root window's
+	///   origin.
+	/// - `available` is the region in which the window can be visible, relative to the root
+	///   window's origin.
+	/// - `under` is the region in which the window is obscured only by save-under windows,
+	///   relative to the root window's origin.
+	/// - `list` is the list to which the results are pushed.
+	fn compute_visibility(
+		&self,
+		win: &Window,
+		x: i32,
+		y: i32,
+		available: &Region,
+		under: &Region,
+		list: &mut Vec<(NonZeroU32, Visibility)>,
+	) {
+		let rect = win.get_rectangle();
+		let border_width = win.get_border_width() as i32;
+		let inside = Rect::new(x, y, x + rect.width as i32, y + rect.height as i32);
+		let outside = Rect::new(
+			inside.x0 - border_width,
+			inside.y0 - border_width,
+			inside.x1 + border_width,
+			inside.y1 + border_width,
+		);
+
+		// Windows that are not viewable are not visible, nor are their inferiors
+		let mut visible = Region::default();
+		let mut under_children = Region::default();
+		if win.is_viewable() {
+			visible = available.clone();
+			visible.intersect_rect(outside);
+			under_children = under.clone();
+			under_children.intersect_rect(inside);
+		}
+		let mut border = visible.clone();
+		border.subtract_rect(inside);
+		visible.intersect_rect(inside);
+
+		// The backing store keeps the parts that are obscured by other windows or outside of the
+		// parent. If the window never had a valid content, there is nothing to keep
+		let old = win.get_visibility();
+		let has_content = win.is_viewable() || !old.inside.is_empty() || !old.saved.is_empty();
+		let mut saved = Region::default();
+		if win.has_backing_store() && has_content {
+			saved = Region::from(inside);
+			saved.subtract(&visible);
+		}
+
+		// Children obscure each other from the top to the bottom of the stack
+		for id in win.get_children().iter().rev() {
+			let Some(child) = self.get_window(*id) else {
+				continue;
+			};
+			let child_rect = child.get_rectangle();
+			let child_border_width = child.get_border_width() as i32;
+			let cx = x + child_rect.x as i32 + child_border_width;
+			let cy = y + child_rect.y as i32 + child_border_width;
+			self.compute_visibility(child, cx, cy, &visible, &under_children, list);
+
+			// Input-only windows are invisible
+			if child.is_viewable() && child.is_output() {
+				let child_outside = Rect::new(
+					cx - child_border_width,
+					cy - child_border_width,
+					cx + child_rect.width as i32 + child_border_width,
+					cy + child_rect.height as i32 + child_border_width,
+				);
+				if child.attributes.save_under {
+					let mut obscured = visible.clone();
+					obscured.intersect_rect(child_outside);
+					under_children.union(&obscured);
+				} else {
+					under_children.subtract_rect(child_outside);
+				}
+				visible.subtract_rect(child_outside);
+			}
+		}
+		saved.union(&under_children);
+
+		list.push((
+			win.get_id(),
+			Visibility {
+				x,
+				y,
+				inside: visible,
+				border,
+				saved,
+			},
+		));
+	}
+
+	/// Recomputes the visible parts of the windows of the screen of the given root window.
+	///
+	/// This function must be called after windows are mapped, unmapped, moved, resized or
+	/// restacked, or when their backing store or save-under attributes change.
+	///
+	/// Areas whose visibility changed are damaged. Areas of windows that became visible are
+	/// restored if their content has been saved, or exposed otherwise.
+	pub fn update_visibility(&mut self, root: NonZeroU32) {
+		let Some(root_win) = self.get_window(root) else {
+			return;
+		};
+		let rect = root_win.get_rectangle();
+		let available = Region::from(Rect::new(0, 0, rect.width as _, rect.height as _));
+		let mut list = vec![];
+		self.compute_visibility(root_win, 0, 0, &available, &Region::default(), &mut list);
+
+		let mut damage = Region::default();
+		let mut exposures = vec![];
+		for (id, vis) in list {
+			// Cannot fail since the list has been built from existing windows
+			let win = self.windows.get_mut(&id).unwrap();
+			let old = win.set_visibility(vis);
+			let vis = win.get_visibility();
+
+			let mut newly_visible = vis.inside.clone();
+			let mut border = vis.border.clone();
+			// If the window moved, it has to be recomposed entirely
+			if old.x == vis.x && old.y == vis.y {
+				newly_visible.subtract(&old.inside);
+				border.subtract(&old.border);
+			}
+			damage.union(&newly_visible);
+			damage.union(&border);
+
+			// The content of the window moves along with it
+			let mut exposed = vis.inside.clone();
+			exposed.translate(-vis.x, -vis.y);
+			exposed.subtract(&old.get_valid());
+			if win.is_output() && !exposed.is_empty() {
+				exposures.push((id, exposed));
+			}
+		}
+		self.damage
+			.get_mut()
+			.entry(root)
+			.or_default()
+			.union(&damage);
+
+		for (id, exposed) in exposures {
+			self.expose(id, &exposed);
+		}
+	}
+
+	/// Fills the given region of a window with its background, then sends `Expose` events for
+	/// it to the clients having selected them.
+	///
+	/// `region` is relative to the window's origin.
+	pub fn expose(&mut self, wid: NonZeroU32, region: &Region) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		let rects = region.get_rects();
+		for r in rects {
+			win.clear(self, &r.into());
+		}
+
+		for (i, r) in rects.iter().enumerate() {
+			let rect = Rectangle::from(r);
+			let ev = Expose {
+				code: event::EXPOSE,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				window: wid.get(),
+				x: rect.x as _,
+				y: rect.y as _,
+				width: rect.width,
+				height: rect.height,
+				count: (rects.len() - i - 1) as _,
+
+				_padding1: [0; 14],
+			};
+			self.deliver_event(wid, Event::Exposure as _, &ev);
+		}
+	}
+
+	/// Sets the map state of the inferiors of the given window, according to whether the window
+	/// is viewable.
+	///
+	/// Unmapped windows and their inferiors are left unchanged.
+	fn update_inferiors_map_state(&mut self, wid: NonZeroU32, viewable: bool) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+
+		for id in win.get_children().to_vec() {
+			let Some(child) = self.get_window_mut(id) else {
+				continue;
+			};
+			if matches!(child.attributes.map_state, MapState::Unmapped) {
+				continue;
+			}
+
+			child.attributes.map_state = if viewable {
+				MapState::Viewable
+			} else {
+				MapState::Unviewable
+			};
+			self.update_inferiors_map_state(id, viewable);
+		}
+	}
+
+	/// Maps the given window. If the window becomes viewable, its mapped inferiors do too and the
+	/// areas becoming visible are exposed.
+	///
+	/// If the window doesn't exist or is already mapped, the function does nothing.
+	pub fn map_window(&mut self, wid: NonZeroU32) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		if !matches!(win.attributes.map_state, MapState::Unmapped) {
+			return;
+		}
+
+		let viewable = win
+			.get_parent()
+			.and_then(|id| self.get_window(id))
+			.map(|p| p.is_viewable())
+			.unwrap_or(true);
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+
+		// Cannot fail since the window has been checked before
+		let win = self.get_window_mut(wid).unwrap();
+		win.attributes.map_state = if viewable {
+			MapState::Viewable
+		} else {
+			MapState::Unviewable
+		};
+		self.update_inferiors_map_state(wid, viewable);
+
+		if viewable {
+			self.update_visibility(root);
+		}
+	}
+
+	/// Unmaps the given window. Its inferiors become unviewable and the areas they leave are
+	/// exposed.
+	///
+	/// If the window doesn't exist, is a root window or is already unmapped, the function does
+	/// nothing.
+	pub fn unmap_window(&mut self, wid: NonZeroU32) {
+		let Some(win) = self.get_window_mut(wid) else {
+			return;
+		};
+		if win.is_root() || matches!(win.attributes.map_state, MapState::Unmapped) {
+			return;
+		}
+
+		let viewable = win.is_viewable();
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		win.attributes.map_state = MapState::Unmapped;
+		self.update_inferiors_map_state(wid, false);
+
+		if viewable {
+			self.update_visibility(root);
+			self.check_focus_viewable();
+		}
+	}
+
+	/// Tells whether some areas are waiting to be recomposed.
+	pub fn is_damaged(&self) -> bool {
+		self.damage.borrow().values().any(|r| !r.is_empty())
+	}
+
 	/// Renders to the screen.
+	///
+	/// Only the damaged areas are recomposed.
 	pub fn render(&mut self) {
-		// TODO Avoid rendering the whole screen: only render parts that changed
+		let mut damage = self.damage.take();
 
-		// Render recursively starting from root windows
-		for s in &self.screens {
-			let root_id = s.get_root_window_id();
+		for i in 0..self.screens.len() {
+			let root_id = self.screens[i].get_root_window_id();
+			if let Some(region) = damage.remove(&root_id) {
+				self.screens[i].add_damage(&region);
+			}
 
+			let area = self.screens[i].take_damage();
+			if area.is_empty() {
+				continue;
+			}
 			if let Some(root) = self.get_window(root_id) {
-				root.render_full(self, s);
+				root.compose(self, &self.screens[i], &area);
 			}
-		}
+			// The cursor is drawn over the windows
+			self.pointer.draw(&self.screens[i], &area);
 
-		for s in &mut self.screens {
-			s.swap_buffers();
+			self.screens[i].swap_buffers();
 		}
 	}
 }
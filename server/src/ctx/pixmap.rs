@@ -0,0 +1,105 @@
+//! A pixmap is an off-screen drawable.
+//!
+//! Pixmaps are shared through reference counting: when a pixmap is freed while being used (for
+//! example as a window background or a GC tile), its storage stays alive until the last user
+//! releases it.
+
+use super::image::Image;
+use super::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::Rectangle;
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::cell::RefMut;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+/// A shared reference to a pixmap.
+pub type PixmapRef = Rc<Pixmap>;
+
+/// Structure representing a pixmap.
+pub struct Pixmap {
+	/// The ID of the pixmap.
+	id: NonZeroU32,
+	/// The ID of the root window of the screen the pixmap belongs to.
+	root: NonZeroU32,
+
+	/// The pixmap's storage.
+	image: RefCell<Image>,
+}
+
+impl Pixmap {
+	/// Creates a new pixmap.
+	///
+	/// Arguments:
+	/// - `id` is the ID of the pixmap.
+	/// - `root` is the ID of the root window of the screen the pixmap belongs to.
+	/// - `width` and `height` are the dimensions of the pixmap in pixels.
+	/// - `depth` is the depth of the pixmap.
+	///
+	/// If the depth is not supported, the function returns a Value error. If the storage cannot be
+	/// allocated, the function returns an Alloc error.
+	pub fn new(
+		id: NonZeroU32,
+		root: NonZeroU32,
+		width: u16,
+		height: u16,
+		depth: u8,
+	) -> Result<Self, Error> {
+		Ok(Self {
+			id,
+			root,
+
+			image: RefCell::new(Image::new(width, height, depth)?),
+		})
+	}
+
+	/// Returns the ID of the pixmap.
+	pub fn get_id(&self) -> NonZeroU32 {
+		self.id
+	}
+}
+
+impl fmt::Debug for Pixmap {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt.debug_struct("Pixmap")
+			.field("id", &self.id)
+			.field("root", &self.root)
+			.finish()
+	}
+}
+
+impl Drawable for Pixmap {
+	fn get_depth(&self) -> u8 {
+		self.image.borrow().get_depth()
+	}
+
+	fn get_root(&self) -> u32 {
+		self.root.get()
+	}
+
+	fn get_rectangle(&self) -> Rectangle {
+		let image = self.image.borrow();
+
+		Rectangle {
+			x: 0,
+			y: 0,
+
+			width: image.get_width(),
+			height: image.get_height(),
+		}
+	}
+
+	fn get_border_width(&self) -> u16 {
+		0
+	}
+
+	fn get_image(&self) -> Option<Ref<'_, Image>> {
+		Some(self.image.borrow())
+	}
+
+	fn get_image_mut(&self) -> Option<RefMut<'_, Image>> {
+		Some(self.image.borrow_mut())
+	}
+}
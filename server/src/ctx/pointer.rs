@@ -1,9 +1,53 @@
-//! TODO doc
+//! The pointer is the position on screen controlled by pointing devices.
+//!
+//! The cursor of the window under the pointer is displayed at its position. When possible, it is
+//! displayed on the cursor plane of the screen, which moves without recomposing anything.
+//! Otherwise, it is drawn by software over the composed windows, the area it covers being
+//! recomposed when it moves or changes.
+//!
+//! Pressing a button grabs the pointer implicitly: until every button is released, pointer
+//! events are reported to the client that received the press.
+
+use super::cursor::CursorRef;
+use super::region::Rect;
+use super::region::Region;
+use super::screen::Screen;
+use crate::input::accel::Acceleration;
+use crate::input::MouseButton;
+use crate::protocol::ButMask;
+use std::collections::HashSet;
+use std::num::NonZeroU32;
+
+/// A grab of the pointer by a client.
+#[derive(Clone, Debug)]
+pub struct PointerGrab {
+	/// The window events are reported relative to.
+	pub window: NonZeroU32,
+	/// The ID of the grabbing client.
+	pub client: u32,
+	/// If true, events that would normally be reported to the client are reported normally.
+	/// Other events are reported relative to the grab window.
+	pub owner_events: bool,
+	/// The events reported relative to the grab window.
+	pub event_mask: u32,
+}
+
+/// Returns the state mask bit of the given button. Buttons without a bit return zero.
+pub fn get_button_mask(button: MouseButton) -> u16 {
+	match button {
+		MouseButton::Button1 => ButMask::Button1 as _,
+		MouseButton::Button2 => ButMask::Button2 as _,
+		MouseButton::Button3 => ButMask::Button3 as _,
+		MouseButton::Button4 => ButMask::Button4 as _,
+		MouseButton::Button5 => ButMask::Button5 as _,
+		_ => 0,
+	}
+}
 
 /// A pointer displayed on a screen.
-#[derive(Default)]
 pub struct Pointer {
-	/// The ID of the screen the pointer is located on.
+	/// The ID of the root window of the screen the pointer is located on. Zero if no screen has
+	/// been initialized.
 	screen: u32,
 
 	/// The X position of the pointer relative to the screen's top-left corner.
@@ -11,6 +55,217 @@ pub struct Pointer {
 	/// The Y position of the pointer relative to the screen's top-left corner.
 	y: i16,
 
-	/// The ID of the cursor associated with the pointer.
-	cursor: u32,
+	/// The cursor currently displayed. None if no cursor is displayed.
+	cursor: Option<CursorRef>,
+	/// Tells whether the cursor is displayed.
+	visible: bool,
+	/// Tells whether the cursor is displayed on the cursor plane of the screen instead of being
+	/// drawn by software.
+	hardware: bool,
+
+	/// The acceleration of relative motions.
+	accel: Acceleration,
+
+	/// The buttons currently pressed. Bit `n` is set if button `n` is pressed.
+	buttons: u16,
+	/// The deepest window containing the pointer, as last reported by crossing events.
+	window: Option<NonZeroU32>,
+	/// The active grab, if any.
+	grab: Option<PointerGrab>,
+	/// The clients that received a motion hint since the last time they queried the pointer.
+	motion_hints: HashSet<u32>,
+	/// Tells whether the pointer moved since the last `MotionNotify` event.
+	motion_pending: bool,
+}
+
+impl Default for Pointer {
+	fn default() -> Self {
+		Self {
+			screen: 0,
+
+			x: 0,
+			y: 0,
+
+			cursor: None,
+			visible: true,
+			hardware: false,
+
+			accel: Acceleration::default(),
+
+			buttons: 0,
+			window: None,
+			grab: None,
+			motion_hints: HashSet::new(),
+			motion_pending: false,
+		}
+	}
+}
+
+impl Pointer {
+	/// Returns the ID of the root window of the screen the pointer is located on.
+	pub fn get_screen(&self) -> u32 {
+		self.screen
+	}
+
+	/// Returns the position of the pointer relative to the screen's top-left corner.
+	pub fn get_position(&self) -> (i16, i16) {
+		(self.x, self.y)
+	}
+
+	/// Moves the pointer to the given position on the screen whose root window has the ID
+	/// `screen`.
+	pub fn set_position(&mut self, screen: u32, x: i16, y: i16) {
+		self.screen = screen;
+		self.x = x;
+		self.y = y;
+	}
+
+	/// Returns the acceleration of relative motions.
+	pub fn get_accel(&self) -> &Acceleration {
+		&self.accel
+	}
+
+	/// Returns a mutable reference to the acceleration of relative motions.
+	pub fn get_accel_mut(&mut self) -> &mut Acceleration {
+		&mut self.accel
+	}
+
+	/// Tells whether at least one button is pressed.
+	pub fn has_buttons_pressed(&self) -> bool {
+		self.buttons != 0
+	}
+
+	/// Sets whether the given button is pressed.
+	pub fn set_button(&mut self, button: MouseButton, pressed: bool) {
+		let bit = 1 << button as u16;
+		if pressed {
+			self.buttons |= bit;
+		} else {
+			self.buttons &= !bit;
+		}
+	}
+
+	/// Returns the state mask of the buttons currently pressed, made of `ButMask` bits.
+	pub fn get_button_state(&self) -> u16 {
+		(1..=5)
+			.filter(|n| self.buttons & (1 << n) != 0)
+			.map(|n| (ButMask::Button1 as u16) << (n - 1))
+			.fold(0, |a, b| a | b)
+	}
+
+	/// Returns the deepest window containing the pointer, as last reported by crossing events.
+	pub fn get_window(&self) -> Option<NonZeroU32> {
+		self.window
+	}
+
+	/// Sets the deepest window containing the pointer.
+	pub fn set_window(&mut self, window: Option<NonZeroU32>) {
+		self.window = window;
+	}
+
+	/// Returns the active grab, if any.
+	pub fn get_grab(&self) -> Option<&PointerGrab> {
+		self.grab.as_ref()
+	}
+
+	/// Sets the active grab.
+	pub fn set_grab(&mut self, grab: Option<PointerGrab>) {
+		self.grab = grab;
+	}
+
+	/// Tells whether the given client received a motion hint it hasn't acknowledged by querying
+	/// the pointer.
+	pub fn has_motion_hint(&self, client: u32) -> bool {
+		self.motion_hints.contains(&client)
+	}
+
+	/// Records that the given client received a motion hint.
+	pub fn add_motion_hint(&mut self, client: u32) {
+		self.motion_hints.insert(client);
+	}
+
+	/// Allows the given client to receive a motion hint again.
+	pub fn clear_motion_hint(&mut self, client: u32) {
+		self.motion_hints.remove(&client);
+	}
+
+	/// Allows every client to receive a motion hint again.
+	pub fn clear_motion_hints(&mut self) {
+		self.motion_hints.clear();
+	}
+
+	/// Tells whether the pointer moved since the last `MotionNotify` event.
+	pub fn is_motion_pending(&self) -> bool {
+		self.motion_pending
+	}
+
+	/// Sets whether the pointer moved since the last `MotionNotify` event.
+	pub fn set_motion_pending(&mut self, pending: bool) {
+		self.motion_pending = pending;
+	}
+
+	/// Returns the cursor currently displayed.
+	pub fn get_cursor(&self) -> Option<&CursorRef> {
+		self.cursor.as_ref()
+	}
+
+	/// Sets the cursor currently displayed.
+	pub fn set_cursor(&mut self, cursor: Option<CursorRef>) {
+		self.cursor = cursor;
+	}
+
+	/// Tells whether the cursor is displayed.
+	pub fn is_visible(&self) -> bool {
+		self.visible
+	}
+
+	/// Sets whether the cursor is displayed.
+	pub fn set_visible(&mut self, visible: bool) {
+		self.visible = visible;
+	}
+
+	/// Tells whether the cursor is displayed on the cursor plane of the screen.
+	pub fn is_hardware(&self) -> bool {
+		self.hardware
+	}
+
+	/// Sets whether the cursor is displayed on the cursor plane of the screen.
+	pub fn set_hardware(&mut self, hardware: bool) {
+		self.hardware = hardware;
+	}
+
+	/// Returns the area of the screen covered by the cursor drawn by software. If no cursor is
+	/// drawn by software, the function returns None.
+	pub fn get_cursor_bounds(&self) -> Option<Rect> {
+		let cursor = self
+			.cursor
+			.as_ref()
+			.filter(|_| self.visible && !self.hardware)?;
+		Some(cursor.get_bounds(self.x as _, self.y as _))
+	}
+
+	/// Draws the cursor on the current framebuffer of the given screen, in the given area.
+	///
+	/// If the pointer is not on this screen, the function does nothing.
+	pub fn draw(&self, screen: &Screen, area: &Region) {
+		if screen.get_root_window_id().get() != self.screen {
+			return;
+		}
+		let (Some(cursor), Some(bounds)) = (&self.cursor, self.get_cursor_bounds()) else {
+			return;
+		};
+
+		let fb = screen.get_curr_fb();
+		let mut area = area.clone();
+		area.intersect_rect(bounds);
+		for r in area.get_rects() {
+			for py in r.y0..r.y1 {
+				for px in r.x0..r.x1 {
+					if let Some(pixel) = cursor.get_pixel(px - bounds.x0, py - bounds.y0) {
+						fb.set_pixel(px as _, py as _, pixel);
+					}
+				}
+			}
+		}
+	}
 }
@@ -0,0 +1,324 @@
+//! A region is a set of pixels, represented as a list of disjoint rectangles.
+//!
+//! Regions are used to track the parts of the screens that have to be recomposed.
+
+use crate::protocol::Rectangle;
+use std::cmp::max;
+use std::cmp::min;
+
+/// A rectangle delimited by its corners. `(x0, y0)` is inclusive and `(x1, y1)` is exclusive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+	/// The X position of the left edge.
+	pub x0: i32,
+	/// The Y position of the top edge.
+	pub y0: i32,
+	/// The X position of the right edge.
+	pub x1: i32,
+	/// The Y position of the bottom edge.
+	pub y1: i32,
+}
+
+impl Rect {
+	/// Creates a new rectangle from its corners.
+	pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+		Self {
+			x0,
+			y0,
+			x1,
+			y1,
+		}
+	}
+
+	/// Tells whether the rectangle contains no pixel.
+	pub fn is_empty(&self) -> bool {
+		self.x0 >= self.x1 || self.y0 >= self.y1
+	}
+
+	/// Tells whether the pixel at the given position is inside of the rectangle.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		x >= self.x0 && y >= self.y0 && x < self.x1 && y < self.y1
+	}
+
+	/// Tells whether `other` is entirely inside of the rectangle.
+	pub fn contains_rect(&self, other: &Rect) -> bool {
+		other.x0 >= self.x0 && other.y0 >= self.y0 && other.x1 <= self.x1 && other.y1 <= self.y1
+	}
+
+	/// Returns the intersection of the rectangle with `other`. The result may be empty.
+	pub fn intersect(&self, other: &Rect) -> Rect {
+		Rect {
+			x0: max(self.x0, other.x0),
+			y0: max(self.y0, other.y0),
+			x1: min(self.x1, other.x1),
+			y1: min(self.y1, other.y1),
+		}
+	}
+
+	/// Returns the smallest rectangle containing both the rectangle and `other`.
+	///
+	/// Empty rectangles are ignored.
+	pub fn bounding(&self, other: &Rect) -> Rect {
+		if self.is_empty() {
+			return *other;
+		}
+		if other.is_empty() {
+			return *self;
+		}
+
+		Rect {
+			x0: min(self.x0, other.x0),
+			y0: min(self.y0, other.y0),
+			x1: max(self.x1, other.x1),
+			y1: max(self.y1, other.y1),
+		}
+	}
+
+	/// Returns the rectangle moved by `(dx, dy)`.
+	pub fn translate(&self, dx: i32, dy: i32) -> Rect {
+		Rect {
+			x0: self.x0 + dx,
+			y0: self.y0 + dy,
+			x1: self.x1 + dx,
+			y1: self.y1 + dy,
+		}
+	}
+
+	/// Returns the parts of the rectangle that are outside of `other`, as at most four disjoint
+	/// rectangles.
+	pub fn subtract(&self, other: &Rect) -> Vec<Rect> {
+		if self.intersect(other).is_empty() {
+			return vec![*self];
+		}
+
+		let mid_y0 = max(self.y0, other.y0);
+		let mid_y1 = min(self.y1, other.y1);
+		[
+			Rect::new(self.x0, self.y0, self.x1, mid_y0),
+			Rect::new(self.x0, mid_y1, self.x1, self.y1),
+			Rect::new(self.x0, mid_y0, min(self.x1, other.x0), mid_y1),
+			Rect::new(max(self.x0, other.x1), mid_y0, self.x1, mid_y1),
+		]
+		.into_iter()
+		.filter(|r| !r.is_empty())
+		.collect()
+	}
+}
+
+impl From<&Rectangle> for Rect {
+	fn from(rect: &Rectangle) -> Self {
+		let x = rect.x as i32;
+		let y = rect.y as i32;
+		Self::new(x, y, x + rect.width as i32, y + rect.height as i32)
+	}
+}
+
+impl From<&Rect> for Rectangle {
+	fn from(rect: &Rect) -> Self {
+		Self {
+			x: rect.x0 as _,
+			y: rect.y0 as _,
+
+			width: (rect.x1 - rect.x0) as _,
+			height: (rect.y1 - rect.y0) as _,
+		}
+	}
+}
+
+/// A set of pixels.
+#[derive(Clone, Debug, Default)]
+pub struct Region {
+	/// The disjoint, non-empty rectangles making the region.
+	rects: Vec<Rect>,
+}
+
+impl From<Rect> for Region {
+	fn from(rect: Rect) -> Self {
+		let mut region = Self::default();
+		region.union_rect(rect);
+		region
+	}
+}
+
+impl Region {
+	/// Tells whether the region contains no pixel.
+	pub fn is_empty(&self) -> bool {
+		self.rects.is_empty()
+	}
+
+	/// Tells whether the pixel at the given position is inside of the region.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		self.rects.iter().any(|r| r.contains(x, y))
+	}
+
+	/// Returns the disjoint rectangles making the region.
+	pub fn get_rects(&self) -> &[Rect] {
+		&self.rects
+	}
+
+	/// Returns the smallest rectangle containing the region.
+	pub fn get_bounds(&self) -> Rect {
+		self.rects
+			.iter()
+			.fold(Rect::default(), |acc, r| acc.bounding(r))
+	}
+
+	/// Removes every pixel from the region.
+	pub fn clear(&mut self) {
+		self.rects.clear();
+	}
+
+	/// Adds the pixels of the given rectangle to the region.
+	pub fn union_rect(&mut self, rect: Rect) {
+		if rect.is_empty() {
+			return;
+		}
+
+		// Rectangles covered by the new one are merged into it
+		self.rects.retain(|r| !rect.contains_rect(r));
+
+		let mut pieces = vec![rect];
+		for r in &self.rects {
+			pieces = pieces.iter().flat_map(|p| p.subtract(r)).collect();
+			if pieces.is_empty() {
+				return;
+			}
+		}
+		self.rects.extend(pieces);
+	}
+
+	/// Adds the pixels of `other` to the region.
+	pub fn union(&mut self, other: &Region) {
+		for r in &other.rects {
+			self.union_rect(*r);
+		}
+	}
+
+	/// Removes the pixels of the given rectangle from the region.
+	pub fn subtract_rect(&mut self, rect: Rect) {
+		if rect.is_empty() {
+			return;
+		}
+
+		self.rects = self.rects.iter().flat_map(|r| r.subtract(&rect)).collect();
+	}
+
+	/// Removes the pixels of `other` from the region.
+	pub fn subtract(&mut self, other: &Region) {
+		for r in &other.rects {
+			self.subtract_rect(*r);
+		}
+	}
+
+	/// Keeps only the pixels of the region that are inside of the given rectangle.
+	pub fn intersect_rect(&mut self, rect: Rect) {
+		self.rects = self
+			.rects
+			.iter()
+			.map(|r| r.intersect(&rect))
+			.filter(|r| !r.is_empty())
+			.collect();
+	}
+
+	/// Keeps only the pixels of the region that are inside of `other`.
+	pub fn intersect(&mut self, other: &Region) {
+		self.rects = self
+			.rects
+			.iter()
+			.flat_map(|a| other.rects.iter().map(move |b| a.intersect(b)))
+			.filter(|r| !r.is_empty())
+			.collect();
+	}
+
+	/// Moves the region by `(dx, dy)`.
+	pub fn translate(&mut self, dx: i32, dy: i32) {
+		for r in &mut self.rects {
+			*r = r.translate(dx, dy);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Returns the pixels of the given region in the area `(0, 0)` to `(width, height)`, one
+	/// string per line: `#` if the pixel is inside, `.` otherwise.
+	///
+	/// The function also checks that the rectangles of the region are disjoint and not empty.
+	fn pixels(region: &Region, width: i32, height: i32) -> Vec<String> {
+		let rects = region.get_rects();
+		for (i, a) in rects.iter().enumerate() {
+			assert!(!a.is_empty());
+			assert!(rects[(i + 1)..].iter().all(|b| a.intersect(b).is_empty()));
+		}
+
+		(0..height)
+			.map(|y| {
+				(0..width)
+					.map(|x| if region.contains(x, y) { '#' } else { '.' })
+					.collect()
+			})
+			.collect()
+	}
+
+	/// Subtracting a rectangle from its middle leaves four pieces around it.
+	#[test]
+	fn rect_subtract() {
+		let outer = Rect::new(0, 0, 4, 4);
+		assert_eq!(outer.subtract(&Rect::new(1, 1, 3, 3)).len(), 4);
+		assert_eq!(outer.subtract(&Rect::new(4, 0, 6, 4)), [outer]);
+		assert_eq!(outer.subtract(&Rect::new(-1, -1, 5, 5)), []);
+	}
+
+	/// The union of overlapping rectangles covers each pixel once.
+	#[test]
+	fn union() {
+		let mut region = Region::from(Rect::new(0, 0, 3, 3));
+		region.union_rect(Rect::new(2, 1, 5, 4));
+		region.union_rect(Rect::new(0, 0, 1, 1));
+		region.union_rect(Rect::new(3, 3, 3, 5));
+		assert_eq!(
+			pixels(&region, 6, 5),
+			["###...", "#####.", "#####.", "..###.", "......",]
+		);
+		assert_eq!(region.get_bounds(), Rect::new(0, 0, 5, 4));
+
+		// A rectangle covering the region replaces it
+		region.union_rect(Rect::new(0, 0, 5, 4));
+		assert_eq!(region.get_rects(), [Rect::new(0, 0, 5, 4)]);
+	}
+
+	/// Subtracting removes the pixels of the other region only.
+	#[test]
+	fn subtract() {
+		let mut region = Region::from(Rect::new(0, 0, 5, 4));
+		let mut hole = Region::from(Rect::new(1, 1, 3, 3));
+		hole.union_rect(Rect::new(4, 0, 6, 1));
+		region.subtract(&hole);
+		assert_eq!(
+			pixels(&region, 6, 4),
+			["####..", "#..##.", "#..##.", "#####.",]
+		);
+
+		region.subtract_rect(Rect::new(-1, -1, 10, 10));
+		assert!(region.is_empty());
+	}
+
+	/// Intersecting keeps the pixels inside of both regions.
+	#[test]
+	fn intersect() {
+		let mut region = Region::from(Rect::new(0, 0, 4, 2));
+		region.union_rect(Rect::new(0, 2, 2, 4));
+		let mut other = Region::from(Rect::new(1, 1, 5, 3));
+		other.union_rect(Rect::new(0, 3, 1, 4));
+		region.intersect(&other);
+		assert_eq!(pixels(&region, 5, 4), [".....", ".###.", ".#...", "#....",]);
+
+		region.intersect_rect(Rect::new(0, 0, 2, 2));
+		assert_eq!(region.get_rects(), [Rect::new(1, 1, 2, 2)]);
+
+		region.translate(2, -1);
+		assert_eq!(region.get_rects(), [Rect::new(3, 0, 4, 1)]);
+	}
+}
@@ -0,0 +1,104 @@
+//! The database of named colors, in the format of the `rgb.txt` file.
+//!
+//! Each line of the file contains the red, green and blue components of a color on 8 bits,
+//! followed by its name. Lines starting with `!` are comments.
+//!
+//! The database is loaded from the system's file when present. Otherwise, a built-in copy is
+//! used.
+
+use super::colormap::Color;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs;
+
+/// The paths at which the database is searched, in order.
+pub const PATHS: &[&str] = &["/usr/share/X11/rgb.txt", "/etc/X11/rgb.txt"];
+
+/// The built-in database.
+const BUILTIN: &str = include_str!("rgb.txt");
+
+lazy_static! {
+	/// The database. The key is the lowercase name of the color.
+	static ref DATABASE: HashMap<String, Color> = {
+		let content = PATHS
+			.iter()
+			.find_map(|path| fs::read_to_string(path).ok());
+		parse(content.as_deref().unwrap_or(BUILTIN))
+	};
+}
+
+/// Parses the given database.
+///
+/// Invalid lines are ignored.
+fn parse(content: &str) -> HashMap<String, Color> {
+	content
+		.lines()
+		.filter(|line| !line.starts_with('!'))
+		.filter_map(|line| {
+			let mut iter = line.split_whitespace();
+			let mut component = || iter.next()?.parse::<u8>().ok().map(|v| v as u16 * 257);
+			let color = Color {
+				red: component()?,
+				green: component()?,
+				blue: component()?,
+			};
+			let name = iter.collect::<Vec<_>>().join(" ");
+			(!name.is_empty()).then(|| (name.to_lowercase(), color))
+		})
+		.collect()
+}
+
+/// Returns the color with the given name. The case of the name is ignored.
+///
+/// If the color doesn't exist, the function returns None.
+pub fn lookup(name: &str) -> Option<Color> {
+	DATABASE.get(&name.to_lowercase()).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Comments and invalid lines are ignored, and names are stored in lowercase.
+	#[test]
+	fn parse_database() {
+		let db = parse(
+			"! comment\n\
+			255 250 250\t\tsnow\n\
+			  0   0 128\t\tNavy Blue\n\
+			256   0   0\t\toverflow\n\
+			  1   2\n\
+			  1   2   3\n",
+		);
+		assert_eq!(db.len(), 2);
+		assert_eq!(
+			db.get("snow"),
+			Some(&Color {
+				red: 0xffff,
+				green: 0xfafa,
+				blue: 0xfafa,
+			})
+		);
+		assert_eq!(
+			db.get("navy blue"),
+			Some(&Color {
+				red: 0,
+				green: 0,
+				blue: 0x8080,
+			})
+		);
+	}
+
+	/// Lookups ignore the case of the name.
+	#[test]
+	fn lookup_builtin() {
+		let black = Color {
+			red: 0,
+			green: 0,
+			blue: 0,
+		};
+		assert_eq!(parse(BUILTIN).get("black"), Some(&black));
+		assert_eq!(lookup("BlAcK"), Some(black));
+		assert_eq!(lookup("not a color"), None);
+	}
+}
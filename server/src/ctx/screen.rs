@@ -3,15 +3,30 @@
 //! Since a desktop can be split on several screens, each screens has its own virtual position to
 //! determine on which screen the pointer must appears when hitting a corner.
 
+use super::cursor::Cursor;
+use super::region::Rect;
+use super::region::Region;
+use super::visual;
+use super::visual::VisualRegistry;
 use crate::output::card::DRICard;
 use crate::output::connector::DRIConnector;
 use crate::output::connector::DRMModeModeinfo;
+use crate::output::cursor::CursorPlane;
 use crate::output::framebuffer::Framebuffer;
 use crate::protocol;
+use std::mem;
 use std::mem::size_of;
 use std::num::NonZeroU32;
 use std::ptr;
 
+/// The minimum number of colormaps installed at the same time on a screen.
+pub const MIN_INSTALLED_MAPS: usize = 1;
+/// The maximum number of colormaps installed at the same time on a screen.
+///
+/// Since composition converts pixels with the colormap of each window, installing a colormap
+/// doesn't change the colors displayed on screen.
+pub const MAX_INSTALLED_MAPS: usize = 8;
+
 /// Structure representing a screen.
 pub struct Screen<'a> {
 	/// A reference to the card device.
@@ -29,6 +44,10 @@ pub struct Screen<'a> {
 	fbs: [Framebuffer<'a>; 2],
 	/// The index of the current framebuffer.
 	curr_fb: usize,
+	/// The areas of each framebuffer that are out of date.
+	damage: [Region; 2],
+	/// The cursor plane of the CRTC. None if the driver doesn't support it.
+	cursor_plane: Option<CursorPlane<'a>>,
 
 	/// The absolute virtual X position of the screen.
 	x: u32,
@@ -37,6 +56,12 @@ pub struct Screen<'a> {
 
 	/// The ID of the root window of the screen.
 	root_win_id: NonZeroU32,
+	/// The ID of the default colormap of the screen.
+	default_colormap: NonZeroU32,
+	/// The installed colormaps, from the most recently installed to the least.
+	installed_colormaps: Vec<NonZeroU32>,
+	/// The visuals and depths supported by the screen.
+	visuals: VisualRegistry,
 }
 
 impl<'a> Screen<'a> {
@@ -49,6 +74,10 @@ impl<'a> Screen<'a> {
 	/// - `y` is the absolute virtual Y position of the screen.
 	/// - `mode` is the current mode of the screen.
 	/// - `root_win_id` is the ID of the root window of the screen.
+	/// - `default_colormap` is the ID of the default colormap of the screen. It is installed
+	///   initially.
+	/// - `visuals` is the registry of the visuals and depths supported by the screen.
+	#[allow(clippy::too_many_arguments)]
 	pub fn new(
 		dev: &'a DRICard,
 		conn: DRIConnector,
@@ -56,6 +85,8 @@ impl<'a> Screen<'a> {
 		x: u32,
 		y: u32,
 		root_win_id: NonZeroU32,
+		default_colormap: NonZeroU32,
+		visuals: VisualRegistry,
 	) -> Self {
 		// TODO Handle error
 		let crtc = conn.get_crtc(&dev).unwrap().crtc_id;
@@ -68,6 +99,12 @@ impl<'a> Screen<'a> {
 		fbs[0].map().unwrap();
 		fbs[1].map().unwrap();
 
+		// The content of new framebuffers is undefined
+		let full = Rect::new(0, 0, mode.hdisplay as _, mode.vdisplay as _);
+		let damage = [Region::from(full), Region::from(full)];
+
+		let cursor_plane = CursorPlane::new(dev, crtc).ok();
+
 		Self {
 			dev,
 
@@ -77,11 +114,16 @@ impl<'a> Screen<'a> {
 
 			fbs,
 			curr_fb: 0,
+			damage,
+			cursor_plane,
 
 			x,
 			y,
 
 			root_win_id,
+			default_colormap,
+			installed_colormaps: vec![default_colormap],
+			visuals,
 		}
 	}
 
@@ -107,6 +149,18 @@ impl<'a> Screen<'a> {
 		(self.mode.hdisplay, self.mode.vdisplay)
 	}
 
+	/// Returns the absolute virtual position of the screen.
+	pub fn get_position(&self) -> (u32, u32) {
+		(self.x, self.y)
+	}
+
+	/// Tells whether the given absolute virtual position is on the screen.
+	pub fn contains(&self, x: i64, y: i64) -> bool {
+		let (width, height) = self.get_screen_size();
+		let (x0, y0) = (self.x as i64, self.y as i64);
+		(x0..x0 + width as i64).contains(&x) && (y0..y0 + height as i64).contains(&y)
+	}
+
 	/// Tells whether two screens are adjacents.
 	///
 	/// This function is commutative.
@@ -125,33 +179,87 @@ impl<'a> Screen<'a> {
 		self.root_win_id
 	}
 
-	/// Returns the protocol representation of the screen.
-	pub fn to_protocol_screen(&self) -> Vec<u8> {
-		// TODO Fill according to screen informations
-		let visual = protocol::Visual {
-			visual_id: 0, // TODO
-			class: protocol::VisualClass::DirectColor,
-			bits_per_rgb_value: 24,
-			colormap_entries: 1 << 8, // TODO
+	/// Returns the registry of the visuals and depths supported by the screen.
+	pub fn get_visuals(&self) -> &VisualRegistry {
+		&self.visuals
+	}
 
-			red_mask: 0xff0000,
-			green_mask: 0x00ff00,
-			blue_mask: 0x0000ff,
+	/// Returns the visual of the screen's root window.
+	pub fn get_root_visual(&self) -> protocol::Visual {
+		self.visuals.get_root_visual()
+	}
 
-			_padding: 0,
-		};
-		let depth = protocol::Depth {
-			depth: 24,
+	/// Returns the visual with the given ID. If the screen doesn't support this visual, the
+	/// function returns None.
+	pub fn get_visual(&self, id: u32) -> Option<protocol::Visual> {
+		self.visuals.get_visual(id).map(|(_, v)| v)
+	}
 
-			_padding0: 0,
+	/// Returns the ID of the default colormap of the screen.
+	pub fn get_default_colormap(&self) -> NonZeroU32 {
+		self.default_colormap
+	}
 
-			visuals_len: 1,
+	/// Returns the installed colormaps, from the most recently installed to the least.
+	pub fn get_installed_colormaps(&self) -> &[NonZeroU32] {
+		&self.installed_colormaps
+	}
 
-			_padding1: 0,
-		};
+	/// Tells whether the given colormap is installed.
+	pub fn is_colormap_installed(&self, id: NonZeroU32) -> bool {
+		self.installed_colormaps.contains(&id)
+	}
+
+	/// Installs the given colormap.
+	///
+	/// If too many colormaps are installed, the least recently installed ones are uninstalled.
+	///
+	/// The function returns the list of colormaps whose state changed.
+	pub fn install_colormap(&mut self, id: NonZeroU32) -> Vec<NonZeroU32> {
+		if self.is_colormap_installed(id) {
+			return vec![];
+		}
+
+		self.installed_colormaps.insert(0, id);
+		let mut changed = vec![id];
+		while self.installed_colormaps.len() > MAX_INSTALLED_MAPS {
+			// Cannot fail since the list is not empty
+			changed.push(self.installed_colormaps.pop().unwrap());
+		}
+		changed
+	}
+
+	/// Uninstalls the given colormap.
+	///
+	/// If too few colormaps would remain installed, the default colormap is installed instead.
+	/// If the colormap is the default one, it remains installed.
+	///
+	/// The function returns the list of colormaps whose state changed.
+	pub fn uninstall_colormap(&mut self, id: NonZeroU32) -> Vec<NonZeroU32> {
+		if !self.is_colormap_installed(id) {
+			return vec![];
+		}
+		let remaining = self.installed_colormaps.len() - 1;
+		if remaining < MIN_INSTALLED_MAPS && id == self.default_colormap {
+			return vec![];
+		}
+
+		self.installed_colormaps.retain(|c| *c != id);
+		let mut changed = vec![id];
+		if remaining < MIN_INSTALLED_MAPS {
+			self.installed_colormaps.insert(0, self.default_colormap);
+			changed.push(self.default_colormap);
+		}
+		changed
+	}
+
+	/// Returns the protocol representation of the screen.
+	pub fn to_protocol_screen(&self) -> Vec<u8> {
+		// TODO Fill according to screen informations
+		let depths = self.visuals.get_depths();
 		let screen = protocol::Screen {
 			root: self.root_win_id.get(),
-			default_colormap: 0, // TODO
+			default_colormap: self.default_colormap.get(),
 			white_pixel: 0xffffff,
 			black_pixel: 0x000000,
 			current_input_masks: 0, // TODO
@@ -161,20 +269,21 @@ impl<'a> Screen<'a> {
 			millimeters_width: self.conn.mm_width as _,
 			millimeters_height: self.conn.mm_height as _,
 
-			min_installed_maps: 1, // TODO
-			max_installed_maps: 1, // TODO
+			min_installed_maps: MIN_INSTALLED_MAPS as _,
+			max_installed_maps: MAX_INSTALLED_MAPS as _,
 
-			root_visual: 0,    // TODO
-			backing_stores: 0, // TODO
-			save_unders: 0,    // TODO
-			root_depth: 24,    // TODO
+			root_visual: self.get_root_visual().visual_id,
+			backing_stores: protocol::BackingStore::Always as _,
+			save_unders: 1,
+			root_depth: visual::ROOT_DEPTH,
 
-			allowed_depths_len: 1, // TODO
+			allowed_depths_len: depths.len() as _,
 		};
 
+		let visuals_count: usize = depths.iter().map(|d| d.visuals.len()).sum();
 		let len = size_of::<protocol::Screen>()
-			+ size_of::<protocol::Depth>()
-			+ size_of::<protocol::Visual>();
+			+ depths.len() * size_of::<protocol::Depth>()
+			+ visuals_count * size_of::<protocol::Visual>();
 		let mut data = vec![0; len];
 
 		let mut off = 0;
@@ -186,18 +295,32 @@ impl<'a> Screen<'a> {
 			);
 			off += size_of::<protocol::Screen>();
 
-			ptr::copy_nonoverlapping::<u8>(
-				&depth as *const _ as *const u8,
-				&mut data[off],
-				size_of::<protocol::Depth>(),
-			);
-			off += size_of::<protocol::Depth>();
-
-			ptr::copy_nonoverlapping::<u8>(
-				&visual as *const _ as *const u8,
-				&mut data[off],
-				size_of::<protocol::Visual>(),
-			);
+			for d in depths {
+				let depth = protocol::Depth {
+					depth: d.depth,
+
+					_padding0: 0,
+
+					visuals_len: d.visuals.len() as _,
+
+					_padding1: 0,
+				};
+				ptr::copy_nonoverlapping::<u8>(
+					&depth as *const _ as *const u8,
+					&mut data[off],
+					size_of::<protocol::Depth>(),
+				);
+				off += size_of::<protocol::Depth>();
+
+				for visual in &d.visuals {
+					ptr::copy_nonoverlapping::<u8>(
+						visual as *const _ as *const u8,
+						&mut data[off],
+						size_of::<protocol::Visual>(),
+					);
+					off += size_of::<protocol::Visual>();
+				}
+			}
 		}
 
 		data
@@ -208,6 +331,78 @@ impl<'a> Screen<'a> {
 		&self.fbs[self.curr_fb]
 	}
 
+	/// Marks the given region of the screen as out of date on every framebuffer.
+	pub fn add_damage(&mut self, region: &Region) {
+		let (width, height) = self.get_screen_size();
+		let mut region = region.clone();
+		region.intersect_rect(Rect::new(0, 0, width as _, height as _));
+
+		for d in &mut self.damage {
+			d.union(&region);
+		}
+	}
+
+	/// Returns the region of the current framebuffer that is out of date, and considers it up to
+	/// date.
+	pub fn take_damage(&mut self) -> Region {
+		mem::take(&mut self.damage[self.curr_fb])
+	}
+
+	/// Displays the given cursor on the cursor plane, with its hotspot at `(x, y)`.
+	///
+	/// If the screen has no cursor plane or if the cursor is too large for it, the function
+	/// returns `false` and the cursor has to be drawn by software. If the driver fails to display
+	/// the cursor, the cursor plane is not used anymore.
+	pub fn show_cursor(&mut self, cursor: &Cursor, x: i32, y: i32) -> bool {
+		let Some(plane) = &mut self.cursor_plane else {
+			return false;
+		};
+		let (width, height) = cursor.get_size();
+		let (max_width, max_height) = plane.get_size();
+		if width as u32 > max_width || height as u32 > max_height {
+			plane.hide();
+			return false;
+		}
+
+		let bounds = cursor.get_bounds(x, y);
+		let res = plane.set_image(width as _, height as _, bounds.x0, bounds.y0, |px, py| {
+			cursor
+				.get_pixel(px as _, py as _)
+				.map(|p| 0xff000000 | p)
+				.unwrap_or(0)
+		});
+		if let Err(e) = res {
+			eprintln!("Cannot display the cursor plane: {}", e);
+			self.cursor_plane = None;
+			return false;
+		}
+		true
+	}
+
+	/// Moves the cursor displayed on the cursor plane so that its hotspot is at `(x, y)`.
+	///
+	/// If the driver fails to move the cursor, the cursor plane is not used anymore and the
+	/// function returns `false`.
+	pub fn move_cursor(&mut self, cursor: &Cursor, x: i32, y: i32) -> bool {
+		let Some(plane) = &self.cursor_plane else {
+			return false;
+		};
+		let bounds = cursor.get_bounds(x, y);
+		if let Err(e) = plane.move_to(bounds.x0, bounds.y0) {
+			eprintln!("Cannot move the cursor plane: {}", e);
+			self.cursor_plane = None;
+			return false;
+		}
+		true
+	}
+
+	/// Hides the cursor displayed on the cursor plane, if any.
+	pub fn hide_cursor(&self) {
+		if let Some(plane) = &self.cursor_plane {
+			plane.hide();
+		}
+	}
+
 	/// Swap frame buffers, thus displaying the next frame to the screen.
 	pub fn swap_buffers(&mut self) {
 		let fb = &self.fbs[self.curr_fb];
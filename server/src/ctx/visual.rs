@@ -0,0 +1,166 @@
+//! The visuals and depths supported by a screen.
+//!
+//! Windows can be created with any of the visuals of the registry. Since framebuffers always use
+//! the same format, the content of windows is converted when composed on screen.
+
+use super::image;
+use crate::protocol::Visual;
+use crate::protocol::VisualClass;
+
+/// The depth of the root window.
+pub const ROOT_DEPTH: u8 = 24;
+
+/// A depth supported by a screen, along with the visuals windows of this depth can use.
+///
+/// Depths without visuals can only be used by pixmaps.
+pub struct VisualDepth {
+	/// The depth.
+	pub depth: u8,
+	/// The visuals of the depth.
+	pub visuals: Vec<Visual>,
+}
+
+/// Creates a visual with the given parameters.
+fn visual(
+	visual_id: u32,
+	class: VisualClass,
+	bits_per_rgb_value: u8,
+	masks: [u32; 3],
+	colormap_entries: u16,
+) -> Visual {
+	Visual {
+		visual_id,
+		class,
+		bits_per_rgb_value,
+		colormap_entries,
+
+		red_mask: masks[0],
+		green_mask: masks[1],
+		blue_mask: masks[2],
+
+		_padding: 0,
+	}
+}
+
+/// The registry of the visuals and depths supported by a screen.
+pub struct VisualRegistry {
+	/// The supported depths, by increasing order.
+	depths: Vec<VisualDepth>,
+	/// The ID of the visual of the root window.
+	root_visual: u32,
+}
+
+impl VisualRegistry {
+	/// Creates the registry of a screen.
+	///
+	/// `first_id` is the ID of the first visual. The following IDs are assigned to the next
+	/// visuals. They must not be used by another screen.
+	pub fn new(first_id: u32) -> Self {
+		let rgb = [0xff0000, 0x00ff00, 0x0000ff];
+		// Depths 1, 4 and 15 have no visual, they are available for pixmaps only
+		let depths = vec![
+			VisualDepth {
+				depth: 1,
+				visuals: vec![],
+			},
+			VisualDepth {
+				depth: 4,
+				visuals: vec![],
+			},
+			VisualDepth {
+				depth: 8,
+				visuals: vec![visual(
+					first_id + 1,
+					VisualClass::PseudoColor,
+					8,
+					[0; 3],
+					256,
+				)],
+			},
+			VisualDepth {
+				depth: 15,
+				visuals: vec![],
+			},
+			VisualDepth {
+				depth: 16,
+				visuals: vec![visual(
+					first_id + 2,
+					VisualClass::TrueColor,
+					6,
+					[0xf800, 0x07e0, 0x001f],
+					64,
+				)],
+			},
+			VisualDepth {
+				depth: ROOT_DEPTH,
+				visuals: vec![visual(first_id, VisualClass::TrueColor, 8, rgb, 256)],
+			},
+			// The alpha channel occupies the remaining bits. Windows are opaque on screen
+			VisualDepth {
+				depth: 32,
+				visuals: vec![visual(first_id + 3, VisualClass::TrueColor, 8, rgb, 256)],
+			},
+		];
+
+		Self {
+			depths,
+			root_visual: first_id,
+		}
+	}
+
+	/// Returns the supported depths, by increasing order.
+	pub fn get_depths(&self) -> &[VisualDepth] {
+		&self.depths
+	}
+
+	/// Tells whether the given depth is supported.
+	pub fn has_depth(&self, depth: u8) -> bool {
+		self.depths.iter().any(|d| d.depth == depth)
+	}
+
+	/// Returns the visual with the given ID, along with its depth.
+	///
+	/// If the visual doesn't exist, the function returns None.
+	pub fn get_visual(&self, id: u32) -> Option<(u8, Visual)> {
+		self.depths.iter().find_map(|d| {
+			d.visuals
+				.iter()
+				.find(|v| v.visual_id == id)
+				.map(|v| (d.depth, *v))
+		})
+	}
+
+	/// Returns the visual of the root window.
+	pub fn get_root_visual(&self) -> Visual {
+		// Cannot fail since the root visual is in the registry
+		self.get_visual(self.root_visual).unwrap().1
+	}
+}
+
+/// Converts the given pixel of the given visual to the format of framebuffers, without using a
+/// colormap.
+///
+/// For visuals having a single index for the three channels, the pixel is interpreted as a gray
+/// level over `depth` bits.
+pub fn to_rgb(visual: &Visual, depth: u8, pixel: u32) -> u32 {
+	let scale = |mask: u32| {
+		if mask == 0 {
+			return 0;
+		}
+		let max = (mask >> mask.trailing_zeros()) as u64;
+		let val = ((pixel & mask) >> mask.trailing_zeros()) as u64;
+		(val * 0xff / max) as u32
+	};
+
+	match visual.class {
+		VisualClass::TrueColor | VisualClass::DirectColor => {
+			(scale(visual.red_mask) << 16)
+				| (scale(visual.green_mask) << 8)
+				| scale(visual.blue_mask)
+		}
+		_ => {
+			let level = scale(image::depth_mask(depth));
+			(level << 16) | (level << 8) | level
+		}
+	}
+}
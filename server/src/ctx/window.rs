@@ -1,19 +1,36 @@
 //! TODO doc
 
+use super::cursor::CursorRef;
+use super::gc::FillStyle;
+use super::gc::SubWindowMode;
+use super::gc::GC;
+use super::image::Image;
+use super::pixmap::PixmapRef;
+use super::region::Region;
+use super::visual;
 use super::Drawable;
 use crate::ctx::Context;
 use crate::ctx::Screen;
+use crate::protocol::error::Error;
 use crate::protocol::BackingStore;
 use crate::protocol::BitGravity;
 use crate::protocol::Class;
+use crate::protocol::Event;
 use crate::protocol::MapState;
 use crate::protocol::Rectangle;
 use crate::protocol::WinGravity;
-use std::cmp::max;
+use crate::raster::rect;
+use crate::raster::Painter;
+use crate::raster::Sources;
+use crate::raster::Target;
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::cell::RefMut;
 use std::cmp::min;
 use std::collections::HashMap;
-use std::collections::HashSet;
+use std::mem;
 use std::num::NonZeroU32;
+use std::slice;
 
 /// A property associated to a window.
 #[derive(Debug)]
@@ -74,6 +91,19 @@ impl Property {
 	}
 }
 
+/// The background of a window.
+#[derive(Clone, Debug)]
+pub enum Background {
+	/// No background. The content of the window is left unchanged when exposed.
+	None,
+	/// The background of the parent window is used, relative to the parent's origin.
+	ParentRelative,
+	/// The window is filled with the given pixel.
+	Pixel(u32),
+	/// The window is tiled with the given pixmap, relative to the window's origin.
+	Pixmap(PixmapRef),
+}
+
 /// Structure storing a window's attributes.
 #[derive(Debug)]
 pub struct WindowAttributes {
@@ -89,7 +119,7 @@ pub struct WindowAttributes {
 	pub bit_gravity: BitGravity,
 	/// TODO doc
 	pub win_gravity: WinGravity,
-	/// TODO doc
+	/// Tells when the server maintains the content of the obscured parts of the window.
 	pub backing_store: BackingStore,
 	/// TODO doc
 	pub backing_planes: u32,
@@ -97,13 +127,14 @@ pub struct WindowAttributes {
 	pub backing_pixel: u32,
 	/// TODO doc
 	pub override_redirect: bool,
-	/// TODO doc
+	/// Tells whether the content of the windows obscured by the window is saved, so that they
+	/// don't have to be exposed when the window is unmapped.
 	pub save_under: bool,
 	/// TODO doc
 	pub event_mask: u32,
 	/// TODO doc
 	pub do_not_propagate_mask: u32,
-	/// TODO doc
+	/// The ID of the colormap of the window. If zero, the window has no colormap.
 	pub colormap: u32,
 	/// TODO doc
 	pub cursor: u32,
@@ -115,8 +146,6 @@ pub struct WindowAttributes {
 	/// TODO doc
 	pub backing_places: u32,
 	/// TODO doc
-	pub map_is_installed: u8,
-	/// TODO doc
 	pub map_state: MapState,
 }
 
@@ -143,12 +172,43 @@ impl Default for WindowAttributes {
 			visual: 0,
 			class: Class::InputOnly,
 			backing_places: 0,
-			map_is_installed: 0,
-			map_state: MapState::Unviewable,
+			map_state: MapState::Unmapped,
 		}
 	}
 }
 
+/// The parts of a window that are visible on screen.
+///
+/// Positions are relative to the origin of the root window.
+#[derive(Clone, Debug, Default)]
+pub struct Visibility {
+	/// The X position of the window's origin.
+	pub x: i32,
+	/// The Y position of the window's origin.
+	pub y: i32,
+	/// The visible part of the inside of the window. It excludes the areas obscured by siblings,
+	/// by siblings of ancestors and by viewable `InputOutput` children.
+	pub inside: Region,
+	/// The visible part of the window's border.
+	pub border: Region,
+	/// The parts of the inside of the window that are not visible but whose content is preserved,
+	/// either by the window's backing store or because they are obscured by save-under windows.
+	///
+	/// Drawing is not clipped on these parts, and they are restored instead of being exposed when
+	/// they become visible.
+	pub saved: Region,
+}
+
+impl Visibility {
+	/// Returns the parts of the window whose content is valid, relative to the window's origin.
+	pub fn get_valid(&self) -> Region {
+		let mut valid = self.inside.clone();
+		valid.union(&self.saved);
+		valid.translate(-self.x, -self.y);
+		valid
+	}
+}
+
 /// A window to be rendered on screen.
 #[derive(Debug)]
 pub struct Window {
@@ -157,8 +217,11 @@ pub struct Window {
 
 	/// The ID of the parent window.
 	parent: Option<NonZeroU32>,
-	/// The list of indexes of the childrens of the current window.
-	children: HashSet<NonZeroU32>,
+	/// The ID of the root window of the window's screen.
+	root: NonZeroU32,
+	/// The list of IDs of the children of the current window, from the bottom to the top of the
+	/// stack.
+	children: Vec<NonZeroU32>,
 
 	/// The depth of the pixmap.
 	depth: u8,
@@ -166,62 +229,106 @@ pub struct Window {
 	rect: Rectangle,
 	/// The width of the window's border.
 	border_width: u16,
+	/// The window's storage. If the window is input-only, there is no storage.
+	image: Option<RefCell<Image>>,
 
 	/// The list of properties of the window. The key is the name of the property.
 	properties: HashMap<String, Property>,
 
 	/// The window's attributes.
 	pub attributes: WindowAttributes,
+	/// The events selected by each client on the window. The key is the ID of the client and the
+	/// value is the event mask.
+	event_masks: HashMap<u32, u32>,
+	/// The window's background.
+	background: Background,
+	/// The pixmap used as the window's border, if any.
+	border_pixmap: Option<PixmapRef>,
+	/// The cursor of the window. If None, the cursor of the parent is used.
+	cursor: Option<CursorRef>,
+
+	/// The visible parts of the window. Empty if the window is not viewable.
+	visibility: Visibility,
 }
 
 impl Window {
-	/// Creates a new root window.
+	/// Creates a new window.
 	///
 	/// Arguments:
 	/// - `ctx` is the context on which the window will be added.
+	/// - `id` is the ID of the window.
 	/// - `parent` is the ID of the parent window. If None, the window is a root window.
 	/// - `rect` represents the position and dimensions of the window relative to its parent.
 	///
-	/// The function allocates an ID for the window and adds it to the given context.
+	/// The function adds the window to the given context.
 	///
 	/// If the window is root, the X/Y position is zero-ed.
+	///
+	/// If the parent window doesn't exist, the function returns an error.
 	pub fn new<'c>(
 		ctx: &'c mut Context,
+		id: NonZeroU32,
 		parent: Option<NonZeroU32>,
 		mut rect: Rectangle,
-	) -> &'c mut Self {
-		let id = NonZeroU32::new(1).unwrap(); // TODO Allocate? Take as param?
+	) -> Result<&'c mut Self, Error> {
+		let root = match parent {
+			Some(parent_id) => {
+				let parent = ctx
+					.get_window_mut(parent_id)
+					.ok_or(Error::Window(parent_id.get()))?;
+				// New windows are placed on top of their siblings
+				parent.children.push(id);
+
+				parent.root
+			}
 
-		if parent.is_none() {
-			rect.x = 0;
-			rect.y = 0;
-		}
+			None => {
+				rect.x = 0;
+				rect.y = 0;
+
+				id
+			}
+		};
 
 		let win = Self {
 			id,
 
-			parent: None,
-			children: HashSet::new(),
+			parent,
+			root,
+			children: Vec::new(),
 
-			depth: 24, // TODO
+			depth: visual::ROOT_DEPTH,
 			rect,
 			border_width: 0,
+			image: None,
 
 			properties: HashMap::new(),
 
 			attributes: WindowAttributes::default(),
-		};
+			event_masks: HashMap::new(),
+			background: Background::None,
+			border_pixmap: None,
+			cursor: None,
 
-		if let Some(parent_id) = win.parent {
-			// TODO Return error if None instead of unwrap
-			let parent = ctx.get_window_mut(parent_id).unwrap();
-			parent.children.insert(id);
-		}
+			visibility: Visibility::default(),
+		};
 
 		// Insert window in context
 		ctx.windows.insert(id, win);
 
-		ctx.windows.get_mut(&id).unwrap()
+		Ok(ctx.windows.get_mut(&id).unwrap())
+	}
+
+	/// Removes the window with the given ID from the context and from the children of its
+	/// parent.
+	///
+	/// The window must have no children. If the window doesn't exist, the function returns None.
+	pub fn remove(ctx: &mut Context, id: NonZeroU32) -> Option<Self> {
+		let win = ctx.windows.remove(&id)?;
+		if let Some(parent) = win.parent.and_then(|p| ctx.windows.get_mut(&p)) {
+			parent.children.retain(|c| *c != id);
+		}
+		Some(win)
 	}
 
 	/// Returns the ID of the window.
@@ -239,23 +346,82 @@ impl Window {
 		self.parent.is_none()
 	}
 
-	/// The list indexes of the children of the window.
-	pub fn get_children(&self) -> &HashSet<NonZeroU32> {
+	/// The list of IDs of the children of the window, from the bottom to the top of the stack.
+	pub fn get_children(&self) -> &[NonZeroU32] {
 		&self.children
 	}
 
-	/// Returns the depth of the window.
-	pub fn set_depth(&mut self, depth: u8) {
+	/// Sets the depth of the window.
+	///
+	/// If the window's storage cannot be allocated, the function returns an error.
+	pub fn set_depth(&mut self, depth: u8) -> Result<(), Error> {
 		self.depth = depth;
+		self.update_image()
+	}
+
+	/// Updates the window's storage according to its class, size and depth.
+	///
+	/// The content of the previous storage is kept where it overlaps the new one. If the
+	/// background is a single pixel, the rest is filled with it.
+	///
+	/// If the new storage cannot be created, the window is left without storage and the function
+	/// returns an error.
+	fn update_image(&mut self) -> Result<(), Error> {
+		if !self.is_output() {
+			self.image = None;
+			return Ok(());
+		}
+
+		let width = self.rect.width;
+		let height = self.rect.height;
+		if let Some(image) = &self.image {
+			let image = image.borrow();
+			if image.get_width() == width
+				&& image.get_height() == height
+				&& image.get_depth() == self.depth
+			{
+				return Ok(());
+			}
+		}
+
+		let mut image = match Image::new(width, height, self.depth) {
+			Ok(image) => image,
+			Err(e) => {
+				self.image = None;
+				return Err(e);
+			}
+		};
+		if let Background::Pixel(pixel) = self.background {
+			image.fill(pixel);
+		}
+
+		if let Some(old) = self.image.take() {
+			let old = old.into_inner();
+			if old.get_depth() == self.depth {
+				let w = min(width, old.get_width()) as usize;
+				let h = min(height, old.get_height()) as usize;
+				for y in 0..h {
+					for x in 0..w {
+						image.set_pixel(x, y, old.get_pixel(x, y));
+					}
+				}
+			}
+		}
+
+		self.image = Some(RefCell::new(image));
+		Ok(())
 	}
 
 	/// Sets the position and size of the window.
-	pub fn set_rectangle(&mut self, rect: Rectangle) {
+	///
+	/// If the window's storage cannot be allocated, the function returns an error.
+	pub fn set_rectangle(&mut self, rect: Rectangle) -> Result<(), Error> {
 		if self.is_root() && (rect.x != 0 || rect.y != 0) {
-			return;
+			return Ok(());
 		}
 
 		self.rect = rect;
+		self.update_image()
 	}
 
 	/// Sets the width of the border.
@@ -287,63 +453,257 @@ impl Window {
 	}
 
 	/// Sets the window's attributes.
-	pub fn set_attributes(&mut self, attr: WindowAttributes) {
+	///
+	/// If the window's storage cannot be allocated, the function returns an error.
+	pub fn set_attributes(&mut self, attr: WindowAttributes) -> Result<(), Error> {
 		self.attributes = attr;
+		self.update_image()
 	}
 
-	/// Tells whether the window can render anything on screen.
-	pub fn is_output(&self) -> bool {
-		matches!(self.attributes.class, Class::InputOutput)
+	/// Returns the mask of events selected by the given client on the window.
+	pub fn get_event_mask(&self, client: u32) -> u32 {
+		self.event_masks.get(&client).cloned().unwrap_or(0)
 	}
 
-	/// Renders the window's background with a single color.
-	pub fn render_pixel_background(&self, screen: &Screen) {
-		let fb = screen.get_curr_fb();
+	/// Selects the events in `mask` for the given client on the window, replacing its previous
+	/// selection.
+	///
+	/// The `event_mask` attribute is updated to the union of the masks of all clients.
+	///
+	/// `SubstructureRedirect`, `ResizeRedirect` and `ButtonPress` can be selected by only one
+	/// client at a time. If another client already selected one of them, the function returns an
+	/// error.
+	pub fn select_events(&mut self, client: u32, mask: u32) -> Result<(), Error> {
+		let exclusive = Event::SubstructureRedirect as u32
+			| Event::ResizeRedirect as u32
+			| Event::ButtonPress as u32;
+		let taken = self
+			.event_masks
+			.iter()
+			.any(|(c, m)| *c != client && m & mask & exclusive != 0);
+		if taken {
+			return Err(Error::Access);
+		}
 
-		let (screen_width, screen_height) = screen.get_screen_size();
+		if mask != 0 {
+			self.event_masks.insert(client, mask);
+		} else {
+			self.event_masks.remove(&client);
+		}
 
-		let x = self.rect.x as isize;
-		let y = self.rect.y as isize;
-		let width = self.rect.width as isize;
-		let height = self.rect.height as isize;
+		self.attributes.event_mask = self.event_masks.values().fold(0, |a, b| a | b);
+		Ok(())
+	}
+
+	/// Returns an iterator over the IDs of the clients having selected at least one of the events
+	/// in `mask` on the window.
+	pub fn get_selecting_clients(&self, mask: u32) -> impl Iterator<Item = u32> + '_ {
+		self.event_masks
+			.iter()
+			.filter(move |(_, m)| *m & mask != 0)
+			.map(|(client, _)| *client)
+	}
 
-		let x_begin = max(x, 0) as usize;
-		let y_begin = max(y, 0) as usize;
-		let x_end = min((x + width) as usize, screen_width as usize);
-		let y_end = min((y + height) as usize, screen_height as usize);
+	/// Returns the window's background.
+	pub fn get_background(&self) -> &Background {
+		&self.background
+	}
 
-		let ptr = fb.get_buffer_ptr().unwrap().as_ptr();
-		for y in y_begin..y_end {
-			for x in x_begin..x_end {
-				let i = y * width as usize + x;
+	/// Sets the window's background.
+	///
+	/// The previous pixmap, if any, is released.
+	///
+	/// The content of the window is not updated.
+	pub fn set_background(&mut self, background: Background) {
+		self.background = background;
+	}
 
-				// TODO use window's background color
-				unsafe {
-					*ptr.add(i) = 0xffffff;
+	/// Returns the background actually used by the window, with the position of its origin
+	/// relative to the window's origin.
+	///
+	/// If the background is `ParentRelative`, the parent's background is used, recursively.
+	pub fn get_effective_background<'c>(
+		&'c self,
+		ctx: &'c Context,
+	) -> Option<(&'c Background, i32, i32)> {
+		let mut win = self;
+		let mut x = 0;
+		let mut y = 0;
+
+		loop {
+			match &win.background {
+				Background::ParentRelative => {
+					let parent = win.parent.and_then(|id| ctx.get_window(id))?;
+					x -= win.rect.x as i32 + win.border_width as i32;
+					y -= win.rect.y as i32 + win.border_width as i32;
+					win = parent;
 				}
+
+				b => return Some((b, x, y)),
 			}
 		}
 	}
 
-	/// Renders the window's background.
-	pub fn render_background(&self, screen: &Screen) {
-		// TODO If a pixmap is specified, render it to background
-		// However, pixel has priority over pixmap
+	/// Fills the given area of the window with its background.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `area` is the area to fill, relative to the window's origin.
+	///
+	/// Only the visible parts of the area are filled. If the window has no background, the
+	/// function does nothing. Otherwise, the area is marked as damaged.
+	pub fn clear(&self, ctx: &Context, area: &Rectangle) {
+		let Some((background, x, y)) = self.get_effective_background(ctx) else {
+			return;
+		};
+
+		let mut gc = GC::new(self.depth, self.root.get());
+		match background {
+			Background::Pixel(pixel) => gc.foreground = *pixel,
 
-		self.render_pixel_background(screen);
+			Background::Pixmap(pixmap) => {
+				gc.fill_style = FillStyle::Tiled;
+				gc.tile = Some(pixmap.clone());
+				gc.tile_stipple_x_origin = x as _;
+				gc.tile_stipple_y_origin = y as _;
+			}
+
+			_ => return,
+		}
+
+		let sources = Sources::from_gc(&gc, self.id);
+		let targets = Target::list(ctx, self.id, self, &SubWindowMode::ClipByChildren);
+		if let Some(mut image) = self.get_image_mut() {
+			let mut painter = Painter::for_target(&mut image, &targets[0], &gc, &sources);
+			rect::poly_fill_rectangle(&mut painter, slice::from_ref(area));
+			ctx.damage(self.id, painter.get_damage());
+		}
 	}
 
-	/// Renders the full window, including children windows.
-	pub fn render_full(&self, ctx: &Context, screen: &Screen) {
-		if self.is_output() {
-			self.render_background(screen);
+	/// Returns the pixmap used as the window's border, if any.
+	pub fn get_border_pixmap(&self) -> Option<&PixmapRef> {
+		self.border_pixmap.as_ref()
+	}
 
-			// TODO render content
+	/// Sets the pixmap used as the window's border.
+	///
+	/// The previous pixmap, if any, is released.
+	pub fn set_border_pixmap(&mut self, pixmap: Option<PixmapRef>) {
+		self.border_pixmap = pixmap;
+	}
+
+	/// Returns the cursor of the window, if any.
+	pub fn get_cursor(&self) -> Option<&CursorRef> {
+		self.cursor.as_ref()
+	}
+
+	/// Sets the cursor of the window. If None, the cursor of the parent is used.
+	///
+	/// The previous cursor, if any, is released.
+	pub fn set_cursor(&mut self, cursor: Option<CursorRef>) {
+		self.cursor = cursor;
+	}
+
+	/// Tells whether the window and all of its ancestors are mapped.
+	pub fn is_viewable(&self) -> bool {
+		matches!(self.attributes.map_state, MapState::Viewable)
+	}
+
+	/// Tells whether the server maintains the content of the obscured parts of the window.
+	pub fn has_backing_store(&self) -> bool {
+		match self.attributes.backing_store {
+			BackingStore::NotUseful => false,
+			BackingStore::WhenMapped => self.is_viewable(),
+			BackingStore::Always => true,
+		}
+	}
+
+	/// Returns the visible parts of the window.
+	pub fn get_visibility(&self) -> &Visibility {
+		&self.visibility
+	}
+
+	/// Sets the visible parts of the window. The function returns the previous value.
+	pub fn set_visibility(&mut self, visibility: Visibility) -> Visibility {
+		mem::replace(&mut self.visibility, visibility)
+	}
+
+	/// Tells whether the window can render anything on screen.
+	pub fn is_output(&self) -> bool {
+		matches!(self.attributes.class, Class::InputOutput)
+	}
+
+	/// Returns the pixel of the window's border at the given position, relative to the window's
+	/// origin.
+	fn get_border_pixel(&self, x: i32, y: i32) -> u32 {
+		let Some(pixmap) = &self.border_pixmap else {
+			return self.attributes.border_pixel;
+		};
+		let Some(image) = pixmap.get_image() else {
+			return self.attributes.border_pixel;
+		};
+
+		// The border tile has the same origin as the background tile
+		let x = x.rem_euclid(image.get_width() as _);
+		let y = y.rem_euclid(image.get_height() as _);
+		image.get_pixel(x as _, y as _)
+	}
+
+	/// Composes the window and its viewable inferiors into the current framebuffer of the screen.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `screen` is the screen the window belongs to.
+	/// - `area` is the region of the screen to recompose.
+	///
+	/// Only the visible parts of the window are composed. Since they don't overlap with the ones
+	/// of other windows, windows can be composed in any order.
+	pub fn compose(&self, ctx: &Context, screen: &Screen, area: &Region) {
+		if !self.is_viewable() {
+			return;
+		}
+
+		let fb = screen.get_curr_fb();
+		let vis = &self.visibility;
+		let colormap = NonZeroU32::new(self.attributes.colormap).and_then(|c| ctx.get_colormap(c));
+		let visual = screen.get_visual(self.attributes.visual);
+		let convert = |pixel: u32| match (self.depth, colormap, &visual) {
+			(1, ..) if pixel != 0 => 0xffffff,
+			(1, ..) => 0,
+			(_, Some(colormap), _) => colormap.get_rgb(pixel),
+			// Without colormap, pixels are interpreted according to the window's visual
+			(_, None, Some(visual)) => visual::to_rgb(visual, self.depth, pixel),
+			(_, None, None) => pixel & 0xffffff,
+		};
+
+		if let Some(image) = self.get_image() {
+			let mut inside = vis.inside.clone();
+			inside.intersect(area);
+
+			for r in inside.get_rects() {
+				for py in r.y0..r.y1 {
+					for px in r.x0..r.x1 {
+						let pixel = image.get_pixel((px - vis.x) as _, (py - vis.y) as _);
+						fb.set_pixel(px as _, py as _, convert(pixel));
+					}
+				}
+			}
+		}
+
+		let mut border = vis.border.clone();
+		border.intersect(area);
+		for r in border.get_rects() {
+			for py in r.y0..r.y1 {
+				for px in r.x0..r.x1 {
+					let pixel = self.get_border_pixel(px - vis.x, py - vis.y);
+					fb.set_pixel(px as _, py as _, convert(pixel));
+				}
+			}
 		}
 
 		for c in &self.children {
-			if let Some(child_win) = ctx.get_window(*c) {
-				child_win.render_full(ctx, screen);
+			if let Some(child) = ctx.get_window(*c) {
+				child.compose(ctx, screen, area);
 			}
 		}
 	}
@@ -355,8 +715,7 @@ impl Drawable for Window {
 	}
 
 	fn get_root(&self) -> u32 {
-		// TODO
-		0
+		self.root.get()
 	}
 
 	fn get_rectangle(&self) -> Rectangle {
@@ -366,4 +725,12 @@ impl Drawable for Window {
 	fn get_border_width(&self) -> u16 {
 		self.border_width
 	}
+
+	fn get_image(&self) -> Option<Ref<'_, Image>> {
+		self.image.as_ref().map(|i| i.borrow())
+	}
+
+	fn get_image_mut(&self) -> Option<RefMut<'_, Image>> {
+		self.image.as_ref().map(|i| i.borrow_mut())
+	}
 }
@@ -0,0 +1,157 @@
+//! Parser for fonts in the Glyph Bitmap Distribution Format (BDF).
+//!
+//! BDF files are text files made of keywords followed by their arguments, one per line. The
+//! bitmap of each character is given as hexadecimal rows.
+
+use super::Font;
+use super::FontProperty;
+use super::Glyph;
+use super::PropValue;
+use super::LEFT_TO_RIGHT;
+use crate::protocol::CharInfo;
+
+/// Parses the value of a property.
+///
+/// Strings are quoted, with quotes inside of them doubled.
+fn parse_prop_value(value: &str) -> Option<PropValue> {
+	let value = value.trim();
+	match value.strip_prefix('"') {
+		Some(s) => {
+			let s = s.strip_suffix('"').unwrap_or(s);
+			Some(PropValue::Str(s.replace("\"\"", "\"")))
+		}
+		None => value.parse().ok().map(PropValue::Int),
+	}
+}
+
+/// Parses the hexadecimal rows of a bitmap, each row being `row_len` bytes long.
+fn parse_bitmap<'a, I: Iterator<Item = &'a str>>(rows: I, row_len: usize) -> Option<Vec<u8>> {
+	let mut bitmap = vec![];
+	for row in rows {
+		for i in 0..row_len {
+			let byte = row
+				.get((i * 2)..(i * 2 + 2))
+				.map(|b| u8::from_str_radix(b, 16))
+				.unwrap_or(Ok(0))
+				.ok()?;
+			bitmap.push(byte);
+		}
+	}
+	Some(bitmap)
+}
+
+/// Parses the given BDF file.
+///
+/// If the file is invalid, the function returns None.
+pub fn parse(data: &str) -> Option<Font> {
+	let mut lines = data.lines().map(str::trim);
+	if !lines.next()?.starts_with("STARTFONT") {
+		return None;
+	}
+
+	let mut properties = vec![];
+	let mut glyphs = vec![];
+	let mut ascent = None;
+	let mut descent = None;
+	let mut default_char = None;
+
+	// The encoding, width and bounding box of the current character
+	let mut encoding: Option<i32> = None;
+	let mut width = 0;
+	let mut bbx = [0i32; 4];
+
+	while let Some(line) = lines.next() {
+		let (keyword, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+		let mut ints = args
+			.split_whitespace()
+			.map(|a| a.parse::<i32>().unwrap_or_default());
+		match keyword {
+			"STARTPROPERTIES" => {
+				for line in lines.by_ref() {
+					if line.starts_with("ENDPROPERTIES") {
+						break;
+					}
+					let (name, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+					let Some(value) = parse_prop_value(value) else {
+						continue;
+					};
+					match (name, &value) {
+						("FONT_ASCENT", PropValue::Int(v)) => ascent = Some(*v as i16),
+						("FONT_DESCENT", PropValue::Int(v)) => descent = Some(*v as i16),
+						("DEFAULT_CHAR", PropValue::Int(v)) => default_char = Some(*v as u16),
+						_ => {}
+					}
+					properties.push(FontProperty {
+						name: name.to_owned(),
+						value,
+					});
+				}
+			}
+
+			"STARTCHAR" => {
+				encoding = None;
+				width = 0;
+				bbx = [0; 4];
+			}
+			// Characters without a standard encoding may have a non-standard one as second
+			// argument
+			"ENCODING" => {
+				let std = ints.next();
+				let non_std = ints.next();
+				encoding = match (std, non_std) {
+					(Some(-1), Some(e)) => Some(e),
+					(e, _) => e,
+				};
+			}
+			"DWIDTH" => width = ints.next()?,
+			"BBX" => {
+				for b in &mut bbx {
+					*b = ints.next()?;
+				}
+			}
+			"BITMAP" => {
+				let rows = lines
+					.by_ref()
+					.take_while(|l| !l.starts_with("ENDCHAR"))
+					.collect::<Vec<_>>();
+				let [w, h, x_off, y_off] = bbx;
+				let row_len = (w.max(0) as usize).div_ceil(8);
+				let height = h.max(0) as usize;
+				// Missing rows are empty
+				let mut bitmap = parse_bitmap(rows.into_iter().take(height), row_len)?;
+				bitmap.resize(row_len * height, 0);
+
+				let Some(code) = encoding.filter(|e| (0..=0xffff).contains(e)) else {
+					continue;
+				};
+				let info = CharInfo {
+					left_side_bearing: x_off as _,
+					right_side_bearing: (x_off + w) as _,
+					character_width: width as _,
+					ascent: (h + y_off) as _,
+					descent: -y_off as _,
+					attributes: 0,
+				};
+				glyphs.push((
+					code as u16,
+					Glyph {
+						info,
+						bitmap,
+					},
+				));
+			}
+
+			"ENDFONT" => break,
+			_ => {}
+		}
+	}
+
+	Font::new(
+		glyphs,
+		properties,
+		ascent,
+		descent,
+		default_char.unwrap_or(0),
+		LEFT_TO_RIGHT,
+	)
+}
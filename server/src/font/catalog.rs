@@ -0,0 +1,403 @@
+//! The catalog lists the fonts available in the directories of the font path.
+//!
+//! Each directory contains a `fonts.dir` file associating font files with their XLFD name, and
+//! optionally a `fonts.alias` file giving other names to fonts.
+//!
+//! An element of the path may also be a catalogue, written `catalogue:<dir>`. Each entry of the
+//! catalogue directory is a link to a font directory. Entries are used in the order of their
+//! names.
+//!
+//! Names are matched case-insensitively against patterns, in which `*` matches any sequence of
+//! characters and `?` matches any single character.
+//!
+//! Built-in fonts are compiled into the server so that `fixed` and `cursor` are always available,
+//! even without font files. They are used when no font of the path matches.
+
+use super::bdf;
+use super::pcf;
+use super::Font;
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::rc::Weak;
+
+/// The directories searched for fonts by default, in order.
+pub const DEFAULT_PATH: &[&str] = &[
+	"/usr/share/fonts/X11/misc",
+	"/usr/share/fonts/X11/75dpi",
+	"/usr/share/fonts/X11/100dpi",
+];
+
+/// The prefix of path elements that are catalogues.
+const CATALOGUE_PREFIX: &str = "catalogue:";
+
+/// The maximum number of aliases followed to resolve a name.
+const MAX_ALIAS_DEPTH: usize = 16;
+
+/// The built-in fonts, with the names they are available under.
+const BUILTINS: &[(&[&str], &str)] = &[
+	(
+		&[
+			"fixed",
+			"-Misc-Fixed-Medium-R-SemiCondensed--13-120-75-75-C-60-ISO8859-1",
+		],
+		include_str!("fixed.bdf"),
+	),
+	(
+		&[
+			"cursor",
+			"-Misc-Cursor-Medium-R-Normal--16-160-75-75-P-0-Adobe-FontSpecific",
+		],
+		include_str!("cursor.bdf"),
+	),
+];
+
+/// Tells whether `name` matches the given pattern. The comparison is case-insensitive.
+pub fn matches(pattern: &str, name: &str) -> bool {
+	let pattern = pattern.as_bytes();
+	let name = name.as_bytes();
+
+	// Position in the pattern after the last `*` and position in the name it matched at
+	let mut backtrack = None;
+	let (mut p, mut n) = (0, 0);
+	while n < name.len() {
+		match pattern.get(p) {
+			Some(b'*') => {
+				backtrack = Some((p + 1, n));
+				p += 1;
+			}
+			Some(c) if *c == b'?' || c.eq_ignore_ascii_case(&name[n]) => {
+				p += 1;
+				n += 1;
+			}
+			_ => {
+				// Extending the match of the last `*` by one character
+				let Some((bp, bn)) = backtrack else {
+					return false;
+				};
+				backtrack = Some((bp, bn + 1));
+				p = bp;
+				n = bn + 1;
+			}
+		}
+	}
+	pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Parses a line of a `fonts.alias` file, which is made of two fields separated by blanks.
+///
+/// Fields can be quoted and characters can be escaped with a backslash.
+fn parse_alias_line(line: &str) -> Option<(String, String)> {
+	let mut fields = vec![];
+	let mut chars = line.chars().peekable();
+	while fields.len() < 2 {
+		while chars.next_if(|c| c.is_whitespace()).is_some() {}
+		chars.peek()?;
+
+		let mut field = String::new();
+		let mut quoted = false;
+		while let Some(c) = chars.next() {
+			match c {
+				'"' => quoted = !quoted,
+				'\\' => field.extend(chars.next()),
+				c if c.is_whitespace() && !quoted => break,
+				c => field.push(c),
+			}
+		}
+		fields.push(field);
+	}
+	let target = fields.pop()?;
+	let name = fields.pop()?;
+	Some((name, target))
+}
+
+/// A directory of the font path.
+struct FontDir {
+	/// The fonts of the directory, with their name and the path to their file.
+	fonts: Vec<(String, PathBuf)>,
+	/// The aliases of the directory, with their name and the name they stand for.
+	aliases: Vec<(String, String)>,
+}
+
+impl FontDir {
+	/// Reads the directory at the given path.
+	///
+	/// Missing or invalid files are ignored.
+	fn read(path: &Path) -> Self {
+		let mut fonts = vec![];
+		if let Ok(content) = fs::read_to_string(path.join("fonts.dir")) {
+			// The first line is the number of fonts
+			for line in content.lines().skip(1) {
+				let Some((file, name)) = line.trim().split_once(char::is_whitespace) else {
+					continue;
+				};
+				fonts.push((name.trim().to_owned(), path.join(file)));
+			}
+		}
+
+		let mut aliases = vec![];
+		if let Ok(content) = fs::read_to_string(path.join("fonts.alias")) {
+			for line in content.lines() {
+				let line = line.trim();
+				if line.is_empty() || line.starts_with('!') {
+					continue;
+				}
+				// The names of the files of the directory, without extension, become aliases
+				if line == "FILE_NAMES_ALIASES" {
+					for (name, file) in &fonts {
+						let Some(file_name) = file.file_name().and_then(|f| f.to_str()) else {
+							continue;
+						};
+						let alias = file_name.split('.').next().unwrap_or(file_name);
+						aliases.push((alias.to_owned(), name.clone()));
+					}
+					continue;
+				}
+				aliases.extend(parse_alias_line(line));
+			}
+		}
+
+		Self {
+			fonts,
+			aliases,
+		}
+	}
+}
+
+/// Returns the font directories of the given path element.
+///
+/// If the element is a catalogue, the directories it links to are returned. Missing directories
+/// are ignored.
+fn read_element(elem: &str) -> Vec<FontDir> {
+	let Some(catalogue) = elem.strip_prefix(CATALOGUE_PREFIX) else {
+		return vec![FontDir::read(Path::new(elem))];
+	};
+	let Ok(entries) = fs::read_dir(catalogue) else {
+		return vec![];
+	};
+	let mut entries = entries
+		.filter_map(Result::ok)
+		.map(|e| e.path())
+		.collect::<Vec<_>>();
+	entries.sort();
+	// Links are followed by `is_dir`
+	entries
+		.into_iter()
+		.filter(|p| p.is_dir())
+		.map(|p| FontDir::read(&p))
+		.collect()
+}
+
+/// Tells whether the given path element is valid.
+///
+/// A font directory is valid if it contains a `fonts.dir` file. A catalogue is valid if it is a
+/// directory.
+pub fn is_valid_element(elem: &str) -> bool {
+	match elem.strip_prefix(CATALOGUE_PREFIX) {
+		Some(catalogue) => Path::new(catalogue).is_dir(),
+		None => Path::new(elem).join("fonts.dir").is_file(),
+	}
+}
+
+/// The location of a font.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Source {
+	/// The path to a font file.
+	File(PathBuf),
+	/// The index of a built-in font.
+	Builtin(usize),
+}
+
+/// Returns the first built-in font matching the given pattern.
+fn builtin(pattern: &str) -> Option<Source> {
+	BUILTINS
+		.iter()
+		.position(|(names, _)| names.iter().any(|name| matches(pattern, name)))
+		.map(Source::Builtin)
+}
+
+/// Loads the font in the file at the given path.
+///
+/// Files compressed with gzip are decompressed first. If the file cannot be read or is invalid,
+/// the function returns None.
+fn load(path: &Path) -> Option<Font> {
+	let raw = fs::read(path).ok()?;
+	let name = path.to_str()?;
+	let (data, name) = match name.strip_suffix(".gz") {
+		Some(name) => {
+			let mut data = vec![];
+			GzDecoder::new(raw.as_slice()).read_to_end(&mut data).ok()?;
+			(data, name)
+		}
+		None => (raw, name),
+	};
+
+	if name.ends_with(".pcf") {
+		pcf::parse(&data)
+	} else if name.ends_with(".bdf") {
+		bdf::parse(&String::from_utf8_lossy(&data))
+	} else {
+		None
+	}
+}
+
+/// The catalog of available fonts.
+pub struct FontCatalog {
+	/// The path restored when the path is reset.
+	default_path: Vec<String>,
+	/// The elements of the font path, in order.
+	path: Vec<String>,
+	/// The content of each directory of the path.
+	dirs: Vec<FontDir>,
+	/// The fonts currently loaded.
+	loaded: HashMap<Source, Weak<Font>>,
+}
+
+impl FontCatalog {
+	/// Creates a catalog with the fonts of the given path, which is also the default path.
+	pub fn new(path: Vec<String>) -> Self {
+		let mut catalog = Self {
+			default_path: path.clone(),
+			path: vec![],
+			dirs: vec![],
+			loaded: HashMap::new(),
+		};
+		catalog.set_path(path);
+		catalog
+	}
+
+	/// Returns the elements of the font path.
+	pub fn get_path(&self) -> &[String] {
+		&self.path
+	}
+
+	/// Sets the font path, reading the content of its directories.
+	///
+	/// Fonts that are already loaded remain available to their users.
+	pub fn set_path(&mut self, path: Vec<String>) {
+		self.dirs = path.iter().flat_map(|p| read_element(p)).collect();
+		self.path = path;
+	}
+
+	/// Restores the default font path.
+	pub fn reset_path(&mut self) {
+		self.set_path(self.default_path.clone());
+	}
+
+	/// Returns the names matching the given pattern, without duplicates.
+	///
+	/// `max` is the maximum number of names to return.
+	pub fn list(&self, pattern: &str, max: usize) -> Vec<String> {
+		let mut names: Vec<String> = vec![];
+		let all = self.dirs.iter().flat_map(|d| {
+			let fonts = d.fonts.iter().map(|(name, _)| name.as_str());
+			let aliases = d.aliases.iter().map(|(name, _)| name.as_str());
+			fonts.chain(aliases)
+		});
+		let builtins = BUILTINS.iter().flat_map(|(names, _)| names.iter().copied());
+		let all = all.chain(builtins);
+		for name in all.filter(|name| matches(pattern, name)) {
+			if names.len() >= max {
+				break;
+			}
+			if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+				names.push(name.to_owned());
+			}
+		}
+		names
+	}
+
+	/// Returns the location of the first font matching the given pattern. Built-in fonts come
+	/// after the fonts of the path.
+	///
+	/// `depth` is the number of aliases followed so far.
+	fn resolve(&self, pattern: &str, depth: usize) -> Option<Source> {
+		if depth > MAX_ALIAS_DEPTH {
+			return None;
+		}
+		self.dirs
+			.iter()
+			.find_map(|d| {
+				d.fonts
+					.iter()
+					.find(|(name, _)| matches(pattern, name))
+					.map(|(_, file)| Source::File(file.clone()))
+					.or_else(|| {
+						d.aliases
+							.iter()
+							.filter(|(name, _)| matches(pattern, name))
+							.find_map(|(_, target)| self.resolve(target, depth + 1))
+					})
+			})
+			.or_else(|| builtin(pattern))
+	}
+
+	/// Opens the first font matching the given pattern.
+	///
+	/// If the font is already loaded, it is shared. If the file of the font cannot be loaded, the
+	/// built-in font matching the pattern is used instead, so that built-in fonts are always
+	/// available. If no font can be opened, the function returns None.
+	pub fn open(&mut self, pattern: &str) -> Option<Rc<Font>> {
+		let source = self.resolve(pattern, 0)?;
+		self.open_source(source).or_else(|| {
+			let source = builtin(pattern)?;
+			self.open_source(source)
+		})
+	}
+
+	/// Opens the font at the given location.
+	///
+	/// If the font is already loaded, it is shared. If it cannot be loaded, the function returns
+	/// None.
+	fn open_source(&mut self, source: Source) -> Option<Rc<Font>> {
+		if let Some(font) = self.loaded.get(&source).and_then(Weak::upgrade) {
+			return Some(font);
+		}
+
+		let font = match &source {
+			Source::File(path) => load(path)?,
+			Source::Builtin(i) => bdf::parse(BUILTINS[*i].1)?,
+		};
+		let font = Rc::new(font);
+		self.loaded.retain(|_, f| f.strong_count() > 0);
+		self.loaded.insert(source, Rc::downgrade(&font));
+		Some(font)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The built-in `fixed` font matches the XLFD name of the font it replaces.
+	#[test]
+	fn builtin_xlfd() {
+		let mut catalog = FontCatalog::new(vec![]);
+		assert!(catalog
+			.open("-misc-fixed-medium-r-semicondensed--13-*")
+			.is_some());
+	}
+
+	/// When the file of a font shadowing a built-in font cannot be loaded, the built-in font is
+	/// used.
+	#[test]
+	fn broken_file_fallback() {
+		let dir = std::env::temp_dir().join(format!("visto-fonts-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let name = "-misc-fixed-medium-r-semicondensed--13-120-75-75-c-60-iso8859-1";
+		fs::write(dir.join("fonts.dir"), format!("1\nbroken.pcf {name}\n")).unwrap();
+		fs::write(dir.join("fonts.alias"), format!("fixed {name}\n")).unwrap();
+		fs::write(dir.join("broken.pcf"), "not a font").unwrap();
+
+		let mut catalog = FontCatalog::new(vec![dir.to_str().unwrap().to_owned()]);
+		let fixed = catalog.open("fixed");
+		let xlfd = catalog.open(name);
+		fs::remove_dir_all(&dir).unwrap();
+		assert!(fixed.is_some());
+		assert!(xlfd.is_some());
+	}
+}
@@ -0,0 +1,352 @@
+//! Core fonts are bitmap fonts stored on the server and used to draw text.
+//!
+//! Fonts are loaded from PCF or BDF files, found in the directories of the font path.
+//!
+//! Characters are identified by a 16 bits code. For fonts with a single row of characters, the
+//! code is the index of the character. Otherwise, the most significant byte is the row (`byte1`)
+//! and the least significant byte is the column (`byte2`).
+
+pub mod bdf;
+pub mod catalog;
+pub mod pcf;
+
+use crate::protocol::CharInfo;
+use std::cmp::max;
+use std::cmp::min;
+
+/// Draw direction: left to right.
+pub const LEFT_TO_RIGHT: u8 = 0;
+/// Draw direction: right to left.
+pub const RIGHT_TO_LEFT: u8 = 1;
+
+/// The value of a font property.
+#[derive(Clone, Debug)]
+pub enum PropValue {
+	/// An integer.
+	Int(i32),
+	/// A string. It is interned as an atom when sent to clients.
+	Str(String),
+}
+
+/// A property of a font, such as its family name or its point size.
+#[derive(Clone, Debug)]
+pub struct FontProperty {
+	/// The name of the property.
+	pub name: String,
+	/// The value of the property.
+	pub value: PropValue,
+}
+
+/// A character of a font.
+pub struct Glyph {
+	/// The metrics of the character.
+	pub info: CharInfo,
+	/// The bitmap of the character. Rows go from top to bottom. Each row is padded to a byte and
+	/// the most significant bit of each byte is the leftmost pixel.
+	///
+	/// The bitmap covers the bounding box of the character, from the left side bearing to the
+	/// right side bearing and from the ascent to the descent.
+	pub bitmap: Vec<u8>,
+}
+
+impl Glyph {
+	/// Returns the width of the glyph's bitmap in pixels.
+	pub fn get_width(&self) -> i32 {
+		max(
+			self.info.right_side_bearing as i32 - self.info.left_side_bearing as i32,
+			0,
+		)
+	}
+
+	/// Returns the height of the glyph's bitmap in pixels.
+	pub fn get_height(&self) -> i32 {
+		max(self.info.ascent as i32 + self.info.descent as i32, 0)
+	}
+
+	/// Tells whether the pixel at the given position of the glyph's bitmap is set.
+	///
+	/// Positions outside of the bitmap are not set.
+	pub fn get_pixel(&self, x: i32, y: i32) -> bool {
+		if x < 0 || y < 0 || x >= self.get_width() || y >= self.get_height() {
+			return false;
+		}
+		let row_len = (self.get_width() as usize).div_ceil(8);
+		let byte = self
+			.bitmap
+			.get(y as usize * row_len + x as usize / 8)
+			.copied()
+			.unwrap_or(0);
+		byte & (0x80 >> (x % 8)) != 0
+	}
+}
+
+/// The extents of a string of characters.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TextExtents {
+	/// The maximum ascent of the characters.
+	pub overall_ascent: i16,
+	/// The maximum descent of the characters.
+	pub overall_descent: i16,
+	/// The sum of the widths of the characters.
+	pub overall_width: i32,
+	/// The leftmost extent of the characters, relative to the origin of the string.
+	pub overall_left: i32,
+	/// The rightmost extent of the characters, relative to the origin of the string.
+	pub overall_right: i32,
+}
+
+/// Returns the bounds of the given metrics. The first element is made of the minimum of each
+/// field and the second of the maximum.
+fn compute_bounds<'a, I: Iterator<Item = &'a CharInfo>>(infos: I) -> (CharInfo, CharInfo) {
+	let mut bounds: Option<(CharInfo, CharInfo)> = None;
+	for i in infos {
+		let Some((min_b, max_b)) = &mut bounds else {
+			bounds = Some((*i, *i));
+			continue;
+		};
+		macro_rules! update {
+			($field:ident) => {
+				min_b.$field = min(min_b.$field, i.$field);
+				max_b.$field = max(max_b.$field, i.$field);
+			};
+		}
+		update!(left_side_bearing);
+		update!(right_side_bearing);
+		update!(character_width);
+		update!(ascent);
+		update!(descent);
+		update!(attributes);
+	}
+	bounds.unwrap_or_default()
+}
+
+/// Structure representing a loaded font.
+pub struct Font {
+	/// The properties of the font.
+	properties: Vec<FontProperty>,
+
+	/// The first column of characters.
+	min_char_or_byte2: u16,
+	/// The last column of characters.
+	max_char_or_byte2: u16,
+	/// The first row of characters.
+	min_byte1: u8,
+	/// The last row of characters.
+	max_byte1: u8,
+	/// The character used in place of undefined ones.
+	default_char: u16,
+	/// The direction in which characters are drawn.
+	draw_direction: u8,
+
+	/// The logical extent of the font above the baseline.
+	font_ascent: i16,
+	/// The logical extent of the font below the baseline.
+	font_descent: i16,
+
+	/// The characters of the font, row by row. Undefined characters are None.
+	glyphs: Vec<Option<Glyph>>,
+	/// The minimum of each metric over every character.
+	min_bounds: CharInfo,
+	/// The maximum of each metric over every character.
+	max_bounds: CharInfo,
+}
+
+impl Font {
+	/// Creates a font.
+	///
+	/// Arguments:
+	/// - `glyphs` is the list of characters of the font, along with their code.
+	/// - `properties` is the list of properties of the font.
+	/// - `ascent` and `descent` are the logical extents of the font. If None, the maximum extents
+	///   of the characters are used.
+	/// - `default_char` is the character used in place of undefined ones.
+	/// - `draw_direction` is the direction in which characters are drawn.
+	///
+	/// If the font has no character, the function returns None.
+	pub fn new(
+		glyphs: Vec<(u16, Glyph)>,
+		properties: Vec<FontProperty>,
+		ascent: Option<i16>,
+		descent: Option<i16>,
+		default_char: u16,
+		draw_direction: u8,
+	) -> Option<Self> {
+		// Characters with empty metrics don't exist
+		let glyphs = glyphs
+			.into_iter()
+			.filter(|(_, g)| g.info != CharInfo::default())
+			.collect::<Vec<_>>();
+
+		let byte1 = || glyphs.iter().map(|(c, _)| (c >> 8) as u8);
+		let byte2 = || glyphs.iter().map(|(c, _)| c & 0xff);
+		let mut font = Self {
+			properties,
+
+			min_char_or_byte2: byte2().min()?,
+			max_char_or_byte2: byte2().max()?,
+			min_byte1: byte1().min()?,
+			max_byte1: byte1().max()?,
+			default_char,
+			draw_direction,
+
+			font_ascent: 0,
+			font_descent: 0,
+
+			glyphs: vec![],
+			min_bounds: CharInfo::default(),
+			max_bounds: CharInfo::default(),
+		};
+
+		(font.min_bounds, font.max_bounds) = compute_bounds(glyphs.iter().map(|(_, g)| &g.info));
+		font.font_ascent = ascent.unwrap_or(font.max_bounds.ascent);
+		font.font_descent = descent.unwrap_or(font.max_bounds.descent);
+
+		let len = font.get_columns() * (font.max_byte1 - font.min_byte1 + 1) as usize;
+		font.glyphs.resize_with(len, || None);
+		for (c, g) in glyphs {
+			// Cannot fail since the ranges include every character
+			let i = font.get_index(c).unwrap();
+			font.glyphs[i] = Some(g);
+		}
+
+		Some(font)
+	}
+
+	/// Returns the number of characters on each row.
+	fn get_columns(&self) -> usize {
+		(self.max_char_or_byte2 - self.min_char_or_byte2 + 1) as usize
+	}
+
+	/// Returns the index of the given character in the list of glyphs. If the character is
+	/// outside of the font's ranges, the function returns None.
+	fn get_index(&self, c: u16) -> Option<usize> {
+		let byte1 = (c >> 8) as u8;
+		let byte2 = c & 0xff;
+		if !(self.min_byte1..=self.max_byte1).contains(&byte1)
+			|| !(self.min_char_or_byte2..=self.max_char_or_byte2).contains(&byte2)
+		{
+			return None;
+		}
+		let row = (byte1 - self.min_byte1) as usize;
+		let col = (byte2 - self.min_char_or_byte2) as usize;
+		Some(row * self.get_columns() + col)
+	}
+
+	/// Returns the properties of the font.
+	pub fn get_properties(&self) -> &[FontProperty] {
+		&self.properties
+	}
+
+	/// Returns the first column of characters.
+	pub fn get_min_char_or_byte2(&self) -> u16 {
+		self.min_char_or_byte2
+	}
+
+	/// Returns the last column of characters.
+	pub fn get_max_char_or_byte2(&self) -> u16 {
+		self.max_char_or_byte2
+	}
+
+	/// Returns the first row of characters.
+	pub fn get_min_byte1(&self) -> u8 {
+		self.min_byte1
+	}
+
+	/// Returns the last row of characters.
+	pub fn get_max_byte1(&self) -> u8 {
+		self.max_byte1
+	}
+
+	/// Returns the character used in place of undefined ones.
+	pub fn get_default_char(&self) -> u16 {
+		self.default_char
+	}
+
+	/// Returns the direction in which characters are drawn.
+	pub fn get_draw_direction(&self) -> u8 {
+		self.draw_direction
+	}
+
+	/// Returns the logical extent of the font above the baseline.
+	pub fn get_font_ascent(&self) -> i16 {
+		self.font_ascent
+	}
+
+	/// Returns the logical extent of the font below the baseline.
+	pub fn get_font_descent(&self) -> i16 {
+		self.font_descent
+	}
+
+	/// Returns the minimum of each metric over every character.
+	pub fn get_min_bounds(&self) -> &CharInfo {
+		&self.min_bounds
+	}
+
+	/// Returns the maximum of each metric over every character.
+	pub fn get_max_bounds(&self) -> &CharInfo {
+		&self.max_bounds
+	}
+
+	/// Tells whether every character in the font's ranges is defined.
+	pub fn all_chars_exist(&self) -> bool {
+		self.glyphs.iter().all(Option::is_some)
+	}
+
+	/// Returns the metrics of every character in the font's ranges, row by row. Undefined
+	/// characters have empty metrics.
+	pub fn get_char_infos(&self) -> Vec<CharInfo> {
+		self.glyphs
+			.iter()
+			.map(|g| g.as_ref().map(|g| g.info).unwrap_or_default())
+			.collect()
+	}
+
+	/// Tells whether the given character is defined in the font.
+	pub fn is_defined(&self, c: u16) -> bool {
+		self.get_index(c)
+			.and_then(|i| self.glyphs.get(i))
+			.is_some_and(Option::is_some)
+	}
+
+	/// Returns the glyph to draw for the given character.
+	///
+	/// If the character is undefined, the default character is used. If it is undefined too, the
+	/// function returns None and nothing is drawn.
+	pub fn get_glyph(&self, c: u16) -> Option<&Glyph> {
+		let get = |c| self.glyphs.get(self.get_index(c)?)?.as_ref();
+		get(c).or_else(|| get(self.default_char))
+	}
+
+	/// Returns the extents of the given string of characters.
+	pub fn text_extents(&self, text: &[u16]) -> TextExtents {
+		let mut extents: Option<TextExtents> = None;
+		for info in text
+			.iter()
+			.filter_map(|c| self.get_glyph(*c))
+			.map(|g| &g.info)
+		{
+			let Some(e) = &mut extents else {
+				extents = Some(TextExtents {
+					overall_ascent: info.ascent,
+					overall_descent: info.descent,
+					overall_width: info.character_width as _,
+					overall_left: info.left_side_bearing as _,
+					overall_right: info.right_side_bearing as _,
+				});
+				continue;
+			};
+			e.overall_ascent = max(e.overall_ascent, info.ascent);
+			e.overall_descent = max(e.overall_descent, info.descent);
+			e.overall_left = min(
+				e.overall_left,
+				e.overall_width + info.left_side_bearing as i32,
+			);
+			e.overall_right = max(
+				e.overall_right,
+				e.overall_width + info.right_side_bearing as i32,
+			);
+			e.overall_width += info.character_width as i32;
+		}
+		extents.unwrap_or_default()
+	}
+}
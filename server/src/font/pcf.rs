@@ -0,0 +1,331 @@
+//! Parser for fonts in the Portable Compiled Format (PCF).
+//!
+//! A PCF file starts with a table of contents pointing to tables. Each table begins with a
+//! format word telling the byte order of its content and, for bitmaps, their padding.
+
+use super::Font;
+use super::FontProperty;
+use super::Glyph;
+use super::PropValue;
+use super::LEFT_TO_RIGHT;
+use crate::protocol;
+use crate::protocol::CharInfo;
+
+/// The magic number at the beginning of PCF files.
+const MAGIC: &[u8] = b"\x01fcp";
+
+/// Table type: properties.
+const PCF_PROPERTIES: u32 = 1 << 0;
+/// Table type: accelerators.
+const PCF_ACCELERATORS: u32 = 1 << 1;
+/// Table type: metrics.
+const PCF_METRICS: u32 = 1 << 2;
+/// Table type: bitmaps.
+const PCF_BITMAPS: u32 = 1 << 3;
+/// Table type: encodings.
+const PCF_BDF_ENCODINGS: u32 = 1 << 5;
+/// Table type: accelerators, as defined in the BDF file the font has been compiled from.
+const PCF_BDF_ACCELERATORS: u32 = 1 << 8;
+
+/// Format flag: the metrics are compressed.
+const PCF_COMPRESSED_METRICS: u32 = 0x100;
+/// Format flag: the most significant byte comes first.
+const PCF_BYTE_MASK: u32 = 1 << 2;
+/// Format flag: the most significant bit comes first.
+const PCF_BIT_MASK: u32 = 1 << 3;
+
+/// An entry of the table of contents.
+struct TocEntry {
+	/// The type of the table.
+	table_type: u32,
+	/// The offset of the table in the file.
+	offset: usize,
+}
+
+/// Reader over the content of a table.
+struct Reader<'a> {
+	/// The content of the file.
+	data: &'a [u8],
+	/// The current offset.
+	off: usize,
+	/// Tells whether the most significant byte comes first.
+	msb: bool,
+}
+
+impl<'a> Reader<'a> {
+	/// Creates a reader for the table at the given offset.
+	///
+	/// The function returns the reader along with the format of the table.
+	fn table(data: &'a [u8], offset: usize) -> Option<(Self, u32)> {
+		let mut reader = Self {
+			data,
+			off: offset,
+			msb: false,
+		};
+		let format = reader.u32()?;
+		reader.msb = format & PCF_BYTE_MASK != 0;
+		Some((reader, format))
+	}
+
+	/// Reads the given number of bytes.
+	fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+		let b = self.data.get(self.off..(self.off + len))?;
+		self.off += len;
+		Some(b)
+	}
+
+	/// Reads a byte.
+	fn u8(&mut self) -> Option<u8> {
+		Some(self.bytes(1)?[0])
+	}
+
+	/// Reads a 16 bits integer.
+	fn i16(&mut self) -> Option<i16> {
+		let b = self.bytes(2)?.try_into().ok()?;
+		Some(if self.msb {
+			i16::from_be_bytes(b)
+		} else {
+			i16::from_le_bytes(b)
+		})
+	}
+
+	/// Reads a 32 bits integer.
+	fn u32(&mut self) -> Option<u32> {
+		let b = self.bytes(4)?.try_into().ok()?;
+		Some(if self.msb {
+			u32::from_be_bytes(b)
+		} else {
+			u32::from_le_bytes(b)
+		})
+	}
+
+	/// Reads uncompressed metrics.
+	fn metrics(&mut self) -> Option<CharInfo> {
+		Some(CharInfo {
+			left_side_bearing: self.i16()?,
+			right_side_bearing: self.i16()?,
+			character_width: self.i16()?,
+			ascent: self.i16()?,
+			descent: self.i16()?,
+			attributes: self.i16()? as _,
+		})
+	}
+
+	/// Reads compressed metrics.
+	fn compressed_metrics(&mut self) -> Option<CharInfo> {
+		let mut field = || Some(self.u8()? as i16 - 0x80);
+		Some(CharInfo {
+			left_side_bearing: field()?,
+			right_side_bearing: field()?,
+			character_width: field()?,
+			ascent: field()?,
+			descent: field()?,
+			attributes: 0,
+		})
+	}
+}
+
+/// Returns the null-terminated string at the given offset of `strings`.
+fn get_string(strings: &[u8], offset: usize) -> Option<String> {
+	let s = strings.get(offset..)?;
+	let end = s.iter().position(|b| *b == 0).unwrap_or(s.len());
+	Some(String::from_utf8_lossy(&s[..end]).into_owned())
+}
+
+/// Parses the properties table.
+fn parse_properties(data: &[u8], offset: usize) -> Option<Vec<FontProperty>> {
+	let (mut r, _) = Reader::table(data, offset)?;
+	let count = r.u32()? as usize;
+	let raw = (0..count)
+		.map(|_| Some((r.u32()?, r.u8()? != 0, r.u32()?)))
+		.collect::<Option<Vec<_>>>()?;
+	// The list is padded to four bytes
+	r.bytes(protocol::pad(count))?;
+	let strings_len = r.u32()? as usize;
+	let strings = r.bytes(strings_len)?;
+
+	raw.into_iter()
+		.map(|(name, is_string, value)| {
+			let value = if is_string {
+				PropValue::Str(get_string(strings, value as _)?)
+			} else {
+				PropValue::Int(value as _)
+			};
+			Some(FontProperty {
+				name: get_string(strings, name as _)?,
+				value,
+			})
+		})
+		.collect()
+}
+
+/// Parses an accelerators table. The function returns the ascent and descent of the font, along
+/// with its draw direction.
+fn parse_accelerators(data: &[u8], offset: usize) -> Option<(i16, i16, u8)> {
+	let (mut r, _) = Reader::table(data, offset)?;
+	// Skipping noOverlap, constantMetrics, terminalFont, constantWidth, inkInside and inkMetrics
+	r.bytes(6)?;
+	let draw_direction = r.u8()?;
+	r.u8()?;
+	let ascent = r.u32()? as i32;
+	let descent = r.u32()? as i32;
+	Some((ascent as _, descent as _, draw_direction))
+}
+
+/// Parses the metrics table.
+fn parse_metrics(data: &[u8], offset: usize) -> Option<Vec<CharInfo>> {
+	let (mut r, format) = Reader::table(data, offset)?;
+	if format & PCF_COMPRESSED_METRICS != 0 {
+		let count = r.i16()? as u16;
+		(0..count).map(|_| r.compressed_metrics()).collect()
+	} else {
+		let count = r.u32()?;
+		(0..count).map(|_| r.metrics()).collect()
+	}
+}
+
+/// Converts the bitmap of a glyph to the layout of [`Glyph::bitmap`].
+///
+/// Arguments:
+/// - `src` is the bitmap in the file's layout.
+/// - `format` is the format of the bitmaps table.
+/// - `info` is the metrics of the glyph.
+fn convert_bitmap(src: &[u8], format: u32, info: &CharInfo) -> Vec<u8> {
+	let width = (info.right_side_bearing as i32 - info.left_side_bearing as i32).max(0) as usize;
+	let height = (info.ascent as i32 + info.descent as i32).max(0) as usize;
+	let glyph_pad = 1 << (format & 0x3);
+	let scan_unit = 1 << ((format >> 4) & 0x3);
+	let msb_byte = format & PCF_BYTE_MASK != 0;
+	let msb_bit = format & PCF_BIT_MASK != 0;
+
+	let row_len = width.div_ceil(8);
+	let src_row_len = row_len.div_ceil(glyph_pad) * glyph_pad;
+	let mut bitmap = Vec::with_capacity(row_len * height);
+	for y in 0..height {
+		let mut row = src
+			.get((y * src_row_len)..((y + 1) * src_row_len))
+			.map(<[u8]>::to_vec)
+			.unwrap_or_else(|| vec![0; src_row_len]);
+		// Bytes are ordered within scan units like bits are ordered within bytes
+		if msb_byte != msb_bit && scan_unit > 1 {
+			for unit in row.chunks_mut(scan_unit) {
+				unit.reverse();
+			}
+		}
+		if !msb_bit {
+			for b in &mut row {
+				*b = b.reverse_bits();
+			}
+		}
+		bitmap.extend_from_slice(&row[..row_len]);
+	}
+	bitmap
+}
+
+/// Parses the bitmaps table, returning the bitmap of each glyph.
+fn parse_bitmaps(data: &[u8], offset: usize, metrics: &[CharInfo]) -> Option<Vec<Vec<u8>>> {
+	let (mut r, format) = Reader::table(data, offset)?;
+	let count = r.u32()? as usize;
+	let offsets = (0..count)
+		.map(|_| Some(r.u32()? as usize))
+		.collect::<Option<Vec<_>>>()?;
+	let sizes = (0..4)
+		.map(|_| Some(r.u32()? as usize))
+		.collect::<Option<Vec<_>>>()?;
+	let bitmaps = r.bytes(sizes[(format & 0x3) as usize])?;
+
+	offsets
+		.iter()
+		.zip(metrics)
+		.map(|(off, info)| Some(convert_bitmap(bitmaps.get(*off..)?, format, info)))
+		.collect()
+}
+
+/// Parses the encodings table.
+///
+/// The function returns the code of each glyph, along with the default character.
+fn parse_encodings(data: &[u8], offset: usize) -> Option<(Vec<(u16, usize)>, u16)> {
+	let (mut r, _) = Reader::table(data, offset)?;
+	let min_byte2 = r.i16()? as u16;
+	let max_byte2 = r.i16()? as u16;
+	let min_byte1 = r.i16()? as u16;
+	let max_byte1 = r.i16()? as u16;
+	let default_char = r.i16()? as u16;
+
+	let mut codes = vec![];
+	for byte1 in min_byte1..=max_byte1 {
+		for byte2 in min_byte2..=max_byte2 {
+			let index = r.i16()? as u16;
+			if index != 0xffff {
+				codes.push(((byte1 << 8) | byte2, index as usize));
+			}
+		}
+	}
+	Some((codes, default_char))
+}
+
+/// Parses the given PCF file.
+///
+/// If the file is invalid, the function returns None.
+pub fn parse(data: &[u8]) -> Option<Font> {
+	if !data.starts_with(MAGIC) {
+		return None;
+	}
+	let mut r = Reader {
+		data,
+		off: MAGIC.len(),
+		msb: false,
+	};
+	let count = r.u32()?;
+	let toc = (0..count)
+		.map(|_| {
+			let table_type = r.u32()?;
+			// Skipping the format and size, which are repeated in the table
+			r.bytes(8)?;
+			let offset = r.u32()? as usize;
+			Some(TocEntry {
+				table_type,
+				offset,
+			})
+		})
+		.collect::<Option<Vec<_>>>()?;
+	let find = |table_type| {
+		toc.iter()
+			.find(|e| e.table_type == table_type)
+			.map(|e| e.offset)
+	};
+
+	let properties = match find(PCF_PROPERTIES) {
+		Some(off) => parse_properties(data, off)?,
+		None => vec![],
+	};
+	let accelerators = find(PCF_BDF_ACCELERATORS)
+		.or_else(|| find(PCF_ACCELERATORS))
+		.and_then(|off| parse_accelerators(data, off));
+	let metrics = parse_metrics(data, find(PCF_METRICS)?)?;
+	let bitmaps = parse_bitmaps(data, find(PCF_BITMAPS)?, &metrics)?;
+	let (codes, default_char) = parse_encodings(data, find(PCF_BDF_ENCODINGS)?)?;
+
+	let glyphs = codes
+		.into_iter()
+		.filter_map(|(code, index)| {
+			let glyph = Glyph {
+				info: *metrics.get(index)?,
+				bitmap: bitmaps.get(index)?.clone(),
+			};
+			Some((code, glyph))
+		})
+		.collect();
+	let (ascent, descent, draw_direction) = match accelerators {
+		Some((ascent, descent, dir)) => (Some(ascent), Some(descent), dir),
+		None => (None, None, LEFT_TO_RIGHT),
+	};
+	Font::new(
+		glyphs,
+		properties,
+		ascent,
+		descent,
+		default_char,
+		draw_direction,
+	)
+}
@@ -0,0 +1,172 @@
+//! Pointer acceleration makes the pointer move faster when the device moves fast, so that the
+//! whole screen can be crossed without moving the device too much while keeping precision for
+//! slow motions.
+//!
+//! The acceleration is a multiplier, given as a fraction, applied to relative motions according
+//! to the selected profile. Absolute motions are never accelerated.
+
+/// The default numerator of the acceleration multiplier.
+pub const DEFAULT_NUMERATOR: u16 = 2;
+/// The default denominator of the acceleration multiplier.
+pub const DEFAULT_DENOMINATOR: u16 = 1;
+/// The default threshold of the acceleration, in device units.
+pub const DEFAULT_THRESHOLD: u16 = 4;
+
+/// The way the multiplier is applied to motions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccelProfile {
+	/// The multiplier is applied to every motion, whatever its speed.
+	Flat,
+	/// The multiplier is applied to motions of more than `threshold` units. Slower motions are
+	/// not accelerated.
+	#[default]
+	Threshold,
+	/// The acceleration grows smoothly with the speed of the motion, reaching the multiplier at
+	/// twice the threshold.
+	Adaptive,
+}
+
+impl AccelProfile {
+	/// Returns the profile with the given name. If the name is unknown, the function returns
+	/// None.
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"flat" => Some(Self::Flat),
+			"threshold" => Some(Self::Threshold),
+			"adaptive" => Some(Self::Adaptive),
+			_ => None,
+		}
+	}
+}
+
+/// The acceleration of a pointer.
+#[derive(Debug)]
+pub struct Acceleration {
+	/// The profile of the acceleration.
+	pub profile: AccelProfile,
+	/// The numerator of the multiplier.
+	pub numerator: u16,
+	/// The denominator of the multiplier. Never zero.
+	pub denominator: u16,
+	/// The speed from which motions are accelerated, in device units.
+	pub threshold: u16,
+
+	/// The fractional parts of the accelerated motions not applied yet, so that slow motions are
+	/// not lost to rounding.
+	remainder: (f64, f64),
+}
+
+impl Default for Acceleration {
+	fn default() -> Self {
+		Self {
+			profile: AccelProfile::default(),
+			numerator: DEFAULT_NUMERATOR,
+			denominator: DEFAULT_DENOMINATOR,
+			threshold: DEFAULT_THRESHOLD,
+
+			remainder: (0., 0.),
+		}
+	}
+}
+
+impl Acceleration {
+	/// Returns the multiplier to apply to a motion of the given delta.
+	fn get_factor(&self, delta_x: i32, delta_y: i32) -> f64 {
+		let accel = self.numerator as f64 / self.denominator.max(1) as f64;
+		let threshold = self.threshold as f64;
+		match self.profile {
+			AccelProfile::Flat => accel,
+			// Same as the X11 sample server: the speed is the sum of both axes
+			AccelProfile::Threshold => {
+				if (delta_x.abs() + delta_y.abs()) as f64 > threshold {
+					accel
+				} else {
+					1.
+				}
+			}
+			AccelProfile::Adaptive => {
+				let speed = (delta_x as f64).hypot(delta_y as f64);
+				let ratio = if threshold > 0. {
+					(speed / (2. * threshold)).min(1.)
+				} else {
+					1.
+				};
+				1. + (accel - 1.) * ratio
+			}
+		}
+	}
+
+	/// Applies the acceleration to the given relative motion, returning the resulting motion.
+	pub fn apply(&mut self, delta_x: i32, delta_y: i32) -> (i32, i32) {
+		let factor = self.get_factor(delta_x, delta_y);
+		let x = delta_x as f64 * factor + self.remainder.0;
+		let y = delta_y as f64 * factor + self.remainder.1;
+		let (x_int, y_int) = (x.trunc(), y.trunc());
+		self.remainder = (x - x_int, y - y_int);
+		(x_int as _, y_int as _)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Returns an acceleration with the given profile and parameters.
+	fn accel(
+		profile: AccelProfile,
+		numerator: u16,
+		denominator: u16,
+		threshold: u16,
+	) -> Acceleration {
+		Acceleration {
+			profile,
+			numerator,
+			denominator,
+			threshold,
+
+			..Default::default()
+		}
+	}
+
+	/// Motions up to the threshold are not accelerated, the speed being the sum of both axes.
+	#[test]
+	fn threshold() {
+		let mut a = accel(AccelProfile::Threshold, 2, 1, 4);
+		assert_eq!(a.apply(4, 0), (4, 0));
+		assert_eq!(a.apply(2, -2), (2, -2));
+		assert_eq!(a.apply(3, 2), (6, 4));
+		assert_eq!(a.apply(-3, -2), (-6, -4));
+		assert_eq!(a.apply(0, -5), (0, -10));
+	}
+
+	/// Every motion is accelerated, fractional parts being kept for the next motions.
+	#[test]
+	fn flat() {
+		let mut a = accel(AccelProfile::Flat, 3, 2, 100);
+		assert_eq!(a.apply(3, 1), (4, 1));
+		assert_eq!(a.apply(1, 1), (2, 2));
+		assert_eq!(a.apply(-1, 0), (-1, 0));
+		assert_eq!(a.apply(-1, 0), (-2, 0));
+	}
+
+	/// A denominator of zero is handled as one.
+	#[test]
+	fn zero_denominator() {
+		let mut a = accel(AccelProfile::Flat, 3, 0, 0);
+		assert_eq!(a.apply(2, -1), (6, -3));
+	}
+
+	/// The multiplier grows with the speed, up to twice the threshold.
+	#[test]
+	fn adaptive() {
+		let mut a = accel(AccelProfile::Adaptive, 3, 1, 5);
+		assert_eq!(a.apply(0, 0), (0, 0));
+		assert_eq!(a.apply(3, 4), (6, 8));
+		assert_eq!(a.apply(-6, -8), (-18, -24));
+		assert_eq!(a.apply(-30, 40), (-90, 120));
+
+		// Without threshold, every motion has the full multiplier
+		let mut a = accel(AccelProfile::Adaptive, 3, 1, 0);
+		assert_eq!(a.apply(1, 0), (3, 0));
+	}
+}
@@ -1,12 +1,19 @@
 //! TODO doc
 
+use super::info::Bits;
+use super::info::DeviceInfo;
+use super::translate::Translator;
+use super::Input;
 use crate::util;
+use std::collections::VecDeque;
 use std::ffi::c_int;
 use std::ffi::c_short;
 use std::fs::File;
+use std::fs::OpenOptions;
 use std::io;
 use std::io::Read;
 use std::mem::size_of;
+use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::prelude::AsRawFd;
 use std::path::PathBuf;
 
@@ -426,6 +433,9 @@ pub const KEY_FN_S: u16 = 0x1e3;
 pub const KEY_FN_B: u16 = 0x1e4;
 pub const KEY_FN_RIGHT_SHIFT: u16 = 0x1e5;
 
+pub const KEY_MAX: u16 = 0x2ff;
+pub const KEY_CNT: u16 = KEY_MAX + 1;
+
 /*
  * Relative axes
  */
@@ -515,7 +525,8 @@ pub const ABS_MT_TOOL_Y: u16 = 0x3d;
 pub const ABS_MAX: u16 = 0x3f;
 pub const ABS_CNT: u16 = ABS_MAX + 1;
 
-// TODO Allow buffering of several events at once
+/// Evdev ioctl command base.
+pub const EVDEV_IOCTL_BASE: u64 = b'E' as u64;
 
 /// EvDev notifies events in the format represented by this structure.
 #[derive(Clone, Copy)]
@@ -529,6 +540,8 @@ pub struct EvDevInputEvent {
 
 /// Structure representing an input device.
 pub struct InputDevice {
+	/// The path to the device's file.
+	path: PathBuf,
 	/// The device's file.
 	file: File,
 
@@ -536,40 +549,80 @@ pub struct InputDevice {
 	buff: [u8; size_of::<EvDevInputEvent>()],
 	/// The cursor on the buffer.
 	cursor: usize,
+
+	/// The capabilities of the device.
+	info: DeviceInfo,
+	/// Translates the events of the device into inputs.
+	translator: Translator,
 }
 
 impl InputDevice {
 	/// Returns a device from the given device file's path.
+	///
+	/// The device is probed to know its capabilities. If the file is not an evdev device, the
+	/// function returns an error.
 	pub fn from_path(path: &PathBuf) -> io::Result<Self> {
+		let file = OpenOptions::new()
+			.read(true)
+			.custom_flags(libc::O_NONBLOCK)
+			.open(path)?;
+		let info = DeviceInfo::probe(&file)?;
+		let translator = Translator::new(&info);
+
 		Ok(Self {
-			file: File::open(path)?,
+			path: path.clone(),
+			file,
 
 			buff: [0; size_of::<EvDevInputEvent>()],
 			cursor: 0,
+
+			info,
+			translator,
 		})
 	}
 
-	/// Returns the next event. The function blocks until at least one event is available.
-	///
-	/// If EOF has been reached, the function returns None.
+	/// Returns the path to the device's file.
+	pub fn get_path(&self) -> &PathBuf {
+		&self.path
+	}
+
+	/// Returns the capabilities of the device.
+	pub fn get_info(&self) -> &DeviceInfo {
+		&self.info
+	}
+
+	/// Returns the next event. If no event is available, the function returns None.
 	pub fn next(&mut self) -> io::Result<Option<EvDevInputEvent>> {
-		loop {
-			let len = self.file.read(&mut self.buff[self.cursor..])?;
-			if len == 0 {
-				break;
+		while self.cursor < size_of::<EvDevInputEvent>() {
+			match self.file.read(&mut self.buff[self.cursor..]) {
+				Ok(0) => return Ok(None),
+				Ok(len) => self.cursor += len,
+				Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+				Err(e) => return Err(e),
 			}
-
-			self.cursor += len;
 		}
 
-		if self.cursor >= size_of::<EvDevInputEvent>() {
-			let ev = *unsafe { util::reinterpret(&self.buff) };
-			self.cursor = 0;
+		let ev = *unsafe { util::reinterpret(&self.buff) };
+		self.cursor = 0;
+		Ok(Some(ev))
+	}
 
-			Ok(Some(ev))
-		} else {
-			Ok(None)
+	/// Reads every available event and pushes the inputs they translate into to `inputs`.
+	pub fn read_inputs(&mut self, inputs: &mut VecDeque<Input>) -> io::Result<()> {
+		while let Some(ev) = self.next()? {
+			self.translator.push(&ev, inputs);
+			if self.translator.is_desync() {
+				let keys = Bits::read_keys(&self.file)?;
+				self.translator.resync(|code| keys.has(code), inputs);
+			}
 		}
+		Ok(())
+	}
+
+	/// Releases every key and button currently pressed on the device, pushing the corresponding
+	/// inputs to `inputs`.
+	pub fn release_all(&mut self, inputs: &mut VecDeque<Input>) {
+		self.translator.release_all(inputs);
 	}
 }
 
@@ -0,0 +1,106 @@
+//! Hotplug allows to notice devices that are plugged or unplugged while the server is running.
+//!
+//! The directory of device files is watched with inotify. Device files are created by the kernel
+//! (or udev) when a device is plugged, and their permissions may be adjusted afterwards, in which
+//! case opening them succeeds only once the permissions changed.
+
+use std::ffi::CString;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::mem::size_of;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::io::FromRawFd;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The size of the buffer used to read events.
+const BUFF_SIZE: usize = 4096;
+
+/// A change in the watched directory.
+#[derive(Debug)]
+pub enum HotplugEvent {
+	/// The file at the given path has been created, or its attributes changed.
+	Added(PathBuf),
+	/// The file at the given path has been removed.
+	Removed(PathBuf),
+}
+
+/// Watches a directory for files being created or removed.
+pub struct Watcher {
+	/// The inotify instance.
+	file: File,
+	/// The watched directory.
+	dir: PathBuf,
+}
+
+impl Watcher {
+	/// Starts watching the given directory.
+	pub fn new(dir: &Path) -> io::Result<Self> {
+		let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+		if fd < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		// Takes ownership so that the file descriptor is closed on error
+		let file = unsafe { File::from_raw_fd(fd) };
+
+		let path = CString::new(dir.as_os_str().as_bytes())
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+		let mask = libc::IN_CREATE | libc::IN_ATTRIB | libc::IN_MOVED_TO | libc::IN_DELETE;
+		let res = unsafe { libc::inotify_add_watch(fd, path.as_ptr(), mask) };
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(Self {
+			file,
+			dir: dir.to_path_buf(),
+		})
+	}
+
+	/// Returns the changes that happened in the directory since the last call. If no change
+	/// happened, the function returns an empty list.
+	pub fn read_events(&mut self) -> io::Result<Vec<HotplugEvent>> {
+		let mut events = vec![];
+		let mut buff = [0u8; BUFF_SIZE];
+		loop {
+			let len = match self.file.read(&mut buff) {
+				Ok(0) => break,
+				Ok(len) => len,
+				Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+				Err(e) => return Err(e),
+			};
+
+			// The kernel only returns whole events
+			let mut off = 0;
+			while off + size_of::<libc::inotify_event>() <= len {
+				let ev: libc::inotify_event =
+					unsafe { std::ptr::read_unaligned(buff[off..].as_ptr() as *const _) };
+				off += size_of::<libc::inotify_event>();
+				let name = &buff[off..(off + ev.len as usize).min(len)];
+				off += ev.len as usize;
+
+				// The name is padded with zeros
+				let name_len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+				if name_len == 0 {
+					continue;
+				}
+				let path = self.dir.join(OsStr::from_bytes(&name[..name_len]));
+				if ev.mask & libc::IN_DELETE != 0 {
+					events.push(HotplugEvent::Removed(path));
+				} else {
+					events.push(HotplugEvent::Added(path));
+				}
+			}
+		}
+		Ok(events)
+	}
+}
+
+impl AsRawFd for Watcher {
+	fn as_raw_fd(&self) -> i32 {
+		self.file.as_raw_fd()
+	}
+}
@@ -0,0 +1,226 @@
+//! Devices are probed when they are opened, to know what they are able to report.
+//!
+//! According to their capabilities, devices are classified as keyboards, mice, touchpads,
+//! touchscreens, tablets or switches. A device may belong to several classes, or to none if it
+//! reports nothing the server is interested in.
+
+use super::device::*;
+use crate::ioc;
+use crate::ioctl::IOC_READ;
+use crate::ior;
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+/// The maximum length of a device's name.
+const NAME_MAX: usize = 256;
+
+/// ioctl command: Get the ID of the device.
+const EVIOCGID: u64 = ior!(EVDEV_IOCTL_BASE, 0x02, InputId);
+
+/// ioctl command: Get the state of an absolute axis. The axis is added to the command's number.
+const EVIOCGABS: u64 = ior!(EVDEV_IOCTL_BASE, 0x40, InputAbsInfo);
+
+/// Returns the ioctl command to get the name of the device, written on `len` bytes.
+const fn eviocgname(len: usize) -> u64 {
+	ioc!(IOC_READ, EVDEV_IOCTL_BASE, 0x06, len as u64)
+}
+
+/// Returns the ioctl command to get the properties of the device, written on `len` bytes.
+const fn eviocgprop(len: usize) -> u64 {
+	ioc!(IOC_READ, EVDEV_IOCTL_BASE, 0x09, len as u64)
+}
+
+/// Returns the ioctl command to get the codes of events of type `ev` supported by the device,
+/// written on `len` bytes. If `ev` is zero, the command gets the supported event types.
+const fn eviocgbit(ev: u16, len: usize) -> u64 {
+	ioc!(IOC_READ, EVDEV_IOCTL_BASE, 0x20 + ev as u64, len as u64)
+}
+
+/// The identifiers of a device.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct InputId {
+	/// The type of bus the device is connected to.
+	pub bustype: u16,
+	/// The ID of the device's vendor.
+	pub vendor: u16,
+	/// The ID of the product.
+	pub product: u16,
+	/// The version of the product.
+	pub version: u16,
+}
+
+/// The state of an absolute axis.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct InputAbsInfo {
+	/// The current value.
+	pub value: i32,
+	/// The minimum value.
+	pub minimum: i32,
+	/// The maximum value.
+	pub maximum: i32,
+	/// The noise of the values.
+	pub fuzz: i32,
+	/// The size of the dead zone around the center.
+	pub flat: i32,
+	/// The resolution of the values, in units per millimeter.
+	pub resolution: i32,
+}
+
+/// A class of devices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceClass {
+	/// A keyboard, having letter keys.
+	Keyboard,
+	/// A mouse, reporting relative motions.
+	Mouse,
+	/// A touchpad, reporting the absolute position of a finger on a surface that isn't the
+	/// screen.
+	Touchpad,
+	/// A touchscreen, reporting the absolute position of a finger on the screen.
+	Touchscreen,
+	/// A tablet, reporting the absolute position of a pen.
+	Tablet,
+	/// A switch, such as the lid of a laptop.
+	Switch,
+}
+
+/// Returns the ioctl command to get the keys currently pressed on the device, written on `len`
+/// bytes.
+const fn eviocgkey(len: usize) -> u64 {
+	ioc!(IOC_READ, EVDEV_IOCTL_BASE, 0x18, len as u64)
+}
+
+/// A set of bits, as returned by evdev.
+pub struct Bits(Vec<u8>);
+
+impl Bits {
+	/// Reads the set of bits returned by the given ioctl command, which must not return more than
+	/// `count` bits.
+	///
+	/// `cmd` returns the command for the given length in bytes.
+	fn read<F: Fn(usize) -> u64>(file: &File, cmd: F, count: u16) -> io::Result<Self> {
+		let mut bits = vec![0u8; (count as usize).div_ceil(8)];
+		let res = unsafe { libc::ioctl(file.as_raw_fd(), cmd(bits.len()), bits.as_mut_ptr()) };
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(Self(bits))
+	}
+
+	/// Reads the set of keys currently pressed on the device of the given file.
+	pub fn read_keys(file: &File) -> io::Result<Self> {
+		Self::read(file, eviocgkey, KEY_CNT)
+	}
+
+	/// Tells whether the given bit is set.
+	pub fn has(&self, bit: u16) -> bool {
+		let bit = bit as usize;
+		self.0
+			.get(bit / 8)
+			.is_some_and(|b| b & (1 << (bit % 8)) != 0)
+	}
+}
+
+/// The capabilities of a device.
+#[derive(Debug)]
+pub struct DeviceInfo {
+	/// The name of the device.
+	pub name: String,
+	/// The identifiers of the device.
+	pub id: InputId,
+
+	/// The range of the X absolute axis, if the device has one.
+	pub abs_x: Option<InputAbsInfo>,
+	/// The range of the Y absolute axis, if the device has one.
+	pub abs_y: Option<InputAbsInfo>,
+
+	/// The classes of the device.
+	pub classes: Vec<DeviceClass>,
+}
+
+impl DeviceInfo {
+	/// Probes the device of the given file.
+	///
+	/// If the file is not an evdev device, the function returns an error.
+	pub fn probe(file: &File) -> io::Result<Self> {
+		let fd = file.as_raw_fd();
+
+		let mut name = [0u8; NAME_MAX];
+		let res = unsafe { libc::ioctl(fd, eviocgname(name.len()), name.as_mut_ptr()) };
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+		let name = String::from_utf8_lossy(&name[..len]).into_owned();
+
+		let mut id = InputId::default();
+		let res = unsafe { libc::ioctl(fd, EVIOCGID, &mut id as *mut _) };
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		// Old kernels don't support properties
+		let props = Bits::read(file, eviocgprop, INPUT_PROP_CNT).unwrap_or(Bits(vec![]));
+		let types = Bits::read(file, |len| eviocgbit(0, len), EV_CNT)?;
+		let keys = Bits::read(file, |len| eviocgbit(EV_KEY, len), KEY_CNT)?;
+		let rels = Bits::read(file, |len| eviocgbit(EV_REL, len), REL_CNT)?;
+		let abs = Bits::read(file, |len| eviocgbit(EV_ABS, len), ABS_CNT)?;
+
+		let abs_info = |axis| {
+			let mut info = InputAbsInfo::default();
+			let res = unsafe { libc::ioctl(fd, EVIOCGABS + axis as u64, &mut info as *mut _) };
+			(res >= 0).then_some(info)
+		};
+		let has_abs = types.has(EV_ABS) && abs.has(ABS_X) && abs.has(ABS_Y);
+		let (abs_x, abs_y) = if has_abs {
+			(abs_info(ABS_X), abs_info(ABS_Y))
+		} else {
+			(None, None)
+		};
+
+		let mut classes = vec![];
+		let has_keys = types.has(EV_KEY);
+		if has_keys
+			&& [KEY_Q, KEY_A, KEY_Z, KEY_SPACE]
+				.iter()
+				.all(|k| keys.has(*k))
+		{
+			classes.push(DeviceClass::Keyboard);
+		}
+		if has_keys && types.has(EV_REL) && rels.has(REL_X) && rels.has(REL_Y) {
+			classes.push(DeviceClass::Mouse);
+		}
+		if has_keys && abs_x.is_some() && abs_y.is_some() {
+			if keys.has(BTN_TOOL_PEN) || keys.has(BTN_STYLUS) {
+				classes.push(DeviceClass::Tablet);
+			} else if props.has(INPUT_PROP_DIRECT) {
+				classes.push(DeviceClass::Touchscreen);
+			} else if keys.has(BTN_TOOL_FINGER) {
+				classes.push(DeviceClass::Touchpad);
+			} else if keys.has(BTN_TOUCH) {
+				classes.push(DeviceClass::Touchscreen);
+			}
+		}
+		if types.has(EV_SW) {
+			classes.push(DeviceClass::Switch);
+		}
+
+		Ok(Self {
+			name,
+			id,
+
+			abs_x,
+			abs_y,
+
+			classes,
+		})
+	}
+
+	/// Tells whether the device belongs to the given class.
+	pub fn is(&self, class: DeviceClass) -> bool {
+		self.classes.contains(&class)
+	}
+}
@@ -6,14 +6,22 @@
 //! - A touchpad
 //! - A touchscreen
 
+pub mod accel;
 pub mod device;
+pub mod hotplug;
+pub mod info;
+pub mod translate;
 
 use crate::poll::PollHandler;
-use device::EvDevInputEvent;
 use device::InputDevice;
+use hotplug::HotplugEvent;
+use hotplug::Watcher;
+use std::collections::VecDeque;
 use std::fs;
 use std::io;
 use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::path::PathBuf;
 
 /// The path to the directory containing evdev device files.
 const EV_DEV_DIR: &str = "/dev/input";
@@ -21,32 +29,47 @@ const EV_DEV_DIR: &str = "/dev/input";
 /// A keycode.
 pub type Keycode = u8;
 
-/// Enumeration of mouse button.
-#[derive(Debug)]
+/// The smallest keycode. Keycodes are the evdev key codes offset by this value.
+pub const MIN_KEYCODE: Keycode = 8;
+/// The largest keycode.
+pub const MAX_KEYCODE: Keycode = 255;
+
+/// Absolute positions are scaled to the range `0..=ABS_RANGE` on each axis.
+pub const ABS_RANGE: u32 = 0xffff;
+
+/// Enumeration of mouse button. The value of each variant is the number of the button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
 pub enum MouseButton {
 	/// Left click.
-	Button1,
-	/// Right click.
-	Button2,
+	Button1 = 1,
 	/// Middle click.
-	Button3,
+	Button2 = 2,
+	/// Right click.
+	Button3 = 3,
 	/// Scroll up.
-	Button4,
+	Button4 = 4,
 	/// Scroll down.
-	Button5,
+	Button5 = 5,
+	/// Scroll left.
+	Button6 = 6,
+	/// Scroll right.
+	Button7 = 7,
+	/// Back side button.
+	Button8 = 8,
+	/// Forward side button.
+	Button9 = 9,
 }
 
-// TODO Specify units in doc
 /// An enumeration of input actions.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Input {
-	// TODO Specify keycodes
-	/// Keyboard key press.
+	/// Keyboard key press, with the X keycode of the key.
 	KeyPress(Keycode),
-	/// Keyboard key release.
+	/// Keyboard key release, with the X keycode of the key.
 	KeyRelease(Keycode),
 
-	/// Moving the cursor relative to the previous position.
+	/// Moving the cursor relative to the previous position, in device units.
 	RelativeMove {
 		/// The X delta relative to the previous position.
 		delta_x: i32,
@@ -54,7 +77,7 @@ pub enum Input {
 		delta_y: i32,
 	},
 
-	/// Moving the cursor to an absolute position.
+	/// Moving the cursor to an absolute position, scaled to the range `0..=ABS_RANGE`.
 	AbsoluteMove {
 		/// The X position.
 		x: u32,
@@ -69,86 +92,292 @@ pub enum Input {
 	// TODO touchpad
 }
 
-impl TryFrom<EvDevInputEvent> for Input {
-	type Error = ();
-
-	fn try_from(ev: EvDevInputEvent) -> Result<Self, Self::Error> {
-		// TODO
-		println!("input: {} {} {}", ev.r#type, ev.code, ev.value);
-		Err(())
-	}
-}
-
 /// Structure managing input devices.
 pub struct InputManager {
+	/// Watches the directory of devices to notice plugged and unplugged devices.
+	watcher: Watcher,
 	/// The list of devices.
 	devs: Vec<InputDevice>,
+	/// The inputs translated but not consumed yet.
+	pending: VecDeque<Input>,
 }
 
 impl InputManager {
 	/// Creates a new instance.
 	///
 	/// The function registers devices to the given poll handler in order to wake it up when a
-	/// device is ready for reading.
+	/// device is ready for reading or when a device is plugged or unplugged.
 	pub fn new(poll: &mut PollHandler) -> io::Result<Self> {
-		let mut devs = vec![];
+		// Watching first so that no device is missed between the scan and the watch
+		let watcher = Watcher::new(Path::new(EV_DEV_DIR))?;
+		poll.add_fd(&watcher);
+
+		let mut manager = Self {
+			watcher,
+			devs: vec![],
+			pending: VecDeque::new(),
+		};
 		for ent in fs::read_dir(EV_DEV_DIR)? {
 			let ent = ent?;
 			let ent_type = ent.file_type()?;
 			if ent_type.is_dir() {
 				continue;
 			}
+			if let Err(e) = manager.add_device(&ent.path(), poll) {
+				eprintln!("Cannot acquire input `{}`: {}", ent.path().display(), e);
+			}
+		}
 
-			let path = ent.path();
-			let result = InputDevice::from_path(&path);
-
-			let dev = match result {
-				Ok(dev) => {
-					println!("Acquired input: {}", path.display());
-					dev
-				}
-
-				Err(e) => {
-					eprintln!("Cannot acquire input `{}`: {}", path.display(), e);
-					continue;
-				}
-			};
+		Ok(manager)
+	}
 
-			poll.add_fd(&dev);
-			devs.push(dev);
+	/// Opens the device at the given path and registers it to the given poll handler.
+	///
+	/// If the device is already open or if it isn't useful, the function does nothing.
+	fn add_device(&mut self, path: &PathBuf, poll: &mut PollHandler) -> io::Result<()> {
+		if self.devs.iter().any(|d| d.get_path() == path) {
+			return Ok(());
+		}
+		let dev = match InputDevice::from_path(path) {
+			Ok(dev) => dev,
+			// Not an evdev device
+			Err(e) if e.raw_os_error() == Some(libc::ENOTTY) => return Ok(()),
+			Err(e) => return Err(e),
+		};
+		// Devices that report nothing useful are ignored
+		let info = dev.get_info();
+		if info.classes.is_empty() {
+			return Ok(());
 		}
+		println!(
+			"Acquired input: {} ({}, {:?})",
+			path.display(),
+			info.name,
+			info.classes
+		);
 
-		// TODO Init inotify (hotplug)
+		poll.add_fd(&dev);
+		self.devs.push(dev);
+		Ok(())
+	}
+
+	/// Closes the device at the given path and unregisters it from the given poll handler.
+	///
+	/// Keys and buttons that are pressed on the device are released.
+	fn remove_device(&mut self, path: &PathBuf, poll: &mut PollHandler) {
+		let Some(i) = self.devs.iter().position(|d| d.get_path() == path) else {
+			return;
+		};
+		let mut dev = self.devs.remove(i);
+		println!("Released input: {}", path.display());
+		dev.release_all(&mut self.pending);
+		poll.remove_fd(&dev);
+	}
 
-		Ok(Self {
-			devs,
-		})
+	/// Handles devices that have been plugged or unplugged.
+	fn handle_hotplug(&mut self, poll: &mut PollHandler) -> io::Result<()> {
+		for ev in self.watcher.read_events()? {
+			match ev {
+				HotplugEvent::Added(path) => {
+					// Permissions of the file may not be set yet. In which case, opening is
+					// retried when they change
+					if let Err(e) = self.add_device(&path, poll) {
+						if e.kind() != io::ErrorKind::PermissionDenied {
+							eprintln!("Cannot acquire input `{}`: {}", path.display(), e);
+						}
+					}
+				}
+				HotplugEvent::Removed(path) => self.remove_device(&path, poll),
+			}
+		}
+		Ok(())
 	}
 
 	/// Consumes and returns the next input. If no input is available, the function returns None.
-	pub fn next(&mut self) -> io::Result<Option<Input>> {
-		// TODO Clean and Optimize
+	///
+	/// Arguments:
+	/// - `poll` is the poll handler devices are registered to.
+	/// - `ready` is the list of file descriptors that the poll handler returned as ready for
+	///   reading. Only the devices in this list are read.
+	pub fn next(&mut self, poll: &mut PollHandler, ready: &[i32]) -> io::Result<Option<Input>> {
+		if let Some(i) = self.pending.pop_front() {
+			return Ok(Some(i));
+		}
 
-		let mut poll_handler = PollHandler::new();
-		for d in &mut self.devs {
-			println!("-> {}", d.as_raw_fd());
-			poll_handler.add_fd(d);
+		if ready.contains(&self.watcher.as_raw_fd()) {
+			self.handle_hotplug(poll)?;
 		}
-		let fds = poll_handler.poll();
-		println!("=> {:?}", fds);
 
+		let mut removed = vec![];
 		for d in &mut self.devs {
-			if !fds.iter().filter(|f| **f == d.as_raw_fd()).next().is_some() {
+			if !ready.contains(&d.as_raw_fd()) {
 				continue;
 			}
+			// A device that cannot be read anymore has been unplugged
+			if let Err(e) = d.read_inputs(&mut self.pending) {
+				if e.raw_os_error() != Some(libc::ENODEV) {
+					eprintln!("Cannot read input `{}`: {}", d.get_path().display(), e);
+				}
+				removed.push(d.get_path().clone());
+			}
+		}
+		for path in removed {
+			self.remove_device(&path, poll);
+		}
+
+		Ok(self.pending.pop_front())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::input::device::*;
+	use crate::input::info::InputId;
+	use crate::io;
+	use crate::iow;
+	use std::ffi::c_int;
+	use std::fs::File;
+	use std::fs::OpenOptions;
+	use std::io::Write;
+	use std::mem::size_of;
+	use std::time::Duration;
+	use std::time::Instant;
+
+	/// uinput ioctl command base.
+	const UINPUT_IOCTL_BASE: u64 = b'U' as u64;
+	/// uinput ioctl command: Create the device.
+	const UI_DEV_CREATE: u64 = io!(UINPUT_IOCTL_BASE, 1);
+	/// uinput ioctl command: Destroy the device.
+	const UI_DEV_DESTROY: u64 = io!(UINPUT_IOCTL_BASE, 2);
+	/// uinput ioctl command: Set the identifiers of the device.
+	const UI_DEV_SETUP: u64 = iow!(UINPUT_IOCTL_BASE, 3, UinputSetup);
+	/// uinput ioctl command: Enable a type of event.
+	const UI_SET_EVBIT: u64 = iow!(UINPUT_IOCTL_BASE, 100, c_int);
+	/// uinput ioctl command: Enable a key.
+	const UI_SET_KEYBIT: u64 = iow!(UINPUT_IOCTL_BASE, 101, c_int);
+
+	/// The maximum duration to wait for the device to be noticed.
+	const TIMEOUT: Duration = Duration::from_secs(5);
+
+	/// The identifiers of a uinput device.
+	#[repr(C)]
+	struct UinputSetup {
+		/// The identifiers of the device.
+		id: InputId,
+		/// The name of the device.
+		name: [u8; 80],
+		/// The maximum number of force feedback effects.
+		ff_effects_max: u32,
+	}
+
+	/// Performs the given uinput ioctl command on the given file.
+	fn ioctl<T>(file: &File, cmd: u64, arg: T) {
+		let res = unsafe { libc::ioctl(file.as_raw_fd(), cmd, arg) };
+		assert!(res >= 0, "ioctl failed: {}", io::Error::last_os_error());
+	}
 
-			if let Some(i) = d.next()? {
-				if let Ok(i) = i.try_into() {
-					return Ok(Some(i));
+	/// Creates a virtual keyboard.
+	fn create_keyboard() -> File {
+		let file = OpenOptions::new()
+			.write(true)
+			.open("/dev/uinput")
+			.expect("cannot open uinput");
+		ioctl(&file, UI_SET_EVBIT, EV_KEY as c_int);
+		for key in [KEY_Q, KEY_A, KEY_Z, KEY_SPACE] {
+			ioctl(&file, UI_SET_KEYBIT, key as c_int);
+		}
+
+		let mut setup = UinputSetup {
+			id: InputId {
+				bustype: 0x06, // BUS_VIRTUAL
+				vendor: 0x1234,
+				product: 0x5678,
+				version: 1,
+			},
+			name: [0; 80],
+			ff_effects_max: 0,
+		};
+		let name = b"visto test keyboard";
+		setup.name[..name.len()].copy_from_slice(name);
+		ioctl(&file, UI_DEV_SETUP, &setup as *const UinputSetup);
+		ioctl(&file, UI_DEV_CREATE, 0);
+
+		file
+	}
+
+	/// Writes an event on the given virtual device.
+	fn emit(file: &mut File, r#type: u16, code: u16, value: i32) {
+		let ev = EvDevInputEvent {
+			time: libc::timeval {
+				tv_sec: 0,
+				tv_usec: 0,
+			},
+			r#type: r#type as _,
+			code: code as _,
+			value,
+		};
+		let buff = unsafe {
+			std::slice::from_raw_parts(
+				&ev as *const EvDevInputEvent as *const u8,
+				size_of::<EvDevInputEvent>(),
+			)
+		};
+		file.write_all(buff).unwrap();
+	}
+
+	/// Polls and reads inputs until `f` returns true, or panics after a timeout.
+	fn wait_for<F: FnMut(&InputManager, Option<Input>) -> bool>(
+		manager: &mut InputManager,
+		poll: &mut PollHandler,
+		mut f: F,
+	) {
+		let deadline = Instant::now() + TIMEOUT;
+		loop {
+			let remaining = deadline.saturating_duration_since(Instant::now());
+			assert!(!remaining.is_zero(), "timeout");
+			let ready = poll.poll(Some(remaining));
+			loop {
+				let input = manager.next(poll, &ready).unwrap();
+				let end = input.is_none();
+				if f(manager, input) {
+					return;
+				}
+				if end {
+					break;
 				}
 			}
 		}
+	}
+
+	/// Plugs a virtual keyboard, types a key on it and unplugs it.
+	///
+	/// The test requires the permission to create uinput devices.
+	#[test]
+	#[ignore]
+	fn uinput_hotplug() {
+		let mut poll = PollHandler::new();
+		let mut manager = InputManager::new(&mut poll).unwrap();
+		let count = manager.devs.len();
+
+		let mut keyboard = create_keyboard();
+		wait_for(&mut manager, &mut poll, |m, _| m.devs.len() == count + 1);
+
+		emit(&mut keyboard, EV_KEY, KEY_A, 1);
+		emit(&mut keyboard, EV_SYN, SYN_REPORT, 0);
+		let keycode = KEY_A as Keycode + MIN_KEYCODE;
+		wait_for(
+			&mut manager,
+			&mut poll,
+			|_, input| matches!(input, Some(Input::KeyPress(k)) if k == keycode),
+		);
 
-		Ok(None)
+		// Keys that are down when the device is unplugged are released
+		ioctl(&keyboard, UI_DEV_DESTROY, 0);
+		let mut released = false;
+		wait_for(&mut manager, &mut poll, |m, input| {
+			released |= matches!(input, Some(Input::KeyRelease(k)) if k == keycode);
+			released && m.devs.len() == count
+		});
 	}
 }
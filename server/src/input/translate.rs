@@ -0,0 +1,585 @@
+//! Translation of evdev events into inputs.
+//!
+//! A device reports the changes of its state as a frame of events terminated by a `SYN_REPORT`
+//! event. Events are accumulated until the end of the frame, then turned into inputs:
+//! - Motions of the frame are merged into at most one absolute and one relative move. They come
+//!   first so that buttons are pressed at the new position
+//! - Keys and buttons follow, in the order they were reported
+//! - Wheel motions are turned into clicks of buttons 4 to 7
+//!
+//! Touchpads move the pointer relatively to its position: the motion of the finger becomes a
+//! relative move. Touchscreens and tablets move the pointer to an absolute position, and a touch
+//! of the surface is a press of the first button.
+//!
+//! If the kernel reports that events were dropped (`SYN_DROPPED`), everything up to the next
+//! `SYN_REPORT` is discarded. Then, the state of the keys of the device has to be read again, so
+//! that keys and buttons whose release was lost are released.
+
+use super::device::*;
+use super::info::DeviceClass;
+use super::info::DeviceInfo;
+use super::info::InputAbsInfo;
+use super::Input;
+use super::Keycode;
+use super::MouseButton;
+use super::ABS_RANGE;
+use super::MIN_KEYCODE;
+use std::cmp::max;
+use std::cmp::min;
+use std::collections::VecDeque;
+
+/// The value of a hi-res wheel event corresponding to one detent of the wheel.
+const WHEEL_DETENT: i32 = 120;
+
+/// Value of a key event: release.
+const KEY_RELEASED: i32 = 0;
+/// Value of a key event: press.
+const KEY_PRESSED: i32 = 1;
+
+/// The range of an absolute axis, as reported by the device.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AbsRange {
+	/// The minimum value.
+	pub minimum: i32,
+	/// The maximum value.
+	pub maximum: i32,
+}
+
+impl AbsRange {
+	/// Scales the given value of the axis to the range `0..=ABS_RANGE`.
+	pub fn scale(&self, value: i32) -> u32 {
+		if self.maximum <= self.minimum {
+			return 0;
+		}
+		let value = min(max(value, self.minimum), self.maximum);
+		let off = (value as i64 - self.minimum as i64) as u64;
+		let len = (self.maximum as i64 - self.minimum as i64) as u64;
+		(off * ABS_RANGE as u64 / len) as u32
+	}
+}
+
+/// Returns the X keycode of the given evdev key code. If the key has no X keycode, the function
+/// returns None.
+pub fn to_keycode(code: u16) -> Option<Keycode> {
+	// Codes from `BTN_MISC` on are buttons
+	if code == KEY_RESERVED || code >= BTN_MISC {
+		return None;
+	}
+	Keycode::try_from(code + MIN_KEYCODE as u16).ok()
+}
+
+/// Returns the mouse button of the given evdev button code. If the button isn't a mouse button,
+/// the function returns None.
+///
+/// `touch` tells whether touching the surface of the device is a press of the first button.
+pub fn to_mouse_button(code: u16, touch: bool) -> Option<MouseButton> {
+	match code {
+		BTN_LEFT => Some(MouseButton::Button1),
+		BTN_MIDDLE => Some(MouseButton::Button2),
+		BTN_RIGHT => Some(MouseButton::Button3),
+		BTN_SIDE | BTN_BACK => Some(MouseButton::Button8),
+		BTN_EXTRA | BTN_FORWARD => Some(MouseButton::Button9),
+		BTN_TOUCH if touch => Some(MouseButton::Button1),
+		BTN_STYLUS if touch => Some(MouseButton::Button2),
+		BTN_STYLUS2 if touch => Some(MouseButton::Button3),
+		_ => None,
+	}
+}
+
+/// The state of a wheel of a device.
+#[derive(Default)]
+struct Wheel {
+	/// Tells whether the device reports hi-res events for the wheel. If so, legacy events are
+	/// ignored since they report the same motions.
+	hi_res: bool,
+	/// The motion accumulated since the last click, in hi-res units.
+	acc: i32,
+}
+
+impl Wheel {
+	/// Handles a motion of the wheel.
+	///
+	/// Arguments:
+	/// - `value` is the motion.
+	/// - `hi_res` tells whether the motion is in hi-res units.
+	fn motion(&mut self, value: i32, hi_res: bool) {
+		if hi_res {
+			if !self.hi_res {
+				self.hi_res = true;
+				self.acc = 0;
+			}
+			self.acc += value;
+		} else if !self.hi_res {
+			self.acc += value * WHEEL_DETENT;
+		}
+	}
+
+	/// Returns the number of clicks accumulated and consumes them. Positive clicks go in the
+	/// positive direction.
+	fn take_clicks(&mut self) -> i32 {
+		let clicks = self.acc / WHEEL_DETENT;
+		self.acc -= clicks * WHEEL_DETENT;
+		clicks
+	}
+}
+
+/// Translates the events of a device into inputs.
+#[derive(Default)]
+pub struct Translator {
+	/// The range of the X absolute axis, if the device has one.
+	abs_x: Option<AbsRange>,
+	/// The range of the Y absolute axis, if the device has one.
+	abs_y: Option<AbsRange>,
+	/// Tells whether the device is a touchpad.
+	touchpad: bool,
+	/// Tells whether touching the surface of the device is a press of the first button.
+	touch_button: bool,
+
+	/// The last absolute position reported on each axis, in device units.
+	abs_pos: (i32, i32),
+	/// Tells whether the absolute position changed in the current frame.
+	abs_moved: bool,
+	/// The relative motion accumulated in the current frame.
+	rel: (i32, i32),
+	/// Tells whether a finger touches the touchpad.
+	touching: bool,
+	/// The position of the finger on the touchpad at the end of the previous frame. None if the
+	/// finger just started touching.
+	last_touch: Option<(i32, i32)>,
+	/// The keys and buttons inputs of the current frame, with the code of their key.
+	keys: Vec<(u16, Input)>,
+	/// The keys and buttons currently pressed on the device, with the input releasing them.
+	pressed: Vec<(u16, Input)>,
+	/// The vertical wheel.
+	wheel: Wheel,
+	/// The horizontal wheel.
+	hwheel: Wheel,
+
+	/// Tells whether events are dropped until the next `SYN_REPORT`.
+	dropping: bool,
+	/// Tells whether events have been dropped and the state of the device has to be read again.
+	desync: bool,
+}
+
+impl Translator {
+	/// Creates a translator for the device with the given capabilities.
+	pub fn new(info: &DeviceInfo) -> Self {
+		let range = |info: &InputAbsInfo| AbsRange {
+			minimum: info.minimum,
+			maximum: info.maximum,
+		};
+		Self {
+			abs_x: info.abs_x.as_ref().map(range),
+			abs_y: info.abs_y.as_ref().map(range),
+			touchpad: info.is(DeviceClass::Touchpad),
+			touch_button: info.is(DeviceClass::Touchscreen) || info.is(DeviceClass::Tablet),
+
+			..Default::default()
+		}
+	}
+
+	/// Discards the current frame.
+	///
+	/// Partial motions of the wheels are kept since they add up across frames.
+	fn reset_frame(&mut self) {
+		self.abs_moved = false;
+		self.rel = (0, 0);
+		self.keys.clear();
+	}
+
+	/// Handles a key event.
+	fn key(&mut self, code: u16, value: i32) {
+		let pressed = match value {
+			KEY_PRESSED => true,
+			KEY_RELEASED => false,
+			// Autorepeat is handled by the server
+			_ => return,
+		};
+		if self.touchpad && code == BTN_TOUCH {
+			self.touching = pressed;
+			return;
+		}
+		let input = if let Some(button) = to_mouse_button(code, self.touch_button) {
+			if pressed {
+				Input::ButtonPress(button)
+			} else {
+				Input::ButtonRelease(button)
+			}
+		} else if let Some(keycode) = to_keycode(code) {
+			if pressed {
+				Input::KeyPress(keycode)
+			} else {
+				Input::KeyRelease(keycode)
+			}
+		} else {
+			return;
+		};
+		self.keys.push((code, input));
+	}
+
+	/// Handles a relative axis event.
+	fn rel(&mut self, code: u16, value: i32) {
+		match code {
+			REL_X => self.rel.0 += value,
+			REL_Y => self.rel.1 += value,
+			REL_WHEEL => self.wheel.motion(value, false),
+			REL_WHEEL_HI_RES => self.wheel.motion(value, true),
+			REL_HWHEEL => self.hwheel.motion(value, false),
+			REL_HWHEEL_HI_RES => self.hwheel.motion(value, true),
+			_ => {}
+		}
+	}
+
+	/// Handles an absolute axis event.
+	fn abs(&mut self, code: u16, value: i32) {
+		match (code, self.abs_x, self.abs_y) {
+			(ABS_X, Some(_), _) => self.abs_pos.0 = value,
+			(ABS_Y, _, Some(_)) => self.abs_pos.1 = value,
+			_ => return,
+		}
+		self.abs_moved = true;
+	}
+
+	/// Ends the current frame, pushing its inputs to `inputs`.
+	fn report(&mut self, inputs: &mut VecDeque<Input>) {
+		if self.touchpad {
+			// The finger moves the pointer only while it touches
+			if !self.touching {
+				self.last_touch = None;
+			} else if self.abs_moved {
+				if let Some((x, y)) = self.last_touch {
+					self.rel.0 += self.abs_pos.0 - x;
+					self.rel.1 += self.abs_pos.1 - y;
+				}
+				self.last_touch = Some(self.abs_pos);
+			} else if self.last_touch.is_none() {
+				self.last_touch = Some(self.abs_pos);
+			}
+		} else if self.abs_moved {
+			if let (Some(range_x), Some(range_y)) = (self.abs_x, self.abs_y) {
+				inputs.push_back(Input::AbsoluteMove {
+					x: range_x.scale(self.abs_pos.0),
+					y: range_y.scale(self.abs_pos.1),
+				});
+			}
+		}
+		if self.rel != (0, 0) {
+			inputs.push_back(Input::RelativeMove {
+				delta_x: self.rel.0,
+				delta_y: self.rel.1,
+			});
+		}
+		for (code, input) in self.keys.drain(..) {
+			match input {
+				Input::KeyPress(k) => self.pressed.push((code, Input::KeyRelease(k))),
+				Input::ButtonPress(b) => self.pressed.push((code, Input::ButtonRelease(b))),
+				_ => self.pressed.retain(|(c, _)| *c != code),
+			}
+			inputs.push_back(input);
+		}
+
+		// Each click of a wheel is a press immediately followed by a release
+		let wheels = [
+			(
+				self.wheel.take_clicks(),
+				MouseButton::Button4,
+				MouseButton::Button5,
+			),
+			(
+				self.hwheel.take_clicks(),
+				MouseButton::Button7,
+				MouseButton::Button6,
+			),
+		];
+		for (clicks, positive, negative) in wheels {
+			let button = if clicks > 0 { positive } else { negative };
+			for _ in 0..clicks.abs() {
+				inputs.push_back(Input::ButtonPress(button));
+				inputs.push_back(Input::ButtonRelease(button));
+			}
+		}
+
+		self.reset_frame();
+	}
+
+	/// Releases every key and button currently pressed on the device, pushing the corresponding
+	/// inputs to `inputs`.
+	///
+	/// This function is used when the device is removed, so that nothing remains pressed.
+	pub fn release_all(&mut self, inputs: &mut VecDeque<Input>) {
+		self.reset_frame();
+		inputs.extend(self.pressed.drain(..).map(|(_, input)| input));
+	}
+
+	/// Tells whether events have been dropped. If so, [`Self::resync`] must be called with the
+	/// current state of the device.
+	pub fn is_desync(&self) -> bool {
+		self.desync
+	}
+
+	/// Updates the state of the translator after events have been dropped. Keys and buttons that
+	/// are not pressed anymore are released, pushing the corresponding inputs to `inputs`.
+	///
+	/// `is_down` tells whether the key with the given code is currently pressed on the device.
+	pub fn resync<F: Fn(u16) -> bool>(&mut self, is_down: F, inputs: &mut VecDeque<Input>) {
+		self.desync = false;
+		self.pressed.retain(|(code, input)| {
+			let down = is_down(*code);
+			if !down {
+				inputs.push_back(*input);
+			}
+			down
+		});
+		if self.touchpad {
+			self.touching = is_down(BTN_TOUCH);
+			self.last_touch = None;
+		}
+	}
+
+	/// Handles the given event. If the event ends a frame, the inputs of the frame are pushed to
+	/// `inputs`.
+	pub fn push(&mut self, ev: &EvDevInputEvent, inputs: &mut VecDeque<Input>) {
+		let (ev_type, code) = (ev.r#type as u16, ev.code as u16);
+		if ev_type == EV_SYN {
+			match code {
+				SYN_REPORT if self.dropping => {
+					self.dropping = false;
+					self.desync = true;
+					self.reset_frame();
+					self.wheel.acc = 0;
+					self.hwheel.acc = 0;
+				}
+				SYN_REPORT => self.report(inputs),
+				SYN_DROPPED => self.dropping = true,
+				_ => {}
+			}
+			return;
+		}
+		if self.dropping {
+			return;
+		}
+		match ev_type {
+			EV_KEY => self.key(code, ev.value),
+			EV_REL => self.rel(code, ev.value),
+			EV_ABS => self.abs(code, ev.value),
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The X keycode of the `A` key.
+	const A: Keycode = KEY_A as Keycode + MIN_KEYCODE;
+	/// The X keycode of the left `Shift` key.
+	const SHIFT: Keycode = KEY_LEFTSHIFT as Keycode + MIN_KEYCODE;
+
+	/// Pushes the given events to the translator and returns the resulting inputs.
+	fn feed(translator: &mut Translator, events: &[(u16, u16, i32)]) -> Vec<Input> {
+		let mut inputs = VecDeque::new();
+		for (r#type, code, value) in events {
+			let ev = EvDevInputEvent {
+				time: libc::timeval {
+					tv_sec: 0,
+					tv_usec: 0,
+				},
+				r#type: *r#type as _,
+				code: *code as _,
+				value: *value,
+			};
+			translator.push(&ev, &mut inputs);
+		}
+		inputs.into()
+	}
+
+	/// Values are scaled to the full range and clamped to the range of the axis.
+	#[test]
+	fn abs_scale() {
+		let range = AbsRange {
+			minimum: -100,
+			maximum: 100,
+		};
+		assert_eq!(range.scale(-100), 0);
+		assert_eq!(range.scale(0), ABS_RANGE / 2);
+		assert_eq!(range.scale(100), ABS_RANGE);
+		assert_eq!(range.scale(-1000), 0);
+		assert_eq!(range.scale(1000), ABS_RANGE);
+
+		let empty = AbsRange {
+			minimum: 5,
+			maximum: 5,
+		};
+		assert_eq!(empty.scale(5), 0);
+	}
+
+	/// Events are reported at the end of the frame, motions being merged and coming first.
+	#[test]
+	fn frame_batching() {
+		let mut translator = Translator::default();
+		let inputs = feed(
+			&mut translator,
+			&[
+				(EV_REL, REL_X, 3),
+				(EV_KEY, KEY_A, KEY_PRESSED),
+				(EV_REL, REL_X, 2),
+				(EV_REL, REL_Y, -1),
+			],
+		);
+		assert_eq!(inputs, []);
+
+		let inputs = feed(&mut translator, &[(EV_SYN, SYN_REPORT, 0)]);
+		assert_eq!(
+			inputs,
+			[
+				Input::RelativeMove {
+					delta_x: 5,
+					delta_y: -1,
+				},
+				Input::KeyPress(A),
+			]
+		);
+
+		// Autorepeat events are ignored
+		let inputs = feed(
+			&mut translator,
+			&[(EV_KEY, KEY_A, 2), (EV_SYN, SYN_REPORT, 0)],
+		);
+		assert_eq!(inputs, []);
+	}
+
+	/// Absolute motions are scaled, and only the last position of the frame is reported.
+	#[test]
+	fn absolute_move() {
+		let range = AbsRange {
+			minimum: 0,
+			maximum: 1000,
+		};
+		let mut translator = Translator {
+			abs_x: Some(range),
+			abs_y: Some(range),
+			touch_button: true,
+			..Default::default()
+		};
+		let inputs = feed(
+			&mut translator,
+			&[
+				(EV_ABS, ABS_X, 100),
+				(EV_ABS, ABS_X, 500),
+				(EV_ABS, ABS_Y, 1000),
+				(EV_KEY, BTN_TOUCH, KEY_PRESSED),
+				(EV_SYN, SYN_REPORT, 0),
+			],
+		);
+		assert_eq!(
+			inputs,
+			[
+				Input::AbsoluteMove {
+					x: range.scale(500),
+					y: ABS_RANGE,
+				},
+				Input::ButtonPress(MouseButton::Button1),
+			]
+		);
+	}
+
+	/// Wheel motions become clicks of buttons 4 to 7. Hi-res motions add up across frames and
+	/// make legacy events ignored.
+	#[test]
+	fn wheels() {
+		let mut translator = Translator::default();
+		let inputs = feed(
+			&mut translator,
+			&[
+				(EV_REL, REL_WHEEL, 2),
+				(EV_REL, REL_HWHEEL, -1),
+				(EV_SYN, SYN_REPORT, 0),
+			],
+		);
+		assert_eq!(
+			inputs,
+			[
+				Input::ButtonPress(MouseButton::Button4),
+				Input::ButtonRelease(MouseButton::Button4),
+				Input::ButtonPress(MouseButton::Button4),
+				Input::ButtonRelease(MouseButton::Button4),
+				Input::ButtonPress(MouseButton::Button6),
+				Input::ButtonRelease(MouseButton::Button6),
+			]
+		);
+
+		let half = WHEEL_DETENT / 2;
+		let inputs = feed(
+			&mut translator,
+			&[(EV_REL, REL_WHEEL_HI_RES, -half), (EV_SYN, SYN_REPORT, 0)],
+		);
+		assert_eq!(inputs, []);
+		let inputs = feed(
+			&mut translator,
+			&[
+				(EV_REL, REL_WHEEL, -1),
+				(EV_REL, REL_WHEEL_HI_RES, -half),
+				(EV_SYN, SYN_REPORT, 0),
+			],
+		);
+		assert_eq!(
+			inputs,
+			[
+				Input::ButtonPress(MouseButton::Button5),
+				Input::ButtonRelease(MouseButton::Button5),
+			]
+		);
+	}
+
+	/// Events up to the end of a frame are dropped, then keys released in the meantime are
+	/// released once the state of the device is known.
+	#[test]
+	fn dropped_events() {
+		let mut translator = Translator::default();
+		let inputs = feed(
+			&mut translator,
+			&[
+				(EV_KEY, KEY_LEFTSHIFT, KEY_PRESSED),
+				(EV_KEY, KEY_A, KEY_PRESSED),
+				(EV_KEY, BTN_LEFT, KEY_PRESSED),
+				(EV_SYN, SYN_REPORT, 0),
+			],
+		);
+		assert_eq!(
+			inputs,
+			[
+				Input::KeyPress(SHIFT),
+				Input::KeyPress(A),
+				Input::ButtonPress(MouseButton::Button1),
+			]
+		);
+
+		let inputs = feed(
+			&mut translator,
+			&[
+				(EV_SYN, SYN_DROPPED, 0),
+				(EV_KEY, KEY_LEFTSHIFT, KEY_RELEASED),
+				(EV_SYN, SYN_REPORT, 0),
+			],
+		);
+		assert_eq!(inputs, []);
+		assert!(translator.is_desync());
+
+		// Only `A` is still pressed
+		let mut inputs = VecDeque::new();
+		translator.resync(|code| code == KEY_A, &mut inputs);
+		assert!(!translator.is_desync());
+		assert_eq!(
+			Vec::from(inputs),
+			[
+				Input::KeyRelease(SHIFT),
+				Input::ButtonRelease(MouseButton::Button1),
+			]
+		);
+
+		let mut inputs = VecDeque::new();
+		translator.release_all(&mut inputs);
+		assert_eq!(Vec::from(inputs), [Input::KeyRelease(A)]);
+	}
+}
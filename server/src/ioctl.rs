@@ -0,0 +1,70 @@
+//! An ioctl command number is made of the direction of the transfer, the type of the driver, the
+//! number of the command and the size of its argument.
+//!
+//! The macros of this module build command numbers the same way as the `_IO*` macros of the
+//! kernel.
+
+/// Direction of ioctl commands: no argument is transferred.
+pub const IOC_NONE: u64 = 0;
+/// Direction of ioctl commands: the argument is written to the kernel.
+pub const IOC_WRITE: u64 = 1;
+/// Direction of ioctl commands: the argument is read from the kernel.
+pub const IOC_READ: u64 = 2;
+
+/// Returns the ioctl command with the given direction, type, number and argument size.
+#[macro_export]
+macro_rules! ioc {
+	($dir:expr, $type:expr, $nr:expr, $size:expr) => {
+		(($dir) << 30) | (($type) << 8) | ($nr) | (($size) << 16)
+	};
+}
+
+/// Returns the ioctl command with the given type and number, without argument.
+#[macro_export]
+macro_rules! io {
+	($type:expr, $nr:expr) => {
+		$crate::ioc!($crate::ioctl::IOC_NONE, $type, $nr, 0)
+	};
+}
+
+/// Returns the ioctl command with the given type and number, writing an argument of the given
+/// type.
+#[macro_export]
+macro_rules! iow {
+	($type:expr, $nr:expr, $arg:ty) => {
+		$crate::ioc!(
+			$crate::ioctl::IOC_WRITE,
+			$type,
+			$nr,
+			std::mem::size_of::<$arg>() as u64
+		)
+	};
+}
+
+/// Returns the ioctl command with the given type and number, reading an argument of the given
+/// type.
+#[macro_export]
+macro_rules! ior {
+	($type:expr, $nr:expr, $arg:ty) => {
+		$crate::ioc!(
+			$crate::ioctl::IOC_READ,
+			$type,
+			$nr,
+			std::mem::size_of::<$arg>() as u64
+		)
+	};
+}
+
+/// Returns the ioctl command with the given type and number, writing and reading an argument of
+/// the given type.
+#[macro_export]
+macro_rules! iowr {
+	($type:expr, $nr:expr, $arg:ty) => {
+		$crate::ioc!(
+			$crate::ioctl::IOC_WRITE | $crate::ioctl::IOC_READ,
+			$type,
+			$nr,
+			std::mem::size_of::<$arg>() as u64
+		)
+	};
+}
@@ -5,18 +5,24 @@
 
 pub mod ctx;
 pub mod extension;
+pub mod font;
 pub mod id_allocator;
 pub mod input;
+pub mod ioctl;
 pub mod net;
 pub mod output;
 pub mod poll;
 pub mod protocol;
+pub mod raster;
 pub mod screens_layout;
 pub mod util;
 
 use ctx::client::Client;
+use ctx::keymap::Keymap;
 use ctx::Context;
+use font::catalog;
 use id_allocator::IDAllocator;
+use input::accel::AccelProfile;
 use input::InputManager;
 use net::Listener;
 use output::card::DRICard;
@@ -24,10 +30,15 @@ use poll::PollHandler;
 use std::env;
 use std::path::Path;
 use std::process::exit;
+use std::time::Duration;
+use std::time::Instant;
 
 /// The release number.
 pub const RELEASE_NUMBER: u32 = 0;
 
+/// The minimum duration between two frames.
+const FRAME_INTERVAL: Duration = Duration::from_micros(16667);
+
 /// Structure containing command line arguments.
 struct Args {
 	/// The display number.
@@ -38,6 +49,15 @@ struct Args {
 
 	/// Tells whether the cursor is enabled.
 	cursor: bool,
+
+	/// The default font path.
+	font_path: Vec<String>,
+
+	/// The profile of the pointer acceleration.
+	accel_profile: AccelProfile,
+
+	/// The keymap loaded from a file. If None, the built-in keymap is used.
+	keymap: Option<Keymap>,
 }
 
 impl Args {
@@ -48,6 +68,15 @@ impl Args {
 			network: false,
 
 			cursor: true,
+
+			font_path: catalog::DEFAULT_PATH
+				.iter()
+				.map(|p| p.to_string())
+				.collect(),
+
+			accel_profile: AccelProfile::default(),
+
+			keymap: None,
 		}
 	}
 }
@@ -74,11 +103,26 @@ fn parse_args() -> Result<Args, String> {
 		args.display = parse_display(&disp)?;
 	}
 
-	let iter = env::args().skip(1);
-	for arg in iter {
+	let mut iter = env::args().skip(1);
+	while let Some(arg) = iter.next() {
 		match arg.as_str() {
 			"-network" => args.network = true,
 			"-nocursor" => args.cursor = false,
+			// The elements of the path are separated by commas
+			"-fp" => {
+				let path = iter.next().ok_or("Missing font path after `-fp`")?;
+				args.font_path = path.split(',').map(str::to_owned).collect();
+			}
+
+			"-accel" => {
+				let name = iter.next().ok_or("Missing profile after `-accel`")?;
+				args.accel_profile = AccelProfile::from_name(&name)
+					.ok_or_else(|| format!("Invalid acceleration profile `{}`", name))?;
+			}
+			"-keymap" => {
+				let path = iter.next().ok_or("Missing file after `-keymap`")?;
+				args.keymap = Some(Keymap::load(&path)?);
+			}
 
 			_ if matches!(arg.chars().next(), Some(':')) => {
 				args.display = parse_display(&arg)?;
@@ -116,8 +160,26 @@ fn main() {
 		exit(1);
 	});
 
+	// Invalid elements of the font path are ignored
+	let font_path = args
+		.font_path
+		.into_iter()
+		.filter(|elem| {
+			let valid = catalog::is_valid_element(elem);
+			if !valid {
+				eprintln!("ignoring invalid font path element `{}`", elem);
+			}
+			valid
+		})
+		.collect();
+
 	// Creating context
-	let mut ctx = Context::new();
+	let mut ctx = Context::new(font_path);
+	ctx.get_pointer_mut().set_visible(args.cursor);
+	ctx.get_pointer_mut().get_accel_mut().profile = args.accel_profile;
+	if let Some(keymap) = args.keymap {
+		*ctx.get_keyboard_mut().get_keymap_mut() = keymap;
+	}
 	ctx.init_screens(&dri_cards, None); // TODO read layout from config if present
 
 	// Creating listener
@@ -135,9 +197,21 @@ fn main() {
 	});
 
 	let mut client_id_allocator = IDAllocator::from_range(0..8192);
+	let mut next_frame = Instant::now();
 	loop {
-		// Waiting until something has to be done
-		poll.poll();
+		// Waiting until something has to be done, or until the next frame if the screen has to
+		// be recomposed
+		let now = Instant::now();
+		let frame_timeout = ctx
+			.is_damaged()
+			.then(|| next_frame.saturating_duration_since(now));
+		// Held keys are repeated even if nothing happens
+		let repeat_timeout = ctx
+			.get_keyboard()
+			.get_repeat_deadline()
+			.map(|d| d.saturating_duration_since(now));
+		let timeout = frame_timeout.into_iter().chain(repeat_timeout).min();
+		let ready = poll.poll(timeout);
 
 		// TODO Add a maximum number of clients
 
@@ -158,14 +232,32 @@ fn main() {
 		}
 
 		// Ticking clients
-		ctx.tick_clients(&mut poll);
+		ctx.tick_clients(&mut poll, &ready);
+		// Requests may have changed the window under the pointer or its cursor
+		ctx.update_pointer_window();
+		ctx.update_cursor();
 
 		// Handle inputs
-		for i in input_manager.next() {
-			// TODO
-			println!("input: {:?}", i);
+		loop {
+			match input_manager.next(&mut poll, &ready) {
+				Ok(Some(i)) => ctx.handle_input(i),
+				Ok(None) => break,
+				Err(e) => {
+					eprintln!("Failed to read input: {}", e);
+					break;
+				}
+			}
+		}
+		// Motions of the batch are reported in a single event
+		ctx.flush_motion();
+		ctx.repeat_keys();
+		// Events caused by inputs and repeated keys are not written by the ticks of clients
+		ctx.flush_events();
+
+		// Recomposing damaged areas, at most once per frame
+		if ctx.is_damaged() && Instant::now() >= next_frame {
+			ctx.render();
+			next_frame = Instant::now() + FRAME_INTERVAL;
 		}
-
-		// TODO ctx.render();
 	}
 }
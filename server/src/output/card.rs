@@ -1,10 +1,29 @@
 //! A card is a device handling screens.
 
+use super::DRM_IOCTL_GET_CAP;
 use super::DRM_IOCTL_MODE_GETRESOURCES;
 use std::fs::File;
 use std::fs::OpenOptions;
 use std::os::unix::io::AsRawFd;
 
+/// Capability: the width of the cursor plane.
+const DRM_CAP_CURSOR_WIDTH: u64 = 0x8;
+/// Capability: the height of the cursor plane.
+const DRM_CAP_CURSOR_HEIGHT: u64 = 0x9;
+
+/// The size of the cursor plane used when the card doesn't tell it.
+const DEFAULT_CURSOR_SIZE: u32 = 64;
+
+/// Structure to get a capability of a card from DRM.
+#[derive(Debug, Default)]
+#[repr(C)]
+pub struct DRMGetCap {
+	/// The capability.
+	capability: u64,
+	/// The value of the capability.
+	value: u64,
+}
+
 /// Structure to get card resources from DRM.
 #[derive(Debug, Default)]
 #[repr(C)]
@@ -158,4 +177,27 @@ impl DRICard {
 	pub fn get_encoder_ids(&self) -> &[u32] {
 		&self.encoder_ids
 	}
+
+	/// Returns the value of the given capability. If the card doesn't know the capability, the
+	/// function returns None.
+	fn get_cap(&self, capability: u64) -> Option<u64> {
+		let mut cap = DRMGetCap {
+			capability,
+			value: 0,
+		};
+		let res =
+			unsafe { libc::ioctl(self.dev.as_raw_fd(), DRM_IOCTL_GET_CAP, &mut cap as *mut _) };
+		(res >= 0).then_some(cap.value)
+	}
+
+	/// Returns the size of the buffer of the cursor plane.
+	pub fn get_cursor_size(&self) -> (u32, u32) {
+		let get = |cap| {
+			self.get_cap(cap)
+				.filter(|v| *v > 0)
+				.map(|v| v as u32)
+				.unwrap_or(DEFAULT_CURSOR_SIZE)
+		};
+		(get(DRM_CAP_CURSOR_WIDTH), get(DRM_CAP_CURSOR_HEIGHT))
+	}
 }
@@ -0,0 +1,234 @@
+//! The cursor plane is a small buffer displayed by the hardware over the framebuffer, at a
+//! position that can be changed without recomposing the screen.
+//!
+//! Not every driver supports it, and the size of the buffer is limited. In these cases, the
+//! cursor has to be drawn by software.
+
+use super::card::DRICard;
+use super::framebuffer::DRMModeCreateDumb;
+use super::framebuffer::DRMModeDestroyDumb;
+use super::framebuffer::DRMModeMapDumb;
+use super::DRM_IOCTL_MODE_CREATE_DUMB;
+use super::DRM_IOCTL_MODE_CURSOR;
+use super::DRM_IOCTL_MODE_DESTROY_DUMB;
+use super::DRM_IOCTL_MODE_MAP_DUMB;
+use std::ffi::c_void;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::ptr::null_mut;
+use std::ptr::NonNull;
+
+/// Cursor command flag: sets the buffer of the cursor.
+const DRM_MODE_CURSOR_BO: u32 = 0x01;
+/// Cursor command flag: moves the cursor.
+const DRM_MODE_CURSOR_MOVE: u32 = 0x02;
+
+/// Structure used by the command to set or move the cursor of a CRTC.
+#[derive(Clone, Debug, Default)]
+#[repr(C)]
+pub struct DRMModeCursor {
+	/// Tells which fields of the command are used.
+	flags: u32,
+	/// The ID of the CRTC.
+	crtc_id: u32,
+	/// The X position of the cursor's top-left corner.
+	x: i32,
+	/// The Y position of the cursor's top-left corner.
+	y: i32,
+	/// The width of the cursor's buffer.
+	width: u32,
+	/// The height of the cursor's buffer.
+	height: u32,
+	/// The handle to the dumb buffer of the cursor. If zero, the cursor is hidden.
+	handle: u32,
+}
+
+/// Destroys the dumb buffer with the given handle on the card with the given file descriptor.
+fn destroy_dumb(fd: i32, handle: u32) {
+	let mut cmd = DRMModeDestroyDumb {
+		handle,
+	};
+	unsafe {
+		libc::ioctl(fd, DRM_IOCTL_MODE_DESTROY_DUMB, &mut cmd as *mut _);
+	}
+}
+
+/// Structure representing the cursor plane of a CRTC.
+pub struct CursorPlane<'a> {
+	/// The card of the CRTC.
+	card: &'a DRICard,
+	/// The ID of the CRTC.
+	crtc: u32,
+
+	/// The handle to the dumb buffer.
+	handle: u32,
+	/// The width of the buffer in pixels.
+	width: u32,
+	/// The height of the buffer in pixels.
+	height: u32,
+	/// The length of a line in bytes.
+	pitch: u32,
+	/// The pointer to the memory chunk the buffer is mapped to.
+	buff: NonNull<u32>,
+	/// The size of the mapped memory chunk in bytes.
+	buff_len: usize,
+}
+
+impl<'a> CursorPlane<'a> {
+	/// Creates the buffer of the cursor of the given CRTC.
+	///
+	/// The size of the buffer is the one supported by the card. If the buffer cannot be created,
+	/// the function returns the error of the driver.
+	pub fn new(card: &'a DRICard, crtc: u32) -> io::Result<Self> {
+		let fd = card.get_device().as_raw_fd();
+		let (width, height) = card.get_cursor_size();
+
+		// Create dumb buffer
+		let mut dumb_buff = DRMModeCreateDumb {
+			height,
+			width,
+			bpp: 32,
+			..Default::default()
+		};
+		let res = unsafe { libc::ioctl(fd, DRM_IOCTL_MODE_CREATE_DUMB, &mut dumb_buff as *mut _) };
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		// Map it
+		let mut cmd = DRMModeMapDumb {
+			handle: dumb_buff.handle,
+			..Default::default()
+		};
+		let res = unsafe { libc::ioctl(fd, DRM_IOCTL_MODE_MAP_DUMB, &mut cmd as *mut _) };
+		if res < 0 {
+			let err = io::Error::last_os_error();
+			destroy_dumb(fd, dumb_buff.handle);
+			return Err(err);
+		}
+		let buff_ptr = unsafe {
+			libc::mmap(
+				null_mut::<c_void>(),
+				dumb_buff.size as _,
+				libc::PROT_READ | libc::PROT_WRITE,
+				libc::MAP_SHARED,
+				fd,
+				cmd.offset as _,
+			)
+		};
+		if buff_ptr == libc::MAP_FAILED {
+			let err = io::Error::last_os_error();
+			destroy_dumb(fd, dumb_buff.handle);
+			return Err(err);
+		}
+		// Cannot fail since the kernel doesn't map at the address zero
+		let buff = NonNull::new(buff_ptr as *mut u32).unwrap();
+
+		Ok(Self {
+			card,
+			crtc,
+
+			handle: dumb_buff.handle,
+			width,
+			height,
+			pitch: dumb_buff.pitch,
+			buff,
+			buff_len: dumb_buff.size as _,
+		})
+	}
+
+	/// Returns the maximum size of a cursor image.
+	pub fn get_size(&self) -> (u32, u32) {
+		(self.width, self.height)
+	}
+
+	/// Sends the given cursor command.
+	fn command(&self, mut cmd: DRMModeCursor) -> io::Result<()> {
+		let fd = self.card.get_device().as_raw_fd();
+		cmd.crtc_id = self.crtc;
+		let res = unsafe { libc::ioctl(fd, DRM_IOCTL_MODE_CURSOR, &mut cmd as *mut _) };
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(())
+	}
+
+	/// Displays the given image as the cursor, with its top-left corner at `(x, y)`.
+	///
+	/// Arguments:
+	/// - `width` and `height` are the size of the image.
+	/// - `get_pixel` returns the ARGB value of the pixel at the given position of the image.
+	///
+	/// If the image is larger than the buffer, the function returns an `InvalidInput` error. If
+	/// the driver doesn't support the cursor plane, the function returns its error.
+	pub fn set_image<F: Fn(u32, u32) -> u32>(
+		&mut self,
+		width: u32,
+		height: u32,
+		x: i32,
+		y: i32,
+		get_pixel: F,
+	) -> io::Result<()> {
+		if width > self.width || height > self.height {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"cursor image too large",
+			));
+		}
+
+		// Pixels outside of the image are transparent
+		let line_len = self.pitch as usize / 4;
+		for py in 0..self.height {
+			for px in 0..self.width {
+				let pixel = if px < width && py < height {
+					get_pixel(px, py)
+				} else {
+					0
+				};
+				let i = py as usize * line_len + px as usize;
+				unsafe {
+					*self.buff.as_ptr().add(i) = pixel;
+				}
+			}
+		}
+
+		self.command(DRMModeCursor {
+			flags: DRM_MODE_CURSOR_BO | DRM_MODE_CURSOR_MOVE,
+			x,
+			y,
+			width: self.width,
+			height: self.height,
+			handle: self.handle,
+			..Default::default()
+		})
+	}
+
+	/// Moves the cursor so that its top-left corner is at `(x, y)`.
+	pub fn move_to(&self, x: i32, y: i32) -> io::Result<()> {
+		self.command(DRMModeCursor {
+			flags: DRM_MODE_CURSOR_MOVE,
+			x,
+			y,
+			..Default::default()
+		})
+	}
+
+	/// Hides the cursor.
+	pub fn hide(&self) {
+		let _ = self.command(DRMModeCursor {
+			flags: DRM_MODE_CURSOR_BO,
+			..Default::default()
+		});
+	}
+}
+
+impl Drop for CursorPlane<'_> {
+	fn drop(&mut self) {
+		// The buffer cannot be destroyed while it is displayed
+		self.hide();
+		unsafe {
+			libc::munmap(self.buff.as_ptr() as *mut c_void, self.buff_len);
+		}
+		destroy_dumb(self.card.get_device().as_raw_fd(), self.handle);
+	}
+}
@@ -17,19 +17,19 @@ use std::ptr::NonNull;
 #[repr(C)]
 pub struct DRMModeCreateDumb {
 	/// The height of the buffer in pixels.
-	height: u32,
+	pub height: u32,
 	/// The width of the buffer in pixels.
-	width: u32,
+	pub width: u32,
 	/// Bits-Per-Pixel
-	bpp: u32,
+	pub bpp: u32,
 	/// Flags.
-	flags: u32,
+	pub flags: u32,
 	/// The handle to the create buffer.
-	handle: u32,
+	pub handle: u32,
 	/// TODO doc
-	pitch: u32,
+	pub pitch: u32,
 	/// The size of the buffer in bytes.
-	size: u32,
+	pub size: u32,
 }
 
 /// Structure used by the command to create a framebuffer.
@@ -57,11 +57,19 @@ pub struct DRMModeFBCmd {
 #[repr(C)]
 pub struct DRMModeMapDumb {
 	/// The dumb buffer's handdle.
-	handle: u32,
+	pub handle: u32,
 	/// Padding.
-	pad: u32,
+	pub pad: u32,
 	/// The offset at which the buffer is located on the card's file.
-	offset: u64,
+	pub offset: u64,
+}
+
+/// Structure used by the command to destroy a dumb buffer.
+#[derive(Clone, Debug, Default)]
+#[repr(C)]
+pub struct DRMModeDestroyDumb {
+	/// The dumb buffer's handle.
+	pub handle: u32,
 }
 
 /// Structure representing a framebuffer.
@@ -73,6 +81,13 @@ pub struct Framebuffer<'a> {
 	/// The framebuffer's ID.
 	fb_id: u32,
 
+	/// The width of the framebuffer in pixels.
+	width: u32,
+	/// The height of the framebuffer in pixels.
+	height: u32,
+	/// The length of a line in bytes.
+	pitch: u32,
+
 	/// The pointer to the memory chunk the buffer is mapped to.
 	buff: Option<NonNull<u32>>,
 	/// The length of the buffer in bytes.
@@ -114,6 +129,10 @@ impl<'a> Framebuffer<'a> {
 			dumb_handle: dumb_buff.handle,
 			fb_id: cmd.fb_id,
 
+			width,
+			height,
+			pitch: dumb_buff.pitch,
+
 			buff: None,
 			buff_len: dumb_buff.size as _,
 		})
@@ -162,6 +181,29 @@ impl<'a> Framebuffer<'a> {
 	pub fn get_buffer_len(&self) -> usize {
 		self.buff_len / size_of::<u32>()
 	}
+
+	/// Returns the length of a line in bytes.
+	pub fn get_pitch(&self) -> u32 {
+		self.pitch
+	}
+
+	/// Sets the pixel at the given position.
+	///
+	/// If the position is outside of the framebuffer or if the buffer is not mapped, the function
+	/// does nothing.
+	pub fn set_pixel(&self, x: u32, y: u32, pixel: u32) {
+		let Some(buff) = self.buff else {
+			return;
+		};
+		if x >= self.width || y >= self.height {
+			return;
+		}
+
+		let i = y as usize * (self.pitch as usize / size_of::<u32>()) + x as usize;
+		unsafe {
+			*buff.as_ptr().add(i) = pixel;
+		}
+	}
 }
 
 impl<'a> Framebuffer<'a> {
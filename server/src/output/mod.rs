@@ -4,49 +4,21 @@
 
 pub mod card;
 pub mod connector;
+pub mod cursor;
 pub mod framebuffer;
 
-/// ioctl macro: TODO doc
-macro_rules! ioc {
-	($a:expr, $b:expr, $c:expr, $d:expr) => {
-		(($a) << 30) | (($b) << 8) | ($c) | (($d) << 16)
-	};
-}
-
-/// ioctl macro: TODO doc
-macro_rules! io {
-	($a:expr, $b:expr) => {
-		ioc!(0, $a, $b, 0)
-	};
-}
-
-/// ioctl macro: TODO doc
-macro_rules! iow {
-	($a:expr, $b:expr, $c:ty) => {
-		ioc!(1, $a, $b, std::mem::size_of::<$c>() as u64)
-	};
-}
-
-/// ioctl macro: TODO doc
-macro_rules! ior {
-	($a:expr, $b:expr, $c:ty) => {
-		ioc!(2, $a, $b, std::mem::size_of::<$c>() as u64)
-	};
-}
-
-/// ioctl macro: TODO doc
-macro_rules! iowr {
-	($a:expr, $b:expr, $c:ty) => {
-		ioc!(3, $a, $b, std::mem::size_of::<$c>() as u64)
-	};
-}
+use crate::iowr;
 
 /// DRM ioctl command base.
 const DRM_IOCTL_BASE: u64 = b'd' as u64;
+/// DRM ioctl command: Get a capability of the card.
+const DRM_IOCTL_GET_CAP: u64 = iowr!(DRM_IOCTL_BASE, 0x0c, card::DRMGetCap);
 /// DRM ioctl command: Get DRM card resources.
 const DRM_IOCTL_MODE_GETRESOURCES: u64 = iowr!(DRM_IOCTL_BASE, 0xa0, card::DRMModeCardRes);
 /// DRM ioctl command: Get DRM encoder.
 const DRM_IOCTL_MODE_GETCRTC: u64 = iowr!(DRM_IOCTL_BASE, 0xa1, connector::DRMModeCRTC);
+/// DRM ioctl command: Set or move the cursor of a CRTC.
+const DRM_IOCTL_MODE_CURSOR: u64 = iowr!(DRM_IOCTL_BASE, 0xa3, cursor::DRMModeCursor);
 /// DRM ioctl command: Get DRM encoder.
 const DRM_IOCTL_MODE_GETENCODER: u64 = iowr!(DRM_IOCTL_BASE, 0xa6, connector::DRMModeEncoder);
 /// DRM ioctl command: Get DRM connector.
@@ -63,3 +35,6 @@ const DRM_IOCTL_MODE_PAGE_FLIP: u64 = iowr!(DRM_IOCTL_BASE, 0xb0, connector::DRM
 const DRM_IOCTL_MODE_CREATE_DUMB: u64 = iowr!(DRM_IOCTL_BASE, 0xb2, framebuffer::DRMModeCreateDumb);
 /// DRM ioctl command: Map a dumb buffer.
 const DRM_IOCTL_MODE_MAP_DUMB: u64 = iowr!(DRM_IOCTL_BASE, 0xb3, framebuffer::DRMModeMapDumb);
+/// DRM ioctl command: Destroy a dumb buffer.
+const DRM_IOCTL_MODE_DESTROY_DUMB: u64 =
+	iowr!(DRM_IOCTL_BASE, 0xb4, framebuffer::DRMModeDestroyDumb);
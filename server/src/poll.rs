@@ -2,6 +2,7 @@
 //! Instead, the process is put to sleep by the kernel until something is available for reading.
 
 use std::os::unix::prelude::AsRawFd;
+use std::time::Duration;
 
 /// The poll handler takes a list of objects working on a file descriptor and allows to wait for
 /// events on them.
@@ -40,19 +41,26 @@ impl PollHandler {
 	/// Polls on every registered file descriptors, blocking until at least one file descriptor is
 	/// ready.
 	///
+	/// If `timeout` is not None, the function returns after this duration at most, even if no
+	/// file descriptor is ready.
+	///
 	/// The function returns the list of file descriptors ready for reading.
-	pub fn poll(&mut self) -> Vec<i32> {
+	pub fn poll(&mut self, timeout: Option<Duration>) -> Vec<i32> {
+		let timeout = timeout
+			.map(|t| t.as_millis().try_into().unwrap_or(i32::MAX))
+			.unwrap_or(-1);
 		unsafe {
-			libc::poll(self.fds.as_mut_ptr(), self.fds.len() as _, -1);
+			libc::poll(self.fds.as_mut_ptr(), self.fds.len() as _, timeout);
 		}
 
 		// Remove invalid (closed?) file descriptors from the list
 		// FIXME self.fds.retain(|fd| fd.revents | libc::POLLNVAL == 0);
 
-		// Collecting results
+		// Collecting results. A closed file descriptor is reported as ready so that the closure
+		// is noticed when reading
 		self.fds
 			.iter()
-			.filter(|fd| fd.revents | libc::POLLIN != 0)
+			.filter(|fd| fd.revents & (libc::POLLIN | libc::POLLHUP | libc::POLLERR) != 0)
 			.map(|fd| fd.fd)
 			.collect()
 	}
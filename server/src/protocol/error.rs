@@ -26,7 +26,7 @@ pub struct XError {
 }
 
 /// Structure representing a custom error.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct CustomError {
 	/// The error code.
 	pub code: u8,
@@ -38,7 +38,7 @@ pub struct CustomError {
 }
 
 /// Enumeration of X protocol errors.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Error {
 	/// TODO doc
 	Request,
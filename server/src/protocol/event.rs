@@ -1,43 +1,442 @@
 //! This module implements events.
+//!
+//! Every event is 32 bytes long. Except for `KeymapNotify`, the sequence number of an event is
+//! the one of the last request handled for the client receiving it. It is set when the event is
+//! written to the client.
 
-// TODO
-
-/// Enumeration of events.
-pub enum Event {
-	KeyPress,
-	KeyRelease,
-	ButtonPress,
-	ButtonRelease,
-	MotionNotify,
-
-	EnterNotify,
-	LeaveNotify,
-
-	FocusIn,
-	FocusOut,
-
-	KeymapNotify,
-	Expose,
-	GraphicsExposure,
-	NoExposure,
-	VisibilityNotify,
-	CreateNotify,
-	DestroyNotify,
-	UnmapNotify,
-	MapNotify,
-	MapRequest,
-	ReparentNotify,
-	ConfigureNotify,
-	GravityNotify,
-	ResizeRequest,
-	ConfigureRequest,
-	CirculateNotify,
-	CirculateRequest,
-	PropertyNotify,
-	SelectionClear,
-	SelectionRequest,
-	SelectionNotify,
-	ColormapNotify,
-	MappingNotify,
-	ClientMessage,
+use std::mem::size_of;
+use std::ptr;
+
+/// Event code: KeyPress
+pub const KEY_PRESS: u8 = 2;
+/// Event code: KeyRelease
+pub const KEY_RELEASE: u8 = 3;
+/// Event code: ButtonPress
+pub const BUTTON_PRESS: u8 = 4;
+/// Event code: ButtonRelease
+pub const BUTTON_RELEASE: u8 = 5;
+/// Event code: MotionNotify
+pub const MOTION_NOTIFY: u8 = 6;
+/// Event code: EnterNotify
+pub const ENTER_NOTIFY: u8 = 7;
+/// Event code: LeaveNotify
+pub const LEAVE_NOTIFY: u8 = 8;
+/// Event code: FocusIn
+pub const FOCUS_IN: u8 = 9;
+/// Event code: FocusOut
+pub const FOCUS_OUT: u8 = 10;
+/// Event code: KeymapNotify
+pub const KEYMAP_NOTIFY: u8 = 11;
+/// Event code: Expose
+pub const EXPOSE: u8 = 12;
+/// Event code: GraphicsExposure
+pub const GRAPHICS_EXPOSURE: u8 = 13;
+/// Event code: NoExposure
+pub const NO_EXPOSURE: u8 = 14;
+/// Event code: VisibilityNotify
+pub const VISIBILITY_NOTIFY: u8 = 15;
+/// Event code: CreateNotify
+pub const CREATE_NOTIFY: u8 = 16;
+/// Event code: DestroyNotify
+pub const DESTROY_NOTIFY: u8 = 17;
+/// Event code: UnmapNotify
+pub const UNMAP_NOTIFY: u8 = 18;
+/// Event code: MapNotify
+pub const MAP_NOTIFY: u8 = 19;
+/// Event code: MapRequest
+pub const MAP_REQUEST: u8 = 20;
+/// Event code: ReparentNotify
+pub const REPARENT_NOTIFY: u8 = 21;
+/// Event code: ConfigureNotify
+pub const CONFIGURE_NOTIFY: u8 = 22;
+/// Event code: ConfigureRequest
+pub const CONFIGURE_REQUEST: u8 = 23;
+/// Event code: GravityNotify
+pub const GRAVITY_NOTIFY: u8 = 24;
+/// Event code: ResizeRequest
+pub const RESIZE_REQUEST: u8 = 25;
+/// Event code: CirculateNotify
+pub const CIRCULATE_NOTIFY: u8 = 26;
+/// Event code: CirculateRequest
+pub const CIRCULATE_REQUEST: u8 = 27;
+/// Event code: PropertyNotify
+pub const PROPERTY_NOTIFY: u8 = 28;
+/// Event code: SelectionClear
+pub const SELECTION_CLEAR: u8 = 29;
+/// Event code: SelectionRequest
+pub const SELECTION_REQUEST: u8 = 30;
+/// Event code: SelectionNotify
+pub const SELECTION_NOTIFY: u8 = 31;
+/// Event code: ColormapNotify
+pub const COLORMAP_NOTIFY: u8 = 32;
+/// Event code: ClientMessage
+pub const CLIENT_MESSAGE: u8 = 33;
+/// Event code: MappingNotify
+pub const MAPPING_NOTIFY: u8 = 34;
+
+/// An event in the protocol's format.
+pub type RawEvent = [u8; 32];
+
+/// Converts the given event structure to the protocol's format.
+///
+/// The structure must be exactly 32 bytes long.
+pub fn to_raw<E>(event: &E) -> RawEvent {
+	assert_eq!(size_of::<E>(), size_of::<RawEvent>());
+
+	let mut raw = [0; 32];
+	unsafe {
+		ptr::copy_nonoverlapping(event as *const _ as *const u8, raw.as_mut_ptr(), raw.len());
+	}
+	raw
+}
+
+/// Sets the sequence number of the given event.
+///
+/// `KeymapNotify` events don't have a sequence number and are left unchanged.
+pub fn set_sequence_number(event: &mut RawEvent, seq_nbr: u16) {
+	if event[0] & 0x7f != KEYMAP_NOTIFY {
+		event[2..4].copy_from_slice(&seq_nbr.to_ne_bytes());
+	}
+}
+
+/// `MotionNotify` detail: the event is sent normally.
+pub const MOTION_NORMAL: u8 = 0;
+/// `MotionNotify` detail: the event is a hint, no other motion is reported until the client
+/// queries the pointer.
+pub const MOTION_HINT: u8 = 1;
+
+/// The relation between the window of a crossing or focus event and the other window involved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NotifyDetail {
+	/// The event window is an inferior of the other window.
+	Ancestor = 0,
+	/// The event window is between both windows in the hierarchy.
+	Virtual = 1,
+	/// The other window is an inferior of the event window.
+	Inferior = 2,
+	/// Neither window is an inferior of the other.
+	Nonlinear = 3,
+	/// The event window is between a window and the common ancestor of both windows.
+	NonlinearVirtual = 4,
+	/// The event window contains the pointer (focus events only).
+	Pointer = 5,
+	/// The focus moved from or to `PointerRoot` (focus events only).
+	PointerRoot = 6,
+	/// The focus moved from or to `None` (focus events only).
+	None = 7,
+}
+
+/// The cause of a crossing or focus event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NotifyMode {
+	/// The event is caused by a motion of the pointer or a change of focus.
+	Normal = 0,
+	/// The event is caused by the activation of a grab.
+	Grab = 1,
+	/// The event is caused by the deactivation of a grab.
+	Ungrab = 2,
+	/// The focus changed while the keyboard is grabbed (focus events only).
+	WhileGrabbed = 3,
+}
+
+/// The format shared by the `KeyPress`, `KeyRelease`, `ButtonPress`, `ButtonRelease` and
+/// `MotionNotify` events, reporting an input of a device.
+#[repr(C, packed)]
+pub struct KeyButtonPointer {
+	/// The event code.
+	pub code: u8,
+	/// The keycode, the button, or the detail of a motion.
+	pub detail: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The time of the event, in milliseconds.
+	pub time: u32,
+	/// The root window of the screen the pointer is on.
+	pub root: u32,
+	/// The window the event is reported relative to.
+	pub event: u32,
+	/// The child of the event window that is an ancestor of the source window, if any.
+	pub child: u32,
+	/// The X position of the pointer relative to the root window.
+	pub root_x: i16,
+	/// The Y position of the pointer relative to the root window.
+	pub root_y: i16,
+	/// The X position of the pointer relative to the event window.
+	pub event_x: i16,
+	/// The Y position of the pointer relative to the event window.
+	pub event_y: i16,
+	/// The state of the modifiers and buttons before the event.
+	pub state: u16,
+	/// Tells whether the event window is on the same screen as the root window.
+	pub same_screen: u8,
+
+	/// Padding.
+	pub _padding: u8,
+}
+
+/// The format shared by the `EnterNotify` and `LeaveNotify` events, reporting that the pointer
+/// entered or left a window.
+#[repr(C, packed)]
+pub struct EnterLeaveNotify {
+	/// The event code.
+	pub code: u8,
+	/// The relation between the event window and the other window.
+	pub detail: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The time of the event, in milliseconds.
+	pub time: u32,
+	/// The root window of the screen the pointer is on.
+	pub root: u32,
+	/// The window the event is reported on.
+	pub event: u32,
+	/// The child of the event window containing the pointer, if any.
+	pub child: u32,
+	/// The X position of the pointer relative to the root window.
+	pub root_x: i16,
+	/// The Y position of the pointer relative to the root window.
+	pub root_y: i16,
+	/// The X position of the pointer relative to the event window.
+	pub event_x: i16,
+	/// The Y position of the pointer relative to the event window.
+	pub event_y: i16,
+	/// The state of the modifiers and buttons.
+	pub state: u16,
+	/// The cause of the event.
+	pub mode: u8,
+	/// Bit `0x01` tells whether the event window is the focus window or an inferior of it. Bit
+	/// `0x02` tells whether the event window is on the same screen as the root window.
+	pub same_screen_focus: u8,
+}
+
+/// The format shared by the `FocusIn` and `FocusOut` events, reporting that a window gained or
+/// lost the input focus.
+#[repr(C, packed)]
+pub struct FocusInOut {
+	/// The event code.
+	pub code: u8,
+	/// The relation between the event window and the other window.
+	pub detail: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The window the event is reported on.
+	pub event: u32,
+	/// The cause of the event.
+	pub mode: u8,
+
+	/// Padding.
+	pub _padding: [u8; 23],
+}
+
+/// The `KeymapNotify` event, reporting the state of the keyboard after an `EnterNotify` or a
+/// `FocusIn` event.
+#[repr(C, packed)]
+pub struct KeymapNotify {
+	/// The event code.
+	pub code: u8,
+	/// The state of the keys with keycodes 8 to 255. Bit `n` of byte `i` represents the key with
+	/// keycode `8 * (i + 1) + n`.
+	pub keys: [u8; 31],
+}
+
+/// `MappingNotify` request: the modifier mapping changed.
+pub const MAPPING_MODIFIER: u8 = 0;
+/// `MappingNotify` request: the keyboard mapping changed.
+pub const MAPPING_KEYBOARD: u8 = 1;
+/// `MappingNotify` request: the pointer mapping changed.
+pub const MAPPING_POINTER: u8 = 2;
+
+/// The `MappingNotify` event, reporting a change of the keyboard, modifier or pointer mapping
+/// to every client.
+#[repr(C, packed)]
+pub struct MappingNotify {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The mapping that changed.
+	pub request: u8,
+	/// The first keycode whose mapping changed, for keyboard mappings.
+	pub first_keycode: u8,
+	/// The number of keycodes whose mapping changed, for keyboard mappings.
+	pub count: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 25],
+}
+
+/// The `Expose` event, reporting that a region of a window has to be redrawn.
+#[repr(C, packed)]
+pub struct Expose {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The window.
+	pub window: u32,
+	/// The X position of the region, relative to the window's origin.
+	pub x: u16,
+	/// The Y position of the region, relative to the window's origin.
+	pub y: u16,
+	/// The width of the region.
+	pub width: u16,
+	/// The height of the region.
+	pub height: u16,
+	/// The number of `Expose` events following this one for the same exposure.
+	pub count: u16,
+
+	/// Padding.
+	pub _padding1: [u8; 14],
+}
+
+/// The `GraphicsExposure` event, reporting that a region of the destination of a copy could not
+/// be computed because its source was not available.
+#[repr(C, packed)]
+pub struct GraphicsExposure {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The destination drawable.
+	pub drawable: u32,
+	/// The X position of the region, relative to the drawable's origin.
+	pub x: u16,
+	/// The Y position of the region, relative to the drawable's origin.
+	pub y: u16,
+	/// The width of the region.
+	pub width: u16,
+	/// The height of the region.
+	pub height: u16,
+	/// The minor opcode of the request that generated the event.
+	pub minor_opcode: u16,
+	/// The number of `GraphicsExposure` events following this one for the same request.
+	pub count: u16,
+	/// The major opcode of the request that generated the event.
+	pub major_opcode: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 11],
+}
+
+/// The `NoExposure` event, reporting that a copy did not generate any `GraphicsExposure` event.
+#[repr(C, packed)]
+pub struct NoExposure {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The destination drawable.
+	pub drawable: u32,
+	/// The minor opcode of the request that generated the event.
+	pub minor_opcode: u16,
+	/// The major opcode of the request that generated the event.
+	pub major_opcode: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 21],
+}
+
+/// The `UnmapNotify` event, reporting that a window has been unmapped.
+#[repr(C, packed)]
+pub struct UnmapNotify {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The window on which the event has been selected. Either the unmapped window or its parent.
+	pub event: u32,
+	/// The unmapped window.
+	pub window: u32,
+	/// Tells whether the window has been unmapped because its parent was resized and its
+	/// gravity is `Unmap`.
+	pub from_configure: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 19],
+}
+
+/// The `MapNotify` event, reporting that a window has been mapped.
+#[repr(C, packed)]
+pub struct MapNotify {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The window on which the event has been selected. Either the mapped window or its parent.
+	pub event: u32,
+	/// The mapped window.
+	pub window: u32,
+	/// The `override_redirect` attribute of the window.
+	pub override_redirect: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 19],
+}
+
+/// The `MapRequest` event, sent to the client having selected `SubstructureRedirect` on the
+/// parent of a window another client attempts to map.
+#[repr(C, packed)]
+pub struct MapRequest {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The parent of the window.
+	pub parent: u32,
+	/// The window to be mapped.
+	pub window: u32,
+
+	/// Padding.
+	pub _padding1: [u8; 20],
+}
+
+/// The `ColormapNotify` event, reporting that the colormap of a window changed, or that it has
+/// been installed or uninstalled.
+#[repr(C, packed)]
+pub struct ColormapNotify {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number. Set when the event is written.
+	pub seq_nbr: u16,
+
+	/// The window.
+	pub window: u32,
+	/// The colormap of the window. If zero, the window has no colormap.
+	pub colormap: u32,
+	/// Tells whether the colormap attribute of the window changed. If zero, the colormap has
+	/// been installed or uninstalled.
+	pub new: u8,
+	/// The state of the colormap: `0` if uninstalled, `1` if installed.
+	pub state: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 18],
 }
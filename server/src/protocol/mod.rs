@@ -20,6 +20,17 @@ pub const REPLY_TYPE_ERROR: u8 = 0;
 /// Reply type: Normal reply
 pub const REPLY_TYPE_REPLY: u8 = 1;
 
+/// Timestamp standing for the current server time.
+pub const CURRENT_TIME: u32 = 0;
+
+/// Tells whether the timestamp `a` is earlier than the timestamp `b`.
+///
+/// Timestamps wrap around: the half of the range preceding `b` is earlier, the other half is
+/// later.
+pub fn is_time_before(a: u32, b: u32) -> bool {
+	(b.wrapping_sub(a) as i32) > 0
+}
+
 /// The header of a request.
 #[repr(C, packed)]
 pub struct XRequest {
@@ -214,27 +225,33 @@ pub enum DeviceEvent {
 }
 
 /// Enumeration of keymasks.
+#[derive(Clone, Copy, Debug)]
+#[repr(u16)]
 pub enum KeyMask {
-	Shift,
-	Lock,
-	Control,
-	Mod1,
-	Mod2,
-	Mod3,
-	Mod4,
-	Mod5,
+	Shift = 0x0001,
+	Lock = 0x0002,
+	Control = 0x0004,
+	Mod1 = 0x0008,
+	Mod2 = 0x0010,
+	Mod3 = 0x0020,
+	Mod4 = 0x0040,
+	Mod5 = 0x0080,
 }
 
 /// Enumeration of button masks.
+#[derive(Clone, Copy, Debug)]
+#[repr(u16)]
 pub enum ButMask {
-	Button1,
-	Button2,
-	Button3,
-	Button4,
-	Button5,
+	Button1 = 0x0100,
+	Button2 = 0x0200,
+	Button3 = 0x0400,
+	Button4 = 0x0800,
+	Button5 = 0x1000,
 }
 
 /// A 2D point.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
 pub struct Point {
 	/// X position.
 	pub x: i16,
@@ -244,6 +261,7 @@ pub struct Point {
 
 /// A rectangle.
 #[derive(Clone, Debug)]
+#[repr(C)]
 pub struct Rectangle {
 	/// X position.
 	pub x: i16,
@@ -257,6 +275,8 @@ pub struct Rectangle {
 }
 
 /// An arc.
+#[derive(Clone, Debug)]
+#[repr(C)]
 pub struct Arc {
 	/// X position.
 	pub x: i16,
@@ -268,9 +288,9 @@ pub struct Arc {
 	/// The height of the arc.
 	pub height: u16,
 
-	/// TODO doc
+	/// The start angle of the arc, in 64ths of degree, relative to the three-o'clock position.
 	pub angle1: i16,
-	/// TODO doc
+	/// The extent of the arc, in 64ths of degree, relative to the start angle.
 	pub angle2: i16,
 }
 
@@ -295,11 +315,11 @@ pub struct Host {
 /// Structure representing a X format.
 #[repr(C, packed)]
 pub struct Format {
-	/// TODO doc
+	/// The depth.
 	pub depth: u8,
-	/// TODO doc
+	/// The number of bits used to store a pixel.
 	pub bits_per_pixel: u8,
-	/// TODO doc
+	/// The number of bits each scanline is padded to.
 	pub scanline_pad: u8,
 
 	/// Padding.
@@ -364,6 +384,7 @@ pub struct Depth {
 }
 
 /// Enumeration of visual classes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum VisualClass {
 	/// A degenerate case of GrayScale where values are predefined and read-only.
@@ -383,6 +404,7 @@ pub enum VisualClass {
 }
 
 /// Structure representing a X visual.
+#[derive(Clone, Copy)]
 #[repr(C, packed)]
 pub struct Visual {
 	/// The visual's ID.
@@ -405,7 +427,100 @@ pub struct Visual {
 	pub _padding: u32,
 }
 
+/// The metrics of a character of a font.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C, packed)]
+pub struct CharInfo {
+	/// The extent of the character on the left of its origin.
+	pub left_side_bearing: i16,
+	/// The extent of the character on the right of its origin.
+	pub right_side_bearing: i16,
+	/// The offset from the origin of the character to the origin of the next one.
+	pub character_width: i16,
+	/// The extent of the character above the baseline.
+	pub ascent: i16,
+	/// The extent of the character below the baseline.
+	pub descent: i16,
+	/// Attributes of the character, not interpreted by the server.
+	pub attributes: u16,
+}
+
+/// A property of a font.
+#[repr(C, packed)]
+pub struct FontProp {
+	/// The atom of the name of the property.
+	pub name: u32,
+	/// The value of the property. For string properties, this is an atom.
+	pub value: u32,
+}
+
 /// Pads the given number `n`.
 pub fn pad(n: usize) -> usize {
 	(4 - (n % 4)) % 4
 }
+
+/// The way coordinates of a list of points are interpreted.
+#[derive(Clone, Copy, Debug)]
+pub enum CoordinateMode {
+	/// Coordinates are relative to the drawable's origin.
+	Origin,
+	/// Coordinates are relative to the previous point. The first point is relative to the
+	/// drawable's origin.
+	Previous,
+}
+
+impl TryFrom<u8> for CoordinateMode {
+	type Error = Error;
+
+	fn try_from(v: u8) -> Result<Self, Self::Error> {
+		match v {
+			0 => Ok(Self::Origin),
+			1 => Ok(Self::Previous),
+
+			_ => Err(Error::Value(v as _)),
+		}
+	}
+}
+
+impl CoordinateMode {
+	/// Returns the positions of the given points relative to the drawable's origin.
+	pub fn resolve(&self, points: &[Point]) -> Vec<(i32, i32)> {
+		let mut prev = (0, 0);
+		points
+			.iter()
+			.map(|p| {
+				let p = (p.x as i32, p.y as i32);
+				prev = match self {
+					Self::Origin => p,
+					Self::Previous => (prev.0 + p.0, prev.1 + p.1),
+				};
+				prev
+			})
+			.collect()
+	}
+}
+
+/// The format of images exchanged with clients.
+#[derive(Clone, Copy, Debug)]
+pub enum ImageFormat {
+	/// A single bit plane, drawn using the foreground and background pixels.
+	XYBitmap,
+	/// One bit plane per bit of depth, the most significant plane first.
+	XYPixmap,
+	/// Pixels are stored contiguously, according to the pixmap format of the depth.
+	ZPixmap,
+}
+
+impl TryFrom<u8> for ImageFormat {
+	type Error = Error;
+
+	fn try_from(v: u8) -> Result<Self, Self::Error> {
+		match v {
+			0 => Ok(Self::XYBitmap),
+			1 => Ok(Self::XYPixmap),
+			2 => Ok(Self::ZPixmap),
+
+			_ => Err(Error::Value(v as _)),
+		}
+	}
+}
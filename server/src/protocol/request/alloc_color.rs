@@ -0,0 +1,111 @@
+//! The `AllocColor` request allows to allocate a read-only colormap entry with the closest color
+//! to the given one.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::colormap::Color;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct AllocColorReply {
+	/// The type of the reply.
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// The sequence number of the request.
+	seq_nbr: u16,
+	/// Length of the reply in 4 bytes units.
+	reply_length: u32,
+	/// The red component of the allocated color.
+	red: u16,
+	/// The green component of the allocated color.
+	green: u16,
+	/// The blue component of the allocated color.
+	blue: u16,
+	/// Padding.
+	_padding1: u16,
+	/// The pixel of the allocated entry.
+	pixel: u32,
+	/// Padding.
+	_padding2: [u8; 12],
+}
+
+/// Header of the `AllocColor` request.
+#[repr(C, packed)]
+pub struct AllocColorHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The red component of the color.
+	red: u16,
+	/// The green component of the color.
+	green: u16,
+	/// The blue component of the color.
+	blue: u16,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct AllocColor {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The color to allocate.
+	color: Color,
+}
+
+impl Request for AllocColor {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let colormap = NonZeroU32::new(self.cmap)
+			.and_then(|id| ctx.get_colormap_mut(id))
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		let (pixel, color) = colormap
+			.alloc_color(client.get_id(), &self.color)
+			.map_err(HandleError::Client)?;
+
+		let hdr = AllocColorReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: 0,
+			red: color.red,
+			green: color.green,
+			blue: color.blue,
+			_padding1: 0,
+			pixel,
+			_padding2: [0; 12],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `AllocColor`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<AllocColorHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &AllocColorHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(AllocColor {
+		cmap: hdr.cmap,
+		color: Color {
+			red: hdr.red,
+			green: hdr.green,
+			blue: hdr.blue,
+		},
+	})))
+}
@@ -0,0 +1,105 @@
+//! The `AllocColorCells` request allows to allocate read-write colormap entries, as a set of
+//! pixels and a set of plane masks.
+//!
+//! Every combination of a pixel with a subset of the plane masks is allocated.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct AllocColorCellsReply {
+	/// The type of the reply.
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// The sequence number of the request.
+	seq_nbr: u16,
+	/// Length of the reply in 4 bytes units.
+	reply_length: u32,
+	/// The number of pixels.
+	pixels_len: u16,
+	/// The number of plane masks.
+	masks_len: u16,
+	/// Padding.
+	_padding1: [u8; 20],
+}
+
+/// Header of the `AllocColorCells` request.
+#[repr(C, packed)]
+pub struct AllocColorCellsHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The number of pixels to allocate.
+	colors: u16,
+	/// The number of plane masks to allocate.
+	planes: u16,
+}
+
+/// Structure representing the request.
+pub struct AllocColorCells {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The number of pixels to allocate.
+	colors: u16,
+	/// The number of plane masks to allocate.
+	planes: u16,
+}
+
+impl Request for AllocColorCells {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let colormap = NonZeroU32::new(self.cmap)
+			.and_then(|id| ctx.get_colormap_mut(id))
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		// Plane masks are always contiguous, thus the `contiguous` flag is ignored
+		let (pixels, masks) = colormap
+			.alloc_color_cells(client.get_id(), self.colors, self.planes)
+			.map_err(HandleError::Client)?;
+
+		let hdr = AllocColorCellsReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: (pixels.len() + masks.len()) as _,
+			pixels_len: pixels.len() as _,
+			masks_len: masks.len() as _,
+			_padding1: [0; 20],
+		};
+		let data = pixels
+			.iter()
+			.chain(masks.iter())
+			.flat_map(|v| v.to_ne_bytes())
+			.collect::<Vec<_>>();
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `AllocColorCells`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<AllocColorCellsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &AllocColorCellsHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(AllocColorCells {
+		cmap: hdr.cmap,
+		colors: hdr.colors,
+		planes: hdr.planes,
+	})))
+}
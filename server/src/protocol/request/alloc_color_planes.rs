@@ -0,0 +1,117 @@
+//! The `AllocColorPlanes` request allows to allocate read-write colormap entries, as a set of
+//! pixels and a mask of planes for each of the red, green and blue components.
+//!
+//! Every combination of a pixel with a subset of the planes is allocated.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct AllocColorPlanesReply {
+	/// The type of the reply.
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// The sequence number of the request.
+	seq_nbr: u16,
+	/// Length of the reply in 4 bytes units.
+	reply_length: u32,
+	/// The number of pixels.
+	pixels_len: u16,
+	/// Padding.
+	_padding1: u16,
+	/// The mask of the planes allocated for the red component.
+	red_mask: u32,
+	/// The mask of the planes allocated for the green component.
+	green_mask: u32,
+	/// The mask of the planes allocated for the blue component.
+	blue_mask: u32,
+	/// Padding.
+	_padding2: [u8; 8],
+}
+
+/// Header of the `AllocColorPlanes` request.
+#[repr(C, packed)]
+pub struct AllocColorPlanesHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The number of pixels to allocate.
+	colors: u16,
+	/// The number of planes to allocate for the red component.
+	reds: u16,
+	/// The number of planes to allocate for the green component.
+	greens: u16,
+	/// The number of planes to allocate for the blue component.
+	blues: u16,
+}
+
+/// Structure representing the request.
+pub struct AllocColorPlanes {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The number of pixels to allocate.
+	colors: u16,
+	/// The number of planes to allocate for the red, green and blue components.
+	planes: [u16; 3],
+}
+
+impl Request for AllocColorPlanes {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let colormap = NonZeroU32::new(self.cmap)
+			.and_then(|id| ctx.get_colormap_mut(id))
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		// Planes are always contiguous, thus the `contiguous` flag is ignored
+		let (pixels, [red_mask, green_mask, blue_mask]) = colormap
+			.alloc_color_planes(client.get_id(), self.colors, self.planes)
+			.map_err(HandleError::Client)?;
+
+		let hdr = AllocColorPlanesReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: pixels.len() as _,
+			pixels_len: pixels.len() as _,
+			_padding1: 0,
+			red_mask,
+			green_mask,
+			blue_mask,
+			_padding2: [0; 8],
+		};
+		let data = pixels
+			.iter()
+			.flat_map(|v| v.to_ne_bytes())
+			.collect::<Vec<_>>();
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `AllocColorPlanes`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<AllocColorPlanesHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &AllocColorPlanesHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(AllocColorPlanes {
+		cmap: hdr.cmap,
+		colors: hdr.colors,
+		planes: [hdr.reds, hdr.greens, hdr.blues],
+	})))
+}
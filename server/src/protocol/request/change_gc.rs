@@ -0,0 +1,65 @@
+//! The `ChangeGC` request allows to change values of a graphics context.
+
+use super::create_gc;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `ChangeGC` request.
+#[repr(C, packed)]
+pub struct ChangeGCHdr {
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// The mask of values being changed.
+	bitmask: u32,
+}
+
+/// Structure representing the request.
+pub struct ChangeGC {
+	/// The graphics context's ID.
+	gc: u32,
+	/// The mask of values being changed.
+	bitmask: u32,
+	/// The encoded values.
+	values: Vec<u8>,
+}
+
+impl Request for ChangeGC {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let values =
+			create_gc::read_values(self.bitmask, &self.values).map_err(HandleError::Client)?;
+		let gc = client
+			.get_gc_mut(self.gc)
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+		gc.set_values(ctx, &values).map_err(HandleError::Client)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `ChangeGC`.
+///
+/// Values are decoded and checked when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ChangeGCHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ChangeGCHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ChangeGC {
+		gc: hdr.gc,
+		bitmask: hdr.bitmask,
+		values: buff[size_of::<ChangeGCHdr>()..].to_vec(),
+	})))
+}
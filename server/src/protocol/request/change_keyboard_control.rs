@@ -0,0 +1,219 @@
+//! The `ChangeKeyboardControl` request allows to change the settings of the keyboard.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard;
+use crate::ctx::Context;
+use crate::input;
+use crate::input::Keycode;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `ChangeKeyboardControl` request.
+#[repr(C, packed)]
+pub struct ChangeKeyboardControlHdr {
+	/// The mask of settings being changed.
+	value_mask: u32,
+}
+
+/// The mode of autorepeat.
+#[derive(Clone, Copy)]
+enum AutoRepeatMode {
+	/// Autorepeat is disabled.
+	Off,
+	/// Autorepeat is enabled.
+	On,
+	/// Autorepeat is restored to its default, which is enabled.
+	Default,
+}
+
+/// Returns the value of a percentage, `default` standing for `-1`.
+///
+/// If the value is invalid, the function returns an error.
+fn get_percent(value: u32, default: u8) -> Result<u8, Error> {
+	match value as u8 as i8 {
+		-1 => Ok(default),
+		v @ 0..=100 => Ok(v as _),
+		_ => Err(Error::Value(value)),
+	}
+}
+
+/// Returns the value of a positive setting, `default` standing for `-1`.
+///
+/// If the value is invalid, the function returns an error.
+fn get_value(value: u32, default: u16) -> Result<u16, Error> {
+	match value as u16 as i16 {
+		-1 => Ok(default),
+		v if v < 0 => Err(Error::Value(value)),
+		v => Ok(v as _),
+	}
+}
+
+/// The settings changed by the request.
+#[derive(Default)]
+struct Settings {
+	/// The new volume of key clicks, if changed.
+	key_click_percent: Option<u8>,
+	/// The new volume of the bell, if changed.
+	bell_percent: Option<u8>,
+	/// The new pitch of the bell, if changed.
+	bell_pitch: Option<u16>,
+	/// The new duration of the bell, if changed.
+	bell_duration: Option<u16>,
+	/// The new state of LEDs, if changed. The first element is the LED. If None, every LED is
+	/// changed. The second element tells whether the LED is lit.
+	led: Option<(Option<u8>, bool)>,
+	/// The new autorepeat mode, if changed. The first element is the key. If None, the mode is
+	/// changed for the whole keyboard.
+	auto_repeat: Option<(Option<Keycode>, AutoRepeatMode)>,
+}
+
+/// Structure representing the request.
+pub struct ChangeKeyboardControl {
+	/// The mask of settings being changed.
+	value_mask: u32,
+	/// The values of the settings, in the order of the bits of the mask.
+	values: Vec<u32>,
+}
+
+impl ChangeKeyboardControl {
+	/// Decodes the settings changed by the request.
+	///
+	/// If a value is missing or invalid, the function returns an error.
+	fn get_settings(&self) -> Result<Settings, Error> {
+		let mut settings = Settings::default();
+		let mut led = None;
+		let mut led_mode = None;
+		let mut key = None;
+		let mut auto_repeat_mode = None;
+
+		let mut values = self.values.iter().copied();
+		let set_bits_iter = (0..=7).filter(|i| self.value_mask & (1 << i) != 0);
+		for id in set_bits_iter {
+			let val = values.next().ok_or(Error::Length)?;
+			match id {
+				0 => {
+					settings.key_click_percent =
+						Some(get_percent(val, keyboard::DEFAULT_KEY_CLICK_PERCENT)?)
+				}
+				1 => {
+					settings.bell_percent = Some(get_percent(val, keyboard::DEFAULT_BELL_PERCENT)?)
+				}
+				2 => settings.bell_pitch = Some(get_value(val, keyboard::DEFAULT_BELL_PITCH)?),
+				3 => {
+					settings.bell_duration = Some(get_value(val, keyboard::DEFAULT_BELL_DURATION)?)
+				}
+				4 => {
+					let val = val as u8;
+					if !(1..=32).contains(&val) {
+						return Err(Error::Value(val as _));
+					}
+					led = Some(val);
+				}
+				5 => {
+					led_mode = Some(match val as u8 {
+						0 => false,
+						1 => true,
+						v => return Err(Error::Value(v as _)),
+					})
+				}
+				6 => {
+					let val = val as u8;
+					if val < input::MIN_KEYCODE {
+						return Err(Error::Value(val as _));
+					}
+					key = Some(val);
+				}
+				7 => {
+					auto_repeat_mode = Some(match val as u8 {
+						0 => AutoRepeatMode::Off,
+						1 => AutoRepeatMode::On,
+						2 => AutoRepeatMode::Default,
+						v => return Err(Error::Value(v as _)),
+					})
+				}
+
+				_ => unreachable!(),
+			}
+		}
+
+		// A LED or a key cannot be specified without the corresponding mode
+		if led.is_some() && led_mode.is_none() || key.is_some() && auto_repeat_mode.is_none() {
+			return Err(Error::Match);
+		}
+		settings.led = led_mode.map(|on| (led, on));
+		settings.auto_repeat = auto_repeat_mode.map(|mode| (key, mode));
+
+		Ok(settings)
+	}
+}
+
+impl Request for ChangeKeyboardControl {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let settings = self.get_settings().map_err(HandleError::Client)?;
+
+		let kbd = ctx.get_keyboard_mut();
+		let control = kbd.get_control_mut();
+		if let Some(key_click_percent) = settings.key_click_percent {
+			control.key_click_percent = key_click_percent;
+		}
+		if let Some(bell_percent) = settings.bell_percent {
+			control.bell_percent = bell_percent;
+		}
+		if let Some(bell_pitch) = settings.bell_pitch {
+			control.bell_pitch = bell_pitch;
+		}
+		if let Some(bell_duration) = settings.bell_duration {
+			control.bell_duration = bell_duration;
+		}
+
+		if let Some((led, on)) = settings.led {
+			let mask = led.map(|l| 1 << (l - 1)).unwrap_or(!0);
+			if on {
+				control.led_mask |= mask;
+			} else {
+				control.led_mask &= !mask;
+			}
+		}
+
+		match settings.auto_repeat {
+			Some((Some(key), mode)) => {
+				let bit = 1 << (key % 8);
+				let byte = &mut control.auto_repeats[key as usize / 8];
+				match mode {
+					AutoRepeatMode::Off => *byte &= !bit,
+					AutoRepeatMode::On | AutoRepeatMode::Default => *byte |= bit,
+				}
+			}
+			Some((None, mode)) => {
+				control.global_auto_repeat = !matches!(mode, AutoRepeatMode::Off);
+			}
+			None => {}
+		}
+		kbd.update_repeat();
+
+		Ok(())
+	}
+}
+
+/// Parses `ChangeKeyboardControl`.
+///
+/// Values are checked when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ChangeKeyboardControlHdr>() {
+		return Ok(None);
+	}
+	let hdr: &ChangeKeyboardControlHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ChangeKeyboardControl {
+		value_mask: hdr.value_mask,
+		values: util::read_list(&buff[size_of::<ChangeKeyboardControlHdr>()..]),
+	})))
+}
@@ -0,0 +1,104 @@
+//! The `ChangeKeyboardMapping` request allows to change the keysyms of a range of keycodes.
+//!
+//! Every client is notified of the change with a `MappingNotify` event.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keymap::Keysym;
+use crate::ctx::Context;
+use crate::input;
+use crate::input::Keycode;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::MappingNotify;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `ChangeKeyboardMapping` request.
+#[repr(C, packed)]
+pub struct ChangeKeyboardMappingHdr {
+	/// The first keycode.
+	first_keycode: u8,
+	/// The number of keysyms of each keycode.
+	keysyms_per_keycode: u8,
+
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct ChangeKeyboardMapping {
+	/// The first keycode.
+	first_keycode: Keycode,
+	/// The number of keycodes.
+	count: u8,
+	/// The number of keysyms of each keycode.
+	keysyms_per_keycode: u8,
+	/// The keysyms of each keycode.
+	keysyms: Vec<Keysym>,
+}
+
+impl Request for ChangeKeyboardMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let last = self.first_keycode as u16 + self.count as u16;
+		if self.first_keycode < input::MIN_KEYCODE {
+			return Err(HandleError::Client(Error::Value(self.first_keycode as _)));
+		}
+		if last > input::MAX_KEYCODE as u16 + 1 {
+			return Err(HandleError::Client(Error::Value(self.count as _)));
+		}
+		if self.keysyms_per_keycode == 0 {
+			return Err(HandleError::Client(Error::Value(0)));
+		}
+		let len = self.count as usize * self.keysyms_per_keycode as usize;
+		if self.keysyms.len() < len {
+			return Err(HandleError::Client(Error::Length));
+		}
+
+		ctx.get_keyboard_mut().get_keymap_mut().set_keysyms(
+			self.first_keycode,
+			self.keysyms_per_keycode as _,
+			&self.keysyms[..len],
+		);
+
+		ctx.broadcast_event(&MappingNotify {
+			code: event::MAPPING_NOTIFY,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			request: event::MAPPING_KEYBOARD,
+			first_keycode: self.first_keycode,
+			count: self.count,
+
+			_padding1: [0; 25],
+		});
+
+		Ok(())
+	}
+}
+
+/// Parses `ChangeKeyboardMapping`.
+///
+/// `count` is the number of keycodes.
+///
+/// Keycodes and keysyms are checked when the request is handled.
+pub fn read(buff: &[u8], count: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ChangeKeyboardMappingHdr>() {
+		return Ok(None);
+	}
+	let hdr: &ChangeKeyboardMappingHdr = unsafe { util::reinterpret(&buff[0]) };
+	let keysyms = util::read_list(&buff[size_of::<ChangeKeyboardMappingHdr>()..]);
+
+	Ok(Some(Box::new(ChangeKeyboardMapping {
+		first_keycode: hdr.first_keycode,
+		count,
+		keysyms_per_keycode: hdr.keysyms_per_keycode,
+		keysyms,
+	})))
+}
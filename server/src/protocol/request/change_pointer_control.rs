@@ -0,0 +1,115 @@
+//! The `ChangePointerControl` request allows to change the acceleration of the pointer.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::input::accel;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `ChangePointerControl` request.
+#[repr(C, packed)]
+pub struct ChangePointerControlHdr {
+	/// The numerator of the acceleration multiplier. `-1` restores the default.
+	acceleration_numerator: i16,
+	/// The denominator of the acceleration multiplier. `-1` restores the default.
+	acceleration_denominator: i16,
+	/// The threshold of the acceleration. `-1` restores the default.
+	threshold: i16,
+	/// Tells whether the multiplier is changed.
+	do_acceleration: u8,
+	/// Tells whether the threshold is changed.
+	do_threshold: u8,
+}
+
+/// Returns the value of the given field of the request, `default` standing for `-1`.
+///
+/// If the value is invalid, the function returns an error.
+fn get_value(value: i16, default: u16) -> Result<u16, Error> {
+	match value {
+		-1 => Ok(default),
+		v if v < 0 => Err(Error::Value(v as _)),
+		v => Ok(v as _),
+	}
+}
+
+/// Structure representing the request.
+pub struct ChangePointerControl {
+	/// The numerator of the acceleration multiplier. `-1` restores the default.
+	acceleration_numerator: i16,
+	/// The denominator of the acceleration multiplier. `-1` restores the default.
+	acceleration_denominator: i16,
+	/// The threshold of the acceleration. `-1` restores the default.
+	threshold: i16,
+	/// Tells whether the multiplier is changed.
+	do_acceleration: bool,
+	/// Tells whether the threshold is changed.
+	do_threshold: bool,
+}
+
+impl ChangePointerControl {
+	/// Returns the new numerator and denominator of the multiplier, if changed.
+	fn get_acceleration(&self) -> Result<Option<(u16, u16)>, Error> {
+		if !self.do_acceleration {
+			return Ok(None);
+		}
+		let numerator = get_value(self.acceleration_numerator, accel::DEFAULT_NUMERATOR)?;
+		let denominator = get_value(self.acceleration_denominator, accel::DEFAULT_DENOMINATOR)?;
+		if denominator == 0 {
+			return Err(Error::Value(0));
+		}
+		Ok(Some((numerator, denominator)))
+	}
+
+	/// Returns the new threshold, if changed.
+	fn get_threshold(&self) -> Result<Option<u16>, Error> {
+		if !self.do_threshold {
+			return Ok(None);
+		}
+		get_value(self.threshold, accel::DEFAULT_THRESHOLD).map(Some)
+	}
+}
+
+impl Request for ChangePointerControl {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		// Values are checked before changing anything
+		let acceleration = self.get_acceleration().map_err(HandleError::Client)?;
+		let threshold = self.get_threshold().map_err(HandleError::Client)?;
+
+		let accel = ctx.get_pointer_mut().get_accel_mut();
+		if let Some((numerator, denominator)) = acceleration {
+			accel.numerator = numerator;
+			accel.denominator = denominator;
+		}
+		if let Some(threshold) = threshold {
+			accel.threshold = threshold;
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `ChangePointerControl`.
+///
+/// Values are checked when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ChangePointerControlHdr>() {
+		return Ok(None);
+	}
+	let hdr: &ChangePointerControlHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ChangePointerControl {
+		acceleration_numerator: hdr.acceleration_numerator,
+		acceleration_denominator: hdr.acceleration_denominator,
+		threshold: hdr.threshold,
+		do_acceleration: hdr.do_acceleration != 0,
+		do_threshold: hdr.do_threshold != 0,
+	})))
+}
@@ -55,7 +55,7 @@ struct ChangePropertyHdr {
 /// Structure representing the request.
 pub struct ChangeProperty {
 	/// The action to perform.
-	mode: ChangePropertyMode,
+	mode: u8,
 
 	/// The window's ID.
 	window: u32,
@@ -65,11 +65,26 @@ pub struct ChangeProperty {
 	type_atom: u32,
 	/// The property's format.
 	format: u8,
+	/// The length of the value in format units.
+	length: u32,
 
-	/// The property's data.
+	/// The property's data, including padding.
 	data: Vec<u8>,
 }
 
+impl ChangeProperty {
+	/// Checks the request's values, then returns the action to perform and the property's data.
+	fn get_mode_and_data(&self) -> Result<(ChangePropertyMode, &[u8]), Error> {
+		let mode = ChangePropertyMode::from(self.mode).ok_or(Error::Value(self.mode as _))?;
+		if !matches!(self.format, 8 | 16 | 32) {
+			return Err(Error::Value(self.format as _));
+		}
+		let data_len = self.length as usize * (self.format as usize / 8);
+		let data = self.data.get(..data_len).ok_or(Error::Value(self.length))?;
+		Ok((mode, data))
+	}
+}
+
 impl Request for ChangeProperty {
 	fn handle(
 		&self,
@@ -77,6 +92,8 @@ impl Request for ChangeProperty {
 		_client: &mut Client,
 		_seq_nbr: u16,
 	) -> Result<(), HandleError> {
+		let (mode, data) = self.get_mode_and_data().map_err(HandleError::Client)?;
+
 		let prop_name = ctx
 			.get_atom(self.property)
 			.ok_or(HandleError::Client(Error::Atom(self.property)))?
@@ -88,25 +105,25 @@ impl Request for ChangeProperty {
 			.ok_or(HandleError::Client(Error::Window(self.window)))?;
 
 		if let Some(prop) = win.get_property_mut(&prop_name) {
-			let must_match = matches!(self.mode, ChangePropertyMode::Replace);
+			let must_match = matches!(mode, ChangePropertyMode::Replace);
 			let matching = prop.get_type() == self.type_atom && prop.get_format() == self.format;
 			if must_match && !matching {
 				return Err(HandleError::Client(Error::Match));
 			}
 
-			match self.mode {
+			match mode {
 				ChangePropertyMode::Replace => {
 					win.delete_property(&prop_name);
 
-					let prop = Property::new(self.type_atom, self.format, self.data.clone());
+					let prop = Property::new(self.type_atom, self.format, data.to_vec());
 					win.create_property(prop_name, prop);
 				}
 
-				ChangePropertyMode::Prepend => prop.prepend_data(&self.data),
-				ChangePropertyMode::Append => prop.append_data(&self.data),
+				ChangePropertyMode::Prepend => prop.prepend_data(data),
+				ChangePropertyMode::Append => prop.append_data(data),
 			}
 		} else {
-			let prop = Property::new(self.type_atom, self.format, self.data.clone());
+			let prop = Property::new(self.type_atom, self.format, data.to_vec());
 			win.create_property(prop_name, prop);
 		}
 
@@ -116,31 +133,14 @@ impl Request for ChangeProperty {
 
 /// Parses `ChangeProperty`.
 ///
-/// `mode` is the action to perform.
+/// `mode` is the action to perform. It is checked along with the format and length when the
+/// request is handled.
 pub fn read(buff: &[u8], mode: u8) -> Result<Option<Box<dyn Request>>, Error> {
 	if buff.len() < size_of::<ChangePropertyHdr>() {
 		return Ok(None);
 	}
 	let hdr: &ChangePropertyHdr = unsafe { util::reinterpret(&buff[0]) };
 
-	let mode = ChangePropertyMode::from(mode).ok_or(Error::Value(mode as _))?;
-
-	if !matches!(hdr.format, 8 | 16 | 32) {
-		return Err(Error::Value(hdr.format as _));
-	}
-
-	let data_len = hdr.length as usize * (hdr.format as usize / 8);
-
-	let data_begin = size_of::<ChangePropertyHdr>();
-	let data_end = data_begin + data_len;
-
-	if data_len > buff.len() {
-		return Err(Error::Value(hdr.length));
-	}
-
-	let data_slice = &buff[data_begin..data_end];
-	let data = data_slice.to_vec();
-
 	Ok(Some(Box::new(ChangeProperty {
 		mode,
 
@@ -148,7 +148,8 @@ pub fn read(buff: &[u8], mode: u8) -> Result<Option<Box<dyn Request>>, Error> {
 		property: hdr.property,
 		type_atom: hdr.type_atom,
 		format: hdr.format,
+		length: hdr.length,
 
-		data,
+		data: buff[size_of::<ChangePropertyHdr>()..].to_vec(),
 	})))
 }
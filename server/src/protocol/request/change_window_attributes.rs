@@ -4,6 +4,7 @@ use super::create_window;
 use super::Request;
 use crate::ctx::client::Client;
 use crate::ctx::Context;
+use crate::ctx::Drawable;
 use crate::protocol::error::Error;
 use crate::protocol::request::HandleError;
 use crate::util;
@@ -25,29 +26,85 @@ pub struct ChangeWindowAttributes {
 	/// The window.
 	window: u32,
 
-	/// The list of attributes to change.
-	changed_attrs: Vec<create_window::AttrValue>,
+	/// The mask of attributes being changed.
+	value_mask: u32,
+	/// The encoded values of attributes being changed.
+	values: Vec<u8>,
 }
 
 impl Request for ChangeWindowAttributes {
 	fn handle(
 		&self,
 		ctx: &mut Context,
-		_client: &mut Client,
+		client: &mut Client,
 		_seq_nbr: u16,
 	) -> Result<(), HandleError> {
+		let changed_attrs = create_window::read_attrs(self.value_mask, &self.values)
+			.map_err(HandleError::Client)?;
+
 		let wid =
 			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
 		let win = ctx
-			.get_window_mut(wid)
+			.get_window(wid)
 			.ok_or(HandleError::Client(Error::Window(self.window)))?;
-		create_window::set_attrs(&mut win.attributes, &self.changed_attrs);
+		let parent = win.get_parent().and_then(|id| ctx.get_window(id));
+		let pixmaps =
+			create_window::resolve_pixmap_attrs(ctx, win.get_depth(), parent, &changed_attrs)
+				.map_err(HandleError::Client)?;
+		let colormap =
+			create_window::resolve_colormap(ctx, win.attributes.visual, parent, &changed_attrs)
+				.map_err(HandleError::Client)?;
+		let cursor =
+			create_window::resolve_cursor(ctx, &changed_attrs).map_err(HandleError::Client)?;
+
+		// Cannot fail since the window has been checked before
+		let win = ctx.get_window_mut(wid).unwrap();
+		create_window::select_events(win, client.get_id(), &changed_attrs)
+			.map_err(HandleError::Client)?;
+		create_window::set_attrs(&mut win.attributes, &changed_attrs);
+		create_window::set_pixmap_attrs(win, pixmaps);
+		if let Some(cursor) = cursor {
+			win.set_cursor(cursor);
+		}
+		let colormap_changed = colormap.is_some_and(|c| c != win.attributes.colormap);
+		if let Some(colormap) = colormap {
+			win.attributes.colormap = colormap;
+		}
+		if colormap_changed {
+			ctx.colormap_notify(wid, true);
+			ctx.damage_window(wid);
+		}
+
+		let border_changed = changed_attrs.iter().any(|a| {
+			matches!(
+				a,
+				create_window::AttrValue::BorderPixmap(_)
+					| create_window::AttrValue::BorderPixel(_)
+			)
+		});
+		if border_changed {
+			ctx.damage_window(wid);
+		}
+
+		let saving_changed = changed_attrs.iter().any(|a| {
+			matches!(
+				a,
+				create_window::AttrValue::BackingStore(_) | create_window::AttrValue::SaveUnder(_)
+			)
+		});
+		if saving_changed {
+			// Cannot fail since the window has been checked before
+			let root = ctx.get_window(wid).unwrap().get_root();
+			ctx.update_visibility(NonZeroU32::new(root).unwrap());
+		}
 
 		Ok(())
 	}
 }
 
 /// Parses `ChangeWindowAttributes`.
+///
+/// The attributes are decoded when the request is handled.
 pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
 	if buff.len() < size_of::<ChangeWindowAttributesHdr>() {
 		return Ok(None);
@@ -55,12 +112,10 @@ pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
 
 	let hdr: &ChangeWindowAttributesHdr = unsafe { util::reinterpret(&buff[0]) };
 
-	let attrs_buff = &buff[size_of::<ChangeWindowAttributesHdr>()..];
-	let changed_attrs = create_window::read_attrs(hdr.value_mask, attrs_buff)?;
-
 	Ok(Some(Box::new(ChangeWindowAttributes {
 		window: hdr.window,
 
-		changed_attrs,
+		value_mask: hdr.value_mask,
+		values: buff[size_of::<ChangeWindowAttributesHdr>()..].to_vec(),
 	})))
 }
@@ -0,0 +1,113 @@
+//! The `ClearArea` request allows to fill an area of a window with its background.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::region::Rect;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::cmp::max;
+use std::cmp::min;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `ClearArea` request.
+#[repr(C, packed)]
+pub struct ClearAreaHdr {
+	/// The ID of the window.
+	window: u32,
+	/// The X position of the area.
+	x: i16,
+	/// The Y position of the area.
+	y: i16,
+	/// The width of the area. If zero, the area extends to the right edge of the window.
+	width: u16,
+	/// The height of the area. If zero, the area extends to the bottom edge of the window.
+	height: u16,
+}
+
+/// Structure representing the request.
+pub struct ClearArea {
+	/// Tells whether `Expose` events are generated for the cleared area.
+	exposures: bool,
+	/// The ID of the window.
+	window: u32,
+	/// The X position of the area.
+	x: i16,
+	/// The Y position of the area.
+	y: i16,
+	/// The width of the area. If zero, the area extends to the right edge of the window.
+	width: u16,
+	/// The height of the area. If zero, the area extends to the bottom edge of the window.
+	height: u16,
+}
+
+impl Request for ClearArea {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		if !win.is_output() {
+			return Err(HandleError::Client(Error::Match));
+		}
+
+		// Clipping the area to the window
+		let rect = win.get_rectangle();
+		let x0 = self.x as i32;
+		let y0 = self.y as i32;
+		let x1 = match self.width {
+			0 => rect.width as i32,
+			w => x0 + w as i32,
+		};
+		let y1 = match self.height {
+			0 => rect.height as i32,
+			h => y0 + h as i32,
+		};
+		let x0 = max(x0, 0);
+		let y0 = max(y0, 0);
+		let x1 = min(x1, rect.width as i32);
+		let y1 = min(y1, rect.height as i32);
+		if x0 >= x1 || y0 >= y1 {
+			return Ok(());
+		}
+
+		let area = Rect::new(x0, y0, x1, y1);
+		if self.exposures {
+			// Only the parts of the area that are visible or saved are exposed
+			let mut region = win.get_visibility().get_valid();
+			region.intersect_rect(area);
+			ctx.expose(wid, &region);
+		} else {
+			win.clear(ctx, &(&area).into());
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `ClearArea`.
+pub fn read(buff: &[u8], exposures: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ClearAreaHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ClearAreaHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ClearArea {
+		exposures: exposures != 0,
+		window: hdr.window,
+		x: hdr.x,
+		y: hdr.y,
+		width: hdr.width,
+		height: hdr.height,
+	})))
+}
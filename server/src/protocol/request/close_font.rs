@@ -0,0 +1,54 @@
+//! The `CloseFont` request allows to close a font.
+//!
+//! The font is unloaded only once it isn't referenced anymore, by another font ID or a graphics
+//! context.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `CloseFont` request.
+#[repr(C, packed)]
+pub struct CloseFontHdr {
+	/// The ID of the font.
+	font: u32,
+}
+
+/// Structure representing the request.
+pub struct CloseFont {
+	/// The ID of the font.
+	font: u32,
+}
+
+impl Request for CloseFont {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		NonZeroU32::new(self.font)
+			.and_then(|id| ctx.free_font(id))
+			.ok_or(HandleError::Client(Error::Font(self.font)))?;
+
+		Ok(())
+	}
+}
+
+/// Parses `CloseFont`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CloseFontHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CloseFontHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CloseFont {
+		font: hdr.font,
+	})))
+}
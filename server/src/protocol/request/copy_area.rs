@@ -0,0 +1,74 @@
+//! The `CopyArea` request allows to copy an area of a drawable to another.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::raster::copy;
+use crate::raster::copy::CopyArgs;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `CopyArea` request.
+#[repr(C, packed)]
+pub struct CopyAreaHdr {
+	/// The ID of the source drawable.
+	src_drawable: u32,
+	/// The ID of the destination drawable.
+	dst_drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+	/// The X position of the area in the source drawable.
+	src_x: i16,
+	/// The Y position of the area in the source drawable.
+	src_y: i16,
+	/// The X position of the area in the destination drawable.
+	dst_x: i16,
+	/// The Y position of the area in the destination drawable.
+	dst_y: i16,
+	/// The width of the area.
+	width: u16,
+	/// The height of the area.
+	height: u16,
+}
+
+/// Structure representing the request.
+pub struct CopyArea {
+	/// The parameters of the copy.
+	args: CopyArgs,
+}
+
+impl Request for CopyArea {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		copy::copy(ctx, client, &self.args, None).map_err(HandleError::Client)
+	}
+}
+
+/// Parses `CopyArea`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CopyAreaHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CopyAreaHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CopyArea {
+		args: CopyArgs {
+			src: hdr.src_drawable,
+			dst: hdr.dst_drawable,
+			gc: hdr.gc,
+			src_x: hdr.src_x,
+			src_y: hdr.src_y,
+			dst_x: hdr.dst_x,
+			dst_y: hdr.dst_y,
+			width: hdr.width,
+			height: hdr.height,
+		},
+	})))
+}
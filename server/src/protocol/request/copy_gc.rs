@@ -0,0 +1,77 @@
+//! The `CopyGC` request allows to copy values from a graphics context to another.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `CopyGC` request.
+#[repr(C, packed)]
+pub struct CopyGCHdr {
+	/// The source graphics context.
+	src_gc: u32,
+	/// The destination graphics context.
+	dst_gc: u32,
+
+	/// The mask of values being copied.
+	bitmask: u32,
+}
+
+/// Structure representing the request.
+pub struct CopyGC {
+	/// The source graphics context.
+	src_gc: u32,
+	/// The destination graphics context.
+	dst_gc: u32,
+
+	/// The mask of values being copied.
+	bitmask: u32,
+}
+
+impl Request for CopyGC {
+	fn handle(
+		&self,
+		_ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		if self.bitmask >= 1 << 23 {
+			return Err(HandleError::Client(Error::Value(self.bitmask)));
+		}
+
+		let src = client
+			.get_gc(self.src_gc)
+			.ok_or(HandleError::Client(Error::GContext(self.src_gc)))?
+			.clone();
+		let dst = client
+			.get_gc_mut(self.dst_gc)
+			.ok_or(HandleError::Client(Error::GContext(self.dst_gc)))?;
+		if src.depth != dst.depth || src.root != dst.root {
+			return Err(HandleError::Client(Error::Match));
+		}
+
+		dst.copy_values(&src, self.bitmask);
+		Ok(())
+	}
+}
+
+/// Parses `CopyGC`.
+///
+/// The mask is checked when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CopyGCHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CopyGCHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CopyGC {
+		src_gc: hdr.src_gc,
+		dst_gc: hdr.dst_gc,
+
+		bitmask: hdr.bitmask,
+	})))
+}
@@ -0,0 +1,80 @@
+//! The `CopyPlane` request allows to copy a single bit plane of an area of a drawable to another,
+//! using the foreground and background of a graphics context.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::raster::copy;
+use crate::raster::copy::CopyArgs;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `CopyPlane` request.
+#[repr(C, packed)]
+pub struct CopyPlaneHdr {
+	/// The ID of the source drawable.
+	src_drawable: u32,
+	/// The ID of the destination drawable.
+	dst_drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+	/// The X position of the area in the source drawable.
+	src_x: i16,
+	/// The Y position of the area in the source drawable.
+	src_y: i16,
+	/// The X position of the area in the destination drawable.
+	dst_x: i16,
+	/// The Y position of the area in the destination drawable.
+	dst_y: i16,
+	/// The width of the area.
+	width: u16,
+	/// The height of the area.
+	height: u16,
+	/// The bit plane to copy.
+	bit_plane: u32,
+}
+
+/// Structure representing the request.
+pub struct CopyPlane {
+	/// The parameters of the copy.
+	args: CopyArgs,
+	/// The bit plane to copy. Exactly one bit must be set.
+	bit_plane: u32,
+}
+
+impl Request for CopyPlane {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		copy::copy(ctx, client, &self.args, Some(self.bit_plane)).map_err(HandleError::Client)
+	}
+}
+
+/// Parses `CopyPlane`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CopyPlaneHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CopyPlaneHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CopyPlane {
+		args: CopyArgs {
+			src: hdr.src_drawable,
+			dst: hdr.dst_drawable,
+			gc: hdr.gc,
+			src_x: hdr.src_x,
+			src_y: hdr.src_y,
+			dst_x: hdr.dst_x,
+			dst_y: hdr.dst_y,
+			width: hdr.width,
+			height: hdr.height,
+		},
+		bit_plane: hdr.bit_plane,
+	})))
+}
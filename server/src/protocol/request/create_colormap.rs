@@ -0,0 +1,94 @@
+//! The `CreateColormap` request allows to create a colormap for a visual of a screen.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::colormap::Colormap;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `CreateColormap` request.
+#[repr(C, packed)]
+pub struct CreateColormapHdr {
+	/// The ID of the colormap to create.
+	mid: u32,
+	/// A window on the screen of the colormap.
+	window: u32,
+	/// The ID of the visual of the colormap.
+	visual: u32,
+}
+
+/// Structure representing the request.
+pub struct CreateColormap {
+	/// Tells whether every entry is allocated read-write to the client: `0` for none, `1` for all.
+	alloc: u8,
+	/// The ID of the colormap to create.
+	mid: u32,
+	/// A window on the screen of the colormap.
+	window: u32,
+	/// The ID of the visual of the colormap.
+	visual: u32,
+}
+
+impl Request for CreateColormap {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let alloc_all = match self.alloc {
+			0 => false,
+			1 => true,
+			_ => return Err(HandleError::Client(Error::Value(self.alloc as _))),
+		};
+
+		let mid =
+			NonZeroU32::new(self.mid).ok_or(HandleError::Client(Error::IDChoice(self.mid)))?;
+		if ctx.is_id_used(mid) {
+			return Err(HandleError::Client(Error::IDChoice(self.mid)));
+		}
+
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		// Cannot fail since every window belongs to a screen
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let visual = ctx
+			.get_screen(root)
+			.and_then(|s| s.get_visual(self.visual))
+			.ok_or(HandleError::Client(Error::Match))?;
+
+		let colormap = Colormap::new(mid, root, &visual, alloc_all.then_some(client.get_id()));
+		// Entries of static colormaps cannot be allocated
+		if alloc_all && !colormap.is_dynamic() {
+			return Err(HandleError::Client(Error::Match));
+		}
+		ctx.add_colormap(colormap, client.get_id());
+
+		Ok(())
+	}
+}
+
+/// Parses `CreateColormap`.
+///
+/// The allocation mode is checked when the request is handled.
+pub fn read(buff: &[u8], alloc: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CreateColormapHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CreateColormapHdr = unsafe { util::reinterpret(&buff[0]) };
+	Ok(Some(Box::new(CreateColormap {
+		alloc,
+		mid: hdr.mid,
+		window: hdr.window,
+		visual: hdr.visual,
+	})))
+}
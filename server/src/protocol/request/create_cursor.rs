@@ -0,0 +1,121 @@
+//! The `CreateCursor` request allows to create a cursor from pixmaps.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::cursor::Cursor;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+/// Header of the `CreateCursor` request.
+#[repr(C, packed)]
+pub struct CreateCursorHdr {
+	/// The ID of the cursor.
+	cid: u32,
+	/// The pixmap telling which pixels are drawn with the foreground color.
+	source: u32,
+	/// The pixmap telling which pixels are drawn. If zero, every pixel is drawn.
+	mask: u32,
+	/// The red component of the foreground color.
+	fore_red: u16,
+	/// The green component of the foreground color.
+	fore_green: u16,
+	/// The blue component of the foreground color.
+	fore_blue: u16,
+	/// The red component of the background color.
+	back_red: u16,
+	/// The green component of the background color.
+	back_green: u16,
+	/// The blue component of the background color.
+	back_blue: u16,
+	/// The X position of the hotspot, relative to the source's origin.
+	x: u16,
+	/// The Y position of the hotspot, relative to the source's origin.
+	y: u16,
+}
+
+/// Structure representing the request.
+pub struct CreateCursor {
+	/// The ID of the cursor.
+	cid: u32,
+	/// The pixmap telling which pixels are drawn with the foreground color.
+	source: u32,
+	/// The pixmap telling which pixels are drawn. If zero, every pixel is drawn.
+	mask: u32,
+	/// The foreground color.
+	fore: [u16; 3],
+	/// The background color.
+	back: [u16; 3],
+	/// The X position of the hotspot, relative to the source's origin.
+	x: u16,
+	/// The Y position of the hotspot, relative to the source's origin.
+	y: u16,
+}
+
+impl Request for CreateCursor {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let cid =
+			NonZeroU32::new(self.cid).ok_or(HandleError::Client(Error::IDChoice(self.cid)))?;
+		if ctx.is_id_used(cid) {
+			return Err(HandleError::Client(Error::IDChoice(self.cid)));
+		}
+
+		let source = NonZeroU32::new(self.source)
+			.and_then(|id| ctx.get_pixmap(id))
+			.ok_or(HandleError::Client(Error::Pixmap(self.source)))?;
+		let mask = match NonZeroU32::new(self.mask) {
+			Some(id) => Some(
+				ctx.get_pixmap(id)
+					.ok_or(HandleError::Client(Error::Pixmap(self.mask)))?,
+			),
+			None => None,
+		};
+
+		// Cannot fail since pixmaps always have a storage
+		let source_image = source.get_image().unwrap();
+		let mask_image = mask.and_then(|m| m.get_image());
+		let cursor = Cursor::from_images(
+			&source_image,
+			mask_image.as_deref(),
+			self.x,
+			self.y,
+			self.fore,
+			self.back,
+		)
+		.map_err(HandleError::Client)?;
+		drop(source_image);
+		drop(mask_image);
+		ctx.add_cursor(cid, Rc::new(cursor), client.get_id());
+
+		Ok(())
+	}
+}
+
+/// Parses `CreateCursor`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CreateCursorHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CreateCursorHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CreateCursor {
+		cid: hdr.cid,
+		source: hdr.source,
+		mask: hdr.mask,
+		fore: [hdr.fore_red, hdr.fore_green, hdr.fore_blue],
+		back: [hdr.back_red, hdr.back_green, hdr.back_blue],
+		x: hdr.x,
+		y: hdr.y,
+	})))
+}
@@ -9,8 +9,9 @@ use crate::protocol::error::Error;
 use crate::protocol::request::HandleError;
 use crate::util;
 use std::mem::size_of;
+use std::num::NonZeroU32;
 
-/// TODO doc
+/// Header of the `CreateGC` request.
 #[repr(C, packed)]
 pub struct CreateGCHdr {
 	/// The graphics context's ID.
@@ -18,7 +19,7 @@ pub struct CreateGCHdr {
 	/// The ID of the drawable.
 	drawable: u32,
 
-	/// TODO
+	/// The mask of values being set.
 	bitmask: u32,
 }
 
@@ -26,35 +27,57 @@ pub struct CreateGCHdr {
 pub struct CreateGC {
 	/// The graphics context's ID.
 	cid: u32,
-	/// The graphics context.
-	gc: GC,
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The mask of values being set.
+	bitmask: u32,
+	/// The encoded values.
+	values: Vec<u8>,
 }
 
 impl Request for CreateGC {
 	fn handle(
 		&self,
-		_ctx: &mut Context,
+		ctx: &mut Context,
 		client: &mut Client,
 		_seq_nbr: u16,
 	) -> Result<(), HandleError> {
-		client.set_gc(self.cid, self.gc.clone());
+		let id_used = NonZeroU32::new(self.cid)
+			.map(|id| ctx.is_id_used(id))
+			.unwrap_or(true);
+		if id_used || client.get_gc(self.cid).is_some() {
+			return Err(HandleError::Client(Error::IDChoice(self.cid)));
+		}
+
+		let drawable = NonZeroU32::new(self.drawable)
+			.and_then(|id| ctx.get_drawable(id))
+			.ok_or(HandleError::Client(Error::Drawable(self.drawable)))?;
+
+		let mut gc = GC::new(drawable.get_depth(), drawable.get_root());
+		let values = read_values(self.bitmask, &self.values).map_err(HandleError::Client)?;
+		gc.set_values(ctx, &values).map_err(HandleError::Client)?;
+
+		client.set_gc(self.cid, gc);
 		Ok(())
 	}
 }
 
-/// Parses `CreateGC`.
-pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
-	if buff.len() < size_of::<CreateGCHdr>() {
-		return Ok(None);
-	}
-
-	let hdr: &CreateGCHdr = unsafe { util::reinterpret(&buff[0]) };
-
+/// Reads GC values from a request. The function returns the list of values.
+///
+/// Arguments:
+/// - `bitmask` is the bitmask of values to read.
+/// - `buff` is the buffer containing the values.
+pub fn read_values(bitmask: u32, buff: &[u8]) -> Result<Vec<gc::Value>, Error> {
 	let mut values = vec![];
 
-	let mut off = size_of::<CreateGCHdr>();
-	let set_bits_iter = (0..=22).filter(|i| hdr.bitmask & (1 << i) != 0);
+	let mut off = 0;
+	let set_bits_iter = (0..=22).filter(|i| bitmask & (1 << i) != 0);
 	for id in set_bits_iter {
+		// Each value is encoded on 4 bytes
+		if buff.len() < off + 4 {
+			return Err(Error::Length);
+		}
+
 		let size = match id {
 			0 => 1,
 			1 => 4,
@@ -86,11 +109,11 @@ pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
 		let val = match size {
 			1 => unsafe { *util::reinterpret::<_, u8>(&buff[off]) as u32 },
 			2 => unsafe { *util::reinterpret::<_, u16>(&buff[off]) as u32 },
-			4 => unsafe { *util::reinterpret::<_, u32>(&buff[off]) as u32 },
+			4 => unsafe { *util::reinterpret::<_, u32>(&buff[off]) },
 
 			_ => unreachable!(),
 		};
-		off += size;
+		off += 4;
 
 		let val = match id {
 			0 => gc::Value::Function((val as u8).try_into()?),
@@ -105,13 +128,13 @@ pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
 			9 => gc::Value::FillRule((val as u8).try_into()?),
 			10 => gc::Value::Tile(val),
 			11 => gc::Value::Stipple(val),
-			12 => gc::Value::TileStippleXOrigin(val as _),
-			13 => gc::Value::TileStippleYOrigin(val as _),
+			12 => gc::Value::TileStippleXOrigin(val as u16 as _),
+			13 => gc::Value::TileStippleYOrigin(val as u16 as _),
 			14 => gc::Value::Font(val),
 			15 => gc::Value::SubwindowMode((val as u8).try_into()?),
 			16 => gc::Value::GraphicsExposures(val as _),
-			17 => gc::Value::ClipXOrigin(val as _),
-			18 => gc::Value::ClipYOrigin(val as _),
+			17 => gc::Value::ClipXOrigin(val as u16 as _),
+			18 => gc::Value::ClipYOrigin(val as u16 as _),
 			19 => gc::Value::ClipMask(val),
 			20 => gc::Value::DashOffset(val as _),
 			21 => gc::Value::Dashes(val as _),
@@ -123,13 +146,23 @@ pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
 		values.push(val);
 	}
 
-	let gc = GC {
-		drawable: hdr.drawable,
-		values,
-	};
+	Ok(values)
+}
+
+/// Parses `CreateGC`.
+///
+/// Values are decoded and checked when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CreateGCHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CreateGCHdr = unsafe { util::reinterpret(&buff[0]) };
 
 	Ok(Some(Box::new(CreateGC {
 		cid: hdr.cid,
-		gc,
+		drawable: hdr.drawable,
+		bitmask: hdr.bitmask,
+		values: buff[size_of::<CreateGCHdr>()..].to_vec(),
 	})))
 }
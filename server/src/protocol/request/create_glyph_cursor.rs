@@ -0,0 +1,123 @@
+//! The `CreateGlyphCursor` request allows to create a cursor from characters of fonts.
+//!
+//! The hotspot of the cursor is the origin of the source character.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::cursor::Cursor;
+use crate::ctx::Context;
+use crate::font::Font;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+/// Header of the `CreateGlyphCursor` request.
+#[repr(C, packed)]
+pub struct CreateGlyphCursorHdr {
+	/// The ID of the cursor.
+	cid: u32,
+	/// The font of the source character.
+	source_font: u32,
+	/// The font of the mask character. If zero, the pixels of the source character are drawn.
+	mask_font: u32,
+	/// The character telling which pixels are drawn with the foreground color.
+	source_char: u16,
+	/// The character telling which pixels are drawn.
+	mask_char: u16,
+	/// The red component of the foreground color.
+	fore_red: u16,
+	/// The green component of the foreground color.
+	fore_green: u16,
+	/// The blue component of the foreground color.
+	fore_blue: u16,
+	/// The red component of the background color.
+	back_red: u16,
+	/// The green component of the background color.
+	back_green: u16,
+	/// The blue component of the background color.
+	back_blue: u16,
+}
+
+/// Structure representing the request.
+pub struct CreateGlyphCursor {
+	/// The ID of the cursor.
+	cid: u32,
+	/// The font of the source character.
+	source_font: u32,
+	/// The font of the mask character. If zero, the pixels of the source character are drawn.
+	mask_font: u32,
+	/// The character telling which pixels are drawn with the foreground color.
+	source_char: u16,
+	/// The character telling which pixels are drawn.
+	mask_char: u16,
+	/// The foreground color.
+	fore: [u16; 3],
+	/// The background color.
+	back: [u16; 3],
+}
+
+/// Returns the font with the given ID, checking the given character is defined in it.
+///
+/// If the font doesn't exist or if the character isn't defined in it, the function returns an
+/// error.
+fn get_font<'c>(ctx: &'c Context, font: u32, c: u16) -> Result<&'c Rc<Font>, Error> {
+	let font = NonZeroU32::new(font)
+		.and_then(|id| ctx.get_font(id))
+		.ok_or(Error::Font(font))?;
+	if !font.is_defined(c) {
+		return Err(Error::Value(c as _));
+	}
+	Ok(font)
+}
+
+impl Request for CreateGlyphCursor {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let cid =
+			NonZeroU32::new(self.cid).ok_or(HandleError::Client(Error::IDChoice(self.cid)))?;
+		if ctx.is_id_used(cid) {
+			return Err(HandleError::Client(Error::IDChoice(self.cid)));
+		}
+
+		let source_font =
+			get_font(ctx, self.source_font, self.source_char).map_err(HandleError::Client)?;
+		let mask_font = match self.mask_font {
+			0 => None,
+			font => Some(get_font(ctx, font, self.mask_char).map_err(HandleError::Client)?),
+		};
+
+		// Cannot fail since the characters have been checked before
+		let source = source_font.get_glyph(self.source_char).unwrap();
+		let mask = mask_font.map(|font| font.get_glyph(self.mask_char).unwrap());
+		let cursor = Cursor::from_glyphs(source, mask, self.fore, self.back);
+		ctx.add_cursor(cid, Rc::new(cursor), client.get_id());
+
+		Ok(())
+	}
+}
+
+/// Parses `CreateGlyphCursor`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CreateGlyphCursorHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CreateGlyphCursorHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CreateGlyphCursor {
+		cid: hdr.cid,
+		source_font: hdr.source_font,
+		mask_font: hdr.mask_font,
+		source_char: hdr.source_char,
+		mask_char: hdr.mask_char,
+		fore: [hdr.fore_red, hdr.fore_green, hdr.fore_blue],
+		back: [hdr.back_red, hdr.back_green, hdr.back_blue],
+	})))
+}
@@ -0,0 +1,99 @@
+//! The `CreatePixmap` request allows to create a pixmap.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::pixmap::Pixmap;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+/// Header of the `CreatePixmap` request.
+#[repr(C, packed)]
+pub struct CreatePixmapHdr {
+	/// The ID of the pixmap.
+	pid: u32,
+	/// The drawable used to determine the screen of the pixmap.
+	drawable: u32,
+	/// The width of the pixmap.
+	width: u16,
+	/// The height of the pixmap.
+	height: u16,
+}
+
+/// Structure representing the request.
+pub struct CreatePixmap {
+	/// The ID of the pixmap.
+	pid: u32,
+	/// The drawable used to determine the screen of the pixmap.
+	drawable: u32,
+	/// The width of the pixmap.
+	width: u16,
+	/// The height of the pixmap.
+	height: u16,
+
+	/// The depth of the pixmap.
+	depth: u8,
+}
+
+impl Request for CreatePixmap {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let pid =
+			NonZeroU32::new(self.pid).ok_or(HandleError::Client(Error::IDChoice(self.pid)))?;
+		if ctx.is_id_used(pid) {
+			return Err(HandleError::Client(Error::IDChoice(self.pid)));
+		}
+
+		let drawable = NonZeroU32::new(self.drawable)
+			.and_then(|id| ctx.get_drawable(id))
+			.ok_or(HandleError::Client(Error::Drawable(self.drawable)))?;
+		// Cannot fail since roots are windows
+		let root = NonZeroU32::new(drawable.get_root()).unwrap();
+
+		if self.width == 0 {
+			return Err(HandleError::Client(Error::Value(self.width as _)));
+		}
+		if self.height == 0 {
+			return Err(HandleError::Client(Error::Value(self.height as _)));
+		}
+
+		// Cannot fail since every root belongs to a screen
+		let screen = ctx.get_screen(root).unwrap();
+		if !screen.get_visuals().has_depth(self.depth) {
+			return Err(HandleError::Client(Error::Value(self.depth as _)));
+		}
+		let pixmap = Pixmap::new(pid, root, self.width, self.height, self.depth)
+			.map_err(HandleError::Client)?;
+		ctx.add_pixmap(Rc::new(pixmap), client.get_id());
+
+		Ok(())
+	}
+}
+
+/// Parses `CreatePixmap`.
+///
+/// `depth` is the depth of the pixmap.
+pub fn read(buff: &[u8], depth: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CreatePixmapHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CreatePixmapHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CreatePixmap {
+		pid: hdr.pid,
+		drawable: hdr.drawable,
+		width: hdr.width,
+		height: hdr.height,
+
+		depth,
+	})))
+}
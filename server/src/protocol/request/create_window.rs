@@ -2,9 +2,13 @@
 
 use super::Request;
 use crate::ctx::client::Client;
+use crate::ctx::cursor::CursorRef;
+use crate::ctx::pixmap::PixmapRef;
+use crate::ctx::window::Background;
 use crate::ctx::window::Window;
 use crate::ctx::window::WindowAttributes;
 use crate::ctx::Context;
+use crate::ctx::Drawable;
 use crate::protocol::error::Error;
 use crate::protocol::request::HandleError;
 use crate::protocol::BackingStore;
@@ -84,8 +88,10 @@ pub struct CreateWindow {
 	/// TODO doc
 	depth: u8,
 
-	/// The list of attributes being set.
-	attrs: Vec<AttrValue>,
+	/// The mask of attributes being set.
+	value_mask: u32,
+	/// The encoded values of attributes being set.
+	values: Vec<u8>,
 }
 
 impl Request for CreateWindow {
@@ -93,8 +99,61 @@ impl Request for CreateWindow {
 		&self,
 		ctx: &mut Context,
 		client: &mut Client,
-		seq_nbr: u16,
+		_seq_nbr: u16,
 	) -> Result<(), HandleError> {
+		let attrs = read_attrs(self.value_mask, &self.values).map_err(HandleError::Client)?;
+
+		let wid =
+			NonZeroU32::new(self.wid).ok_or(HandleError::Client(Error::IDChoice(self.wid)))?;
+		if ctx.is_id_used(wid) {
+			return Err(HandleError::Client(Error::IDChoice(self.wid)));
+		}
+
+		let parent_id =
+			NonZeroU32::new(self.parent).ok_or(HandleError::Client(Error::Window(self.parent)))?;
+		let parent = ctx
+			.get_window(parent_id)
+			.ok_or(HandleError::Client(Error::Window(self.parent)))?;
+
+		// Interpreting CopyFromParent
+		let depth = if self.depth == 0 {
+			parent.get_depth()
+		} else {
+			self.depth
+		};
+		let class = match self.class {
+			Class::CopyFromParent => parent.attributes.class,
+			c => c,
+		};
+		let visual = if self.visual == 0 {
+			parent.attributes.visual
+		} else {
+			self.visual
+		};
+
+		// The visual must belong to the depth of the window on its screen
+		if matches!(class, Class::InputOutput) {
+			// Cannot fail since every window belongs to a screen
+			let root = NonZeroU32::new(parent.get_root()).unwrap();
+			let screen = ctx.get_screen(root).unwrap();
+			match screen.get_visuals().get_visual(visual) {
+				Some((d, _)) if d == depth => {}
+				_ => return Err(HandleError::Client(Error::Match)),
+			}
+		}
+
+		let pixmaps =
+			resolve_pixmap_attrs(ctx, depth, Some(parent), &attrs).map_err(HandleError::Client)?;
+		let cursor = resolve_cursor(ctx, &attrs).map_err(HandleError::Client)?;
+		// If not given, the colormap is copied from the parent when possible
+		let colormap = resolve_colormap(ctx, visual, Some(parent), &attrs)
+			.map_err(HandleError::Client)?
+			.unwrap_or(if parent.attributes.visual == visual {
+				parent.attributes.colormap
+			} else {
+				0
+			});
+
 		let rect = Rectangle {
 			x: self.x,
 			y: self.y,
@@ -102,16 +161,33 @@ impl Request for CreateWindow {
 			width: self.width,
 			height: self.height,
 		};
-		let mut window = Window::new(ctx, NonZeroU32::new(self.parent), rect);
+		let window = Window::new(ctx, wid, Some(parent_id), rect).map_err(HandleError::Client)?;
 
-		window.set_depth(self.depth);
 		window.set_border_width(self.border_width);
 
-		// TODO Interpret copy_from_parent
-		let mut attr = WindowAttributes::default();
-		set_attrs(&mut attr, &self.attrs);
+		let mut attr = WindowAttributes {
+			class,
+			visual,
+			colormap,
 
-		window.set_attributes(attr);
+			..Default::default()
+		};
+		set_attrs(&mut attr, &attrs);
+
+		// The storage is allocated according to the depth and class. On failure, the window is
+		// not created
+		let res = window
+			.set_depth(depth)
+			.and_then(|_| window.set_attributes(attr));
+		if let Err(e) = res {
+			Window::remove(ctx, wid);
+			return Err(HandleError::Client(e));
+		}
+		// Cannot fail since the window has just been created
+		let window = ctx.get_window_mut(wid).unwrap();
+		set_pixmap_attrs(window, pixmaps);
+		window.set_cursor(cursor.flatten());
+		select_events(window, client.get_id(), &attrs).map_err(HandleError::Client)?;
 
 		Ok(())
 	}
@@ -128,6 +204,11 @@ pub fn read_attrs(bitmask: u32, buff: &[u8]) -> Result<Vec<AttrValue>, Error> {
 	let mut off = 0;
 	let set_bits_iter = (0..=14).filter(|i| bitmask & (1 << i) != 0);
 	for id in set_bits_iter {
+		// Each value is encoded on 4 bytes
+		if buff.len() < off + 4 {
+			return Err(Error::Length);
+		}
+
 		let size = match id {
 			0 => 4,
 			1 => 4,
@@ -151,11 +232,11 @@ pub fn read_attrs(bitmask: u32, buff: &[u8]) -> Result<Vec<AttrValue>, Error> {
 		let val = match size {
 			1 => unsafe { *util::reinterpret::<_, u8>(&buff[off]) as u32 },
 			2 => unsafe { *util::reinterpret::<_, u16>(&buff[off]) as u32 },
-			4 => unsafe { *util::reinterpret::<_, u32>(&buff[off]) as u32 },
+			4 => unsafe { *util::reinterpret::<_, u32>(&buff[off]) },
 
 			_ => unreachable!(),
 		};
-		off += size;
+		off += 4;
 
 		let val = match id {
 			0 => AttrValue::BackgroundPixmap(val),
@@ -198,17 +279,194 @@ pub fn set_attrs(attrs: &mut WindowAttributes, list: &[AttrValue]) {
 			AttrValue::BackingPixel(val) => attrs.backing_pixel = *val,
 			AttrValue::OverrideRedirect(val) => attrs.override_redirect = *val,
 			AttrValue::SaveUnder(val) => attrs.save_under = *val,
-			AttrValue::EventMask(val) => attrs.event_mask = *val,
+			// Selected for each client with `select_events`
+			AttrValue::EventMask(_) => {}
 			AttrValue::DoNotPropagateMask(val) => attrs.do_not_propagate_mask = *val,
-			AttrValue::Colormap(val) => attrs.colormap = *val,
+			// Resolved with `resolve_colormap`
+			AttrValue::Colormap(_) => {}
 			AttrValue::Cursor(val) => attrs.cursor = *val,
 		}
 	}
 }
 
+/// Selects the events given in the attributes list for the given client on the given window.
+///
+/// If the selection is not allowed, the function returns an error.
+pub fn select_events(win: &mut Window, client: u32, list: &[AttrValue]) -> Result<(), Error> {
+	for a in list {
+		if let AttrValue::EventMask(mask) = a {
+			win.select_events(client, *mask)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Resolves the colormap given in the attributes list.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `visual` is the visual of the window.
+/// - `parent` is the parent of the window, if any.
+/// - `list` is the list of attributes.
+///
+/// If the list doesn't contain a colormap, the function returns None.
+///
+/// If the colormap doesn't exist, if its visual doesn't match the window's or if it is
+/// `CopyFromParent` and the parent has no colormap, the function returns an error.
+pub fn resolve_colormap(
+	ctx: &Context,
+	visual: u32,
+	parent: Option<&Window>,
+	list: &[AttrValue],
+) -> Result<Option<u32>, Error> {
+	let Some(id) = list.iter().rev().find_map(|a| match a {
+		AttrValue::Colormap(id) => Some(*id),
+		_ => None,
+	}) else {
+		return Ok(None);
+	};
+
+	// CopyFromParent
+	if id == 0 {
+		return match parent {
+			Some(parent)
+				if parent.attributes.visual == visual && parent.attributes.colormap != 0 =>
+			{
+				Ok(Some(parent.attributes.colormap))
+			}
+			_ => Err(Error::Match),
+		};
+	}
+
+	let colormap = NonZeroU32::new(id)
+		.and_then(|id| ctx.get_colormap(id))
+		.ok_or(Error::Colormap(id))?;
+	if colormap.get_visual() != visual {
+		return Err(Error::Match);
+	}
+	Ok(Some(id))
+}
+
+/// Resolves the cursor given in the attributes list.
+///
+/// If the list doesn't contain a cursor, the function returns None. If the cursor is `None`, the
+/// function returns `Some(None)`.
+///
+/// If the cursor doesn't exist, the function returns an error.
+pub fn resolve_cursor(
+	ctx: &Context,
+	list: &[AttrValue],
+) -> Result<Option<Option<CursorRef>>, Error> {
+	let Some(id) = list.iter().rev().find_map(|a| match a {
+		AttrValue::Cursor(id) => Some(*id),
+		_ => None,
+	}) else {
+		return Ok(None);
+	};
+	let Some(id) = NonZeroU32::new(id) else {
+		return Ok(Some(None));
+	};
+
+	let cursor = ctx.get_cursor(id).ok_or(Error::Cursor(id.get()))?;
+	Ok(Some(Some(cursor.clone())))
+}
+
+/// A background or border attribute, resolved from its value.
+pub enum PixmapAttr {
+	/// The background.
+	Background(Background),
+	/// The border pixmap.
+	Border(Option<PixmapRef>),
+}
+
+/// Resolves the pixmaps referenced by the given attributes list.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `depth` is the depth of the window.
+/// - `parent` is the parent of the window, if any.
+/// - `list` is the list of attributes.
+///
+/// If a pixmap doesn't exist or if its depth doesn't match the window's, the function returns an
+/// error.
+///
+/// A background pixel overrides a background pixmap given in the same list.
+pub fn resolve_pixmap_attrs(
+	ctx: &Context,
+	depth: u8,
+	parent: Option<&Window>,
+	list: &[AttrValue],
+) -> Result<Vec<PixmapAttr>, Error> {
+	let resolve = |id: u32| -> Result<PixmapRef, Error> {
+		let pixmap = NonZeroU32::new(id)
+			.and_then(|id| ctx.get_pixmap(id))
+			.ok_or(Error::Pixmap(id))?;
+		if pixmap.get_depth() != depth {
+			return Err(Error::Match);
+		}
+
+		Ok(pixmap.clone())
+	};
+
+	let mut pixmaps = vec![];
+	for a in list {
+		let attr = match a {
+			AttrValue::BackgroundPixmap(0) => PixmapAttr::Background(Background::None),
+			AttrValue::BackgroundPixmap(1) => {
+				// ParentRelative
+				match parent {
+					Some(parent) if parent.get_depth() == depth => {}
+					_ => return Err(Error::Match),
+				}
+
+				PixmapAttr::Background(Background::ParentRelative)
+			}
+			AttrValue::BackgroundPixmap(id) => {
+				PixmapAttr::Background(Background::Pixmap(resolve(*id)?))
+			}
+			// Comes after the background pixmap in the list, thus overriding it
+			AttrValue::BackgroundPixel(pixel) => PixmapAttr::Background(Background::Pixel(*pixel)),
+
+			// CopyFromParent
+			AttrValue::BorderPixmap(0) => {
+				let pixmap = parent.and_then(|p| p.get_border_pixmap()).cloned();
+				if let Some(pixmap) = &pixmap {
+					if pixmap.get_depth() != depth {
+						return Err(Error::Match);
+					}
+				}
+
+				PixmapAttr::Border(pixmap)
+			}
+			AttrValue::BorderPixmap(id) => PixmapAttr::Border(Some(resolve(*id)?)),
+			// Comes after the border pixmap in the list, thus overriding it
+			AttrValue::BorderPixel(_) => PixmapAttr::Border(None),
+
+			_ => continue,
+		};
+
+		pixmaps.push(attr);
+	}
+
+	Ok(pixmaps)
+}
+
+/// Sets the given resolved pixmaps on the given window.
+pub fn set_pixmap_attrs(win: &mut Window, list: Vec<PixmapAttr>) {
+	for a in list {
+		match a {
+			PixmapAttr::Background(background) => win.set_background(background),
+			PixmapAttr::Border(pixmap) => win.set_border_pixmap(pixmap),
+		}
+	}
+}
+
 /// Parses `CreateWindow`.
 ///
 /// TODO doc: depth
+///
+/// The attributes are decoded when the request is handled.
 pub fn read(buff: &[u8], depth: u8) -> Result<Option<Box<dyn Request>>, Error> {
 	if buff.len() < size_of::<CreateWindowHdr>() {
 		return Ok(None);
@@ -216,9 +474,6 @@ pub fn read(buff: &[u8], depth: u8) -> Result<Option<Box<dyn Request>>, Error> {
 
 	let hdr: &CreateWindowHdr = unsafe { util::reinterpret(&buff[0]) };
 
-	let attrs_buff = &buff[size_of::<CreateWindowHdr>()..];
-	let attrs = read_attrs(hdr.value_mask, attrs_buff)?;
-
 	Ok(Some(Box::new(CreateWindow {
 		wid: hdr.wid,
 		parent: hdr.parent,
@@ -232,6 +487,7 @@ pub fn read(buff: &[u8], depth: u8) -> Result<Option<Box<dyn Request>>, Error> {
 
 		depth,
 
-		attrs,
+		value_mask: hdr.value_mask,
+		values: buff[size_of::<CreateWindowHdr>()..].to_vec(),
 	})))
 }
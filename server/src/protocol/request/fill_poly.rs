@@ -0,0 +1,85 @@
+//! The `FillPoly` request allows to fill a polygon.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::CoordinateMode;
+use crate::protocol::Point;
+use crate::raster;
+use crate::raster::polygon;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `FillPoly` request.
+#[repr(C, packed)]
+pub struct FillPolyHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// A hint about the shape of the polygon. Since the server doesn't rely on it, it is ignored.
+	shape: u8,
+	/// The way coordinates are interpreted.
+	coordinate_mode: u8,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct FillPoly {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// A hint about the shape of the polygon.
+	shape: u8,
+	/// The way coordinates are interpreted.
+	coordinate_mode: u8,
+	/// The list of points.
+	points: Vec<Point>,
+}
+
+impl Request for FillPoly {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		if self.shape > 2 {
+			return Err(HandleError::Client(Error::Value(self.shape as _)));
+		}
+		let mode = CoordinateMode::try_from(self.coordinate_mode).map_err(HandleError::Client)?;
+		let points = mode.resolve(&self.points);
+
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			polygon::fill_poly(painter, &points)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `FillPoly`.
+///
+/// The shape and coordinate mode are checked when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<FillPolyHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &FillPolyHdr = unsafe { util::reinterpret(&buff[0]) };
+	let points = util::read_list(&buff[size_of::<FillPolyHdr>()..]);
+
+	Ok(Some(Box::new(FillPoly {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+
+		shape: hdr.shape,
+		coordinate_mode: hdr.coordinate_mode,
+		points,
+	})))
+}
@@ -0,0 +1,63 @@
+//! The `FreeColors` request allows to free colormap entries allocated by the client.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `FreeColors` request.
+#[repr(C, packed)]
+pub struct FreeColorsHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The planes to combine with each pixel.
+	plane_mask: u32,
+}
+
+/// Structure representing the request.
+pub struct FreeColors {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The planes to combine with each pixel.
+	plane_mask: u32,
+	/// The pixels to free.
+	pixels: Vec<u32>,
+}
+
+impl Request for FreeColors {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let colormap = NonZeroU32::new(self.cmap)
+			.and_then(|id| ctx.get_colormap_mut(id))
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		colormap
+			.free_colors(client.get_id(), &self.pixels, self.plane_mask)
+			.map_err(HandleError::Client)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `FreeColors`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<FreeColorsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &FreeColorsHdr = unsafe { util::reinterpret(&buff[0]) };
+	let pixels = util::read_list(&buff[size_of::<FreeColorsHdr>()..]);
+
+	Ok(Some(Box::new(FreeColors {
+		cmap: hdr.cmap,
+		plane_mask: hdr.plane_mask,
+		pixels,
+	})))
+}
@@ -0,0 +1,53 @@
+//! The `FreeCursor` request allows to free a cursor.
+//!
+//! The cursor is freed only once it isn't used anymore by a window.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `FreeCursor` request.
+#[repr(C, packed)]
+pub struct FreeCursorHdr {
+	/// The ID of the cursor.
+	cursor: u32,
+}
+
+/// Structure representing the request.
+pub struct FreeCursor {
+	/// The ID of the cursor.
+	cursor: u32,
+}
+
+impl Request for FreeCursor {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		NonZeroU32::new(self.cursor)
+			.and_then(|id| ctx.free_cursor(id))
+			.ok_or(HandleError::Client(Error::Cursor(self.cursor)))?;
+
+		Ok(())
+	}
+}
+
+/// Parses `FreeCursor`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<FreeCursorHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &FreeCursorHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(FreeCursor {
+		cursor: hdr.cursor,
+	})))
+}
@@ -0,0 +1,50 @@
+//! The `FreeGC` request allows to free a graphics context.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `FreeGC` request.
+#[repr(C, packed)]
+pub struct FreeGCHdr {
+	/// The graphics context's ID.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct FreeGC {
+	/// The graphics context's ID.
+	gc: u32,
+}
+
+impl Request for FreeGC {
+	fn handle(
+		&self,
+		_ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		client
+			.remove_gc(self.gc)
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+
+		Ok(())
+	}
+}
+
+/// Parses `FreeGC`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<FreeGCHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &FreeGCHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(FreeGC {
+		gc: hdr.gc,
+	})))
+}
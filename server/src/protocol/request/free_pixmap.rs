@@ -0,0 +1,53 @@
+//! The `FreePixmap` request allows to free a pixmap.
+//!
+//! The storage of the pixmap is freed only once it isn't referenced anymore.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `FreePixmap` request.
+#[repr(C, packed)]
+pub struct FreePixmapHdr {
+	/// The ID of the pixmap.
+	pixmap: u32,
+}
+
+/// Structure representing the request.
+pub struct FreePixmap {
+	/// The ID of the pixmap.
+	pixmap: u32,
+}
+
+impl Request for FreePixmap {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		NonZeroU32::new(self.pixmap)
+			.and_then(|id| ctx.free_pixmap(id))
+			.ok_or(HandleError::Client(Error::Pixmap(self.pixmap)))?;
+
+		Ok(())
+	}
+}
+
+/// Parses `FreePixmap`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<FreePixmapHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &FreePixmapHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(FreePixmap {
+		pixmap: hdr.pixmap,
+	})))
+}
@@ -0,0 +1,75 @@
+//! The `GetFontPath` request allows to get the list of directories in which fonts are searched.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct GetFontPathReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The number of elements in the path.
+	paths_len: u16,
+
+	/// Padding.
+	_padding1: [u8; 22],
+}
+
+/// Structure representing the request.
+pub struct GetFontPath {}
+
+impl Request for GetFontPath {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		// Elements whose length doesn't fit on a byte cannot be sent
+		let path = ctx
+			.get_font_catalog()
+			.get_path()
+			.iter()
+			.filter(|elem| elem.len() <= u8::MAX as usize)
+			.collect::<Vec<_>>();
+
+		// Each element is preceded by its length
+		let mut data = vec![];
+		for elem in &path {
+			data.push(elem.len() as u8);
+			data.extend_from_slice(elem.as_bytes());
+		}
+		data.resize(data.len() + protocol::pad(data.len()), 0);
+
+		let hdr = GetFontPathReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: (data.len() / 4) as _,
+
+			paths_len: path.len() as _,
+
+			_padding1: [0; 22],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetFontPath`.
+pub fn read(_buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(GetFontPath {})))
+}
@@ -0,0 +1,143 @@
+//! The `GetImage` request allows to read the content of a drawable.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::ImageFormat;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct GetImageReply {
+	/// The type of the reply.
+	reply_type: u8,
+	/// The depth of the drawable.
+	depth: u8,
+	/// The sequence number of the request.
+	seq_nbr: u16,
+	/// Length of the reply in 4 bytes units.
+	reply_length: u32,
+	/// The visual of the window. If the drawable is a pixmap, the value is zero.
+	visual: u32,
+	/// Padding.
+	_padding: [u8; 20],
+}
+
+/// Header of the `GetImage` request.
+#[repr(C, packed)]
+pub struct GetImageHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The X position of the area to read.
+	x: i16,
+	/// The Y position of the area to read.
+	y: i16,
+	/// The width of the area to read.
+	width: u16,
+	/// The height of the area to read.
+	height: u16,
+	/// The mask of planes to read.
+	plane_mask: u32,
+}
+
+/// Structure representing the request.
+pub struct GetImage {
+	/// The format of the image.
+	format: u8,
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The X position of the area to read.
+	x: i16,
+	/// The Y position of the area to read.
+	y: i16,
+	/// The width of the area to read.
+	width: u16,
+	/// The height of the area to read.
+	height: u16,
+	/// The mask of planes to read.
+	plane_mask: u32,
+}
+
+impl Request for GetImage {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let format = match ImageFormat::try_from(self.format).map_err(HandleError::Client)? {
+			ImageFormat::XYBitmap => {
+				return Err(HandleError::Client(Error::Value(self.format as _)))
+			}
+			f => f,
+		};
+
+		let id = NonZeroU32::new(self.drawable)
+			.ok_or(HandleError::Client(Error::Drawable(self.drawable)))?;
+		let drawable = ctx
+			.get_drawable(id)
+			.ok_or(HandleError::Client(Error::Drawable(self.drawable)))?;
+		let visual = ctx.get_window(id).map(|w| w.attributes.visual).unwrap_or(0);
+
+		// TODO Check the window is viewable
+		let image = drawable
+			.get_image()
+			.ok_or(HandleError::Client(Error::Match))?;
+		let x = self.x as i32;
+		let y = self.y as i32;
+		let inside = x >= 0
+			&& y >= 0 && x + self.width as i32 <= image.get_width() as i32
+			&& y + self.height as i32 <= image.get_height() as i32;
+		if !inside {
+			return Err(HandleError::Client(Error::Match));
+		}
+
+		let data = image.encode(
+			format,
+			x as _,
+			y as _,
+			self.width,
+			self.height,
+			self.plane_mask,
+		);
+
+		let hdr = GetImageReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			depth: image.get_depth(),
+			seq_nbr,
+			reply_length: (data.len() / 4) as _,
+			visual,
+			_padding: [0; 20],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetImage`.
+///
+/// The format is checked when the request is handled.
+pub fn read(buff: &[u8], format: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<GetImageHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &GetImageHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(GetImage {
+		format,
+		drawable: hdr.drawable,
+		x: hdr.x,
+		y: hdr.y,
+		width: hdr.width,
+		height: hdr.height,
+		plane_mask: hdr.plane_mask,
+	})))
+}
@@ -0,0 +1,66 @@
+//! The `GetInputFocus` request returns the window receiving the keyboard input.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::Focus;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct GetInputFocusReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// What the focus becomes when the focus window becomes unviewable.
+	revert_to: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The focus: `None` (0), `PointerRoot` (1) or a window.
+	focus: u32,
+
+	/// Padding.
+	_padding: [u8; 20],
+}
+
+/// Structure representing the request.
+pub struct GetInputFocus {}
+
+impl Request for GetInputFocus {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let kbd = ctx.get_keyboard();
+		let focus = match kbd.get_focus() {
+			Focus::None => 0,
+			Focus::PointerRoot => 1,
+			Focus::Window(wid) => wid.get(),
+		};
+
+		let hdr = GetInputFocusReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			revert_to: kbd.get_revert_to() as _,
+			seq_nbr,
+			reply_length: 0,
+
+			focus,
+
+			_padding: [0; 20],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetInputFocus`.
+pub fn read(_buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(GetInputFocus {})))
+}
@@ -0,0 +1,78 @@
+//! The `GetKeyboardControl` request returns the settings of the keyboard.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::KEYMAP_SIZE;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct GetKeyboardControlReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Tells whether autorepeat is enabled.
+	global_auto_repeat: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The LEDs that are lit. Bit `n` represents the LED `n + 1`.
+	led_mask: u32,
+	/// The volume of key clicks, in percent.
+	key_click_percent: u8,
+	/// The volume of the bell, in percent.
+	bell_percent: u8,
+	/// The pitch of the bell, in Hz.
+	bell_pitch: u16,
+	/// The duration of the bell, in milliseconds.
+	bell_duration: u16,
+
+	/// Padding.
+	_padding: u16,
+
+	/// The keys for which autorepeat is enabled. Bit `n` of byte `i` represents the key with
+	/// keycode `8 * i + n`.
+	auto_repeats: [u8; KEYMAP_SIZE],
+}
+
+/// Structure representing the request.
+pub struct GetKeyboardControl {}
+
+impl Request for GetKeyboardControl {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let control = ctx.get_keyboard().get_control();
+		let hdr = GetKeyboardControlReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			global_auto_repeat: control.global_auto_repeat as _,
+			seq_nbr,
+			reply_length: 5,
+
+			led_mask: control.led_mask,
+			key_click_percent: control.key_click_percent,
+			bell_percent: control.bell_percent,
+			bell_pitch: control.bell_pitch,
+			bell_duration: control.bell_duration,
+
+			_padding: 0,
+
+			auto_repeats: control.auto_repeats,
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetKeyboardControl`.
+pub fn read(_buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(GetKeyboardControl {})))
+}
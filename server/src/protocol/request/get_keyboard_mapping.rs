@@ -0,0 +1,96 @@
+//! The `GetKeyboardMapping` request returns the keysyms of a range of keycodes.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::input;
+use crate::input::Keycode;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct GetKeyboardMappingReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The number of keysyms of each keycode.
+	keysyms_per_keycode: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Padding.
+	_padding: [u8; 24],
+}
+
+/// Header of the `GetKeyboardMapping` request.
+#[repr(C, packed)]
+pub struct GetKeyboardMappingHdr {
+	/// The first keycode.
+	first_keycode: u8,
+	/// The number of keycodes.
+	count: u8,
+
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct GetKeyboardMapping {
+	/// The first keycode.
+	first_keycode: Keycode,
+	/// The number of keycodes.
+	count: u8,
+}
+
+impl Request for GetKeyboardMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let last = self.first_keycode as u16 + self.count as u16;
+		if self.first_keycode < input::MIN_KEYCODE {
+			return Err(HandleError::Client(Error::Value(self.first_keycode as _)));
+		}
+		if last > input::MAX_KEYCODE as u16 + 1 {
+			return Err(HandleError::Client(Error::Value(self.count as _)));
+		}
+
+		let keymap = ctx.get_keyboard().get_keymap();
+		let keysyms = keymap.get_keysyms(self.first_keycode, self.count as _);
+
+		let hdr = GetKeyboardMappingReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			keysyms_per_keycode: keymap.get_keysyms_per_keycode() as _,
+			seq_nbr,
+			reply_length: keysyms.len() as _,
+
+			_padding: [0; 24],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client
+			.write(util::as_bytes(keysyms))
+			.map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetKeyboardMapping`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<GetKeyboardMappingHdr>() {
+		return Ok(None);
+	}
+	let hdr: &GetKeyboardMappingHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(GetKeyboardMapping {
+		first_keycode: hdr.first_keycode,
+		count: hdr.count,
+	})))
+}
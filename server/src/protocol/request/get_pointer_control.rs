@@ -0,0 +1,65 @@
+//! The `GetPointerControl` request allows to get the acceleration of the pointer.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct GetPointerControlReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The numerator of the acceleration multiplier.
+	acceleration_numerator: u16,
+	/// The denominator of the acceleration multiplier.
+	acceleration_denominator: u16,
+	/// The threshold of the acceleration.
+	threshold: u16,
+
+	/// Padding.
+	_padding1: [u8; 18],
+}
+
+/// Structure representing the request.
+pub struct GetPointerControl {}
+
+impl Request for GetPointerControl {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let accel = ctx.get_pointer().get_accel();
+		let hdr = GetPointerControlReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: 0,
+
+			acceleration_numerator: accel.numerator,
+			acceleration_denominator: accel.denominator,
+			threshold: accel.threshold,
+
+			_padding1: [0; 18],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetPointerControl`.
+pub fn read(_buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(GetPointerControl {})))
+}
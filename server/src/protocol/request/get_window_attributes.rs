@@ -3,6 +3,7 @@
 use super::Request;
 use crate::ctx::client::Client;
 use crate::ctx::Context;
+use crate::ctx::Drawable;
 use crate::protocol;
 use crate::protocol::error::Error;
 use crate::protocol::request::HandleError;
@@ -81,8 +82,12 @@ impl Request for GetWindowAttributes {
 		let wid =
 			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
 		let win = ctx
-			.get_window_mut(wid)
+			.get_window(wid)
 			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let map_is_installed = NonZeroU32::new(win.attributes.colormap)
+			.zip(NonZeroU32::new(win.get_root()))
+			.and_then(|(cmap, root)| Some(ctx.get_screen(root)?.is_colormap_installed(cmap)))
+			.unwrap_or(false);
 
 		let hdr = GetWindowAttributesReply {
 			reply_type: protocol::REPLY_TYPE_REPLY,
@@ -96,7 +101,7 @@ impl Request for GetWindowAttributes {
 			backing_places: win.attributes.backing_places,
 			backing_pixel: win.attributes.backing_pixel,
 			save_under: if win.attributes.save_under { 1 } else { 0 },
-			map_is_installed: win.attributes.map_is_installed,
+			map_is_installed: map_is_installed as _,
 			map_state: win.attributes.map_state,
 			override_redirect: if win.attributes.override_redirect {
 				1
@@ -105,7 +110,7 @@ impl Request for GetWindowAttributes {
 			},
 			colormap: win.attributes.colormap,
 			all_event_masks: win.attributes.event_mask,
-			your_event_mask: 0, // TODO
+			your_event_mask: win.get_event_mask(client.get_id()),
 			do_not_propagate_mask: win.attributes.do_not_propagate_mask as _,
 			_padding: [0; 2],
 		};
@@ -0,0 +1,156 @@
+//! The `GrabKeyboard` request allows a client to receive every key event, whatever the focus.
+//!
+//! Freezing the processing of events is not supported: synchronous modes behave like
+//! asynchronous ones.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::KeyboardGrab;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Reply status: the keyboard has been grabbed.
+const STATUS_SUCCESS: u8 = 0;
+/// Reply status: the keyboard is grabbed by another client.
+const STATUS_ALREADY_GRABBED: u8 = 1;
+/// Reply status: the time is earlier than the last grab or later than the current server time.
+const STATUS_INVALID_TIME: u8 = 2;
+/// Reply status: the grab window is not viewable.
+const STATUS_NOT_VIEWABLE: u8 = 3;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct GrabKeyboardReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The status of the request.
+	status: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Padding.
+	_padding: [u8; 24],
+}
+
+/// Header of the `GrabKeyboard` request.
+#[repr(C, packed)]
+pub struct GrabKeyboardHdr {
+	/// The window events are reported relative to.
+	grab_window: u32,
+	/// The time of the grab.
+	time: u32,
+	/// The processing mode of pointer events.
+	pointer_mode: u8,
+	/// The processing mode of keyboard events.
+	keyboard_mode: u8,
+
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct GrabKeyboard {
+	/// Tells whether events are reported normally to the client.
+	owner_events: bool,
+	/// The window events are reported relative to.
+	grab_window: u32,
+	/// The time of the grab.
+	time: u32,
+	/// The processing mode of pointer events.
+	pointer_mode: u8,
+	/// The processing mode of keyboard events.
+	keyboard_mode: u8,
+}
+
+impl GrabKeyboard {
+	/// Grabs the keyboard for the given client, returning the status of the request.
+	fn grab(&self, ctx: &mut Context, client: &Client) -> Result<u8, HandleError> {
+		// Modes are either `Synchronous` (0) or `Asynchronous` (1)
+		for mode in [self.pointer_mode, self.keyboard_mode] {
+			if mode > 1 {
+				return Err(HandleError::Client(Error::Value(mode as _)));
+			}
+		}
+
+		let wid = NonZeroU32::new(self.grab_window)
+			.ok_or(HandleError::Client(Error::Window(self.grab_window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.grab_window)))?;
+
+		let kbd = ctx.get_keyboard();
+		if kbd.get_grab().is_some_and(|g| g.client != client.get_id()) {
+			return Ok(STATUS_ALREADY_GRABBED);
+		}
+		let now = ctx.get_time();
+		let time = ctx.resolve_time(self.time);
+		if protocol::is_time_before(time, kbd.get_grab_time())
+			|| protocol::is_time_before(now, time)
+		{
+			return Ok(STATUS_INVALID_TIME);
+		}
+		if !win.is_viewable() {
+			return Ok(STATUS_NOT_VIEWABLE);
+		}
+
+		ctx.grab_keyboard(
+			KeyboardGrab {
+				window: wid,
+				client: client.get_id(),
+				owner_events: self.owner_events,
+			},
+			time,
+		);
+		Ok(STATUS_SUCCESS)
+	}
+}
+
+impl Request for GrabKeyboard {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let status = self.grab(ctx, client)?;
+
+		let hdr = GrabKeyboardReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			status,
+			seq_nbr,
+			reply_length: 0,
+
+			_padding: [0; 24],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GrabKeyboard`.
+///
+/// `owner_events` tells whether events are reported normally to the client.
+///
+/// Modes are checked when the request is handled.
+pub fn read(buff: &[u8], owner_events: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<GrabKeyboardHdr>() {
+		return Ok(None);
+	}
+	let hdr: &GrabKeyboardHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(GrabKeyboard {
+		owner_events: owner_events != 0,
+		grab_window: hdr.grab_window,
+		time: hdr.time,
+		pointer_mode: hdr.pointer_mode,
+		keyboard_mode: hdr.keyboard_mode,
+	})))
+}
@@ -0,0 +1,46 @@
+//! The `ImageText16` request allows to draw a string of 16 bits characters over a background.
+//!
+//! The request works like `ImageText8`, except each character is made of two bytes: the row
+//! (`byte1`) followed by the column (`byte2`).
+
+use super::image_text8::ImageText;
+use super::Request;
+use crate::protocol::error::Error;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `ImageText16` request.
+#[repr(C, packed)]
+pub struct ImageText16Hdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+	/// The X position of the origin of the string.
+	x: i16,
+	/// The Y position of the baseline.
+	y: i16,
+}
+
+/// Parses `ImageText16`.
+///
+/// `len` is the number of characters in the string. The string is decoded when the request is
+/// handled.
+pub fn read(buff: &[u8], len: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ImageText16Hdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ImageText16Hdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ImageText {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		x: hdr.x,
+		y: hdr.y,
+
+		len,
+		wide: true,
+		data: buff[size_of::<ImageText16Hdr>()..].to_vec(),
+	})))
+}
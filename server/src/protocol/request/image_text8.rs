@@ -0,0 +1,108 @@
+//! The `ImageText8` request allows to draw a string of 8 bits characters over a background.
+//!
+//! The background is a rectangle spanning the logical extents of the font and the width of the
+//! string, filled with the background pixel of the graphics context.
+
+use super::poly_text8;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::raster;
+use crate::raster::text;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `ImageText8` request.
+#[repr(C, packed)]
+pub struct ImageText8Hdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+	/// The X position of the origin of the string.
+	x: i16,
+	/// The Y position of the baseline.
+	y: i16,
+}
+
+/// Structure representing an `ImageText8` or `ImageText16` request.
+pub struct ImageText {
+	/// The ID of the drawable.
+	pub drawable: u32,
+	/// The graphics context's ID.
+	pub gc: u32,
+	/// The X position of the origin of the string.
+	pub x: i16,
+	/// The Y position of the baseline.
+	pub y: i16,
+
+	/// The number of characters in the string.
+	pub len: u8,
+	/// Tells whether characters are 16 bits.
+	pub wide: bool,
+	/// The encoded characters.
+	pub data: Vec<u8>,
+}
+
+impl ImageText {
+	/// Decodes the string.
+	///
+	/// If the string is truncated, the function returns an error.
+	fn get_text(&self) -> Result<Vec<u16>, Error> {
+		let char_size = if self.wide { 2 } else { 1 };
+		let chars = self
+			.data
+			.get(..(self.len as usize * char_size))
+			.ok_or(Error::Length)?;
+		let text = if self.wide {
+			chars
+				.chunks_exact(2)
+				.map(|c| u16::from_be_bytes([c[0], c[1]]))
+				.collect()
+		} else {
+			chars.iter().map(|c| *c as u16).collect()
+		};
+		Ok(text)
+	}
+}
+
+impl Request for ImageText {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let text = self.get_text().map_err(HandleError::Client)?;
+		let font = poly_text8::get_gc_font(client, self.gc).map_err(HandleError::Client)?;
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			text::image_text(painter, &font, self.x as _, self.y as _, &text);
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `ImageText8`.
+///
+/// `len` is the number of characters in the string. The string is decoded when the request is
+/// handled.
+pub fn read(buff: &[u8], len: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ImageText8Hdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ImageText8Hdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ImageText {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		x: hdr.x,
+		y: hdr.y,
+
+		len,
+		wide: false,
+		data: buff[size_of::<ImageText8Hdr>()..].to_vec(),
+	})))
+}
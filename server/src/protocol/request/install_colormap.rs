@@ -0,0 +1,54 @@
+//! The `InstallColormap` request allows to install a colormap on its screen.
+//!
+//! If too many colormaps are installed, the least recently installed ones are uninstalled.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `InstallColormap` request.
+#[repr(C, packed)]
+pub struct InstallColormapHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+}
+
+/// Structure representing the request.
+pub struct InstallColormap {
+	/// The ID of the colormap.
+	cmap: u32,
+}
+
+impl Request for InstallColormap {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let id = NonZeroU32::new(self.cmap)
+			.filter(|id| ctx.get_colormap(*id).is_some())
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		ctx.install_colormap(id);
+
+		Ok(())
+	}
+}
+
+/// Parses `InstallColormap`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<InstallColormapHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &InstallColormapHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(InstallColormap {
+		cmap: hdr.cmap,
+	})))
+}
@@ -0,0 +1,115 @@
+//! The `ListFonts` request allows to list the names of the available fonts matching a pattern.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct ListFontsReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The number of names.
+	names_len: u16,
+
+	/// Padding.
+	_padding1: [u8; 22],
+}
+
+/// Header of the `ListFonts` request.
+#[repr(C, packed)]
+pub struct ListFontsHdr {
+	/// The maximum number of names to return.
+	max_names: u16,
+	/// The length of the pattern in bytes.
+	pattern_length: u16,
+}
+
+/// Reads the header and pattern of a `ListFonts` or `ListFontsWithInfo` request.
+///
+/// The function returns the maximum number of names and the pattern. If the buffer is too
+/// short, it returns None.
+pub fn read_pattern(buff: &[u8]) -> Option<(u16, String)> {
+	if buff.len() < size_of::<ListFontsHdr>() {
+		return None;
+	}
+
+	let hdr: &ListFontsHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let pattern_begin = size_of::<ListFontsHdr>();
+	let pattern_end = pattern_begin + hdr.pattern_length as usize;
+	let pattern = String::from_utf8_lossy(buff.get(pattern_begin..pattern_end)?).into_owned();
+	Some((hdr.max_names, pattern))
+}
+
+/// Structure representing the request.
+pub struct ListFonts {
+	/// The maximum number of names to return.
+	max_names: u16,
+	/// The pattern names must match.
+	pattern: String,
+}
+
+impl Request for ListFonts {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		// Names whose length doesn't fit on a byte cannot be sent
+		let names = ctx
+			.get_font_catalog()
+			.list(&self.pattern, self.max_names as _)
+			.into_iter()
+			.filter(|n| n.len() <= u8::MAX as usize)
+			.collect::<Vec<_>>();
+
+		// Each name is preceded by its length
+		let mut data = vec![];
+		for name in &names {
+			data.push(name.len() as u8);
+			data.extend_from_slice(name.as_bytes());
+		}
+		data.resize(data.len() + protocol::pad(data.len()), 0);
+
+		let hdr = ListFontsReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: (data.len() / 4) as _,
+
+			names_len: names.len() as _,
+
+			_padding1: [0; 22],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `ListFonts`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	let Some((max_names, pattern)) = read_pattern(buff) else {
+		return Ok(None);
+	};
+
+	Ok(Some(Box::new(ListFonts {
+		max_names,
+		pattern,
+	})))
+}
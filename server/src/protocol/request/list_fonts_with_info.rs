@@ -0,0 +1,149 @@
+//! The `ListFontsWithInfo` request allows to list the available fonts matching a pattern, along
+//! with their metrics and properties.
+//!
+//! A reply is sent for each font, followed by a last reply with an empty name.
+
+use super::list_fonts;
+use super::query_font;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::CharInfo;
+use crate::protocol::FontProp;
+use crate::util;
+use std::mem::size_of;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct ListFontsWithInfoReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The length of the name of the font. Zero for the last reply.
+	name_length: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The minimum of each metric over every character.
+	min_bounds: CharInfo,
+	/// Padding.
+	_padding0: u32,
+	/// The maximum of each metric over every character.
+	max_bounds: CharInfo,
+	/// Padding.
+	_padding1: u32,
+	/// The first column of characters.
+	min_char_or_byte2: u16,
+	/// The last column of characters.
+	max_char_or_byte2: u16,
+	/// The character used in place of undefined ones.
+	default_char: u16,
+	/// The number of properties.
+	properties_len: u16,
+	/// The direction in which characters are drawn.
+	draw_direction: u8,
+	/// The first row of characters.
+	min_byte1: u8,
+	/// The last row of characters.
+	max_byte1: u8,
+	/// Tells whether every character in the font's ranges is defined.
+	all_chars_exist: u8,
+	/// The logical extent of the font above the baseline.
+	font_ascent: i16,
+	/// The logical extent of the font below the baseline.
+	font_descent: i16,
+	/// A hint of the number of replies remaining.
+	replies_hint: u32,
+}
+
+/// Structure representing the request.
+pub struct ListFontsWithInfo {
+	/// The maximum number of fonts to return.
+	max_names: u16,
+	/// The pattern names must match.
+	pattern: String,
+}
+
+impl Request for ListFontsWithInfo {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		// Names whose length doesn't fit on a byte cannot be sent
+		let names = ctx
+			.get_font_catalog()
+			.list(&self.pattern, self.max_names as _)
+			.into_iter()
+			.filter(|n| n.len() <= u8::MAX as usize)
+			.collect::<Vec<_>>();
+
+		for (i, name) in names.iter().enumerate() {
+			// Fonts that cannot be loaded are skipped
+			let Some(font) = ctx.get_font_catalog_mut().open(name) else {
+				continue;
+			};
+			let props = query_font::get_props(ctx, &font);
+
+			let pad = protocol::pad(name.len());
+			let len = size_of::<ListFontsWithInfoReply>() - 32
+				+ props.len() * size_of::<FontProp>()
+				+ name.len() + pad;
+			let hdr = ListFontsWithInfoReply {
+				reply_type: protocol::REPLY_TYPE_REPLY,
+				name_length: name.len() as _,
+				seq_nbr,
+				reply_length: (len / 4) as _,
+
+				min_bounds: *font.get_min_bounds(),
+				_padding0: 0,
+				max_bounds: *font.get_max_bounds(),
+				_padding1: 0,
+				min_char_or_byte2: font.get_min_char_or_byte2(),
+				max_char_or_byte2: font.get_max_char_or_byte2(),
+				default_char: font.get_default_char(),
+				properties_len: props.len() as _,
+				draw_direction: font.get_draw_direction(),
+				min_byte1: font.get_min_byte1(),
+				max_byte1: font.get_max_byte1(),
+				all_chars_exist: font.all_chars_exist() as _,
+				font_ascent: font.get_font_ascent(),
+				font_descent: font.get_font_descent(),
+				replies_hint: (names.len() - i - 1) as _,
+			};
+			client.write_obj(&hdr).map_err(HandleError::IO)?;
+			client
+				.write(util::as_bytes(&props))
+				.map_err(HandleError::IO)?;
+			client.write(name.as_bytes()).map_err(HandleError::IO)?;
+			client.write(&[0; 4][..pad]).map_err(HandleError::IO)?;
+		}
+
+		// The last reply only tells the list is over
+		let len = size_of::<ListFontsWithInfoReply>();
+		let mut last = vec![0; len];
+		last[0] = protocol::REPLY_TYPE_REPLY;
+		last[2..4].copy_from_slice(&seq_nbr.to_ne_bytes());
+		last[4..8].copy_from_slice(&((len as u32 - 32) / 4).to_ne_bytes());
+		client.write(&last).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `ListFontsWithInfo`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	let Some((max_names, pattern)) = list_fonts::read_pattern(buff) else {
+		return Ok(None);
+	};
+
+	Ok(Some(Box::new(ListFontsWithInfo {
+		max_names,
+		pattern,
+	})))
+}
@@ -0,0 +1,92 @@
+//! The `ListInstalledColormaps` request allows to list the colormaps installed on the screen of
+//! a window.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct ListInstalledColormapsReply {
+	/// The type of the reply.
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// The sequence number of the request.
+	seq_nbr: u16,
+	/// Length of the reply in 4 bytes units.
+	reply_length: u32,
+	/// The number of colormaps in the list.
+	cmaps_len: u16,
+	/// Padding.
+	_padding1: [u8; 22],
+}
+
+/// Header of the `ListInstalledColormaps` request.
+#[repr(C, packed)]
+pub struct ListInstalledColormapsHdr {
+	/// A window on the screen.
+	window: u32,
+}
+
+/// Structure representing the request.
+pub struct ListInstalledColormaps {
+	/// A window on the screen.
+	window: u32,
+}
+
+impl Request for ListInstalledColormaps {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let win = NonZeroU32::new(self.window)
+			.and_then(|id| ctx.get_window(id))
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		// Cannot fail since every window belongs to a screen
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let cmaps = ctx
+			.get_screen(root)
+			.map(|s| s.get_installed_colormaps())
+			.unwrap_or_default();
+
+		let hdr = ListInstalledColormapsReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: cmaps.len() as _,
+			cmaps_len: cmaps.len() as _,
+			_padding1: [0; 22],
+		};
+		let data = cmaps
+			.iter()
+			.flat_map(|c| c.get().to_ne_bytes())
+			.collect::<Vec<_>>();
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `ListInstalledColormaps`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ListInstalledColormapsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ListInstalledColormapsHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ListInstalledColormaps {
+		window: hdr.window,
+	})))
+}
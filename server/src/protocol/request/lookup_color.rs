@@ -0,0 +1,112 @@
+//! The `LookupColor` request allows to get the color with the given name in the color database,
+//! along with the closest color that can be stored in a colormap.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::rgb;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct LookupColorReply {
+	/// The type of the reply.
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// The sequence number of the request.
+	seq_nbr: u16,
+	/// Length of the reply in 4 bytes units.
+	reply_length: u32,
+	/// The red component of the color in the database.
+	exact_red: u16,
+	/// The green component of the color in the database.
+	exact_green: u16,
+	/// The blue component of the color in the database.
+	exact_blue: u16,
+	/// The red component of the closest color the colormap can hold.
+	visual_red: u16,
+	/// The green component of the closest color the colormap can hold.
+	visual_green: u16,
+	/// The blue component of the closest color the colormap can hold.
+	visual_blue: u16,
+	/// Padding.
+	_padding1: [u8; 12],
+}
+
+/// Header of the `LookupColor` request.
+#[repr(C, packed)]
+pub struct LookupColorHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The length of the name in bytes.
+	name_length: u16,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct LookupColor {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The name of the color.
+	name: String,
+}
+
+impl Request for LookupColor {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let colormap = NonZeroU32::new(self.cmap)
+			.and_then(|id| ctx.get_colormap(id))
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		let exact = rgb::lookup(&self.name).ok_or(HandleError::Client(Error::Name))?;
+		let visual = colormap.get_closest(&exact);
+
+		let hdr = LookupColorReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: 0,
+			exact_red: exact.red,
+			exact_green: exact.green,
+			exact_blue: exact.blue,
+			visual_red: visual.red,
+			visual_green: visual.green,
+			visual_blue: visual.blue,
+			_padding1: [0; 12],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `LookupColor`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<LookupColorHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &LookupColorHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let name_begin = size_of::<LookupColorHdr>();
+	let name_end = name_begin + hdr.name_length as usize;
+	if buff.len() < name_end {
+		return Ok(None);
+	}
+	let name = String::from_utf8_lossy(&buff[name_begin..name_end]).into_owned();
+
+	Ok(Some(Box::new(LookupColor {
+		cmap: hdr.cmap,
+		name,
+	})))
+}
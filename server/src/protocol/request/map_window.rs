@@ -0,0 +1,108 @@
+//! The `MapWindow` request allows to map a window.
+//!
+//! If another client has selected `SubstructureRedirect` on the parent of the window, the window
+//! is not mapped and a `MapRequest` event is sent to this client instead.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::MapNotify;
+use crate::protocol::event::MapRequest;
+use crate::protocol::request::HandleError;
+use crate::protocol::Event;
+use crate::protocol::MapState;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `MapWindow` request.
+#[repr(C, packed)]
+pub struct MapWindowHdr {
+	/// The ID of the window.
+	window: u32,
+}
+
+/// Structure representing the request.
+pub struct MapWindow {
+	/// The ID of the window.
+	window: u32,
+}
+
+impl Request for MapWindow {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		if !matches!(win.attributes.map_state, MapState::Unmapped) {
+			return Ok(());
+		}
+		let override_redirect = win.attributes.override_redirect;
+		let parent = win.get_parent();
+
+		if let Some(parent_id) = parent {
+			// Cannot fail since a window cannot outlive its parent
+			let parent = ctx.get_window(parent_id).unwrap();
+			let redirect = parent
+				.get_selecting_clients(Event::SubstructureRedirect as _)
+				.find(|c| *c != client.get_id());
+			if let (false, Some(redirect)) = (override_redirect, redirect) {
+				let ev = MapRequest {
+					code: event::MAP_REQUEST,
+					_padding0: 0,
+					seq_nbr: 0,
+
+					parent: parent_id.get(),
+					window: self.window,
+
+					_padding1: [0; 20],
+				};
+				ctx.send_event(redirect, &ev);
+				return Ok(());
+			}
+		}
+
+		// The notification is sent before the window is exposed
+		let mut ev = MapNotify {
+			code: event::MAP_NOTIFY,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			event: self.window,
+			window: self.window,
+			override_redirect: override_redirect as _,
+
+			_padding1: [0; 19],
+		};
+		ctx.deliver_event(wid, Event::StructureNotify as _, &ev);
+		if let Some(parent_id) = parent {
+			ev.event = parent_id.get();
+			ctx.deliver_event(parent_id, Event::SubstructureNotify as _, &ev);
+		}
+
+		ctx.map_window(wid);
+
+		Ok(())
+	}
+}
+
+/// Parses `MapWindow`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<MapWindowHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &MapWindowHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(MapWindow {
+		window: hdr.window,
+	})))
+}
@@ -1,20 +1,86 @@
 //! This module implements each requests of the X protocol.
 
+pub mod alloc_color;
+pub mod alloc_color_cells;
+pub mod alloc_color_planes;
+pub mod alloc_named_color;
+pub mod change_gc;
+pub mod change_keyboard_control;
+pub mod change_keyboard_mapping;
+pub mod change_pointer_control;
 pub mod change_property;
 pub mod change_window_attributes;
+pub mod clear_area;
+pub mod close_font;
+pub mod copy_area;
+pub mod copy_colormap_and_free;
+pub mod copy_gc;
+pub mod copy_plane;
+pub mod create_colormap;
+pub mod create_cursor;
 pub mod create_gc;
+pub mod create_glyph_cursor;
+pub mod create_pixmap;
 pub mod create_window;
+pub mod fill_poly;
+pub mod free_colormap;
+pub mod free_colors;
+pub mod free_cursor;
+pub mod free_gc;
+pub mod free_pixmap;
 pub mod get_atom_name;
+pub mod get_font_path;
 pub mod get_geometry;
+pub mod get_image;
+pub mod get_input_focus;
+pub mod get_keyboard_control;
+pub mod get_keyboard_mapping;
+pub mod get_modifier_mapping;
+pub mod get_pointer_control;
 pub mod get_property;
 pub mod get_selection_owner;
 pub mod get_window_attributes;
+pub mod grab_keyboard;
 pub mod grab_server;
+pub mod image_text16;
+pub mod image_text8;
+pub mod install_colormap;
 pub mod intern_atom;
+pub mod list_fonts;
+pub mod list_fonts_with_info;
+pub mod list_installed_colormaps;
+pub mod lookup_color;
+pub mod map_window;
 pub mod no_operation;
+pub mod open_font;
+pub mod poly_arc;
+pub mod poly_fill_arc;
+pub mod poly_fill_rectangle;
+pub mod poly_line;
+pub mod poly_point;
+pub mod poly_rectangle;
+pub mod poly_segment;
+pub mod poly_text16;
+pub mod poly_text8;
+pub mod put_image;
+pub mod query_colors;
 pub mod query_extension;
+pub mod query_font;
+pub mod query_keymap;
 pub mod query_pointer;
+pub mod query_text_extents;
+pub mod recolor_cursor;
+pub mod set_clip_rectangles;
+pub mod set_dashes;
+pub mod set_font_path;
+pub mod set_input_focus;
+pub mod set_modifier_mapping;
+pub mod store_colors;
+pub mod store_named_color;
+pub mod ungrab_keyboard;
 pub mod ungrab_server;
+pub mod uninstall_colormap;
+pub mod unmap_window;
 
 use crate::ctx::client::Client;
 use crate::ctx::Context;
@@ -296,17 +362,83 @@ pub fn build_request(
 		CREATE_WINDOW => create_window::read(buff, optional),
 		CHANGE_WINDOW_ATTRIBUTES => change_window_attributes::read(buff, optional),
 		GET_WINDOW_ATTRIBUTES => get_window_attributes::read(buff, optional),
+		MAP_WINDOW => map_window::read(buff, optional),
+		UNMAP_WINDOW => unmap_window::read(buff, optional),
 		GET_GEOMETRY => get_geometry::read(buff, optional),
 		INTERN_ATOM => intern_atom::read(buff, optional),
 		GET_ATOM_NAME => get_atom_name::read(buff, optional),
 		CHANGE_PROPERTY => change_property::read(buff, optional),
 		GET_PROPERTY => get_property::read(buff, optional),
 		GET_SELECTION_OWNER => get_selection_owner::read(buff, optional),
+		GRAB_KEYBOARD => grab_keyboard::read(buff, optional),
+		UNGRAB_KEYBOARD => ungrab_keyboard::read(buff, optional),
 		GRAB_SERVER => grab_server::read(buff, optional),
 		UNGRAB_SERVER => ungrab_server::read(buff, optional),
 		QUERY_POINTER => query_pointer::read(buff, optional),
+		SET_INPUT_FOCUS => set_input_focus::read(buff, optional),
+		GET_INPUT_FOCUS => get_input_focus::read(buff, optional),
+		QUERY_KEYMAP => query_keymap::read(buff, optional),
+		OPEN_FONT => open_font::read(buff, optional),
+		CLOSE_FONT => close_font::read(buff, optional),
+		QUERY_FONT => query_font::read(buff, optional),
+		QUERY_TEXT_EXTENTS => query_text_extents::read(buff, optional),
+		LIST_FONTS => list_fonts::read(buff, optional),
+		LIST_FONTS_WITH_INFO => list_fonts_with_info::read(buff, optional),
+		SET_FONT_PATH => set_font_path::read(buff, optional),
+		GET_FONT_PATH => get_font_path::read(buff, optional),
+		CREATE_PIXMAP => create_pixmap::read(buff, optional),
+		FREE_PIXMAP => free_pixmap::read(buff, optional),
 		CREATE_GC => create_gc::read(buff, optional),
+		CHANGE_GC => change_gc::read(buff, optional),
+		COPY_GC => copy_gc::read(buff, optional),
+		SET_DASHES => set_dashes::read(buff, optional),
+		SET_CLIP_RECTANGLES => set_clip_rectangles::read(buff, optional),
+		FREE_GC => free_gc::read(buff, optional),
+		CLEAR_AREA => clear_area::read(buff, optional),
+		COPY_AREA => copy_area::read(buff, optional),
+		COPY_PLANE => copy_plane::read(buff, optional),
+		POLY_POINT => poly_point::read(buff, optional),
+		POLY_LINE => poly_line::read(buff, optional),
+		POLY_SEGMENT => poly_segment::read(buff, optional),
+		POLY_RECTANGLE => poly_rectangle::read(buff, optional),
+		POLY_ARC => poly_arc::read(buff, optional),
+		FILL_POLY => fill_poly::read(buff, optional),
+		POLY_FILL_RECTANGLE => poly_fill_rectangle::read(buff, optional),
+		POLY_FILL_ARC => poly_fill_arc::read(buff, optional),
+		PUT_IMAGE => put_image::read(buff, optional),
+		GET_IMAGE => get_image::read(buff, optional),
+		POLY_TEXT8 => poly_text8::read(buff, optional),
+		POLY_TEXT16 => poly_text16::read(buff, optional),
+		IMAGE_TEXT8 => image_text8::read(buff, optional),
+		IMAGE_TEXT16 => image_text16::read(buff, optional),
+		CREATE_COLORMAP => create_colormap::read(buff, optional),
+		FREE_COLORMAP => free_colormap::read(buff, optional),
+		COPY_COLORMAP_AND_FREE => copy_colormap_and_free::read(buff, optional),
+		INSTALL_COLORMAP => install_colormap::read(buff, optional),
+		UNINSTALL_COLORMAP => uninstall_colormap::read(buff, optional),
+		LIST_INSTALLED_COLORMAPS => list_installed_colormaps::read(buff, optional),
+		ALLOC_COLOR => alloc_color::read(buff, optional),
+		ALLOC_NAMED_COLOR => alloc_named_color::read(buff, optional),
+		ALLOC_COLOR_CELLS => alloc_color_cells::read(buff, optional),
+		ALLOC_COLOR_PLANES => alloc_color_planes::read(buff, optional),
+		FREE_COLORS => free_colors::read(buff, optional),
+		STORE_COLORS => store_colors::read(buff, optional),
+		STORE_NAMED_COLOR => store_named_color::read(buff, optional),
+		QUERY_COLORS => query_colors::read(buff, optional),
+		LOOKUP_COLOR => lookup_color::read(buff, optional),
+		CREATE_CURSOR => create_cursor::read(buff, optional),
+		CREATE_GLYPH_CURSOR => create_glyph_cursor::read(buff, optional),
+		FREE_CURSOR => free_cursor::read(buff, optional),
+		RECOLOR_CURSOR => recolor_cursor::read(buff, optional),
 		QUERY_EXTENSION => query_extension::read(buff, optional),
+		CHANGE_KEYBOARD_MAPPING => change_keyboard_mapping::read(buff, optional),
+		GET_KEYBOARD_MAPPING => get_keyboard_mapping::read(buff, optional),
+		CHANGE_KEYBOARD_CONTROL => change_keyboard_control::read(buff, optional),
+		GET_KEYBOARD_CONTROL => get_keyboard_control::read(buff, optional),
+		CHANGE_POINTER_CONTROL => change_pointer_control::read(buff, optional),
+		GET_POINTER_CONTROL => get_pointer_control::read(buff, optional),
+		SET_MODIFIER_MAPPING => set_modifier_mapping::read(buff, optional),
+		GET_MODIFIER_MAPPING => get_modifier_mapping::read(buff, optional),
 		NO_OPERATION => no_operation::read(buff, optional),
 
 		// TODO _ => Err(Error::Request),
@@ -351,6 +483,21 @@ pub trait RequestReader {
 		-> Result<Option<(Box<dyn Request>, usize)>, XError>;
 }
 
+/// A request that could not be parsed. Handling it reports the error to the client, so that
+/// the request is consumed and its sequence number is used like any other request.
+pub struct InvalidRequest(pub Error);
+
+impl Request for InvalidRequest {
+	fn handle(
+		&self,
+		_ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		Err(HandleError::Client(self.0.clone()))
+	}
+}
+
 /// The default request reader.
 pub struct DefaultRequestReader {}
 
@@ -387,9 +534,7 @@ impl RequestReader for DefaultRequestReader {
 
 		match build_request(ctx, opcode, buff, hdr.optional) {
 			Ok(request) => Ok(request.map(|r| (r, req))),
-
-			// TODO seq nbr and opcode
-			Err(e) => Err(e.to_protocol(0, 0, opcode)),
+			Err(e) => Ok(Some((Box::new(InvalidRequest(e)), req))),
 		}
 	}
 }
@@ -0,0 +1,73 @@
+//! The `OpenFont` request allows to load a font, given its name.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `OpenFont` request.
+#[repr(C, packed)]
+pub struct OpenFontHdr {
+	/// The ID of the font to create.
+	fid: u32,
+	/// The length of the name in bytes.
+	name_length: u16,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct OpenFont {
+	/// The ID of the font to create.
+	fid: u32,
+	/// The name of the font. It may contain wildcards.
+	name: String,
+}
+
+impl Request for OpenFont {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let fid =
+			NonZeroU32::new(self.fid).ok_or(HandleError::Client(Error::IDChoice(self.fid)))?;
+		if ctx.is_id_used(fid) {
+			return Err(HandleError::Client(Error::IDChoice(self.fid)));
+		}
+
+		let font = ctx
+			.get_font_catalog_mut()
+			.open(&self.name)
+			.ok_or(HandleError::Client(Error::Name))?;
+		ctx.add_font(fid, font, client.get_id());
+
+		Ok(())
+	}
+}
+
+/// Parses `OpenFont`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<OpenFontHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &OpenFontHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let name_begin = size_of::<OpenFontHdr>();
+	let name_end = name_begin + hdr.name_length as usize;
+	if buff.len() < name_end {
+		return Ok(None);
+	}
+	let name = String::from_utf8_lossy(&buff[name_begin..name_end]).into_owned();
+
+	Ok(Some(Box::new(OpenFont {
+		fid: hdr.fid,
+		name,
+	})))
+}
@@ -0,0 +1,63 @@
+//! The `PolyArc` request allows to draw the outlines of arcs.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Arc;
+use crate::raster;
+use crate::raster::arc;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyArc` request.
+#[repr(C, packed)]
+pub struct PolyArcHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyArc {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// The list of arcs.
+	arcs: Vec<Arc>,
+}
+
+impl Request for PolyArc {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			arc::poly_arc(painter, &self.arcs)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolyArc`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyArcHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyArcHdr = unsafe { util::reinterpret(&buff[0]) };
+	let arcs = util::read_list(&buff[size_of::<PolyArcHdr>()..]);
+
+	Ok(Some(Box::new(PolyArc {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+
+		arcs,
+	})))
+}
@@ -0,0 +1,63 @@
+//! The `PolyFillArc` request allows to fill arcs.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Arc;
+use crate::raster;
+use crate::raster::arc;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyFillArc` request.
+#[repr(C, packed)]
+pub struct PolyFillArcHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyFillArc {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// The list of arcs.
+	arcs: Vec<Arc>,
+}
+
+impl Request for PolyFillArc {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			arc::poly_fill_arc(painter, &self.arcs)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolyFillArc`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyFillArcHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyFillArcHdr = unsafe { util::reinterpret(&buff[0]) };
+	let arcs = util::read_list(&buff[size_of::<PolyFillArcHdr>()..]);
+
+	Ok(Some(Box::new(PolyFillArc {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+
+		arcs,
+	})))
+}
@@ -0,0 +1,63 @@
+//! The `PolyFillRectangle` request allows to fill rectangles.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Rectangle;
+use crate::raster;
+use crate::raster::rect;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyFillRectangle` request.
+#[repr(C, packed)]
+pub struct PolyFillRectangleHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyFillRectangle {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// The list of rectangles.
+	rectangles: Vec<Rectangle>,
+}
+
+impl Request for PolyFillRectangle {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			rect::poly_fill_rectangle(painter, &self.rectangles)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolyFillRectangle`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyFillRectangleHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyFillRectangleHdr = unsafe { util::reinterpret(&buff[0]) };
+	let rectangles = util::read_list(&buff[size_of::<PolyFillRectangleHdr>()..]);
+
+	Ok(Some(Box::new(PolyFillRectangle {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+
+		rectangles,
+	})))
+}
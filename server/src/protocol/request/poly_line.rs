@@ -0,0 +1,72 @@
+//! The `PolyLine` request allows to draw connected lines.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::CoordinateMode;
+use crate::protocol::Point;
+use crate::raster;
+use crate::raster::line;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyLine` request.
+#[repr(C, packed)]
+pub struct PolyLineHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyLine {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// The way coordinates are interpreted.
+	coordinate_mode: u8,
+	/// The list of points.
+	points: Vec<Point>,
+}
+
+impl Request for PolyLine {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let mode = CoordinateMode::try_from(self.coordinate_mode).map_err(HandleError::Client)?;
+		let points = mode.resolve(&self.points);
+
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			line::poly_line(painter, &points)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolyLine`.
+///
+/// The coordinate mode is checked when the request is handled.
+pub fn read(buff: &[u8], coordinate_mode: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyLineHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyLineHdr = unsafe { util::reinterpret(&buff[0]) };
+	let points = util::read_list(&buff[size_of::<PolyLineHdr>()..]);
+
+	Ok(Some(Box::new(PolyLine {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+
+		coordinate_mode,
+		points,
+	})))
+}
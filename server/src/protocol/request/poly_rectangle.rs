@@ -0,0 +1,63 @@
+//! The `PolyRectangle` request allows to draw the outlines of rectangles.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Rectangle;
+use crate::raster;
+use crate::raster::rect;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyRectangle` request.
+#[repr(C, packed)]
+pub struct PolyRectangleHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyRectangle {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// The list of rectangles.
+	rectangles: Vec<Rectangle>,
+}
+
+impl Request for PolyRectangle {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			rect::poly_rectangle(painter, &self.rectangles)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolyRectangle`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyRectangleHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyRectangleHdr = unsafe { util::reinterpret(&buff[0]) };
+	let rectangles = util::read_list(&buff[size_of::<PolyRectangleHdr>()..]);
+
+	Ok(Some(Box::new(PolyRectangle {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+
+		rectangles,
+	})))
+}
@@ -0,0 +1,78 @@
+//! The `PolySegment` request allows to draw independent lines.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::raster;
+use crate::raster::line;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolySegment` request.
+#[repr(C, packed)]
+pub struct PolySegmentHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+}
+
+/// A segment, as sent by the client.
+#[repr(C)]
+struct Segment {
+	/// The X position of the start.
+	x1: i16,
+	/// The Y position of the start.
+	y1: i16,
+	/// The X position of the end.
+	x2: i16,
+	/// The Y position of the end.
+	y2: i16,
+}
+
+/// Structure representing the request.
+pub struct PolySegment {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+
+	/// The list of segments.
+	segments: Vec<[(i32, i32); 2]>,
+}
+
+impl Request for PolySegment {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			line::poly_segment(painter, &self.segments)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolySegment`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolySegmentHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolySegmentHdr = unsafe { util::reinterpret(&buff[0]) };
+	let segments = util::read_list::<Segment>(&buff[size_of::<PolySegmentHdr>()..])
+		.into_iter()
+		.map(|s| [(s.x1 as i32, s.y1 as i32), (s.x2 as i32, s.y2 as i32)])
+		.collect();
+
+	Ok(Some(Box::new(PolySegment {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+
+		segments,
+	})))
+}
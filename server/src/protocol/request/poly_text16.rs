@@ -0,0 +1,43 @@
+//! The `PolyText16` request allows to draw strings of 16 bits characters.
+//!
+//! The request works like `PolyText8`, except each character is made of two bytes: the row
+//! (`byte1`) followed by the column (`byte2`).
+
+use super::poly_text8::PolyText;
+use super::Request;
+use crate::protocol::error::Error;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyText16` request.
+#[repr(C, packed)]
+pub struct PolyText16Hdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+	/// The X position of the origin of the first string.
+	x: i16,
+	/// The Y position of the baseline.
+	y: i16,
+}
+
+/// Parses `PolyText16`.
+///
+/// The list of items is decoded when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyText16Hdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyText16Hdr = unsafe { util::reinterpret(&buff[0]) };
+	Ok(Some(Box::new(PolyText {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		x: hdr.x,
+		y: hdr.y,
+
+		wide: true,
+		items: buff[size_of::<PolyText16Hdr>()..].to_vec(),
+	})))
+}
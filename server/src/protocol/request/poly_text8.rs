@@ -0,0 +1,180 @@
+//! The `PolyText8` request allows to draw strings of 8 bits characters.
+//!
+//! The strings are given as a list of text items. An item is either a string, drawn after moving
+//! the origin by a delta, or a font shift changing the font of the graphics context.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::gc;
+use crate::ctx::Context;
+use crate::font::Font;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::raster;
+use crate::raster::text;
+use crate::util;
+use std::mem::size_of;
+use std::rc::Rc;
+
+/// The length of a text item telling the item is a font shift.
+const FONT_SHIFT: u8 = 255;
+
+/// Header of the `PolyText8` request.
+#[repr(C, packed)]
+pub struct PolyText8Hdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+	/// The X position of the origin of the first string.
+	x: i16,
+	/// The Y position of the baseline.
+	y: i16,
+}
+
+/// An item of a `PolyText` request.
+pub enum TextItem {
+	/// A string.
+	Text {
+		/// The offset added to the X position of the origin before drawing the string.
+		delta: i8,
+		/// The characters.
+		text: Vec<u16>,
+	},
+	/// The ID of the font to use for the next strings.
+	Font(u32),
+}
+
+/// Parses the list of text items in the given buffer.
+///
+/// `wide` tells whether characters are 16 bits. If so, the most significant byte is first.
+///
+/// Padding at the end of the list is ignored. If an item is truncated, the function returns an
+/// error.
+pub fn read_items(buff: &[u8], wide: bool) -> Result<Vec<TextItem>, Error> {
+	let char_size = if wide { 2 } else { 1 };
+
+	let mut items = vec![];
+	let mut i = 0;
+	// An item is at least two bytes long, less is padding
+	while buff.len() - i >= 2 {
+		let len = buff[i];
+		if len == FONT_SHIFT {
+			let id = buff.get((i + 1)..(i + 5)).ok_or(Error::Length)?;
+			items.push(TextItem::Font(u32::from_be_bytes([
+				id[0], id[1], id[2], id[3],
+			])));
+			i += 5;
+			continue;
+		}
+
+		let delta = buff[i + 1] as i8;
+		let start = i + 2;
+		let end = start + len as usize * char_size;
+		let chars = buff.get(start..end).ok_or(Error::Length)?;
+		let text = if wide {
+			chars
+				.chunks_exact(2)
+				.map(|c| u16::from_be_bytes([c[0], c[1]]))
+				.collect()
+		} else {
+			chars.iter().map(|c| *c as u16).collect()
+		};
+		items.push(TextItem::Text {
+			delta,
+			text,
+		});
+		i = end;
+	}
+
+	Ok(items)
+}
+
+/// Returns the font of the graphics context with the given ID.
+///
+/// If the graphics context doesn't exist, or if it has no font, the function returns an error.
+pub fn get_gc_font(client: &Client, gc: u32) -> Result<Rc<Font>, Error> {
+	client
+		.get_gc(gc)
+		.ok_or(Error::GContext(gc))?
+		.font
+		.clone()
+		.ok_or(Error::Match)
+}
+
+/// Structure representing a `PolyText8` or `PolyText16` request.
+pub struct PolyText {
+	/// The ID of the drawable.
+	pub drawable: u32,
+	/// The graphics context's ID.
+	pub gc: u32,
+	/// The X position of the origin of the first string.
+	pub x: i16,
+	/// The Y position of the baseline.
+	pub y: i16,
+
+	/// Tells whether characters are 16 bits.
+	pub wide: bool,
+	/// The encoded list of items.
+	pub items: Vec<u8>,
+}
+
+impl Request for PolyText {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let items = read_items(&self.items, self.wide).map_err(HandleError::Client)?;
+
+		let mut x = self.x as i32;
+		let y = self.y as i32;
+
+		for item in &items {
+			match item {
+				// The font remains set on the GC after the request
+				TextItem::Font(id) => client
+					.get_gc_mut(self.gc)
+					.ok_or(Error::GContext(self.gc))
+					.and_then(|gc| gc.set_values(ctx, &[gc::Value::Font(*id)]))
+					.map_err(HandleError::Client)?,
+
+				TextItem::Text {
+					delta,
+					text,
+				} => {
+					let font = get_gc_font(client, self.gc).map_err(HandleError::Client)?;
+					let origin = x + *delta as i32;
+					raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+						text::poly_text(painter, &font, origin, y, text);
+					})
+					.map_err(HandleError::Client)?;
+					x = origin + font.text_extents(text).overall_width;
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `PolyText8`.
+///
+/// The list of items is decoded when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyText8Hdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyText8Hdr = unsafe { util::reinterpret(&buff[0]) };
+	Ok(Some(Box::new(PolyText {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		x: hdr.x,
+		y: hdr.y,
+
+		wide: false,
+		items: buff[size_of::<PolyText8Hdr>()..].to_vec(),
+	})))
+}
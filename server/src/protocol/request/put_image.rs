@@ -0,0 +1,147 @@
+//! The `PutImage` request allows to draw an image sent by the client.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::image;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::ImageFormat;
+use crate::raster;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PutImage` request.
+#[repr(C, packed)]
+pub struct PutImageHdr {
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+	/// The width of the image.
+	width: u16,
+	/// The height of the image.
+	height: u16,
+	/// The X position of the image in the drawable.
+	dst_x: i16,
+	/// The Y position of the image in the drawable.
+	dst_y: i16,
+	/// The number of bits to skip at the beginning of each scanline.
+	left_pad: u8,
+	/// The depth of the image.
+	depth: u8,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct PutImage {
+	/// The format of the image.
+	format: u8,
+	/// The ID of the drawable.
+	drawable: u32,
+	/// The graphics context's ID.
+	gc: u32,
+	/// The width of the image.
+	width: u16,
+	/// The height of the image.
+	height: u16,
+	/// The X position of the image in the drawable.
+	dst_x: i16,
+	/// The Y position of the image in the drawable.
+	dst_y: i16,
+	/// The number of bits to skip at the beginning of each scanline.
+	left_pad: u8,
+	/// The depth of the image.
+	depth: u8,
+
+	/// The image's data.
+	data: Vec<u8>,
+}
+
+impl Request for PutImage {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let format = ImageFormat::try_from(self.format).map_err(HandleError::Client)?;
+		let gc = client
+			.get_gc(self.gc)
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+
+		match format {
+			ImageFormat::XYBitmap if self.depth != 1 => {
+				return Err(HandleError::Client(Error::Match))
+			}
+			ImageFormat::XYPixmap | ImageFormat::ZPixmap if self.depth != gc.depth => {
+				return Err(HandleError::Client(Error::Match))
+			}
+			ImageFormat::ZPixmap if self.left_pad != 0 => {
+				return Err(HandleError::Client(Error::Match))
+			}
+			_ => {}
+		}
+		// The left pad cannot exceed the scanline unit
+		if self.left_pad >= image::BITMAP_SCANLINE_PAD {
+			return Err(HandleError::Client(Error::Match));
+		}
+
+		let len = image::transfer_len(format, self.width, self.height, self.depth, self.left_pad)
+			.ok_or(HandleError::Client(Error::Match))?;
+		if self.data.len() < len {
+			return Err(HandleError::Client(Error::Length));
+		}
+
+		let x = self.dst_x as i32;
+		let y = self.dst_y as i32;
+		raster::draw(ctx, client, self.drawable, self.gc, |painter| {
+			let gc = painter.get_gc();
+			let (fg, bg) = (gc.foreground, gc.background);
+
+			image::decode(
+				format,
+				self.width,
+				self.height,
+				self.depth,
+				self.left_pad,
+				&self.data,
+				|i, j, pixel| {
+					let pixel = match format {
+						ImageFormat::XYBitmap if pixel != 0 => fg,
+						ImageFormat::XYBitmap => bg,
+						_ => pixel,
+					};
+					painter.put(x + i as i32, y + j as i32, pixel);
+				},
+			);
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PutImage`.
+///
+/// The format is checked when the request is handled.
+pub fn read(buff: &[u8], format: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PutImageHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PutImageHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(PutImage {
+		format,
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		width: hdr.width,
+		height: hdr.height,
+		dst_x: hdr.dst_x,
+		dst_y: hdr.dst_y,
+		left_pad: hdr.left_pad,
+		depth: hdr.depth,
+
+		data: buff[size_of::<PutImageHdr>()..].to_vec(),
+	})))
+}
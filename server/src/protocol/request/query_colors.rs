@@ -0,0 +1,96 @@
+//! The `QueryColors` request allows to get the colors of colormap entries.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct QueryColorsReply {
+	/// The type of the reply.
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// The sequence number of the request.
+	seq_nbr: u16,
+	/// Length of the reply in 4 bytes units.
+	reply_length: u32,
+	/// The number of colors.
+	colors_len: u16,
+	/// Padding.
+	_padding1: [u8; 22],
+}
+
+/// Header of the `QueryColors` request.
+#[repr(C, packed)]
+pub struct QueryColorsHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+}
+
+/// Structure representing the request.
+pub struct QueryColors {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The pixels to query.
+	pixels: Vec<u32>,
+}
+
+impl Request for QueryColors {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let colormap = NonZeroU32::new(self.cmap)
+			.and_then(|id| ctx.get_colormap(id))
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		let colors = self
+			.pixels
+			.iter()
+			.map(|p| colormap.query_color(*p).ok_or(Error::Value(*p)))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(HandleError::Client)?;
+
+		let hdr = QueryColorsReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: (colors.len() * 2) as _,
+			colors_len: colors.len() as _,
+			_padding1: [0; 22],
+		};
+		// Each color is followed by two bytes of padding
+		let data = colors
+			.iter()
+			.flat_map(|c| [c.red, c.green, c.blue, 0])
+			.flat_map(|v| v.to_ne_bytes())
+			.collect::<Vec<_>>();
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `QueryColors`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<QueryColorsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &QueryColorsHdr = unsafe { util::reinterpret(&buff[0]) };
+	let pixels = util::read_list(&buff[size_of::<QueryColorsHdr>()..]);
+
+	Ok(Some(Box::new(QueryColors {
+		cmap: hdr.cmap,
+		pixels,
+	})))
+}
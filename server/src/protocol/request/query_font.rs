@@ -0,0 +1,161 @@
+//! The `QueryFont` request allows to get the metrics and properties of a font.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::font::Font;
+use crate::font::PropValue;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::CharInfo;
+use crate::protocol::FontProp;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct QueryFontReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The minimum of each metric over every character.
+	min_bounds: CharInfo,
+	/// Padding.
+	_padding1: u32,
+	/// The maximum of each metric over every character.
+	max_bounds: CharInfo,
+	/// Padding.
+	_padding2: u32,
+	/// The first column of characters.
+	min_char_or_byte2: u16,
+	/// The last column of characters.
+	max_char_or_byte2: u16,
+	/// The character used in place of undefined ones.
+	default_char: u16,
+	/// The number of properties.
+	properties_len: u16,
+	/// The direction in which characters are drawn.
+	draw_direction: u8,
+	/// The first row of characters.
+	min_byte1: u8,
+	/// The last row of characters.
+	max_byte1: u8,
+	/// Tells whether every character in the font's ranges is defined.
+	all_chars_exist: u8,
+	/// The logical extent of the font above the baseline.
+	font_ascent: i16,
+	/// The logical extent of the font below the baseline.
+	font_descent: i16,
+	/// The number of character metrics.
+	char_infos_len: u32,
+}
+
+/// Header of the `QueryFont` request.
+#[repr(C, packed)]
+pub struct QueryFontHdr {
+	/// The ID of the font or graphics context.
+	font: u32,
+}
+
+/// Returns the font with the given ID, or the font of the graphics context with the given ID.
+///
+/// If neither exists, or if the graphics context has no font, the function returns an error.
+pub fn get_fontable(ctx: &Context, client: &Client, id: u32) -> Result<Rc<Font>, Error> {
+	let font = NonZeroU32::new(id)
+		.and_then(|fid| ctx.get_font(fid))
+		.cloned()
+		.or_else(|| client.get_gc(id)?.font.clone());
+	font.ok_or(Error::Font(id))
+}
+
+/// Returns the properties of the given font in the protocol's format.
+///
+/// The names of the properties and their string values are interned as atoms.
+pub fn get_props(ctx: &mut Context, font: &Font) -> Vec<FontProp> {
+	font.get_properties()
+		.iter()
+		.map(|p| FontProp {
+			name: ctx.intern_atom(&p.name),
+			value: match &p.value {
+				PropValue::Int(v) => *v as _,
+				PropValue::Str(s) => ctx.intern_atom(s),
+			},
+		})
+		.collect()
+}
+
+/// Structure representing the request.
+pub struct QueryFont {
+	/// The ID of the font or graphics context.
+	font: u32,
+}
+
+impl Request for QueryFont {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let font = get_fontable(ctx, client, self.font).map_err(HandleError::Client)?;
+		let props = get_props(ctx, &font);
+		let char_infos = font.get_char_infos();
+
+		let len = size_of::<QueryFontReply>() - 32
+			+ props.len() * size_of::<FontProp>()
+			+ char_infos.len() * size_of::<CharInfo>();
+		let hdr = QueryFontReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: (len / 4) as _,
+
+			min_bounds: *font.get_min_bounds(),
+			_padding1: 0,
+			max_bounds: *font.get_max_bounds(),
+			_padding2: 0,
+			min_char_or_byte2: font.get_min_char_or_byte2(),
+			max_char_or_byte2: font.get_max_char_or_byte2(),
+			default_char: font.get_default_char(),
+			properties_len: props.len() as _,
+			draw_direction: font.get_draw_direction(),
+			min_byte1: font.get_min_byte1(),
+			max_byte1: font.get_max_byte1(),
+			all_chars_exist: font.all_chars_exist() as _,
+			font_ascent: font.get_font_ascent(),
+			font_descent: font.get_font_descent(),
+			char_infos_len: char_infos.len() as _,
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client
+			.write(util::as_bytes(&props))
+			.map_err(HandleError::IO)?;
+		client
+			.write(util::as_bytes(&char_infos))
+			.map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `QueryFont`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<QueryFontHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &QueryFontHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(QueryFont {
+		font: hdr.font,
+	})))
+}
@@ -1,8 +1,12 @@
-//! TODO doc
+//! The `QueryPointer` request returns the position of the pointer and the state of the buttons
+//! and modifiers.
+//!
+//! It also allows the client to receive a motion hint again.
 
 use super::Request;
 use crate::ctx::client::Client;
 use crate::ctx::Context;
+use crate::ctx::Drawable;
 use crate::protocol;
 use crate::protocol::error::Error;
 use crate::protocol::request::HandleError;
@@ -36,7 +40,7 @@ struct QueryPointerReply {
 	/// The Y position of the pointer relative to the given window.
 	win_y: i16,
 
-	/// TODO doc
+	/// The state of the modifiers and buttons.
 	mask: u16,
 
 	/// Padding.
@@ -65,30 +69,51 @@ impl Request for QueryPointer {
 	) -> Result<(), HandleError> {
 		let wid =
 			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
-		let _win = ctx
-			.get_window_mut(wid)
+		let win = ctx
+			.get_window(wid)
 			.ok_or(HandleError::Client(Error::Window(self.window)))?;
 
+		let pointer = ctx.get_pointer();
+		let root = pointer.get_screen();
+		let (root_x, root_y) = pointer.get_position();
+		// If the pointer is on another screen, only the root is reported
+		let same_screen = win.get_root() == root;
+		let (child, win_x, win_y) = if same_screen {
+			let child = ctx
+				.get_pointer_window()
+				.and_then(|w| ctx.get_child_towards(wid, w));
+			let (origin_x, origin_y) = ctx.get_window_origin(wid);
+			(
+				child.map(NonZeroU32::get).unwrap_or(0),
+				(root_x as i32 - origin_x) as _,
+				(root_y as i32 - origin_y) as _,
+			)
+		} else {
+			(0, 0, 0)
+		};
+
 		let hdr = QueryPointerReply {
 			reply_type: protocol::REPLY_TYPE_REPLY,
-			same_screen: 1, // TODO
+			same_screen: same_screen as _,
 			seq_nbr,
 			reply_length: 0,
 
-			root: 1,  // TODO
-			child: 0, // TODO
+			root,
+			child,
 
-			root_x: 0, // TODO
-			root_y: 0, // TODO
-			win_x: 0,  // TODO
-			win_y: 0,  // TODO
+			root_x,
+			root_y,
+			win_x,
+			win_y,
 
-			mask: 0, // TODO
+			mask: ctx.get_state_mask(),
 
 			_padding: [0; 6],
 		};
 		client.write_obj(&hdr).map_err(|e| HandleError::IO(e))?;
 
+		ctx.get_pointer_mut().clear_motion_hint(client.get_id());
+
 		Ok(())
 	}
 }
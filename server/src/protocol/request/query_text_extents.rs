@@ -0,0 +1,121 @@
+//! The `QueryTextExtents` request allows to get the extents of a string drawn with a font.
+
+use super::query_font;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct QueryTextExtentsReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The direction in which characters are drawn.
+	draw_direction: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The logical extent of the font above the baseline.
+	font_ascent: i16,
+	/// The logical extent of the font below the baseline.
+	font_descent: i16,
+	/// The maximum ascent of the characters.
+	overall_ascent: i16,
+	/// The maximum descent of the characters.
+	overall_descent: i16,
+	/// The sum of the widths of the characters.
+	overall_width: i32,
+	/// The leftmost extent of the characters, relative to the origin of the string.
+	overall_left: i32,
+	/// The rightmost extent of the characters, relative to the origin of the string.
+	overall_right: i32,
+
+	/// Padding.
+	_padding: u32,
+}
+
+/// Header of the `QueryTextExtents` request.
+#[repr(C, packed)]
+pub struct QueryTextExtentsHdr {
+	/// The ID of the font or graphics context.
+	font: u32,
+}
+
+/// Structure representing the request.
+pub struct QueryTextExtents {
+	/// The ID of the font or graphics context.
+	font: u32,
+	/// Tells whether the last character of the string is padding.
+	odd_length: bool,
+	/// The string, including padding.
+	text: Vec<u16>,
+}
+
+impl Request for QueryTextExtents {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let text = match (self.odd_length, self.text.split_last()) {
+			(false, _) => &self.text[..],
+			(true, Some((_, text))) => text,
+			(true, None) => return Err(HandleError::Client(Error::Length)),
+		};
+
+		let font = query_font::get_fontable(ctx, client, self.font).map_err(HandleError::Client)?;
+		let extents = font.text_extents(text);
+
+		let hdr = QueryTextExtentsReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			draw_direction: font.get_draw_direction(),
+			seq_nbr,
+			reply_length: 0,
+
+			font_ascent: font.get_font_ascent(),
+			font_descent: font.get_font_descent(),
+			overall_ascent: extents.overall_ascent,
+			overall_descent: extents.overall_descent,
+			overall_width: extents.overall_width,
+			overall_left: extents.overall_left,
+			overall_right: extents.overall_right,
+
+			_padding: 0,
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `QueryTextExtents`.
+///
+/// `odd_length` tells whether the last character of the string is padding. The length is checked
+/// when the request is handled.
+pub fn read(buff: &[u8], odd_length: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<QueryTextExtentsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &QueryTextExtentsHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	// Characters are sent with the most significant byte first
+	let text = buff[size_of::<QueryTextExtentsHdr>()..]
+		.chunks_exact(2)
+		.map(|c| u16::from_be_bytes([c[0], c[1]]))
+		.collect();
+
+	Ok(Some(Box::new(QueryTextExtents {
+		font: hdr.font,
+		odd_length: odd_length != 0,
+		text,
+	})))
+}
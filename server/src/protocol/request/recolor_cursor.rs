@@ -0,0 +1,75 @@
+//! The `RecolorCursor` request allows to change the colors of a cursor.
+//!
+//! If the cursor is displayed, the change is visible immediately.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `RecolorCursor` request.
+#[repr(C, packed)]
+pub struct RecolorCursorHdr {
+	/// The ID of the cursor.
+	cursor: u32,
+	/// The red component of the foreground color.
+	fore_red: u16,
+	/// The green component of the foreground color.
+	fore_green: u16,
+	/// The blue component of the foreground color.
+	fore_blue: u16,
+	/// The red component of the background color.
+	back_red: u16,
+	/// The green component of the background color.
+	back_green: u16,
+	/// The blue component of the background color.
+	back_blue: u16,
+}
+
+/// Structure representing the request.
+pub struct RecolorCursor {
+	/// The ID of the cursor.
+	cursor: u32,
+	/// The foreground color.
+	fore: [u16; 3],
+	/// The background color.
+	back: [u16; 3],
+}
+
+impl Request for RecolorCursor {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let cursor = NonZeroU32::new(self.cursor)
+			.and_then(|id| ctx.get_cursor(id))
+			.ok_or(HandleError::Client(Error::Cursor(self.cursor)))?;
+		cursor.recolor(self.fore, self.back);
+
+		// The displayed cursor may be the one that changed
+		ctx.refresh_cursor();
+
+		Ok(())
+	}
+}
+
+/// Parses `RecolorCursor`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<RecolorCursorHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &RecolorCursorHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(RecolorCursor {
+		cursor: hdr.cursor,
+		fore: [hdr.fore_red, hdr.fore_green, hdr.fore_blue],
+		back: [hdr.back_red, hdr.back_green, hdr.back_blue],
+	})))
+}
@@ -0,0 +1,81 @@
+//! The `SetClipRectangles` request allows to set the clipping of a graphics context to a list of
+//! rectangles.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::gc::Clip;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Rectangle;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `SetClipRectangles` request.
+#[repr(C, packed)]
+pub struct SetClipRectanglesHdr {
+	/// The graphics context's ID.
+	gc: u32,
+	/// The X origin of the clipping.
+	clip_x_origin: i16,
+	/// The Y origin of the clipping.
+	clip_y_origin: i16,
+}
+
+/// Structure representing the request.
+pub struct SetClipRectangles {
+	/// The graphics context's ID.
+	gc: u32,
+	/// The X origin of the clipping.
+	clip_x_origin: i16,
+	/// The Y origin of the clipping.
+	clip_y_origin: i16,
+
+	/// Tells how rectangles are sorted. Since the server doesn't rely on it, it is only checked.
+	ordering: u8,
+	/// The list of rectangles.
+	rectangles: Vec<Rectangle>,
+}
+
+impl Request for SetClipRectangles {
+	fn handle(
+		&self,
+		_ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		if self.ordering > 3 {
+			return Err(HandleError::Client(Error::Value(self.ordering as _)));
+		}
+
+		let gc = client
+			.get_gc_mut(self.gc)
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+		gc.clip_x_origin = self.clip_x_origin;
+		gc.clip_y_origin = self.clip_y_origin;
+		gc.clip = Clip::Rectangles(self.rectangles.clone());
+
+		Ok(())
+	}
+}
+
+/// Parses `SetClipRectangles`.
+///
+/// `ordering` tells how rectangles are sorted. It is checked when the request is handled.
+pub fn read(buff: &[u8], ordering: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<SetClipRectanglesHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &SetClipRectanglesHdr = unsafe { util::reinterpret(&buff[0]) };
+	let rectangles = util::read_list(&buff[size_of::<SetClipRectanglesHdr>()..]);
+
+	Ok(Some(Box::new(SetClipRectangles {
+		gc: hdr.gc,
+		clip_x_origin: hdr.clip_x_origin,
+		clip_y_origin: hdr.clip_y_origin,
+
+		ordering,
+		rectangles,
+	})))
+}
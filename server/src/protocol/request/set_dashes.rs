@@ -0,0 +1,79 @@
+//! The `SetDashes` request allows to set the dash pattern of a graphics context.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `SetDashes` request.
+#[repr(C, packed)]
+pub struct SetDashesHdr {
+	/// The graphics context's ID.
+	gc: u32,
+	/// The offset in the dash pattern where dashed lines begin.
+	dash_offset: u16,
+	/// The number of dashes.
+	dashes_len: u16,
+}
+
+/// Structure representing the request.
+pub struct SetDashes {
+	/// The graphics context's ID.
+	gc: u32,
+	/// The offset in the dash pattern where dashed lines begin.
+	dash_offset: u16,
+	/// The number of dashes.
+	dashes_len: u16,
+	/// The lengths of alternating dashes.
+	dashes: Vec<u8>,
+}
+
+impl Request for SetDashes {
+	fn handle(
+		&self,
+		_ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let len = self.dashes_len as usize;
+		if self.dashes.len() < len {
+			return Err(HandleError::Client(Error::Length));
+		}
+		let dashes = &self.dashes[..len];
+		if dashes.is_empty() {
+			return Err(HandleError::Client(Error::Value(0)));
+		}
+		if let Some(d) = dashes.iter().find(|d| **d == 0) {
+			return Err(HandleError::Client(Error::Value(*d as _)));
+		}
+
+		let gc = client
+			.get_gc_mut(self.gc)
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+		gc.dash_offset = self.dash_offset;
+		gc.dashes = dashes.to_vec();
+
+		Ok(())
+	}
+}
+
+/// Parses `SetDashes`.
+///
+/// Dashes are checked when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<SetDashesHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &SetDashesHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(SetDashes {
+		gc: hdr.gc,
+		dash_offset: hdr.dash_offset,
+		dashes_len: hdr.dashes_len,
+		dashes: buff[size_of::<SetDashesHdr>()..].to_vec(),
+	})))
+}
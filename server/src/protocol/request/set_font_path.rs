@@ -0,0 +1,89 @@
+//! The `SetFontPath` request allows to set the list of directories in which fonts are searched.
+//!
+//! An empty list restores the default font path.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::font::catalog;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `SetFontPath` request.
+#[repr(C, packed)]
+pub struct SetFontPathHdr {
+	/// The number of elements in the path.
+	paths_len: u16,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct SetFontPath {
+	/// The number of elements in the path.
+	paths_len: u16,
+	/// The encoded elements of the path.
+	data: Vec<u8>,
+}
+
+impl SetFontPath {
+	/// Decodes the elements of the path.
+	///
+	/// If an element is truncated, the function returns an error.
+	fn get_path(&self) -> Result<Vec<String>, Error> {
+		// Each element is preceded by its length
+		let mut path = vec![];
+		let mut data = &self.data[..];
+		for _ in 0..self.paths_len {
+			let (len, rest) = data.split_first().ok_or(Error::Length)?;
+			let elem = rest.get(..(*len as usize)).ok_or(Error::Length)?;
+			path.push(String::from_utf8_lossy(elem).into_owned());
+			data = &rest[elem.len()..];
+		}
+		Ok(path)
+	}
+}
+
+impl Request for SetFontPath {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let path = self.get_path().map_err(HandleError::Client)?;
+		if path.is_empty() {
+			ctx.get_font_catalog_mut().reset_path();
+			return Ok(());
+		}
+
+		// The value of the error is the index of the first invalid element
+		if let Some(i) = path
+			.iter()
+			.position(|elem| !catalog::is_valid_element(elem))
+		{
+			return Err(HandleError::Client(Error::Value(i as _)));
+		}
+		ctx.get_font_catalog_mut().set_path(path);
+
+		Ok(())
+	}
+}
+
+/// Parses `SetFontPath`.
+///
+/// The elements of the path are decoded when the request is handled.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<SetFontPathHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &SetFontPathHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(SetFontPath {
+		paths_len: hdr.paths_len,
+		data: buff[size_of::<SetFontPathHdr>()..].to_vec(),
+	})))
+}
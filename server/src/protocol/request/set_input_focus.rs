@@ -0,0 +1,93 @@
+//! The `SetInputFocus` request allows to change the window receiving the keyboard input.
+//!
+//! The request is ignored if its time is earlier than the last change of the focus or later than
+//! the current server time.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::Focus;
+use crate::ctx::keyboard::RevertTo;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Focus value: `None`.
+const FOCUS_NONE: u32 = 0;
+/// Focus value: `PointerRoot`.
+const FOCUS_POINTER_ROOT: u32 = 1;
+
+/// Header of the `SetInputFocus` request.
+#[repr(C, packed)]
+pub struct SetInputFocusHdr {
+	/// The new focus: `None`, `PointerRoot` or a window.
+	focus: u32,
+	/// The time of the change.
+	time: u32,
+}
+
+/// Structure representing the request.
+pub struct SetInputFocus {
+	/// What the focus becomes when the focus window becomes unviewable.
+	revert_to: u8,
+	/// The new focus.
+	focus: u32,
+	/// The time of the change.
+	time: u32,
+}
+
+impl Request for SetInputFocus {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let revert_to = RevertTo::try_from(self.revert_to).map_err(HandleError::Client)?;
+		let focus = match self.focus {
+			FOCUS_NONE => Focus::None,
+			FOCUS_POINTER_ROOT => Focus::PointerRoot,
+			id => {
+				// Cannot fail since the value is neither zero nor one
+				let wid = NonZeroU32::new(id).unwrap();
+				let win = ctx
+					.get_window(wid)
+					.ok_or(HandleError::Client(Error::Window(id)))?;
+				if !win.is_viewable() {
+					return Err(HandleError::Client(Error::Match));
+				}
+				Focus::Window(wid)
+			}
+		};
+
+		let now = ctx.get_time();
+		let time = ctx.resolve_time(self.time);
+		let last = ctx.get_keyboard().get_focus_time();
+		if protocol::is_time_before(time, last) || protocol::is_time_before(now, time) {
+			return Ok(());
+		}
+		ctx.set_input_focus(focus, revert_to, time);
+
+		Ok(())
+	}
+}
+
+/// Parses `SetInputFocus`.
+///
+/// `revert_to` is what the focus becomes when the focus window becomes unviewable. It is checked
+/// when the request is handled.
+pub fn read(buff: &[u8], revert_to: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<SetInputFocusHdr>() {
+		return Ok(None);
+	}
+	let hdr: &SetInputFocusHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(SetInputFocus {
+		revert_to,
+		focus: hdr.focus,
+		time: hdr.time,
+	})))
+}
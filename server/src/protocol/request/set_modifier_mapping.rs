@@ -0,0 +1,115 @@
+//! The `SetModifierMapping` request allows to change the keycodes mapped to each modifier.
+//!
+//! The mapping is not changed if a key of a modifier being changed is down. Otherwise, every
+//! client is notified of the change with a `MappingNotify` event.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::MODIFIERS;
+use crate::ctx::Context;
+use crate::input;
+use crate::input::Keycode;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::MappingNotify;
+use crate::protocol::request::HandleError;
+
+/// Reply status: the mapping has been changed.
+const STATUS_SUCCESS: u8 = 0;
+/// Reply status: a key of a modifier being changed is down.
+const STATUS_BUSY: u8 = 1;
+
+/// The reply.
+#[repr(C, packed)]
+pub struct SetModifierMappingReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The status of the request.
+	status: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Padding.
+	_padding: [u8; 24],
+}
+
+/// Structure representing the request.
+pub struct SetModifierMapping {
+	/// The number of keycodes of each modifier.
+	keycodes_per_modifier: u8,
+	/// The keycodes of each modifier. Zero entries are unused.
+	keycodes: Vec<Keycode>,
+}
+
+impl SetModifierMapping {
+	/// Returns the keycodes of each modifier after checking them.
+	fn get_keycodes(&self) -> Result<&[Keycode], Error> {
+		let len = self.keycodes_per_modifier as usize * MODIFIERS.len();
+		if self.keycodes.len() < len {
+			return Err(Error::Length);
+		}
+		let keycodes = &self.keycodes[..len];
+		if let Some(k) = keycodes
+			.iter()
+			.find(|k| **k != 0 && **k < input::MIN_KEYCODE)
+		{
+			return Err(Error::Value(*k as _));
+		}
+		Ok(keycodes)
+	}
+}
+
+impl Request for SetModifierMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let keycodes = self.get_keycodes().map_err(HandleError::Client)?;
+		let success = ctx
+			.get_keyboard_mut()
+			.set_modifier_mapping(keycodes.to_vec(), self.keycodes_per_modifier as _);
+
+		let hdr = SetModifierMappingReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			status: if success { STATUS_SUCCESS } else { STATUS_BUSY },
+			seq_nbr,
+			reply_length: 0,
+
+			_padding: [0; 24],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+
+		if success {
+			ctx.broadcast_event(&MappingNotify {
+				code: event::MAPPING_NOTIFY,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				request: event::MAPPING_MODIFIER,
+				first_keycode: 0,
+				count: 0,
+
+				_padding1: [0; 25],
+			});
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `SetModifierMapping`.
+///
+/// `keycodes_per_modifier` is the number of keycodes of each modifier.
+///
+/// Keycodes are checked when the request is handled.
+pub fn read(buff: &[u8], keycodes_per_modifier: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(SetModifierMapping {
+		keycodes_per_modifier,
+		keycodes: buff.to_vec(),
+	})))
+}
@@ -0,0 +1,108 @@
+//! The `StoreColors` request allows to change the colors of read-write colormap entries.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::colormap::Color;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Flag telling that the red component is stored.
+pub const DO_RED: u8 = 0x1;
+/// Flag telling that the green component is stored.
+pub const DO_GREEN: u8 = 0x2;
+/// Flag telling that the blue component is stored.
+pub const DO_BLUE: u8 = 0x4;
+
+/// Converts the given flags to the red, green and blue components to store.
+pub fn flags_to_components(flags: u8) -> [bool; 3] {
+	[
+		flags & DO_RED != 0,
+		flags & DO_GREEN != 0,
+		flags & DO_BLUE != 0,
+	]
+}
+
+/// Header of the `StoreColors` request.
+#[repr(C, packed)]
+pub struct StoreColorsHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+}
+
+/// An item of the request.
+#[derive(Clone, Copy)]
+#[repr(C, packed)]
+pub struct ColorItem {
+	/// The pixel of the entry.
+	pixel: u32,
+	/// The red component.
+	red: u16,
+	/// The green component.
+	green: u16,
+	/// The blue component.
+	blue: u16,
+	/// The components to store.
+	flags: u8,
+	/// Padding.
+	_padding: u8,
+}
+
+/// Structure representing the request.
+pub struct StoreColors {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The colors to store.
+	items: Vec<ColorItem>,
+}
+
+impl Request for StoreColors {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let id =
+			NonZeroU32::new(self.cmap).ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		let colormap = ctx
+			.get_colormap_mut(id)
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+
+		// Every item is stored even if an error occurs with another
+		let mut result = Ok(());
+		for item in &self.items {
+			let color = Color {
+				red: item.red,
+				green: item.green,
+				blue: item.blue,
+			};
+			if let Err(e) =
+				colormap.store_color(item.pixel, &color, flags_to_components(item.flags))
+			{
+				result = Err(HandleError::Client(e));
+			}
+		}
+		ctx.damage_colormap(id);
+
+		result
+	}
+}
+
+/// Parses `StoreColors`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<StoreColorsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &StoreColorsHdr = unsafe { util::reinterpret(&buff[0]) };
+	let items = util::read_list(&buff[size_of::<StoreColorsHdr>()..]);
+
+	Ok(Some(Box::new(StoreColors {
+		cmap: hdr.cmap,
+		items,
+	})))
+}
@@ -0,0 +1,87 @@
+//! The `StoreNamedColor` request allows to change the color of a read-write colormap entry to
+//! the one with the given name in the color database.
+
+use super::store_colors;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::rgb;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `StoreNamedColor` request.
+#[repr(C, packed)]
+pub struct StoreNamedColorHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The pixel of the entry.
+	pixel: u32,
+	/// The length of the name in bytes.
+	name_length: u16,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct StoreNamedColor {
+	/// The components to store.
+	flags: u8,
+	/// The ID of the colormap.
+	cmap: u32,
+	/// The pixel of the entry.
+	pixel: u32,
+	/// The name of the color.
+	name: String,
+}
+
+impl Request for StoreNamedColor {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let id =
+			NonZeroU32::new(self.cmap).ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		let colormap = ctx
+			.get_colormap_mut(id)
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		let color = rgb::lookup(&self.name).ok_or(HandleError::Client(Error::Name))?;
+		colormap
+			.store_color(
+				self.pixel,
+				&color,
+				store_colors::flags_to_components(self.flags),
+			)
+			.map_err(HandleError::Client)?;
+		ctx.damage_colormap(id);
+
+		Ok(())
+	}
+}
+
+/// Parses `StoreNamedColor`.
+pub fn read(buff: &[u8], flags: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<StoreNamedColorHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &StoreNamedColorHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let name_begin = size_of::<StoreNamedColorHdr>();
+	let name_end = name_begin + hdr.name_length as usize;
+	if buff.len() < name_end {
+		return Ok(None);
+	}
+	let name = String::from_utf8_lossy(&buff[name_begin..name_end]).into_owned();
+
+	Ok(Some(Box::new(StoreNamedColor {
+		flags,
+		cmap: hdr.cmap,
+		pixel: hdr.pixel,
+		name,
+	})))
+}
@@ -0,0 +1,62 @@
+//! The `UngrabKeyboard` request releases the grab of the keyboard held by the client.
+//!
+//! The request is ignored if its time is earlier than the grab or later than the current server
+//! time.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `UngrabKeyboard` request.
+#[repr(C, packed)]
+pub struct UngrabKeyboardHdr {
+	/// The time of the release.
+	time: u32,
+}
+
+/// Structure representing the request.
+pub struct UngrabKeyboard {
+	/// The time of the release.
+	time: u32,
+}
+
+impl Request for UngrabKeyboard {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let kbd = ctx.get_keyboard();
+		if kbd.get_grab().is_none_or(|g| g.client != client.get_id()) {
+			return Ok(());
+		}
+		let now = ctx.get_time();
+		let time = ctx.resolve_time(self.time);
+		if protocol::is_time_before(time, kbd.get_grab_time())
+			|| protocol::is_time_before(now, time)
+		{
+			return Ok(());
+		}
+		ctx.ungrab_keyboard();
+
+		Ok(())
+	}
+}
+
+/// Parses `UngrabKeyboard`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<UngrabKeyboardHdr>() {
+		return Ok(None);
+	}
+	let hdr: &UngrabKeyboardHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(UngrabKeyboard {
+		time: hdr.time,
+	})))
+}
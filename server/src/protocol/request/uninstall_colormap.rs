@@ -0,0 +1,54 @@
+//! The `UninstallColormap` request allows to uninstall a colormap from its screen.
+//!
+//! If too few colormaps would remain installed, the default colormap of the screen is installed.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `UninstallColormap` request.
+#[repr(C, packed)]
+pub struct UninstallColormapHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+}
+
+/// Structure representing the request.
+pub struct UninstallColormap {
+	/// The ID of the colormap.
+	cmap: u32,
+}
+
+impl Request for UninstallColormap {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let id = NonZeroU32::new(self.cmap)
+			.filter(|id| ctx.get_colormap(*id).is_some())
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		ctx.uninstall_colormap(id);
+
+		Ok(())
+	}
+}
+
+/// Parses `UninstallColormap`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<UninstallColormapHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &UninstallColormapHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(UninstallColormap {
+		cmap: hdr.cmap,
+	})))
+}
@@ -0,0 +1,83 @@
+//! The `UnmapWindow` request allows to unmap a window.
+//!
+//! Areas of other windows that become visible are exposed.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::UnmapNotify;
+use crate::protocol::request::HandleError;
+use crate::protocol::Event;
+use crate::protocol::MapState;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `UnmapWindow` request.
+#[repr(C, packed)]
+pub struct UnmapWindowHdr {
+	/// The ID of the window.
+	window: u32,
+}
+
+/// Structure representing the request.
+pub struct UnmapWindow {
+	/// The ID of the window.
+	window: u32,
+}
+
+impl Request for UnmapWindow {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		if win.is_root() || matches!(win.attributes.map_state, MapState::Unmapped) {
+			return Ok(());
+		}
+		let parent = win.get_parent();
+
+		// The notification is sent before the areas below the window are exposed
+		let mut ev = UnmapNotify {
+			code: event::UNMAP_NOTIFY,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			event: self.window,
+			window: self.window,
+			from_configure: 0,
+
+			_padding1: [0; 19],
+		};
+		ctx.deliver_event(wid, Event::StructureNotify as _, &ev);
+		if let Some(parent_id) = parent {
+			ev.event = parent_id.get();
+			ctx.deliver_event(parent_id, Event::SubstructureNotify as _, &ev);
+		}
+
+		ctx.unmap_window(wid);
+
+		Ok(())
+	}
+}
+
+/// Parses `UnmapWindow`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<UnmapWindowHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &UnmapWindowHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(UnmapWindow {
+		window: hdr.window,
+	})))
+}
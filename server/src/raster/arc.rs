@@ -0,0 +1,630 @@
+//! Drawing of arcs.
+//!
+//! An arc is a part of the ellipse inscribed in a rectangle. Angles are in 64ths of degree,
+//! starting from the three-o'clock position and going counter-clockwise. On non-circular
+//! ellipses, angles are skewed: an angle designates the point at that angle on the circle, once
+//! the circle is scaled to the ellipse.
+//!
+//! Filled arcs are scan converted exactly: a pixel is drawn if its center is inside the shape.
+//! A pixel whose center is on the boundary is drawn if the inside of the shape is immediately to
+//! its right, or immediately below it if the boundary is horizontal there.
+//!
+//! Outlines are drawn as wide lines along paths approximating the ellipse. The approximation is
+//! fine enough to be invisible, except for pixels whose center is within a fraction of pixel
+//! from the edge of the outline, which may differ from the exact shape.
+
+use super::line;
+use super::line::Dasher;
+use super::line::FPoint;
+use super::Paint;
+use super::Painter;
+use crate::ctx::gc::ArcMode;
+use crate::ctx::gc::JoinStyle;
+use crate::protocol::Arc;
+use std::cmp::max;
+use std::cmp::min;
+use std::cmp::Ordering;
+use std::f64::consts::FRAC_1_SQRT_2;
+
+/// The maximum distance in pixels between two consecutive points of the path approximating an
+/// arc.
+const STEP: f64 = 0.5;
+
+/// An arc, in a form suitable for computations.
+struct Ellipse {
+	/// The X position of the center.
+	cx: f64,
+	/// The Y position of the center.
+	cy: f64,
+	/// The horizontal semi-axis.
+	a: f64,
+	/// The vertical semi-axis.
+	b: f64,
+
+	/// The parametric angle of the start of the arc, in radians.
+	start: f64,
+	/// The parametric angle of the end of the arc, in radians.
+	end: f64,
+	/// Tells whether the arc is a whole ellipse.
+	full: bool,
+}
+
+impl Ellipse {
+	/// Creates an instance from the given arc.
+	fn new(arc: &Arc) -> Self {
+		let a = arc.width as f64 / 2.;
+		let b = arc.height as f64 / 2.;
+
+		// An arc cannot go around more than once
+		let extent = (arc.angle2 as i32).clamp(-360 * 64, 360 * 64);
+		let full = extent.abs() == 360 * 64;
+
+		// Skewed angles are the parametric angles of the ellipse
+		let start = (arc.angle1 as f64 / 64.).to_radians();
+		let end = start + (extent as f64 / 64.).to_radians();
+
+		Self {
+			cx: arc.x as f64 + a,
+			cy: arc.y as f64 + b,
+			a,
+			b,
+
+			start,
+			end,
+			full,
+		}
+	}
+
+	/// Returns the position of the point of the ellipse at the given parametric angle.
+	fn point(&self, t: f64) -> FPoint {
+		(self.cx + self.a * t.cos(), self.cy - self.b * t.sin())
+	}
+
+	/// Returns a path approximating the arc, from its start to its end.
+	fn path(&self) -> Vec<FPoint> {
+		let len = (self.end - self.start).abs() * self.a.max(self.b);
+		let n = ((len / STEP).ceil() as usize).max(1);
+
+		let mut points: Vec<_> = (0..=n)
+			.map(|i| {
+				let t = self.start + (self.end - self.start) * i as f64 / n as f64;
+				self.point(t)
+			})
+			.collect();
+		if self.full {
+			points[n] = points[0];
+		}
+		points
+	}
+}
+
+/// Draws the outlines of the given arcs.
+///
+/// When the end of an arc coincides with the start of the next one, both arcs are joined using
+/// the join style of the graphics context and the dash pattern continues from one to the other.
+pub fn poly_arc(painter: &mut Painter, arcs: &[Arc]) {
+	let gc = painter.get_gc();
+
+	// Points of the paths, each with the style of the join drawn at this point. The corners of
+	// the approximation of an arc are very obtuse, thus they are joined with a miter
+	let mut paths: Vec<Vec<(FPoint, &JoinStyle)>> = vec![];
+	for arc in arcs {
+		let points = Ellipse::new(arc).path();
+		let joined = paths.last_mut().filter(|path| {
+			let (first, last) = (path[0].0, path[path.len() - 1].0);
+			let start = points[0];
+			first != last && (last.0 - start.0).hypot(last.1 - start.1) < 1e-6
+		});
+
+		match joined {
+			Some(path) => {
+				path.last_mut().unwrap().1 = &gc.join_style;
+				path.extend(points[1..].iter().map(|p| (*p, &JoinStyle::Miter)));
+			}
+			None => paths.push(points.iter().map(|p| (*p, &JoinStyle::Miter)).collect()),
+		}
+	}
+
+	for path in paths {
+		let mut dasher = Dasher::new(gc);
+		if gc.line_width == 0 {
+			let mut points: Vec<_> = path
+				.iter()
+				.map(|((x, y), _)| (x.round() as i32, y.round() as i32))
+				.collect();
+			points.dedup();
+			line::thin_lines(painter, &points, &mut dasher);
+		} else {
+			line::wide_lines(painter, &path, &mut dasher);
+		}
+	}
+}
+
+/// Returns the direction of the given angle, in 64ths of degree.
+///
+/// Multiples of 45 degrees give exact and symmetric directions, so that pixels on the axes and
+/// diagonals of an ellipse are on the boundary of the slices starting or ending there.
+fn direction(angle: i32) -> FPoint {
+	const D: f64 = FRAC_1_SQRT_2;
+	const DIAGONALS: [FPoint; 8] = [
+		(1., 0.),
+		(D, D),
+		(0., 1.),
+		(-D, D),
+		(-1., 0.),
+		(-D, -D),
+		(0., -1.),
+		(D, -D),
+	];
+
+	let angle = angle.rem_euclid(360 * 64);
+	if angle % (45 * 64) == 0 {
+		return DIAGONALS[(angle / (45 * 64)) as usize];
+	}
+	let angle = (angle as f64 / 64.).to_radians();
+	(angle.cos(), angle.sin())
+}
+
+/// Tells on which side of a line going through the origin the given point is.
+///
+/// `d` is the direction of the line and `q` the point, with Y going upward. The result is
+/// positive if the point is on the left, negative if it is on the right.
+///
+/// If the point is on the line, the result is the side of the point moved infinitesimally to the
+/// right, then downward, which implements the rules for pixels on the boundary.
+fn side(d: FPoint, q: FPoint) -> f64 {
+	let s = d.0 * q.1 - d.1 * q.0;
+	if s != 0. {
+		s
+	} else if d.1 != 0. {
+		-d.1
+	} else {
+		-d.0
+	}
+}
+
+/// The part of an ellipse filled by `PolyFillArc`.
+enum Region {
+	/// The whole ellipse.
+	Full,
+	/// The region between the arc and the lines going from its ends to the center.
+	PieSlice {
+		/// The direction of the start of the arc.
+		start: FPoint,
+		/// The direction of the end of the arc.
+		end: FPoint,
+		/// Tells whether the arc spans more than half of the ellipse.
+		reflex: bool,
+	},
+	/// The region between the arc and the line joining its ends.
+	Chord {
+		/// The direction of the start of the arc.
+		start: FPoint,
+		/// The direction of the end of the arc.
+		end: FPoint,
+	},
+}
+
+/// An arc, in a form suitable for exact scan conversion.
+///
+/// Positions are doubled so that the center of the ellipse has integer coordinates.
+struct FillArc {
+	/// The doubled X position of the center.
+	cx: i64,
+	/// The doubled Y position of the center.
+	cy: i64,
+	/// The width of the ellipse.
+	w: i64,
+	/// The height of the ellipse.
+	h: i64,
+
+	/// The part of the ellipse to fill.
+	region: Region,
+}
+
+impl FillArc {
+	/// Creates an instance from the given arc.
+	///
+	/// If the arc fills nothing, the function returns None.
+	fn new(arc: &Arc, mode: &ArcMode) -> Option<Self> {
+		let extent = (arc.angle2 as i32).clamp(-360 * 64, 360 * 64);
+		if arc.width == 0 || arc.height == 0 || extent == 0 {
+			return None;
+		}
+
+		// Going counter-clockwise
+		let (angle, extent) = if extent > 0 {
+			(arc.angle1 as i32, extent)
+		} else {
+			(arc.angle1 as i32 + extent, -extent)
+		};
+		let start = direction(angle);
+		let end = direction(angle + extent);
+		let region = match mode {
+			_ if extent == 360 * 64 => Region::Full,
+			ArcMode::PieSlice => Region::PieSlice {
+				start,
+				end,
+				reflex: extent > 180 * 64,
+			},
+			ArcMode::Chord => Region::Chord {
+				start,
+				end,
+			},
+		};
+
+		Some(Self {
+			cx: 2 * arc.x as i64 + arc.width as i64,
+			cy: 2 * arc.y as i64 + arc.height as i64,
+			w: arc.width as _,
+			h: arc.height as _,
+
+			region,
+		})
+	}
+
+	/// Tells whether the pixel at the given position is inside of the ellipse.
+	fn in_ellipse(&self, x: i32, y: i32) -> bool {
+		let dx = 2 * x as i64 - self.cx;
+		let dy = 2 * y as i64 - self.cy;
+		let (w, h) = (self.w as i128, self.h as i128);
+		let dist = (dx as i128 * h).pow(2) + (dy as i128 * w).pow(2);
+
+		match dist.cmp(&(w * h).pow(2)) {
+			Ordering::Less => true,
+			Ordering::Greater => false,
+			// On the boundary, the inside is on the right on the left half, and below at the top
+			Ordering::Equal => dx < 0 || (dx == 0 && dy < 0),
+		}
+	}
+
+	/// Tells whether the pixel at the given position is inside of the region, ignoring the
+	/// boundary of the ellipse.
+	fn in_region(&self, x: i32, y: i32) -> bool {
+		// The position, scaled so that the ellipse is a circle of radius `w * h`
+		let q = (
+			((2 * x as i64 - self.cx) * self.h) as f64,
+			((self.cy - 2 * y as i64) * self.w) as f64,
+		);
+
+		match &self.region {
+			Region::Full => true,
+
+			Region::PieSlice {
+				start,
+				end,
+				reflex,
+			} => {
+				let after_start = side(*start, q) > 0.;
+				let before_end = side(*end, q) < 0.;
+				if *reflex {
+					after_start || before_end
+				} else {
+					after_start && before_end
+				}
+			}
+
+			// The chord is on the left of the arc, going counter-clockwise
+			Region::Chord {
+				start,
+				end,
+			} => {
+				let r = (self.w * self.h) as f64;
+				let d = (end.0 - start.0, end.1 - start.1);
+				side(d, (q.0 - start.0 * r, q.1 - start.1 * r)) < 0.
+			}
+		}
+	}
+
+	/// Returns the span of pixels inside of the ellipse on the scanline `y`, `x1` being
+	/// exclusive.
+	fn span(&self, y: i32) -> (i32, i32) {
+		// Estimation, then adjusted using the exact test
+		let dy = (2 * y as i64 - self.cy) as f64 / self.h as f64;
+		let dx = self.w as f64 * (1. - dy * dy).max(0.).sqrt();
+		let mut x0 = ((self.cx as f64 - dx) / 2.).ceil() as i32;
+		let mut x1 = ((self.cx as f64 + dx) / 2.).ceil() as i32;
+
+		while self.in_ellipse(x0 - 1, y) {
+			x0 -= 1;
+		}
+		while 2 * (x0 as i64) < self.cx && !self.in_ellipse(x0, y) {
+			x0 += 1;
+		}
+		while self.in_ellipse(x1, y) {
+			x1 += 1;
+		}
+		while x1 > x0 && !self.in_ellipse(x1 - 1, y) {
+			x1 -= 1;
+		}
+		(x0, x1)
+	}
+}
+
+/// Fills the given arcs.
+///
+/// The arc mode of the graphics context tells whether the region is closed by a line between
+/// both ends of the arc (chord), or by two lines going through the center (pie slice).
+pub fn poly_fill_arc(painter: &mut Painter, arcs: &[Arc]) {
+	let gc = painter.get_gc();
+	let bounds = painter.get_bounds();
+
+	for arc in arcs {
+		let Some(shape) = FillArc::new(arc, &gc.arc_mode) else {
+			continue;
+		};
+
+		let y0 = max(arc.y as i32, bounds.y0);
+		let y1 = min(arc.y as i32 + arc.height as i32 + 1, bounds.y1);
+		for y in y0..y1 {
+			let (x0, x1) = shape.span(y);
+			let x0 = max(x0, bounds.x0);
+			let x1 = min(x1, bounds.x1);
+
+			if matches!(shape.region, Region::Full) {
+				painter.span(y, x0, x1, Paint::Foreground);
+				continue;
+			}
+			for x in x0..x1 {
+				if shape.in_region(x, y) {
+					painter.plot(x, y, Paint::Foreground);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::raster::tests::render;
+
+	/// Returns the arc inscribed in the given rectangle, between the given angles in degrees.
+	fn arc(x: i16, y: i16, width: u16, height: u16, angle1: i16, angle2: i16) -> Arc {
+		Arc {
+			x,
+			y,
+			width,
+			height,
+
+			angle1: angle1 * 64,
+			angle2: angle2 * 64,
+		}
+	}
+
+	/// A filled circle.
+	#[test]
+	fn fill_circle() {
+		let pixels = render(
+			10,
+			10,
+			|_| {},
+			|p| poly_fill_arc(p, &[arc(0, 0, 9, 9, 0, 360)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"..........",
+				"..######..",
+				".########.",
+				".########.",
+				".########.",
+				".########.",
+				".########.",
+				".########.",
+				"..######..",
+				"..........",
+			]
+		);
+	}
+
+	/// A filled ellipse.
+	#[test]
+	fn fill_ellipse() {
+		let pixels = render(
+			14,
+			8,
+			|_| {},
+			|p| poly_fill_arc(p, &[arc(0, 0, 13, 7, 0, 360)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"..............",
+				"..##########..",
+				".############.",
+				".############.",
+				".############.",
+				".############.",
+				"..##########..",
+				"..............",
+			]
+		);
+	}
+
+	/// A quarter of circle filled as a pie slice.
+	#[test]
+	fn fill_pie_slice() {
+		let pixels = render(
+			10,
+			10,
+			|_| {},
+			|p| poly_fill_arc(p, &[arc(0, 0, 9, 9, 0, 90)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"..........",
+				".....###..",
+				".....####.",
+				".....####.",
+				".....####.",
+				"..........",
+				"..........",
+				"..........",
+				"..........",
+				"..........",
+			]
+		);
+	}
+
+	/// A half circle filled as a chord.
+	#[test]
+	fn fill_chord() {
+		let pixels = render(
+			10,
+			10,
+			|gc| gc.arc_mode = ArcMode::Chord,
+			|p| poly_fill_arc(p, &[arc(0, 0, 9, 9, 0, 180)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"..........",
+				"..######..",
+				".########.",
+				".########.",
+				".########.",
+				"..........",
+				"..........",
+				"..........",
+				"..........",
+				"..........",
+			]
+		);
+	}
+
+	/// Pixels whose center is on the edge of a circle are drawn only if the inside is on their
+	/// right, or below them at the top and bottom.
+	#[test]
+	fn fill_boundary() {
+		let pixels = render(
+			5,
+			5,
+			|_| {},
+			|p| poly_fill_arc(p, &[arc(0, 0, 4, 4, 0, 360)]),
+		);
+		assert_eq!(pixels, ["..#..", ".###.", "####.", ".###.", "....."]);
+	}
+
+	/// Pixels on the lines going to the center follow the same rule as pixels on the edge.
+	#[test]
+	fn fill_pie_slice_boundary() {
+		let pixels = render(
+			11,
+			11,
+			|_| {},
+			|p| poly_fill_arc(p, &[arc(0, 0, 10, 10, 0, 90)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				".....#.....",
+				".....###...",
+				".....####..",
+				".....#####.",
+				".....#####.",
+				"...........",
+				"...........",
+				"...........",
+				"...........",
+				"...........",
+				"...........",
+			]
+		);
+	}
+
+	/// On an ellipse, angles are skewed: 45 degrees is the point where the ellipse crosses its
+	/// bounding rectangle's diagonal, not where the 45 degrees ray does.
+	#[test]
+	fn fill_skewed_pie_slice() {
+		let pixels = render(
+			17,
+			9,
+			|_| {},
+			|p| poly_fill_arc(p, &[arc(0, 0, 16, 8, 0, 45)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				".................",
+				".................",
+				"............###..",
+				"..........######.",
+				".................",
+				".................",
+				".................",
+				".................",
+				".................",
+			]
+		);
+	}
+
+	/// Arcs larger than the drawable are clipped to it.
+	#[test]
+	fn fill_huge_arc() {
+		let pixels = render(
+			4,
+			3,
+			|_| {},
+			|p| poly_fill_arc(p, &[arc(-30000, -30000, 65535, 65535, 0, 360)]),
+		);
+		assert_eq!(pixels, ["####", "####", "####"]);
+	}
+
+	/// The outline of a circle, drawn as a wide line.
+	#[test]
+	fn wide_circle() {
+		let pixels = render(
+			12,
+			12,
+			|gc| gc.line_width = 2,
+			|p| poly_arc(p, &[arc(1, 1, 9, 9, 0, 360)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"............",
+				"...######...",
+				"..########..",
+				".###....###.",
+				".##......##.",
+				".##......##.",
+				".##......##.",
+				".##......##.",
+				".###....###.",
+				"..########..",
+				"...######...",
+				"............",
+			]
+		);
+	}
+
+	/// The outline of a half circle, drawn as a wide line with butt caps.
+	#[test]
+	fn wide_half_circle() {
+		let pixels = render(
+			12,
+			12,
+			|gc| gc.line_width = 2,
+			|p| poly_arc(p, &[arc(1, 1, 9, 9, 0, 180)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"............",
+				"...######...",
+				"..########..",
+				".###....###.",
+				".##......##.",
+				".##......##.",
+				"............",
+				"............",
+				"............",
+				"............",
+				"............",
+				"............",
+			]
+		);
+	}
+}
@@ -0,0 +1,240 @@
+//! Copy of areas between drawables.
+
+use super::Painter;
+use super::Sources;
+use super::Target;
+use crate::ctx::client::Client;
+use crate::ctx::image;
+use crate::ctx::region::Rect;
+use crate::ctx::region::Region;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::GraphicsExposure;
+use crate::protocol::event::NoExposure;
+use crate::protocol::request;
+use crate::protocol::Rectangle;
+use std::num::NonZeroU32;
+
+/// The parameters of a copy between two drawables.
+pub struct CopyArgs {
+	/// The ID of the source drawable.
+	pub src: u32,
+	/// The ID of the destination drawable.
+	pub dst: u32,
+	/// The graphics context's ID.
+	pub gc: u32,
+	/// The X position of the area in the source drawable.
+	pub src_x: i16,
+	/// The Y position of the area in the source drawable.
+	pub src_y: i16,
+	/// The X position of the area in the destination drawable.
+	pub dst_x: i16,
+	/// The Y position of the area in the destination drawable.
+	pub dst_y: i16,
+	/// The width of the area.
+	pub width: u16,
+	/// The height of the area.
+	pub height: u16,
+}
+
+/// Returns the parts of the given target whose content is available, in the coordinates of the
+/// drawable being drawn on.
+fn get_available(target: &Target) -> Region {
+	let Some(image) = target.drawable.get_image() else {
+		return Region::default();
+	};
+	let image_rect = Rect::new(
+		target.x,
+		target.y,
+		target.x + image.get_width() as i32,
+		target.y + image.get_height() as i32,
+	);
+	let rect = target.bounds.intersect(&image_rect);
+
+	match &target.clip {
+		Some(clip) => {
+			let mut available = clip.clone();
+			available.intersect_rect(rect);
+			available
+		}
+		None => Region::from(rect),
+	}
+}
+
+/// Reads the pixels of the given area of the given targets, in order.
+///
+/// The function returns the pixels row by row, along with the region of the area that could be
+/// read. Pixels outside of every target, or obscured, are None.
+fn read(targets: &[Target], area: &Rect) -> (Vec<Option<u32>>, Region) {
+	let width = area.x1 - area.x0;
+	let height = area.y1 - area.y0;
+	let mut pixels = vec![None; width as usize * height as usize];
+	let mut read = Region::default();
+
+	for target in targets {
+		let Some(image) = target.drawable.get_image() else {
+			continue;
+		};
+		let mut available = get_available(target);
+		available.intersect_rect(*area);
+
+		for r in available.get_rects() {
+			for py in r.y0..r.y1 {
+				for px in r.x0..r.x1 {
+					let i = (py - area.y0) * width + (px - area.x0);
+					let pixel = image.get_pixel((px - target.x) as _, (py - target.y) as _);
+					pixels[i as usize] = Some(pixel);
+				}
+			}
+		}
+		read.union(&available);
+	}
+
+	(pixels, read)
+}
+
+/// Copies an area from a drawable to another, as a `CopyArea` or `CopyPlane` request.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `client` is the client owning the graphics context.
+/// - `args` are the parameters of the copy.
+/// - `bit_plane` is the plane to copy for `CopyPlane`. Source pixels having this bit set are drawn
+///   with the foreground, others are drawn with the background. If None, pixels are copied as they
+///   are.
+///
+/// Regions of the source that are outside of the source drawable, or obscured, are not copied. If
+/// the destination is a window, the corresponding regions are filled with its background. If the
+/// GC has `graphics_exposures` set, these regions are reported to the client with
+/// `GraphicsExposure` events, or a `NoExposure` event if there is none.
+pub fn copy(
+	ctx: &mut Context,
+	client: &Client,
+	args: &CopyArgs,
+	bit_plane: Option<u32>,
+) -> Result<(), Error> {
+	let src_id = NonZeroU32::new(args.src).ok_or(Error::Drawable(args.src))?;
+	let dst_id = NonZeroU32::new(args.dst).ok_or(Error::Drawable(args.dst))?;
+	let gc = client.get_gc(args.gc).ok_or(Error::GContext(args.gc))?;
+
+	let src_x = args.src_x as i32;
+	let src_y = args.src_y as i32;
+	let dst_x = args.dst_x as i32;
+	let dst_y = args.dst_y as i32;
+	let width = args.width as i32;
+	let height = args.height as i32;
+	let src_area = Rect::new(src_x, src_y, src_x + width, src_y + height);
+
+	let exposed = {
+		let src = ctx.get_drawable(src_id).ok_or(Error::Drawable(args.src))?;
+		let dst = ctx.get_drawable(dst_id).ok_or(Error::Drawable(args.dst))?;
+		if src.get_root() != dst.get_root() || !gc.is_compatible(dst) {
+			return Err(Error::Match);
+		}
+		match bit_plane {
+			None if src.get_depth() != dst.get_depth() => return Err(Error::Match),
+			Some(plane)
+				if plane.count_ones() != 1 || plane & !image::depth_mask(src.get_depth()) != 0 =>
+			{
+				return Err(Error::Value(plane))
+			}
+			_ => {}
+		}
+		// Drawables without storage are input-only windows
+		if src.get_image().is_none() || dst.get_image().is_none() {
+			return Err(Error::Match);
+		}
+
+		// The source is read first since it may overlap the destination
+		let src_targets = Target::list(ctx, src_id, src, &gc.subwindow_mode);
+		let (pixels, read) = read(&src_targets, &src_area);
+
+		let sources = Sources::from_gc(gc, dst_id);
+		let mut damage = Rect::default();
+		for target in Target::list(ctx, dst_id, dst, &gc.subwindow_mode) {
+			let Some(mut image) = target.drawable.get_image_mut() else {
+				continue;
+			};
+			let mut painter = Painter::for_target(&mut image, &target, gc, &sources);
+
+			for y in 0..height {
+				for x in 0..width {
+					let Some(pixel) = pixels[(y * width + x) as usize] else {
+						continue;
+					};
+					let pixel = match bit_plane {
+						Some(plane) if pixel & plane != 0 => gc.foreground,
+						Some(_) => gc.background,
+						None => pixel,
+					};
+					painter.put(dst_x + x, dst_y + y, pixel);
+				}
+			}
+			damage = damage.bounding(&painter.get_damage());
+		}
+		ctx.damage(dst_id, damage);
+
+		// Regions of the destination whose source is not available
+		let mut exposed = Region::from(src_area);
+		exposed.subtract(&read);
+		exposed.translate(dst_x - src_x, dst_y - src_y);
+		let dst_rect = dst.get_rectangle();
+		exposed.intersect_rect(Rect::new(0, 0, dst_rect.width as _, dst_rect.height as _));
+		exposed
+			.get_rects()
+			.iter()
+			.map(Rectangle::from)
+			.collect::<Vec<_>>()
+	};
+
+	if let Some(win) = ctx.get_window(dst_id) {
+		for rect in &exposed {
+			win.clear(ctx, rect);
+		}
+	}
+
+	if gc.graphics_exposures {
+		let major_opcode = match bit_plane {
+			Some(_) => request::COPY_PLANE,
+			None => request::COPY_AREA,
+		};
+
+		if exposed.is_empty() {
+			let ev = NoExposure {
+				code: event::NO_EXPOSURE,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				drawable: args.dst,
+				minor_opcode: 0,
+				major_opcode,
+
+				_padding1: [0; 21],
+			};
+			ctx.send_event(client.get_id(), &ev);
+		}
+
+		for (i, rect) in exposed.iter().enumerate() {
+			let ev = GraphicsExposure {
+				code: event::GRAPHICS_EXPOSURE,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				drawable: args.dst,
+				x: rect.x as _,
+				y: rect.y as _,
+				width: rect.width,
+				height: rect.height,
+				minor_opcode: 0,
+				count: (exposed.len() - i - 1) as _,
+				major_opcode,
+
+				_padding1: [0; 11],
+			};
+			ctx.send_event(client.get_id(), &ev);
+		}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,690 @@
+//! Drawing of lines.
+//!
+//! Lines with a width of zero are thin lines, drawn one pixel wide. Other lines are wide lines,
+//! drawn as filled shapes in which each pixel is drawn only once.
+
+use super::polygon;
+use super::Coverage;
+use super::Paint;
+use super::Painter;
+use crate::ctx::gc::CapStyle;
+use crate::ctx::gc::FillRule;
+use crate::ctx::gc::JoinStyle;
+use crate::ctx::gc::LineStyle;
+use crate::ctx::gc::GC;
+use std::cmp::max;
+use std::cmp::min;
+
+/// The angle in degrees under which miter joins are drawn as bevel joins.
+const MITER_LIMIT: f64 = 11.;
+
+/// A point with floating coordinates.
+pub type FPoint = (f64, f64);
+
+/// Tracks the position in the dash pattern of a graphics context along a line.
+pub struct Dasher {
+	/// The line style.
+	style: LineStyle,
+	/// The dash pattern. A pattern with an odd number of elements is repeated twice.
+	dashes: Vec<u8>,
+
+	/// The index of the current dash.
+	index: usize,
+	/// The length remaining in the current dash.
+	remaining: f64,
+}
+
+impl Dasher {
+	/// Creates a new instance at the dash offset of the given graphics context.
+	pub fn new(gc: &GC) -> Self {
+		let mut dashes = gc.dashes.clone();
+		if !dashes.len().is_multiple_of(2) {
+			dashes.extend_from_within(..);
+		}
+		let total: u32 = dashes.iter().map(|d| *d as u32).sum();
+
+		let mut dasher = Self {
+			style: gc.line_style.clone(),
+			remaining: dashes[0] as f64,
+			dashes,
+
+			index: 0,
+		};
+		dasher.advance((gc.dash_offset as u32 % total) as f64);
+		dasher
+	}
+
+	/// Returns the paint of the current dash. If nothing is to be drawn, the function returns
+	/// None.
+	fn paint(&self) -> Option<Paint> {
+		match self.style {
+			LineStyle::Solid => Some(Paint::Foreground),
+			_ if self.index.is_multiple_of(2) => Some(Paint::Foreground),
+			LineStyle::OnOffDash => None,
+			LineStyle::DoubleDash => Some(Paint::Background),
+		}
+	}
+
+	/// Returns the length remaining before the end of the current dash.
+	fn remaining(&self) -> f64 {
+		match self.style {
+			LineStyle::Solid => f64::INFINITY,
+			_ => self.remaining,
+		}
+	}
+
+	/// Advances in the pattern by the given length.
+	fn advance(&mut self, mut len: f64) {
+		if matches!(self.style, LineStyle::Solid) {
+			return;
+		}
+
+		while len >= self.remaining {
+			len -= self.remaining;
+			self.index = (self.index + 1) % self.dashes.len();
+			self.remaining = self.dashes[self.index] as f64;
+		}
+		self.remaining -= len;
+	}
+}
+
+/// Returns the pixels of the thin line going from `a` to `b`, in order, including both ends.
+fn bresenham(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+	// Swapping coordinates so that the X axis is the major axis
+	let x_major = (b.0 - a.0).abs() >= (b.1 - a.1).abs();
+	let swap = |p: (i32, i32)| if x_major { p } else { (p.1, p.0) };
+	let (a, b) = (swap(a), swap(b));
+
+	// Lines are always computed in the same direction so that a line from `a` to `b` covers the
+	// same pixels as a line from `b` to `a`
+	let reverse = b.0 < a.0;
+	let (start, end) = if reverse { (b, a) } else { (a, b) };
+
+	let dx = end.0 - start.0;
+	let dy = (end.1 - start.1).abs();
+	let step = (end.1 - start.1).signum();
+
+	let mut pixels = Vec::with_capacity(dx as usize + 1);
+	let mut err = 2 * dy - dx;
+	let mut y = start.1;
+	for x in start.0..=end.0 {
+		pixels.push(swap((x, y)));
+		if err > 0 {
+			y += step;
+			err -= 2 * dx;
+		}
+		err += 2 * dy;
+	}
+
+	if reverse {
+		pixels.reverse();
+	}
+	pixels
+}
+
+/// Draws thin lines connecting the given points.
+pub fn thin_lines(painter: &mut Painter, points: &[(i32, i32)], dasher: &mut Dasher) {
+	let closed = points.len() > 2 && points.first() == points.last();
+	let not_last = matches!(painter.get_gc().cap_style, CapStyle::NotLast);
+
+	let count = points.len().saturating_sub(1);
+	for (i, line) in points.windows(2).enumerate() {
+		let mut pixels = bresenham(line[0], line[1]);
+		// Points shared by two lines are drawn only once
+		if i + 1 < count || closed || not_last {
+			pixels.pop();
+		}
+
+		for (x, y) in pixels {
+			if let Some(paint) = dasher.paint() {
+				painter.plot(x, y, paint);
+			}
+			dasher.advance(1.);
+		}
+	}
+}
+
+/// Adds a disc to the given coverage.
+///
+/// Arguments:
+/// - `center` is the center of the disc.
+/// - `radius` is the radius of the disc.
+fn disc(cov: &mut Coverage, center: FPoint, radius: f64) {
+	let bounds = cov.get_bounds();
+	let y0 = max((center.1 - radius).ceil() as i32, bounds.y0);
+	let y1 = min((center.1 + radius).ceil() as i32, bounds.y1);
+	for y in y0..y1 {
+		let dy = y as f64 - center.1;
+		let sq = radius * radius - dy * dy;
+		if sq < 0. {
+			continue;
+		}
+
+		let dx = sq.sqrt();
+		cov.add_span(
+			y,
+			(center.0 - dx).ceil() as i32,
+			(center.0 + dx).ceil() as i32,
+		);
+	}
+}
+
+/// Adds a polygon to the given coverage.
+fn polygon(cov: &mut Coverage, points: &[FPoint]) {
+	let bounds = cov.get_bounds();
+	polygon::scan(points, &FillRule::Winding, &bounds, |y, x0, x1| {
+		cov.add_span(y, x0, x1)
+	});
+}
+
+/// Adds a piece of wide line to the given coverage.
+///
+/// Arguments:
+/// - `a` and `b` are the ends of the piece.
+/// - `dir` is the unit vector of the line's direction.
+/// - `half` is half of the line's width.
+/// - `caps` are the styles of the caps at each end. If None, the end has no cap.
+fn piece(
+	cov: &mut Coverage,
+	a: FPoint,
+	b: FPoint,
+	dir: FPoint,
+	half: f64,
+	caps: [Option<&CapStyle>; 2],
+) {
+	let ext = caps.map(|c| match c {
+		Some(CapStyle::Projecting) => half,
+		_ => 0.,
+	});
+	let a = (a.0 - dir.0 * ext[0], a.1 - dir.1 * ext[0]);
+	let b = (b.0 + dir.0 * ext[1], b.1 + dir.1 * ext[1]);
+
+	let n = (-dir.1 * half, dir.0 * half);
+	polygon(
+		cov,
+		&[
+			(a.0 + n.0, a.1 + n.1),
+			(b.0 + n.0, b.1 + n.1),
+			(b.0 - n.0, b.1 - n.1),
+			(a.0 - n.0, a.1 - n.1),
+		],
+	);
+
+	for (p, cap) in [a, b].into_iter().zip(caps) {
+		if let Some(CapStyle::Round) = cap {
+			disc(cov, p, half);
+		}
+	}
+}
+
+/// Adds the join between two wide lines to the given coverage.
+///
+/// Arguments:
+/// - `p` is the point at which lines are joined.
+/// - `d0` and `d1` are the unit vectors of the direction of the first and second lines.
+/// - `half` is half of the line's width.
+/// - `style` is the join style.
+fn join(cov: &mut Coverage, p: FPoint, d0: FPoint, d1: FPoint, half: f64, style: &JoinStyle) {
+	// The outer side of the corner is opposite to the direction of the turn
+	let cross = d0.0 * d1.1 - d0.1 * d1.0;
+	let side = if cross > 0. { -half } else { half };
+	let n0 = (-d0.1, d0.0);
+	let n1 = (-d1.1, d1.0);
+	let o0 = (p.0 + n0.0 * side, p.1 + n0.1 * side);
+	let o1 = (p.0 + n1.0 * side, p.1 + n1.1 * side);
+
+	match style {
+		JoinStyle::Round => disc(cov, p, half),
+
+		JoinStyle::Miter => {
+			let cos = -(d0.0 * d1.0 + d0.1 * d1.1);
+			let angle = cos.clamp(-1., 1.).acos().to_degrees();
+			if angle < MITER_LIMIT {
+				polygon(cov, &[p, o0, o1]);
+			} else {
+				let k = side / (1. + n0.0 * n1.0 + n0.1 * n1.1);
+				let tip = (p.0 + (n0.0 + n1.0) * k, p.1 + (n0.1 + n1.1) * k);
+				polygon(cov, &[p, o0, tip, o1]);
+			}
+		}
+
+		JoinStyle::Bevel => polygon(cov, &[p, o0, o1]),
+	}
+}
+
+/// Draws wide lines connecting the given points.
+///
+/// `path` is the list of points, each one with the style of the join drawn at this point.
+pub fn wide_lines(painter: &mut Painter, path: &[(FPoint, &JoinStyle)], dasher: &mut Dasher) {
+	let gc = painter.get_gc();
+	let half = gc.line_width as f64 / 2.;
+
+	let mut path = path.to_vec();
+	path.dedup_by(|a, b| a.0 == b.0);
+	let pts: Vec<FPoint> = path.iter().map(|(p, _)| *p).collect();
+	if pts.is_empty() {
+		return;
+	}
+
+	let margin = gc.line_width as f64 + 1.;
+	let x0 = pts.iter().map(|p| p.0).fold(f64::INFINITY, f64::min) - margin;
+	let y0 = pts.iter().map(|p| p.1).fold(f64::INFINITY, f64::min) - margin;
+	let x1 = pts.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max) + margin;
+	let y1 = pts.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max) + margin;
+	let (x0, y0) = (x0.floor() as i32, y0.floor() as i32);
+	let (x1, y1) = (x1.ceil() as i32, y1.ceil() as i32);
+	// Coverages for each paint, indexed by `Paint`
+	let mut covs = [
+		Coverage::for_painter(painter, x0, y0, x1, y1),
+		Coverage::for_painter(painter, x0, y0, x1, y1),
+	];
+
+	// Caps at the ends of the line and between dashes
+	let line_cap = match gc.cap_style {
+		CapStyle::NotLast => &CapStyle::Butt,
+		ref c => c,
+	};
+	let dash_cap = match gc.line_style {
+		LineStyle::DoubleDash => &CapStyle::Butt,
+		_ => line_cap,
+	};
+
+	if pts.len() == 1 {
+		// A line of length zero only has caps
+		let p = pts[0];
+		if let Some(paint) = dasher.paint() {
+			match line_cap {
+				CapStyle::Round => disc(&mut covs[paint as usize], p, half),
+				CapStyle::Projecting => polygon(
+					&mut covs[paint as usize],
+					&[
+						(p.0 - half, p.1 - half),
+						(p.0 + half, p.1 - half),
+						(p.0 + half, p.1 + half),
+						(p.0 - half, p.1 + half),
+					],
+				),
+				_ => {}
+			}
+		}
+	} else {
+		let closed = pts.len() > 2 && pts.first() == pts.last();
+		let count = pts.len() - 1;
+		let dir = |i: usize| {
+			let (a, b) = (pts[i], pts[i + 1]);
+			let len = (b.0 - a.0).hypot(b.1 - a.1);
+			((b.0 - a.0) / len, (b.1 - a.1) / len, len)
+		};
+
+		for i in 0..count {
+			let a = pts[i];
+			let (dx, dy, len) = dir(i);
+			let first = i == 0 && !closed;
+			let last = i + 1 == count && !closed;
+
+			let mut t = 0.;
+			while t < len {
+				let end = len - t <= dasher.remaining();
+				let t1 = if end { len } else { t + dasher.remaining() };
+
+				if let Some(paint) = dasher.paint() {
+					let start_cap = match t == 0. {
+						true if first => Some(line_cap),
+						true => None,
+						false => Some(dash_cap),
+					};
+					let end_cap = match end {
+						true if last => Some(line_cap),
+						true => None,
+						false => Some(dash_cap),
+					};
+
+					let pa = (a.0 + dx * t, a.1 + dy * t);
+					let pb = (a.0 + dx * t1, a.1 + dy * t1);
+					piece(
+						&mut covs[paint as usize],
+						pa,
+						pb,
+						(dx, dy),
+						half,
+						[start_cap, end_cap],
+					);
+				}
+
+				dasher.advance(t1 - t);
+				t = t1;
+			}
+
+			// Joining with the next line
+			if i + 1 < count || closed {
+				let next = (i + 1) % count;
+				let (ndx, ndy, _) = dir(next);
+				if let Some(paint) = dasher.paint() {
+					join(
+						&mut covs[paint as usize],
+						pts[i + 1],
+						(dx, dy),
+						(ndx, ndy),
+						half,
+						path[i + 1].1,
+					);
+				}
+			}
+		}
+	}
+
+	covs[Paint::Background as usize].paint(painter, Paint::Background);
+	covs[Paint::Foreground as usize].paint(painter, Paint::Foreground);
+}
+
+/// Draws lines connecting the given points, continuing the dash pattern from one line to the
+/// next.
+///
+/// If the first and last points are the same, the path is closed and both ends are joined.
+pub fn poly_line(painter: &mut Painter, points: &[(i32, i32)]) {
+	let mut dasher = Dasher::new(painter.get_gc());
+	if painter.get_gc().line_width == 0 {
+		thin_lines(painter, points, &mut dasher);
+	} else {
+		let join = &painter.get_gc().join_style;
+		let path: Vec<_> = points
+			.iter()
+			.map(|(x, y)| ((*x as f64, *y as f64), join))
+			.collect();
+		wide_lines(painter, &path, &mut dasher);
+	}
+}
+
+/// Draws independent lines, each one being given as a pair of points.
+pub fn poly_segment(painter: &mut Painter, segments: &[[(i32, i32); 2]]) {
+	for s in segments {
+		poly_line(painter, s);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::raster::tests::render;
+
+	/// A thin line along the X axis, without ties between two pixels.
+	#[test]
+	fn thin_line() {
+		let pixels = render(9, 5, |_| {}, |p| poly_line(p, &[(0, 0), (7, 3)]));
+		assert_eq!(
+			pixels,
+			[
+				"##.......",
+				"..##.....",
+				"....##...",
+				"......##.",
+				".........",
+			]
+		);
+	}
+
+	/// A thin line along the Y axis.
+	#[test]
+	fn thin_line_steep() {
+		let pixels = render(5, 8, |_| {}, |p| poly_line(p, &[(1, 0), (3, 6)]));
+		assert_eq!(
+			pixels,
+			[".#...", ".#...", "..#..", "..#..", "..#..", "...#.", "...#.", "....."]
+		);
+	}
+
+	/// A closed path of thin lines, whose shared points are drawn once.
+	#[test]
+	fn thin_line_closed() {
+		let pixels = render(
+			8,
+			6,
+			|_| {},
+			|p| poly_line(p, &[(1, 1), (6, 1), (6, 4), (1, 1)]),
+		);
+		assert_eq!(
+			pixels,
+			["........", ".######.", "..##..#.", "....###.", "......#.", "........",]
+		);
+	}
+
+	/// A thin line whose last point is not drawn.
+	#[test]
+	fn thin_line_not_last() {
+		let pixels = render(
+			6,
+			1,
+			|gc| gc.cap_style = CapStyle::NotLast,
+			|p| poly_line(p, &[(0, 0), (4, 0)]),
+		);
+		assert_eq!(pixels, ["####.."]);
+	}
+
+	/// A wide line with butt caps, ending at its end points.
+	#[test]
+	fn wide_line_butt() {
+		let pixels = render(
+			10,
+			7,
+			|gc| gc.line_width = 3,
+			|p| poly_line(p, &[(1, 3), (8, 3)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"..........",
+				"..........",
+				".#######..",
+				".#######..",
+				".#######..",
+				"..........",
+				"..........",
+			]
+		);
+	}
+
+	/// A wide line with projecting caps, extending beyond its end points by half its width.
+	#[test]
+	fn wide_line_projecting() {
+		let pixels = render(
+			10,
+			7,
+			|gc| {
+				gc.line_width = 3;
+				gc.cap_style = CapStyle::Projecting;
+			},
+			|p| poly_line(p, &[(2, 3), (7, 3)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"..........",
+				"..........",
+				".########.",
+				".########.",
+				".########.",
+				"..........",
+				"..........",
+			]
+		);
+	}
+
+	/// A wide line with round caps.
+	#[test]
+	fn wide_line_round() {
+		let pixels = render(
+			12,
+			9,
+			|gc| {
+				gc.line_width = 5;
+				gc.cap_style = CapStyle::Round;
+			},
+			|p| poly_line(p, &[(3, 4), (8, 4)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"............",
+				"............",
+				"..########..",
+				".##########.",
+				".##########.",
+				".##########.",
+				"..########..",
+				"............",
+				"............",
+			]
+		);
+	}
+
+	/// Wide lines joined with a miter.
+	#[test]
+	fn wide_line_miter() {
+		let pixels = render(
+			10,
+			10,
+			|gc| gc.line_width = 3,
+			|p| poly_line(p, &[(1, 2), (7, 2), (7, 8)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"..........",
+				".########.",
+				".########.",
+				".########.",
+				"......###.",
+				"......###.",
+				"......###.",
+				"......###.",
+				"..........",
+				"..........",
+			]
+		);
+	}
+
+	/// Wide lines joined with a bevel.
+	#[test]
+	fn wide_line_bevel() {
+		let pixels = render(
+			10,
+			10,
+			|gc| {
+				gc.line_width = 3;
+				gc.join_style = JoinStyle::Bevel;
+			},
+			|p| poly_line(p, &[(1, 2), (7, 2), (7, 8)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"..........",
+				".#######..",
+				".########.",
+				".########.",
+				"......###.",
+				"......###.",
+				"......###.",
+				"......###.",
+				"..........",
+				"..........",
+			]
+		);
+	}
+
+	/// A thin dashed line.
+	#[test]
+	fn thin_dashes() {
+		let pixels = render(
+			16,
+			1,
+			|gc| {
+				gc.line_style = LineStyle::OnOffDash;
+				gc.dashes = vec![3, 2];
+			},
+			|p| poly_line(p, &[(0, 0), (15, 0)]),
+		);
+		assert_eq!(pixels, ["###..###..###..#"]);
+	}
+
+	/// A thin dashed line with an odd number of dashes, which is repeated twice, and an offset.
+	#[test]
+	fn thin_dashes_odd_offset() {
+		let pixels = render(
+			16,
+			1,
+			|gc| {
+				gc.line_style = LineStyle::OnOffDash;
+				gc.dashes = vec![3];
+				gc.dash_offset = 1;
+			},
+			|p| poly_line(p, &[(0, 0), (15, 0)]),
+		);
+		assert_eq!(pixels, ["##...###...###.."]);
+	}
+
+	/// A thin double-dashed line, whose odd dashes are drawn with the background.
+	#[test]
+	fn thin_double_dashes() {
+		let pixels = render(
+			16,
+			1,
+			|gc| {
+				gc.line_style = LineStyle::DoubleDash;
+				gc.dashes = vec![3, 2];
+			},
+			|p| poly_line(p, &[(0, 0), (15, 0)]),
+		);
+		assert_eq!(pixels, ["###--###--###--#"]);
+	}
+
+	/// A wide dashed line.
+	#[test]
+	fn wide_dashes() {
+		let pixels = render(
+			16,
+			5,
+			|gc| {
+				gc.line_width = 3;
+				gc.line_style = LineStyle::OnOffDash;
+				gc.dashes = vec![4, 2];
+			},
+			|p| poly_line(p, &[(0, 2), (16, 2)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"................",
+				"####..####..####",
+				"####..####..####",
+				"####..####..####",
+				"................",
+			]
+		);
+	}
+
+	/// A wide double-dashed line.
+	#[test]
+	fn wide_double_dashes() {
+		let pixels = render(
+			16,
+			5,
+			|gc| {
+				gc.line_width = 3;
+				gc.line_style = LineStyle::DoubleDash;
+				gc.dashes = vec![4, 2];
+			},
+			|p| poly_line(p, &[(0, 2), (16, 2)]),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"................",
+				"####--####--####",
+				"####--####--####",
+				"####--####--####",
+				"................",
+			]
+		);
+	}
+}
@@ -0,0 +1,619 @@
+//! The rasterizer draws graphics primitives into the storage of drawables, according to a
+//! graphics context.
+//!
+//! Pixel centers are located at integer coordinates. A pixel is drawn if its center is inside of
+//! the shape being drawn. If the center is exactly on the boundary of the shape, the pixel is
+//! drawn only if the interior of the shape is immediately to its right, or immediately below it
+//! if the boundary is horizontal.
+
+pub mod arc;
+pub mod copy;
+pub mod line;
+pub mod polygon;
+pub mod rect;
+pub mod text;
+
+use crate::ctx::client::Client;
+use crate::ctx::gc::Clip;
+use crate::ctx::gc::FillStyle;
+use crate::ctx::gc::Function;
+use crate::ctx::gc::SubWindowMode;
+use crate::ctx::gc::GC;
+use crate::ctx::image::Image;
+use crate::ctx::pixmap::PixmapRef;
+use crate::ctx::region::Rect;
+use crate::ctx::region::Region;
+use crate::ctx::window::Window;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use std::cell::Ref;
+use std::cmp::max;
+use std::cmp::min;
+use std::num::NonZeroU32;
+use std::ops::Deref;
+
+/// Applies the raster operation `function` to the source pixel `src` and the destination pixel
+/// `dst`. The function returns the resulting pixel.
+pub fn apply_function(function: &Function, src: u32, dst: u32) -> u32 {
+	match function {
+		Function::Clear => 0,
+		Function::And => src & dst,
+		Function::AndReverse => src & !dst,
+		Function::Copy => src,
+		Function::AndInverted => !src & dst,
+		Function::NoOp => dst,
+		Function::Xor => src ^ dst,
+		Function::Or => src | dst,
+		Function::Nor => !(src | dst),
+		Function::Equiv => !src ^ dst,
+		Function::Invert => !dst,
+		Function::OrReverse => src | !dst,
+		Function::CopyInverted => !src,
+		Function::OrInverted => !src | dst,
+		Function::Nand => !(src & dst),
+		Function::Set => !0,
+	}
+}
+
+/// The kind of pixels being drawn, which determines their source according to the fill style.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Paint {
+	/// Filled shapes and even dashes of lines.
+	Foreground,
+	/// Odd dashes of double-dashed lines.
+	Background,
+}
+
+/// The image of a pixmap referenced by a graphics context.
+pub enum Source<'a> {
+	/// The pixmap's storage, borrowed for the duration of the drawing.
+	Borrowed(Ref<'a, Image>),
+	/// A copy of the pixmap's storage, used when the pixmap is also the drawable being drawn on.
+	Copied(Image),
+}
+
+impl Deref for Source<'_> {
+	type Target = Image;
+
+	fn deref(&self) -> &Image {
+		match self {
+			Self::Borrowed(image) => image,
+			Self::Copied(image) => image,
+		}
+	}
+}
+
+/// The images of the pixmaps referenced by a graphics context.
+#[derive(Default)]
+pub struct Sources<'a> {
+	/// The clip mask.
+	pub clip_mask: Option<Source<'a>>,
+	/// The tile.
+	pub tile: Option<Source<'a>>,
+	/// The stipple.
+	pub stipple: Option<Source<'a>>,
+}
+
+impl<'a> Sources<'a> {
+	/// Returns the images of the pixmaps referenced by the given graphics context, to draw on the
+	/// drawable with ID `dst`.
+	///
+	/// Images are borrowed, except the image of a pixmap that is also the drawable being drawn on,
+	/// which is copied.
+	///
+	/// The function must be called before borrowing the storage of the drawable being drawn on.
+	pub fn from_gc(gc: &'a GC, dst: NonZeroU32) -> Self {
+		let get = |pixmap: Option<&'a PixmapRef>| {
+			let pixmap = pixmap?;
+			let image = pixmap.get_image()?;
+			if pixmap.get_id() == dst {
+				Some(Source::Copied(image.clone()))
+			} else {
+				Some(Source::Borrowed(image))
+			}
+		};
+
+		let clip_mask = match &gc.clip {
+			Clip::Mask(pixmap) => get(Some(pixmap)),
+			_ => None,
+		};
+		let tile = match gc.fill_style {
+			FillStyle::Tiled => get(gc.tile.as_ref()),
+			_ => None,
+		};
+		let stipple = match gc.fill_style {
+			FillStyle::Stippled | FillStyle::OpaqueStippled => get(gc.stipple.as_ref()),
+			_ => None,
+		};
+
+		Self {
+			clip_mask,
+			tile,
+			stipple,
+		}
+	}
+}
+
+/// A drawable that is affected when drawing on a drawable: either the drawable itself or, when
+/// drawing through subwindows, one of its viewable inferiors.
+pub struct Target<'c> {
+	/// The drawable.
+	pub drawable: &'c dyn Drawable,
+	/// The X position of the drawable's origin, in the coordinates of the drawable being drawn on.
+	pub x: i32,
+	/// The Y position of the drawable's origin, in the coordinates of the drawable being drawn on.
+	pub y: i32,
+	/// The area of the drawable that is not clipped by its ancestors, in the coordinates of the
+	/// drawable being drawn on.
+	pub bounds: Rect,
+	/// The visible parts of the drawable, in the coordinates of the drawable being drawn on. If
+	/// None, the drawable can be drawn on entirely.
+	pub clip: Option<Region>,
+}
+
+impl<'c> Target<'c> {
+	/// Returns the list of targets affected when drawing on the given drawable.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `id` is the ID of the drawable.
+	/// - `drawable` is the drawable.
+	/// - `mode` is the subwindow mode. If `IncludeInferiors`, the viewable `InputOutput` inferiors
+	///   of the same depth are included, after their parent.
+	pub fn list(
+		ctx: &'c Context,
+		id: NonZeroU32,
+		drawable: &'c dyn Drawable,
+		mode: &SubWindowMode,
+	) -> Vec<Self> {
+		let rect = drawable.get_rectangle();
+		let win = ctx.get_window(id);
+		let mut targets = vec![Self {
+			drawable,
+			x: 0,
+			y: 0,
+			bounds: Rect::new(0, 0, rect.width as _, rect.height as _),
+			clip: win.map(|win| Self::get_window_clip(win, 0, 0)),
+		}];
+		if let (SubWindowMode::IncludeInferiors, Some(win)) = (mode, win) {
+			let bounds = targets[0].bounds;
+			Self::add_inferiors(ctx, win, 0, 0, bounds, &mut targets);
+		}
+
+		targets
+	}
+
+	/// Returns the parts of the given window that can be drawn on, with the window's origin at
+	/// `(x, y)`.
+	fn get_window_clip(win: &Window, x: i32, y: i32) -> Region {
+		let mut clip = win.get_visibility().get_valid();
+		clip.translate(x, y);
+		clip
+	}
+
+	/// Adds the viewable `InputOutput` inferiors of the given window to `targets`, recursively.
+	///
+	/// `x`, `y` and `bounds` are the position and the unclipped area of the window, in the
+	/// coordinates of the drawable being drawn on.
+	fn add_inferiors(
+		ctx: &'c Context,
+		win: &'c Window,
+		x: i32,
+		y: i32,
+		bounds: Rect,
+		targets: &mut Vec<Self>,
+	) {
+		for id in win.get_children() {
+			let Some(child) = ctx.get_window(*id) else {
+				continue;
+			};
+			if !child.is_viewable() || !child.is_output() || child.get_depth() != win.get_depth() {
+				continue;
+			}
+
+			let rect = child.get_rectangle();
+			let border_width = child.get_border_width() as i32;
+			let cx = x + rect.x as i32 + border_width;
+			let cy = y + rect.y as i32 + border_width;
+			let child_bounds = bounds.intersect(&Rect::new(
+				cx,
+				cy,
+				cx + rect.width as i32,
+				cy + rect.height as i32,
+			));
+			if child_bounds.is_empty() {
+				continue;
+			}
+
+			targets.push(Self {
+				drawable: child,
+				x: cx,
+				y: cy,
+				bounds: child_bounds,
+				clip: Some(Self::get_window_clip(child, cx, cy)),
+			});
+			Self::add_inferiors(ctx, child, cx, cy, child_bounds, targets);
+		}
+	}
+}
+
+/// A painter draws pixels into an image, applying the fill style, function, plane mask and
+/// clipping of a graphics context.
+///
+/// Positions given to the painter are in the coordinates of the drawable being drawn on, which
+/// may be an ancestor of the drawable owning the image.
+pub struct Painter<'a> {
+	/// The image being drawn on.
+	image: &'a mut Image,
+	/// The X position of the image's origin.
+	x: i32,
+	/// The Y position of the image's origin.
+	y: i32,
+	/// The area that can be drawn on.
+	bounds: Rect,
+	/// The visible parts of the drawable. If None, the drawable can be drawn on entirely.
+	clip: Option<Region>,
+	/// The smallest rectangle containing every pixel drawn so far.
+	damage: Rect,
+	/// The graphics context.
+	gc: &'a GC,
+
+	/// The images of the pixmaps referenced by the GC.
+	sources: &'a Sources<'a>,
+}
+
+impl<'a> Painter<'a> {
+	/// Creates a new painter drawing on the image of the given target.
+	///
+	/// Arguments:
+	/// - `image` is the image of the target.
+	/// - `target` is the target.
+	/// - `gc` is the graphics context.
+	/// - `sources` are the images of the pixmaps referenced by the GC.
+	pub fn for_target(
+		image: &'a mut Image,
+		target: &Target,
+		gc: &'a GC,
+		sources: &'a Sources<'a>,
+	) -> Self {
+		let bounds = target.bounds.intersect(&Rect::new(
+			target.x,
+			target.y,
+			target.x + image.get_width() as i32,
+			target.y + image.get_height() as i32,
+		));
+
+		Self {
+			image,
+			x: target.x,
+			y: target.y,
+			bounds,
+			clip: target.clip.clone(),
+			damage: Rect::default(),
+			gc,
+
+			sources,
+		}
+	}
+
+	/// Returns the graphics context.
+	pub fn get_gc(&self) -> &'a GC {
+		self.gc
+	}
+
+	/// Returns the area that can be drawn on.
+	pub fn get_bounds(&self) -> Rect {
+		self.bounds
+	}
+
+	/// Returns the smallest rectangle containing every pixel drawn so far.
+	pub fn get_damage(&self) -> Rect {
+		self.damage
+	}
+
+	/// Tells whether the pixel at the given position is removed by the clipping of the GC.
+	fn is_clipped(&self, x: i32, y: i32) -> bool {
+		let x = x - self.gc.clip_x_origin as i32;
+		let y = y - self.gc.clip_y_origin as i32;
+
+		match &self.gc.clip {
+			Clip::None => false,
+			Clip::Mask(_) => match &self.sources.clip_mask {
+				Some(mask) => !mask.contains(x, y) || mask.get_pixel(x as _, y as _) == 0,
+				None => false,
+			},
+			Clip::Rectangles(rects) => !rects.iter().any(|r| {
+				let rx = r.x as i32;
+				let ry = r.y as i32;
+				x >= rx && y >= ry && x < rx + r.width as i32 && y < ry + r.height as i32
+			}),
+		}
+	}
+
+	/// Returns the source pixel to draw at the given position, according to the fill style.
+	///
+	/// If nothing is to be drawn at this position, the function returns None.
+	fn get_source(&self, x: i32, y: i32, paint: Paint) -> Option<u32> {
+		let gc = self.gc;
+		// Returns the position in the given tile or stipple
+		let pos = |image: &Image| {
+			let x = (x - gc.tile_stipple_x_origin as i32).rem_euclid(image.get_width() as _);
+			let y = (y - gc.tile_stipple_y_origin as i32).rem_euclid(image.get_height() as _);
+			(x as usize, y as usize)
+		};
+		// Without stipple, the stipple is filled with ones
+		let stipple = || match &self.sources.stipple {
+			Some(stipple) => {
+				let (x, y) = pos(stipple);
+				stipple.get_pixel(x, y) != 0
+			}
+			None => true,
+		};
+		let pixel = match paint {
+			Paint::Foreground => gc.foreground,
+			Paint::Background => gc.background,
+		};
+
+		match gc.fill_style {
+			FillStyle::Solid => Some(pixel),
+			// Without tile, the tile is filled with the foreground pixel
+			FillStyle::Tiled => match &self.sources.tile {
+				Some(tile) => {
+					let (x, y) = pos(tile);
+					Some(tile.get_pixel(x, y))
+				}
+				None => Some(gc.foreground),
+			},
+			FillStyle::Stippled => stipple().then_some(pixel),
+			FillStyle::OpaqueStippled if stipple() => Some(gc.foreground),
+			FillStyle::OpaqueStippled => Some(gc.background),
+		}
+	}
+
+	/// Draws the pixel at the given position, according to the fill style.
+	///
+	/// If the pixel is outside of the image or clipped, the function does nothing.
+	pub fn plot(&mut self, x: i32, y: i32, paint: Paint) {
+		if let Some(src) = self.get_source(x, y, paint) {
+			self.put(x, y, src);
+		}
+	}
+
+	/// Draws the given source pixel at the given position, ignoring the fill style.
+	///
+	/// If the pixel is outside of the image or clipped, the function does nothing.
+	pub fn put(&mut self, x: i32, y: i32, src: u32) {
+		let gc = self.gc;
+		self.put_with(x, y, src, &gc.function);
+	}
+
+	/// Same as [`Self::put`], but applies the given function instead of the GC's.
+	pub fn put_with(&mut self, x: i32, y: i32, src: u32, function: &Function) {
+		if !self.bounds.contains(x, y) || self.is_clipped(x, y) {
+			return;
+		}
+		if let Some(clip) = &self.clip {
+			if !clip.contains(x, y) {
+				return;
+			}
+		}
+		self.damage = self.damage.bounding(&Rect::new(x, y, x + 1, y + 1));
+
+		let ix = (x - self.x) as usize;
+		let iy = (y - self.y) as usize;
+		let dst = self.image.get_pixel(ix, iy);
+		let val = apply_function(function, src, dst);
+		let pixel = (dst & !self.gc.plane_mask) | (val & self.gc.plane_mask);
+		self.image.set_pixel(ix, iy, pixel);
+	}
+
+	/// Draws the pixels on the scanline `y`, from `x0` (inclusive) to `x1` (exclusive).
+	pub fn span(&mut self, y: i32, x0: i32, x1: i32, paint: Paint) {
+		if y < self.bounds.y0 || y >= self.bounds.y1 {
+			return;
+		}
+
+		for x in max(x0, self.bounds.x0)..min(x1, self.bounds.x1) {
+			self.plot(x, y, paint);
+		}
+	}
+}
+
+/// A coverage is a set of pixels to be drawn. It allows to draw the union of several shapes
+/// while drawing each pixel only once.
+pub struct Coverage {
+	/// The X position of the area covered by the mask.
+	x: i32,
+	/// The Y position of the area covered by the mask.
+	y: i32,
+	/// The width of the area covered by the mask.
+	width: i32,
+	/// The height of the area covered by the mask.
+	height: i32,
+
+	/// The mask telling which pixels are set.
+	mask: Vec<bool>,
+}
+
+impl Coverage {
+	/// Creates an empty coverage.
+	///
+	/// `(x0, y0)` (inclusive) and `(x1, y1)` (exclusive) are the bounds of the area in which
+	/// pixels can be set. Pixels outside of it are ignored.
+	pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+		let width = max(x1 - x0, 0);
+		let height = max(y1 - y0, 0);
+
+		Self {
+			x: x0,
+			y: y0,
+			width,
+			height,
+
+			mask: vec![false; width as usize * height as usize],
+		}
+	}
+
+	/// Creates an empty coverage for the given painter.
+	///
+	/// `(x0, y0)` (inclusive) and `(x1, y1)` (exclusive) are the bounds of the shapes. The area
+	/// of the coverage is the intersection of these bounds and of the painter's bounds.
+	pub fn for_painter(painter: &Painter, x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+		let b = painter.get_bounds();
+		Self::new(max(x0, b.x0), max(y0, b.y0), min(x1, b.x1), min(y1, b.y1))
+	}
+
+	/// Returns the area in which pixels can be set.
+	pub fn get_bounds(&self) -> Rect {
+		Rect::new(self.x, self.y, self.x + self.width, self.y + self.height)
+	}
+
+	/// Sets the pixels on the scanline `y`, from `x0` (inclusive) to `x1` (exclusive).
+	pub fn add_span(&mut self, y: i32, x0: i32, x1: i32) {
+		let y = y - self.y;
+		if y < 0 || y >= self.height {
+			return;
+		}
+
+		let x0 = max(x0 - self.x, 0);
+		let x1 = min(x1 - self.x, self.width);
+		if x0 >= x1 {
+			return;
+		}
+
+		let line = (y * self.width) as usize;
+		self.mask[(line + x0 as usize)..(line + x1 as usize)].fill(true);
+	}
+
+	/// Draws the pixels that are set using the given painter.
+	pub fn paint(&self, painter: &mut Painter, paint: Paint) {
+		for y in 0..self.height {
+			for x in 0..self.width {
+				if self.mask[(y * self.width + x) as usize] {
+					painter.plot(self.x + x, self.y + y, paint);
+				}
+			}
+		}
+	}
+}
+
+/// Draws on a drawable using a graphics context.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `client` is the client owning the graphics context.
+/// - `drawable` is the ID of the drawable.
+/// - `gc` is the ID of the graphics context.
+/// - `f` is the function performing the drawing.
+///
+/// If the subwindow mode of the GC is `IncludeInferiors`, `f` is called once more for each
+/// viewable inferior of the drawable.
+///
+/// The area drawn on is marked as damaged.
+///
+/// If the drawable or the graphics context doesn't exist, or if they don't match, the function
+/// returns an error.
+pub fn draw<F: FnMut(&mut Painter)>(
+	ctx: &Context,
+	client: &Client,
+	drawable: u32,
+	gc: u32,
+	mut f: F,
+) -> Result<(), Error> {
+	let id = NonZeroU32::new(drawable).ok_or(Error::Drawable(drawable))?;
+	let d = ctx.get_drawable(id).ok_or(Error::Drawable(drawable))?;
+	let gc = client.get_gc(gc).ok_or(Error::GContext(gc))?;
+	if !gc.is_compatible(d) {
+		return Err(Error::Match);
+	}
+	// A drawable without storage is an input-only window
+	if d.get_image().is_none() {
+		return Err(Error::Match);
+	}
+
+	let sources = Sources::from_gc(gc, id);
+	let mut damage = Rect::default();
+	for target in Target::list(ctx, id, d, &gc.subwindow_mode) {
+		let Some(mut image) = target.drawable.get_image_mut() else {
+			continue;
+		};
+
+		let mut painter = Painter::for_target(&mut image, &target, gc, &sources);
+		f(&mut painter);
+		damage = damage.bounding(&painter.get_damage());
+	}
+	ctx.damage(id, damage);
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ctx::pixmap::Pixmap;
+
+	/// Draws on a blank image of the given dimensions and returns its pixels, one line of text per
+	/// row.
+	///
+	/// `setup` configures the graphics context, whose foreground is drawn as `#` and background as
+	/// `-`. Pixels left untouched are drawn as `.`.
+	pub fn render<S: FnOnce(&mut GC), F: FnOnce(&mut Painter)>(
+		width: u16,
+		height: u16,
+		setup: S,
+		draw: F,
+	) -> Vec<String> {
+		let id = NonZeroU32::new(1).unwrap();
+		let pixmap = Pixmap::new(id, id, width, height, 8).unwrap();
+		let mut gc = GC::new(8, id.get());
+		gc.foreground = 1;
+		gc.background = 2;
+		setup(&mut gc);
+
+		let sources = Sources::default();
+		let target = Target {
+			drawable: &pixmap,
+			x: 0,
+			y: 0,
+			bounds: Rect::new(0, 0, width as _, height as _),
+			clip: None,
+		};
+		let mut image = pixmap.get_image_mut().unwrap();
+		image.fill(0);
+		draw(&mut Painter::for_target(&mut image, &target, &gc, &sources));
+
+		(0..height as usize)
+			.map(|y| {
+				(0..width as usize)
+					.map(|x| match image.get_pixel(x, y) {
+						0 => '.',
+						1 => '#',
+						_ => '-',
+					})
+					.collect()
+			})
+			.collect()
+	}
+
+	/// The tile is borrowed, unless it is also the drawable being drawn on.
+	#[test]
+	fn sources_copy_destination_only() {
+		let id = NonZeroU32::new(1).unwrap();
+		let other = NonZeroU32::new(2).unwrap();
+		let tile = PixmapRef::new(Pixmap::new(id, id, 2, 2, 8).unwrap());
+		let mut gc = GC::new(8, id.get());
+		gc.fill_style = FillStyle::Tiled;
+		gc.tile = Some(tile.clone());
+
+		let sources = Sources::from_gc(&gc, other);
+		assert!(matches!(sources.tile, Some(Source::Borrowed(_))));
+		drop(sources);
+
+		let sources = Sources::from_gc(&gc, id);
+		assert!(matches!(sources.tile, Some(Source::Copied(_))));
+		// The destination can be drawn on while its copy is used as the tile
+		tile.get_image_mut().unwrap().fill(1);
+		assert_eq!(sources.tile.as_ref().unwrap().get_pixel(0, 0), 0);
+	}
+}
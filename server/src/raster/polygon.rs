@@ -0,0 +1,215 @@
+//! Scan conversion of polygons.
+
+use super::Paint;
+use super::Painter;
+use crate::ctx::gc::FillRule;
+use crate::ctx::region::Rect;
+use std::cmp::max;
+use std::cmp::min;
+
+/// An edge of a polygon, in a form suitable for scanning.
+struct Edge {
+	/// The Y position of the top end.
+	top: f64,
+	/// The Y position of the bottom end.
+	bottom: f64,
+	/// The X position of the top end.
+	x: f64,
+	/// The change in X position of the edge for each scanline.
+	slope: f64,
+	/// The direction of the edge: `1` if it goes downward, `-1` otherwise.
+	dir: i32,
+}
+
+/// Scans the polygon made of the given points.
+///
+/// Arguments:
+/// - `points` is the list of vertices of the polygon. The path is closed automatically.
+/// - `rule` is the rule used to determine which parts of the polygon are inside.
+/// - `bounds` is the area to scan. Spans are clipped to it.
+/// - `f` is called with `(y, x0, x1)` for each span of pixels inside of the polygon, `x1` being
+///   exclusive.
+pub fn scan<F: FnMut(i32, i32, i32)>(
+	points: &[(f64, f64)],
+	rule: &FillRule,
+	bounds: &Rect,
+	mut f: F,
+) {
+	if points.len() < 3 || bounds.is_empty() {
+		return;
+	}
+
+	// Edges sorted by their top end
+	let mut edges: Vec<_> = points
+		.iter()
+		.zip(points.iter().cycle().skip(1))
+		.filter(|(a, b)| a.1 != b.1)
+		.map(|(a, b)| {
+			let (top, bottom, dir) = if a.1 < b.1 { (a, b, 1) } else { (b, a, -1) };
+			let slope = (bottom.0 - top.0) / (bottom.1 - top.1);
+			Edge {
+				top: top.1,
+				bottom: bottom.1,
+				x: top.0,
+				slope,
+				dir,
+			}
+		})
+		.collect();
+	edges.sort_by(|a, b| a.top.total_cmp(&b.top));
+
+	let y_min = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+	let y_max = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+	let y_begin = max(y_min.ceil() as i32, bounds.y0);
+	let y_end = min(y_max.ceil() as i32, bounds.y1);
+
+	// Edges crossing the current scanline
+	let mut active: Vec<&Edge> = vec![];
+	let mut next = 0;
+	let mut crossings: Vec<(f64, i32)> = vec![];
+	for y in y_begin..y_end {
+		let fy = y as f64;
+
+		// Edges include their top end but not their bottom end
+		while next < edges.len() && edges[next].top <= fy {
+			active.push(&edges[next]);
+			next += 1;
+		}
+		active.retain(|e| fy < e.bottom);
+
+		crossings.clear();
+		crossings.extend(active.iter().map(|e| (e.x + (fy - e.top) * e.slope, e.dir)));
+		crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+		// A pixel is inside if its center is in `[x0; x1[`
+		let mut emit = |x0: f64, x1: f64| {
+			let x0 = max(x0.ceil() as i32, bounds.x0);
+			let x1 = min(x1.ceil() as i32, bounds.x1);
+			if x0 < x1 {
+				f(y, x0, x1);
+			}
+		};
+
+		match rule {
+			FillRule::EvenOdd => {
+				for pair in crossings.chunks_exact(2) {
+					emit(pair[0].0, pair[1].0);
+				}
+			}
+
+			FillRule::Winding => {
+				let mut winding = 0;
+				let mut start = 0.;
+				for (x, dir) in &crossings {
+					if winding == 0 {
+						start = *x;
+					}
+					winding += dir;
+					if winding == 0 {
+						emit(start, *x);
+					}
+				}
+			}
+		}
+	}
+}
+
+/// Fills the polygon made of the given points, as a `FillPoly` request.
+///
+/// The fill rule of the painter's graphics context is used.
+pub fn fill_poly(painter: &mut Painter, points: &[(i32, i32)]) {
+	let points: Vec<_> = points.iter().map(|(x, y)| (*x as f64, *y as f64)).collect();
+	let rule = &painter.get_gc().fill_rule;
+	let bounds = painter.get_bounds();
+	scan(&points, rule, &bounds, |y, x0, x1| {
+		painter.span(y, x0, x1, Paint::Foreground)
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::raster::tests::render;
+
+	/// A self-intersecting star, whose center is inside only with the `Winding` rule.
+	const STAR: [(i32, i32); 5] = [(5, 0), (8, 9), (0, 3), (10, 3), (2, 9)];
+
+	/// Pixels on the right and bottom edges are outside of the polygon.
+	#[test]
+	fn fill_rectangle() {
+		let pixels = render(
+			8,
+			6,
+			|_| {},
+			|p| fill_poly(p, &[(1, 1), (6, 1), (6, 4), (1, 4)]),
+		);
+		assert_eq!(
+			pixels,
+			["........", ".#####..", ".#####..", ".#####..", "........", "........",]
+		);
+	}
+
+	/// Polygons larger than the drawable are clipped to it.
+	#[test]
+	fn fill_clipped() {
+		let pixels = render(
+			4,
+			3,
+			|_| {},
+			|p| {
+				fill_poly(
+					p,
+					&[(-1000000, -1000000), (1000000, -1000000), (2, 1000000)],
+				)
+			},
+		);
+		assert_eq!(pixels, ["####", "####", "####"]);
+	}
+
+	/// Filling the star with the `EvenOdd` rule.
+	#[test]
+	fn fill_even_odd() {
+		let pixels = render(11, 10, |_| {}, |p| fill_poly(p, &STAR));
+		assert_eq!(
+			pixels,
+			[
+				"...........",
+				".....#.....",
+				".....#.....",
+				"####..####.",
+				"..##...##..",
+				"...#...#...",
+				"...#..#....",
+				"...##.##...",
+				"...#...#...",
+				"...........",
+			]
+		);
+	}
+
+	/// Filling the star with the `Winding` rule.
+	#[test]
+	fn fill_winding() {
+		let pixels = render(
+			11,
+			10,
+			|gc| gc.fill_rule = FillRule::Winding,
+			|p| fill_poly(p, &STAR),
+		);
+		assert_eq!(
+			pixels,
+			[
+				"...........",
+				".....#.....",
+				".....#.....",
+				"##########.",
+				"..#######..",
+				"...#####...",
+				"...####....",
+				"...##.##...",
+				"...#...#...",
+				"...........",
+			]
+		);
+	}
+}
@@ -0,0 +1,34 @@
+//! Drawing of rectangles.
+
+use super::line;
+use super::Paint;
+use super::Painter;
+use crate::protocol::Rectangle;
+
+/// Draws the outlines of the given rectangles.
+///
+/// Each outline is drawn as a closed path, so that corners are joined.
+pub fn poly_rectangle(painter: &mut Painter, rects: &[Rectangle]) {
+	for r in rects {
+		let x0 = r.x as i32;
+		let y0 = r.y as i32;
+		let x1 = x0 + r.width as i32;
+		let y1 = y0 + r.height as i32;
+
+		line::poly_line(painter, &[(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)]);
+	}
+}
+
+/// Fills the given rectangles.
+pub fn poly_fill_rectangle(painter: &mut Painter, rects: &[Rectangle]) {
+	for r in rects {
+		let x0 = r.x as i32;
+		let y0 = r.y as i32;
+		let x1 = x0 + r.width as i32;
+		let y1 = y0 + r.height as i32;
+
+		for y in y0..y1 {
+			painter.span(y, x0, x1, Paint::Foreground);
+		}
+	}
+}
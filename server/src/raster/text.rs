@@ -0,0 +1,68 @@
+//! Drawing of text.
+//!
+//! The origin of a string is on its baseline. Each glyph is drawn with its left side bearing and
+//! ascent relative to the current origin, which then advances by the glyph's width.
+
+use super::Function;
+use super::Paint;
+use super::Painter;
+use crate::font::Font;
+use crate::font::Glyph;
+
+/// Calls `f` with the position of each set pixel of the given glyph, whose origin is at `(x, y)`.
+fn for_each_pixel<F: FnMut(i32, i32)>(glyph: &Glyph, x: i32, y: i32, mut f: F) {
+	let x0 = x + glyph.info.left_side_bearing as i32;
+	let y0 = y - glyph.info.ascent as i32;
+	for gy in 0..glyph.get_height() {
+		for gx in 0..glyph.get_width() {
+			if glyph.get_pixel(gx, gy) {
+				f(x0 + gx, y0 + gy);
+			}
+		}
+	}
+}
+
+/// Draws the given string with its origin at `(x, y)`, according to the GC's fill style.
+///
+/// Only the pixels of the glyphs are drawn. The function returns the X position of the origin
+/// following the last character.
+pub fn poly_text(painter: &mut Painter, font: &Font, x: i32, y: i32, text: &[u16]) -> i32 {
+	let mut x = x;
+	for glyph in text.iter().filter_map(|c| font.get_glyph(*c)) {
+		for_each_pixel(glyph, x, y, |px, py| {
+			painter.plot(px, py, Paint::Foreground)
+		});
+		x += glyph.info.character_width as i32;
+	}
+	x
+}
+
+/// Draws the given string with its origin at `(x, y)` over a rectangle filled with the GC's
+/// background.
+///
+/// The rectangle spans the logical extents of the font vertically and the width of the string
+/// horizontally. The GC's function and fill style are ignored: pixels are copied as if they were
+/// `Copy` and `Solid`.
+pub fn image_text(painter: &mut Painter, font: &Font, x: i32, y: i32, text: &[u16]) {
+	let gc = painter.get_gc();
+	let extents = font.text_extents(text);
+
+	let b = painter.get_bounds();
+	let x0 = x.max(b.x0);
+	let x1 = (x + extents.overall_width).min(b.x1);
+	let y0 = (y - font.get_font_ascent() as i32).max(b.y0);
+	let y1 = (y + font.get_font_descent() as i32).min(b.y1);
+	for py in y0..y1 {
+		for px in x0..x1 {
+			painter.put_with(px, py, gc.background, &Function::Copy);
+		}
+	}
+
+	let mut x = x;
+	for glyph in text.iter().filter_map(|c| font.get_glyph(*c)) {
+		for_each_pixel(glyph, x, y, |px, py| {
+			painter.put_with(px, py, gc.foreground, &Function::Copy)
+		});
+		x += glyph.info.character_width as i32;
+	}
+}
@@ -1,6 +1,24 @@
 //! This module implements utility functions.
 
+use std::mem::size_of;
+use std::ptr;
+use std::slice;
+
 /// Reinterprets the given pointer in the given type.
 pub unsafe fn reinterpret<A, B>(ptr: *const A) -> &'static B {
 	&*(ptr as *const B)
 }
+
+/// Reads a list of objects of the given type from the given buffer.
+///
+/// Trailing bytes that are not enough to make a whole object are ignored.
+pub fn read_list<T>(buff: &[u8]) -> Vec<T> {
+	buff.chunks_exact(size_of::<T>())
+		.map(|c| unsafe { ptr::read_unaligned(c.as_ptr() as *const T) })
+		.collect()
+}
+
+/// Returns the bytes making the given list of objects.
+pub fn as_bytes<T>(list: &[T]) -> &[u8] {
+	unsafe { slice::from_raw_parts(list.as_ptr() as *const u8, size_of_val(list)) }
+}